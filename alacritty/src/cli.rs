@@ -5,7 +5,7 @@ use std::path::PathBuf;
 use std::rc::Rc;
 
 use alacritty_config::SerdeReplace;
-use clap::{ArgAction, Args, Parser, Subcommand, ValueHint};
+use clap::{ArgAction, Args, Parser, Subcommand, ValueEnum, ValueHint};
 use log::{LevelFilter, error};
 use serde::{Deserialize, Serialize};
 use toml::Value;
@@ -163,6 +163,10 @@ pub struct TerminalOptions {
     #[clap(long)]
     pub hold: bool,
 
+    /// Record the session to the specified file in the asciicast v2 format.
+    #[clap(long, value_hint = ValueHint::FilePath)]
+    pub record: Option<PathBuf>,
+
     /// Command and args to execute (must be last argument).
     #[clap(short = 'e', long, allow_hyphen_values = true, num_args = 1..)]
     command: Vec<String>,
@@ -231,11 +235,14 @@ impl WindowIdentity {
 }
 
 /// Available CLI subcommands.
+#[allow(clippy::large_enum_variant)]
 #[derive(Subcommand, Debug)]
 pub enum Subcommands {
     #[cfg(unix)]
     Msg(MessageOptions),
     Migrate(MigrateOptions),
+    Replay(ReplayOptions),
+    Render(RenderOptions),
 }
 
 /// Send a message to the Alacritty socket.
@@ -263,6 +270,30 @@ pub enum SocketMessage {
 
     /// Read runtime Alacritty configuration.
     GetConfig(IpcGetConfig),
+
+    /// Read the working directory of a window's shell.
+    GetWorkingDirectory(IpcGetWorkingDirectory),
+
+    /// List terminals detached from their windows.
+    ListSessions,
+
+    /// Open a new window for a detached terminal.
+    Attach(IpcAttach),
+
+    /// Write text to a window's shell.
+    SendText(IpcSendText),
+
+    /// Read the text inside a window.
+    GetText(IpcGetText),
+
+    /// List all windows as JSON.
+    ListWindows,
+
+    /// Stream events as newline-delimited JSON.
+    Subscribe(IpcSubscribe),
+
+    /// Save the content of a window as PNG image.
+    Screenshot(IpcScreenshot),
 }
 
 /// Migrate the configuration file.
@@ -289,6 +320,46 @@ pub struct MigrateOptions {
     pub silent: bool,
 }
 
+/// Replay a session recording.
+#[derive(Args, Clone, Debug)]
+pub struct ReplayOptions {
+    /// Path to the asciicast recording.
+    #[clap(value_hint = ValueHint::FilePath)]
+    pub file: PathBuf,
+
+    /// Playback speed multiplier.
+    #[clap(long, default_value_t = 1., value_parser = parse_speed)]
+    pub speed: f64,
+}
+
+/// Render terminal output to a PNG image without opening a window.
+#[derive(Args, Clone, Debug)]
+pub struct RenderOptions {
+    /// File containing the terminal output [default: STDIN].
+    #[clap(value_hint = ValueHint::FilePath)]
+    pub input: Option<PathBuf>,
+
+    /// Path of the PNG image.
+    #[clap(short, long, value_hint = ValueHint::FilePath)]
+    pub output: PathBuf,
+
+    /// Number of terminal columns [default: window.dimensions or 80].
+    #[clap(long)]
+    pub columns: Option<usize>,
+
+    /// Number of terminal lines [default: window.dimensions or 24].
+    #[clap(long)]
+    pub lines: Option<usize>,
+}
+
+/// Parse the replay speed CLI parameter.
+fn parse_speed(input: &str) -> Result<f64, String> {
+    match input.parse::<f64>() {
+        Ok(speed) if speed.is_finite() && speed > 0. => Ok(speed),
+        _ => Err(String::from("Speed must be a positive number")),
+    }
+}
+
 /// Subset of options that we pass to 'create-window' IPC subcommand.
 #[derive(Serialize, Deserialize, Args, Default, Clone, Debug, PartialEq, Eq)]
 pub struct WindowOptions {
@@ -352,6 +423,115 @@ pub struct IpcGetConfig {
     pub window_id: Option<i128>,
 }
 
+/// Parameters to the `get-working-directory` IPC subcommand.
+#[cfg(unix)]
+#[derive(Args, Serialize, Deserialize, Default, Debug, Clone, PartialEq, Eq)]
+pub struct IpcGetWorkingDirectory {
+    /// Window ID for the working directory request.
+    ///
+    /// Without a window ID, the focused window is used.
+    #[clap(short, long, env = "ALACRITTY_WINDOW_ID")]
+    pub window_id: Option<i128>,
+}
+
+/// Parameters to the `attach` IPC subcommand.
+#[cfg(unix)]
+#[derive(Args, Serialize, Deserialize, Default, Debug, Clone, PartialEq, Eq)]
+pub struct IpcAttach {
+    /// ID of the detached session.
+    pub session_id: usize,
+}
+
+/// Parameters to the `send-text` IPC subcommand.
+#[cfg(unix)]
+#[derive(Args, Serialize, Deserialize, Default, Debug, Clone, PartialEq, Eq)]
+pub struct IpcSendText {
+    /// Text which should be written to the shell.
+    pub text: String,
+
+    /// Window ID for the text.
+    ///
+    /// Without a window ID, the focused window is used.
+    #[clap(short, long, env = "ALACRITTY_WINDOW_ID")]
+    pub window_id: Option<i128>,
+}
+
+/// Parameters to the `get-text` IPC subcommand.
+#[cfg(unix)]
+#[derive(Args, Serialize, Deserialize, Default, Debug, Clone, PartialEq, Eq)]
+pub struct IpcGetText {
+    /// Window ID for the text request.
+    ///
+    /// Without a window ID, the focused window is used.
+    #[clap(short, long, env = "ALACRITTY_WINDOW_ID")]
+    pub window_id: Option<i128>,
+
+    /// Include the scrollback history.
+    #[clap(long, conflicts_with = "selection")]
+    pub scrollback: bool,
+
+    /// Only read the selected text.
+    #[clap(long)]
+    pub selection: bool,
+
+    /// Include text attributes as ANSI escape sequences.
+    #[clap(long, conflicts_with = "html")]
+    pub ansi: bool,
+
+    /// Format the text as HTML document, with its colors and attributes.
+    #[clap(long)]
+    pub html: bool,
+}
+
+/// Parameters to the `screenshot` IPC subcommand.
+#[cfg(unix)]
+#[derive(Args, Serialize, Deserialize, Default, Debug, Clone, PartialEq, Eq)]
+pub struct IpcScreenshot {
+    /// Window ID for the screenshot request.
+    ///
+    /// Without a window ID, the focused window is used.
+    #[clap(short, long, env = "ALACRITTY_WINDOW_ID")]
+    pub window_id: Option<i128>,
+
+    /// Path of the PNG image.
+    #[clap(short, long, value_hint = ValueHint::FilePath)]
+    pub output: PathBuf,
+}
+
+/// Parameters to the `subscribe` IPC subcommand.
+#[cfg(unix)]
+#[derive(Args, Serialize, Deserialize, Default, Debug, Clone, PartialEq, Eq)]
+pub struct IpcSubscribe {
+    /// Events which should be streamed, all events are streamed by default.
+    #[clap(value_enum)]
+    pub events: Vec<SubscriptionKind>,
+}
+
+/// Kinds of events available to IPC subscribers.
+#[cfg(unix)]
+#[derive(ValueEnum, Serialize, Deserialize, Debug, Copy, Clone, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SubscriptionKind {
+    /// A window was created.
+    WindowCreated,
+    /// A window was closed.
+    WindowClosed,
+    /// A window gained or lost focus.
+    WindowFocused,
+    /// A window's title changed.
+    Title,
+    /// A terminal rang the bell.
+    Bell,
+    /// A shell exited with an exit code.
+    ChildExit,
+    /// The configuration file was reloaded.
+    ConfigReload,
+    /// Search was started or stopped.
+    Search,
+    /// Vi mode was entered or left.
+    ViMode,
+}
+
 /// Parsed CLI config overrides.
 #[derive(Debug, Default)]
 pub struct ParsedOptions {
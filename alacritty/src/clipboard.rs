@@ -1,3 +1,5 @@
+use std::collections::VecDeque;
+
 use log::{debug, warn};
 use winit::raw_window_handle::RawDisplayHandle;
 
@@ -15,6 +17,9 @@ use copypasta::x11_clipboard::{Primary as X11SelectionClipboard, X11ClipboardCon
 pub struct Clipboard {
     clipboard: Box<dyn ClipboardProvider>,
     selection: Option<Box<dyn ClipboardProvider>>,
+
+    /// Previously copied texts, starting with the most recent one.
+    history: VecDeque<String>,
 }
 
 impl Clipboard {
@@ -25,7 +30,11 @@ impl Clipboard {
                 let (selection, clipboard) = unsafe {
                     wayland_clipboard::create_clipboards_from_external(display.display.as_ptr())
                 };
-                Self { clipboard: Box::new(clipboard), selection: Some(Box::new(selection)) }
+                Self {
+                    clipboard: Box::new(clipboard),
+                    selection: Some(Box::new(selection)),
+                    history: Default::default(),
+                }
             },
             _ => Self::default(),
         }
@@ -34,19 +43,28 @@ impl Clipboard {
     /// Used for tests, to handle missing clipboard provider when built without the `x11`
     /// feature, and as default clipboard value.
     pub fn new_nop() -> Self {
-        Self { clipboard: Box::new(NopClipboardContext::new().unwrap()), selection: None }
+        Self {
+            clipboard: Box::new(NopClipboardContext::new().unwrap()),
+            selection: None,
+            history: Default::default(),
+        }
     }
 }
 
 impl Default for Clipboard {
     fn default() -> Self {
         #[cfg(any(target_os = "macos", windows))]
-        return Self { clipboard: Box::new(ClipboardContext::new().unwrap()), selection: None };
+        return Self {
+            clipboard: Box::new(ClipboardContext::new().unwrap()),
+            selection: None,
+            history: Default::default(),
+        };
 
         #[cfg(all(feature = "x11", not(any(target_os = "macos", windows))))]
         return Self {
             clipboard: Box::new(ClipboardContext::new().unwrap()),
             selection: Some(Box::new(X11ClipboardContext::<X11SelectionClipboard>::new().unwrap())),
+            history: Default::default(),
         };
 
         #[cfg(not(any(feature = "x11", target_os = "macos", windows)))]
@@ -81,4 +99,43 @@ impl Clipboard {
             Ok(text) => text,
         }
     }
+
+    /// Add copied text to the history, keeping at most `size` entries.
+    ///
+    /// Copying text which is already in the history moves it to the front.
+    pub fn push_history(&mut self, text: &str, size: usize) {
+        if text.is_empty() {
+            return;
+        }
+
+        self.history.retain(|entry| entry != text);
+        self.history.push_front(text.into());
+        self.history.truncate(size);
+    }
+
+    /// Previously copied texts, starting with the most recent one.
+    pub fn history(&self) -> &VecDeque<String> {
+        &self.history
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn history_deduplication() {
+        let mut clipboard = Clipboard::new_nop();
+        clipboard.push_history("a", 2);
+        clipboard.push_history("b", 2);
+        clipboard.push_history("", 2);
+        clipboard.push_history("a", 2);
+        assert_eq!(clipboard.history(), &["a", "b"]);
+
+        clipboard.push_history("c", 2);
+        assert_eq!(clipboard.history(), &["c", "a"]);
+
+        clipboard.push_history("d", 0);
+        assert!(clipboard.history().is_empty());
+    }
 }
@@ -1,6 +1,9 @@
 #![allow(clippy::enum_glob_use)]
 
+use std::error::Error;
 use std::fmt::{self, Debug, Display};
+use std::num::NonZeroUsize;
+use std::slice;
 
 use bitflags::bitflags;
 use serde::de::{self, Error as SerdeError, MapAccess, Unexpected, Visitor};
@@ -13,6 +16,7 @@ use winit::keyboard::{
 };
 use winit::platform::scancode::PhysicalKeyExtScancode;
 
+use alacritty_config::SerdeReplace;
 use alacritty_config_derive::{ConfigDeserialize, SerdeReplace};
 
 use alacritty_terminal::term::TermMode;
@@ -20,6 +24,9 @@ use alacritty_terminal::vi_mode::ViMotion;
 
 use crate::config::ui_config::{Hint, Program, StringVisitor};
 
+/// Maximum number of times a macro can be played by a single binding.
+const MAX_MACRO_COUNT: usize = 1000;
+
 /// Describes a state and action to take in that state.
 ///
 /// This is the shared component of `MouseBinding` and `KeyBinding`.
@@ -37,10 +44,16 @@ pub struct Binding<T> {
     /// Excluded binding modes where the binding won't be activated.
     pub notmode: BindingMode,
 
+    /// User-defined binding modes required or excluded by the binding.
+    pub custom_modes: CustomModes,
+
     /// This property is used as part of the trigger detection code.
     ///
     /// For example, this might be a key like "G", or a mouse button.
     pub trigger: T,
+
+    /// Keys which must be pressed in order before the trigger.
+    pub prefix: Vec<ChordStep>,
 }
 
 /// Bindings that are triggered by a keyboard key.
@@ -64,7 +77,10 @@ impl<T: Eq> Binding<T> {
     #[inline]
     pub fn triggers_match(&self, binding: &Binding<T>) -> bool {
         // Check the binding's key and modifiers.
-        if self.trigger != binding.trigger || self.mods != binding.mods {
+        if self.trigger != binding.trigger
+            || self.mods != binding.mods
+            || self.prefix != binding.prefix
+        {
             return false;
         }
 
@@ -81,7 +97,36 @@ impl<T: Eq> Binding<T> {
             return false;
         }
 
-        true
+        !self.custom_modes.excludes(&binding.custom_modes)
+            && !binding.custom_modes.excludes(&self.custom_modes)
+    }
+}
+
+/// User-defined binding modes of a binding.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct CustomModes {
+    /// Modes which must be active for the binding to take effect.
+    pub mode: Vec<String>,
+
+    /// Modes which must not be active for the binding to take effect.
+    pub notmode: Vec<String>,
+}
+
+impl CustomModes {
+    /// Check if the binding can be triggered with the given modes being active.
+    pub fn matches(&self, active: &[String]) -> bool {
+        self.mode.iter().all(|mode| active.contains(mode))
+            && !self.notmode.iter().any(|mode| active.contains(mode))
+    }
+
+    /// All modes referenced by the binding.
+    pub fn iter(&self) -> impl Iterator<Item = &String> {
+        self.mode.iter().chain(&self.notmode)
+    }
+
+    /// Check if a binding requiring these modes is never active together with another binding.
+    fn excludes(&self, other: &CustomModes) -> bool {
+        self.mode.iter().any(|mode| other.notmode.contains(mode))
     }
 }
 
@@ -127,6 +172,21 @@ pub enum Action {
     /// Paste contents of selection buffer.
     PasteSelection,
 
+    /// Pick an earlier entry of the clipboard history to paste.
+    PasteFromHistory,
+
+    /// Select the output of the last shell command.
+    SelectLastCommandOutput,
+
+    /// Store the output of the last shell command into clipboard.
+    CopyLastCommandOutput,
+
+    /// Store current selection into clipboard as HTML, including its colors.
+    CopySelectionAsHtml,
+
+    /// Save the scrollback history with its colors to a file in the current directory.
+    SaveScrollback,
+
     /// Increase font size.
     IncreaseFontSize,
 
@@ -160,6 +220,12 @@ pub enum Action {
     /// Scroll all the way to the bottom.
     ScrollToBottom,
 
+    /// Scroll to the previous shell prompt.
+    ScrollToPreviousPrompt,
+
+    /// Scroll to the next shell prompt.
+    ScrollToNextPrompt,
+
     /// Clear the display buffer(s) to remove history.
     ClearHistory,
 
@@ -223,6 +289,75 @@ pub enum Action {
     /// Create new window in a tab.
     CreateNewTab,
 
+    /// Start or stop recording the session.
+    ToggleRecording,
+
+    /// Save the window's content as PNG image in the current directory.
+    Screenshot,
+
+    /// Activate a user-defined binding mode.
+    #[config(skip)]
+    EnterMode(String),
+
+    /// Deactivate a user-defined binding mode.
+    #[config(skip)]
+    LeaveMode(String),
+
+    /// Toggle a user-defined binding mode.
+    #[config(skip)]
+    ToggleMode(String),
+
+    /// Execute multiple actions in order.
+    #[config(skip)]
+    Sequence(Vec<Action>),
+
+    /// Start recording keyboard input into a macro register.
+    #[config(skip)]
+    StartMacroRecording(String),
+
+    /// Stop recording keyboard input.
+    StopMacroRecording,
+
+    /// Write the input of a macro register to the terminal the given number of times.
+    #[config(skip)]
+    PlayMacro(String, usize),
+
+    /// Split the focused pane, placing the new pane to its right.
+    SplitHorizontal,
+
+    /// Split the focused pane, placing the new pane below it.
+    SplitVertical,
+
+    /// Focus the pane to the left of the focused pane.
+    FocusPaneLeft,
+
+    /// Focus the pane to the right of the focused pane.
+    FocusPaneRight,
+
+    /// Focus the pane above the focused pane.
+    FocusPaneUp,
+
+    /// Focus the pane below the focused pane.
+    FocusPaneDown,
+
+    /// Move the closest vertical divider of the focused pane to the left.
+    ResizePaneLeft,
+
+    /// Move the closest vertical divider of the focused pane to the right.
+    ResizePaneRight,
+
+    /// Move the closest horizontal divider of the focused pane up.
+    ResizePaneUp,
+
+    /// Move the closest horizontal divider of the focused pane down.
+    ResizePaneDown,
+
+    /// Close the focused pane.
+    ClosePane,
+
+    /// Toggle the focused pane filling the entire window.
+    ZoomPane,
+
     /// Toggle fullscreen.
     ToggleFullscreen,
 
@@ -281,6 +416,16 @@ impl From<MouseAction> for Action {
     }
 }
 
+impl Action {
+    /// Actions executed when this action is triggered, in order.
+    pub fn actions(&self) -> &[Action] {
+        match self {
+            Action::Sequence(actions) => actions,
+            action => slice::from_ref(action),
+        }
+    }
+}
+
 /// Display trait used for error logging.
 impl Display for Action {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
@@ -391,7 +536,9 @@ macro_rules! bindings {
                 mods: _mods,
                 mode: _mode,
                 notmode: _notmode,
+                custom_modes: CustomModes::default(),
                 action: $action.into(),
+                prefix: Vec::new(),
             });
         )*
 
@@ -740,10 +887,92 @@ impl<'a> Deserialize<'a> for BindingKey {
     }
 }
 
-#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+/// Key with its required modifiers, as part of a key sequence.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ChordKey {
+    pub key: BindingKey,
+    pub mods: ModifiersState,
+}
+
+impl ChordKey {
+    /// Parse a key with optional modifiers, like `Control+Shift+A`.
+    fn parse(text: &str) -> Result<Self, String> {
+        let (mods, key) = if text == "+" {
+            ("", "+")
+        } else if let Some(mods) = text.strip_suffix("++") {
+            (mods, "+")
+        } else {
+            text.rsplit_once('+').unwrap_or(("", text))
+        };
+
+        let mods = if mods.is_empty() {
+            ModifiersState::empty()
+        } else {
+            let mods = SerdeValue::String(mods.replace('+', "|"));
+            ModsWrapper::deserialize(mods).map_err(|err| err.to_string())?.into_inner()
+        };
+
+        let key = BindingKey::deserialize(SerdeValue::String(key.into()))
+            .map_err(|err| format!("invalid key `{key}`: {err}"))?;
+
+        Ok(Self { key, mods })
+    }
+}
+
+impl<'a> Deserialize<'a> for ChordKey {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'a>,
+    {
+        let text = String::deserialize(deserializer)?;
+        Self::parse(text.trim()).map_err(D::Error::custom)
+    }
+}
+
+impl SerdeReplace for ChordKey {
+    fn replace(&mut self, value: SerdeValue) -> Result<(), Box<dyn Error>> {
+        *self = Self::deserialize(value)?;
+
+        Ok(())
+    }
+}
+
+/// Key of a key sequence preceding the binding's trigger.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ChordStep {
+    /// The key configured as `keyboard.leader`.
+    Leader,
+
+    /// Any other key.
+    Key(ChordKey),
+}
+
+/// Parse a comma separated key sequence, like `Control+A, C`.
+fn parse_key_sequence(text: &str) -> Result<(Vec<ChordStep>, ChordKey), String> {
+    let mut steps = text
+        .split(',')
+        .map(str::trim)
+        .map(|step| {
+            if step.eq_ignore_ascii_case("leader") {
+                Ok(ChordStep::Leader)
+            } else {
+                ChordKey::parse(step).map(ChordStep::Key)
+            }
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    match steps.pop() {
+        Some(ChordStep::Key(key)) if !steps.is_empty() => Ok((steps, key)),
+        Some(ChordStep::Key(_)) => Err(String::from("key sequences require at least two keys")),
+        _ => Err(String::from("key sequences can't end with the leader key")),
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub struct ModeWrapper {
     pub mode: BindingMode,
     pub not_mode: BindingMode,
+    pub custom: CustomModes,
 }
 
 bitflags! {
@@ -782,7 +1011,11 @@ impl BindingMode {
 
 impl Default for ModeWrapper {
     fn default() -> Self {
-        Self { mode: BindingMode::empty(), not_mode: BindingMode::empty() }
+        Self {
+            mode: BindingMode::empty(),
+            not_mode: BindingMode::empty(),
+            custom: Default::default(),
+        }
     }
 }
 
@@ -798,7 +1031,8 @@ impl<'a> Deserialize<'a> for ModeWrapper {
 
             fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
                 f.write_str(
-                    "a combination of AppCursor | AppKeypad | Alt | Vi, possibly with negation (~)",
+                    "a combination of AppCursor | AppKeypad | Alt | Vi | Search or custom modes, \
+                     possibly with negation (~)",
                 )
             }
 
@@ -806,11 +1040,11 @@ impl<'a> Deserialize<'a> for ModeWrapper {
             where
                 E: de::Error,
             {
-                let mut res =
-                    ModeWrapper { mode: BindingMode::empty(), not_mode: BindingMode::empty() };
+                let mut res = ModeWrapper::default();
 
                 for modifier in value.split('|') {
-                    match modifier.trim().to_lowercase().as_str() {
+                    let modifier = modifier.trim();
+                    match modifier.to_lowercase().as_str() {
                         "appcursor" => res.mode |= BindingMode::APP_CURSOR,
                         "~appcursor" => res.not_mode |= BindingMode::APP_CURSOR,
                         "appkeypad" => res.mode |= BindingMode::APP_KEYPAD,
@@ -821,7 +1055,14 @@ impl<'a> Deserialize<'a> for ModeWrapper {
                         "~vi" => res.not_mode |= BindingMode::VI,
                         "search" => res.mode |= BindingMode::SEARCH,
                         "~search" => res.not_mode |= BindingMode::SEARCH,
-                        _ => return Err(E::invalid_value(Unexpected::Str(modifier), &self)),
+                        // Other names refer to modes defined in `keyboard.modes`.
+                        _ => match modifier.strip_prefix('~') {
+                            Some(name) if is_mode_name(name) => {
+                                res.custom.notmode.push(name.into())
+                            },
+                            None if is_mode_name(modifier) => res.custom.mode.push(modifier.into()),
+                            _ => return Err(E::invalid_value(Unexpected::Str(modifier), &self)),
+                        },
                     }
                 }
 
@@ -832,6 +1073,96 @@ impl<'a> Deserialize<'a> for ModeWrapper {
     }
 }
 
+/// Parse the action of a binding.
+///
+/// Lists of actions are only accepted when `allow_sequence` is set, to prevent nesting them.
+fn parse_action(value: SerdeValue, allow_sequence: bool) -> Result<Action, String> {
+    let value = match value {
+        SerdeValue::Array(values) if allow_sequence => {
+            let actions = values
+                .into_iter()
+                .enumerate()
+                .map(|(i, value)| {
+                    parse_action(value, false).map_err(|err| format!("action[{i}]: {err}"))
+                })
+                .collect::<Result<_, _>>()?;
+            return Ok(Action::Sequence(actions));
+        },
+        SerdeValue::Table(table) => return table_action(table),
+        value => value,
+    };
+
+    if let Ok(vi_action) = ViAction::deserialize(value.clone()) {
+        Ok(vi_action.into())
+    } else if let Ok(vi_motion) = SerdeViMotion::deserialize(value.clone()) {
+        Ok(vi_motion.0.into())
+    } else if let Ok(search_action) = SearchAction::deserialize(value.clone()) {
+        Ok(search_action.into())
+    } else if let Ok(mouse_action) = MouseAction::deserialize(value.clone()) {
+        Ok(mouse_action.into())
+    } else {
+        match (Action::deserialize(value.clone()), value) {
+            (Ok(action), _) => Ok(action),
+            (Err(_), SerdeValue::String(value)) => {
+                Err(format!("unknown keyboard action `{value}`"))
+            },
+            (Err(err), _) => Err(err.to_string()),
+        }
+    }
+}
+
+/// Parse an action with an argument, like `{ chars = "ls\n" }` or `{ EnterMode = "resize" }`.
+fn table_action(table: toml::Table) -> Result<Action, String> {
+    let mut entries = table.into_iter();
+    let (action, value) = match (entries.next(), entries.next()) {
+        (Some(entry), None) => entry,
+        _ => return Err(String::from("expected a single action, like { chars = \"ls\\n\" }")),
+    };
+
+    let result = match action.to_lowercase().as_str() {
+        "chars" => String::deserialize(value).map(Action::Esc),
+        "command" => Program::deserialize(value).map(Action::Command),
+        "hint" => Hint::deserialize(value).map(|hint| Action::Hint(Rc::new(hint))),
+        "entermode" => return mode_name(value).map(Action::EnterMode),
+        "leavemode" => return mode_name(value).map(Action::LeaveMode),
+        "togglemode" => return mode_name(value).map(Action::ToggleMode),
+        "startmacrorecording" => String::deserialize(value).map(Action::StartMacroRecording),
+        "playmacro" => MacroPlayback::deserialize(value).and_then(|playback| match playback {
+            MacroPlayback::Register(register) => Ok(Action::PlayMacro(register, 1)),
+            MacroPlayback::Repeated { count, .. } if count.get() > MAX_MACRO_COUNT => {
+                Err(SerdeError::custom(format!("count is larger than {MAX_MACRO_COUNT}")))
+            },
+            MacroPlayback::Repeated { register, count } => {
+                Ok(Action::PlayMacro(register, count.get()))
+            },
+        }),
+        _ => return Err(format!("unknown keyboard action `{action}`")),
+    };
+
+    result.map_err(|err| format!("invalid {action}: {err}"))
+}
+
+/// Argument of the `PlayMacro` action, like `"a"` or `{ register = "a", count = 3 }`.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum MacroPlayback {
+    Register(String),
+    Repeated { register: String, count: NonZeroUsize },
+}
+
+/// Parse the name of a user-defined binding mode.
+fn mode_name(value: SerdeValue) -> Result<String, String> {
+    match value {
+        SerdeValue::String(name) if is_mode_name(&name) => Ok(name),
+        value => Err(format!("invalid binding mode {value}")),
+    }
+}
+
+/// Check if a string is a valid name for a user-defined binding mode.
+fn is_mode_name(name: &str) -> bool {
+    !name.is_empty() && name.chars().all(|c| c.is_alphanumeric() || c == '_' || c == '-')
+}
+
 struct MouseButtonWrapper(MouseButton);
 
 impl MouseButtonWrapper {
@@ -898,10 +1229,12 @@ impl<'a> Deserialize<'a> for MouseButtonWrapper {
 #[derive(PartialEq, Eq)]
 struct RawBinding {
     key: Option<BindingKey>,
+    prefix: Vec<ChordStep>,
     mouse: Option<MouseButton>,
     mods: ModifiersState,
     mode: BindingMode,
     notmode: BindingMode,
+    custom_modes: CustomModes,
     action: Action,
 }
 
@@ -914,6 +1247,8 @@ impl RawBinding {
                 action: self.action,
                 mode: self.mode,
                 notmode: self.notmode,
+                custom_modes: self.custom_modes,
+                prefix: self.prefix,
             })
         } else {
             Err(Box::new(self))
@@ -928,6 +1263,8 @@ impl RawBinding {
                 action: self.action,
                 mode: self.mode,
                 notmode: self.notmode,
+                custom_modes: self.custom_modes,
+                prefix: self.prefix,
             })
         } else {
             Err(Box::new(self))
@@ -940,10 +1277,12 @@ impl<'a> Deserialize<'a> for RawBinding {
     where
         D: Deserializer<'a>,
     {
-        const FIELDS: &[&str] = &["key", "mods", "mode", "action", "chars", "mouse", "command"];
+        const FIELDS: &[&str] =
+            &["key", "keys", "mods", "mode", "action", "chars", "mouse", "command"];
 
         enum Field {
             Key,
+            Keys,
             Mods,
             Mode,
             Action,
@@ -972,6 +1311,7 @@ impl<'a> Deserialize<'a> for RawBinding {
                     {
                         match value.to_ascii_lowercase().as_str() {
                             "key" => Ok(Field::Key),
+                            "keys" => Ok(Field::Keys),
                             "mods" => Ok(Field::Mods),
                             "mode" => Ok(Field::Mode),
                             "action" => Ok(Field::Action),
@@ -1001,10 +1341,12 @@ impl<'a> Deserialize<'a> for RawBinding {
             {
                 let mut mods: Option<ModifiersState> = None;
                 let mut key: Option<BindingKey> = None;
+                let mut keys: Option<(Vec<ChordStep>, ChordKey)> = None;
                 let mut chars: Option<String> = None;
                 let mut action: Option<Action> = None;
                 let mut mode: Option<BindingMode> = None;
                 let mut not_mode: Option<BindingMode> = None;
+                let mut custom_modes: Option<CustomModes> = None;
                 let mut mouse: Option<MouseButton> = None;
                 let mut command: Option<Program> = None;
 
@@ -1038,6 +1380,14 @@ impl<'a> Deserialize<'a> for RawBinding {
                                 },
                             }
                         },
+                        Field::Keys => {
+                            if keys.is_some() {
+                                return Err(<V::Error as Error>::duplicate_field("keys"));
+                            }
+
+                            let value = map.next_value::<String>()?;
+                            keys = Some(parse_key_sequence(&value).map_err(V::Error::custom)?);
+                        },
                         Field::Mods => {
                             if mods.is_some() {
                                 return Err(<V::Error as Error>::duplicate_field("mods"));
@@ -1053,6 +1403,7 @@ impl<'a> Deserialize<'a> for RawBinding {
                             let mode_deserializer = map.next_value::<ModeWrapper>()?;
                             mode = Some(mode_deserializer.mode);
                             not_mode = Some(mode_deserializer.not_mode);
+                            custom_modes = Some(mode_deserializer.custom);
                         },
                         Field::Action => {
                             if action.is_some() {
@@ -1060,33 +1411,7 @@ impl<'a> Deserialize<'a> for RawBinding {
                             }
 
                             let value = map.next_value::<SerdeValue>()?;
-
-                            action = if let Ok(vi_action) = ViAction::deserialize(value.clone()) {
-                                Some(vi_action.into())
-                            } else if let Ok(vi_motion) = SerdeViMotion::deserialize(value.clone())
-                            {
-                                Some(vi_motion.0.into())
-                            } else if let Ok(search_action) =
-                                SearchAction::deserialize(value.clone())
-                            {
-                                Some(search_action.into())
-                            } else if let Ok(mouse_action) = MouseAction::deserialize(value.clone())
-                            {
-                                Some(mouse_action.into())
-                            } else {
-                                match Action::deserialize(value.clone()).map_err(V::Error::custom) {
-                                    Ok(action) => Some(action),
-                                    Err(err) => {
-                                        let value = match value {
-                                            SerdeValue::String(string) => string,
-                                            _ => return Err(err),
-                                        };
-                                        return Err(V::Error::custom(format!(
-                                            "unknown keyboard action `{value}`"
-                                        )));
-                                    },
-                                }
-                            };
+                            action = Some(parse_action(value, true).map_err(V::Error::custom)?);
                         },
                         Field::Chars => {
                             if chars.is_some() {
@@ -1114,21 +1439,39 @@ impl<'a> Deserialize<'a> for RawBinding {
 
                 let mode = mode.unwrap_or_else(BindingMode::empty);
                 let not_mode = not_mode.unwrap_or_else(BindingMode::empty);
-                let mods = mods.unwrap_or_default();
+                let custom_modes = custom_modes.unwrap_or_default();
+                let mut mods = mods.unwrap_or_default();
+
+                // Key sequences replace the binding's key and modifiers.
+                let mut prefix = Vec::new();
+                if let Some((steps, last)) = keys {
+                    if key.is_some() || mods != ModifiersState::empty() || mouse.is_some() {
+                        return Err(V::Error::custom(
+                            "keys can't be combined with key, mods or mouse",
+                        ));
+                    }
+
+                    prefix = steps;
+                    key = Some(last.key);
+                    mods = last.mods;
+                }
 
                 let action = match (action, chars, command) {
                     (Some(action @ Action::ViMotion(_)), None, None)
                     | (Some(action @ Action::Vi(_)), None, None) => action,
                     (Some(action @ Action::Search(_)), None, None) => action,
-                    (Some(action @ Action::Mouse(_)), None, None) => {
-                        if mouse.is_none() {
+                    (Some(action), None, None) => {
+                        let mouse_action = action
+                            .actions()
+                            .iter()
+                            .find(|action| matches!(action, Action::Mouse(_)));
+                        if let (Some(mouse_action), None) = (mouse_action, mouse) {
                             return Err(V::Error::custom(format!(
-                                "action `{action}` is only available for mouse bindings",
+                                "action `{mouse_action}` is only available for mouse bindings",
                             )));
                         }
                         action
                     },
-                    (Some(action), None, None) => action,
                     (None, Some(chars), None) => Action::Esc(chars),
                     (None, None, Some(cmd)) => Action::Command(cmd),
                     _ => {
@@ -1142,7 +1485,16 @@ impl<'a> Deserialize<'a> for RawBinding {
                     return Err(V::Error::custom("bindings require mouse button or key"));
                 }
 
-                Ok(RawBinding { mode, notmode: not_mode, action, key, mouse, mods })
+                Ok(RawBinding {
+                    mode,
+                    notmode: not_mode,
+                    custom_modes,
+                    action,
+                    key,
+                    prefix,
+                    mouse,
+                    mods,
+                })
             }
         }
 
@@ -1253,7 +1605,9 @@ mod tests {
                 action: Action::None,
                 mode: BindingMode::empty(),
                 notmode: BindingMode::empty(),
+                custom_modes: Default::default(),
                 trigger: Default::default(),
+                prefix: Default::default(),
             }
         }
     }
@@ -1443,4 +1797,115 @@ mod tests {
         assert!(!binding.is_triggered_by(BindingMode::ALT_SCREEN, mods, &t));
         assert!(!binding.is_triggered_by(BindingMode::ALT_SCREEN | BindingMode::VI, mods, &t));
     }
+
+    #[test]
+    fn key_sequence_binding() {
+        let binding: KeyBinding =
+            toml::from_str("keys = \"Control+Shift+A, Leader, c\"\naction = \"Copy\"").unwrap();
+
+        let key = |key: &str| BindingKey::Keycode {
+            key: Key::Character(key.into()),
+            location: KeyLocation::Any,
+        };
+        let ctrl_shift_a =
+            ChordKey { key: key("a"), mods: ModifiersState::CONTROL | ModifiersState::SHIFT };
+        assert_eq!(binding.prefix, [ChordStep::Key(ctrl_shift_a), ChordStep::Leader]);
+        assert_eq!(binding.trigger, key("c"));
+        assert_eq!(binding.mods, ModifiersState::empty());
+
+        let plus = ChordKey::parse("Control++").unwrap();
+        assert_eq!(plus.key, BindingKey::Keycode {
+            key: Key::Character("+".into()),
+            location: KeyLocation::Any,
+        });
+        assert_eq!(plus.mods, ModifiersState::CONTROL);
+
+        assert!(parse_key_sequence("c").is_err());
+        assert!(parse_key_sequence("c, Leader").is_err());
+        assert!(parse_key_sequence("Hyper+a, c").is_err());
+        assert!(
+            toml::from_str::<KeyBinding>("keys = \"a, b\"\nkey = \"c\"\nchars = \"x\"").is_err()
+        );
+    }
+
+    #[test]
+    fn custom_mode_binding() {
+        let binding: KeyBinding = toml::from_str(
+            "key = \"H\"\nmode = \"resize | ~Vi | ~scroll\"\naction = { LeaveMode = \"resize\" }",
+        )
+        .unwrap();
+
+        assert_eq!(binding.notmode, BindingMode::VI);
+        assert_eq!(binding.custom_modes.mode, ["resize"]);
+        assert_eq!(binding.custom_modes.notmode, ["scroll"]);
+        assert_eq!(binding.action, Action::LeaveMode("resize".into()));
+
+        assert!(!binding.custom_modes.matches(&[]));
+        assert!(binding.custom_modes.matches(&["resize".into()]));
+        assert!(!binding.custom_modes.matches(&["resize".into(), "scroll".into()]));
+
+        let mut other = binding.clone();
+        other.custom_modes = CustomModes { mode: vec!["scroll".into()], notmode: Vec::new() };
+        assert!(!binding.triggers_match(&other));
+        other.custom_modes = CustomModes::default();
+        assert!(binding.triggers_match(&other));
+
+        assert!(
+            toml::from_str::<KeyBinding>("key = \"H\"\naction = { EnterMode = \"\" }").is_err()
+        );
+        assert!(toml::from_str::<KeyBinding>("key = \"H\"\naction = { Copy = \"a\" }").is_err());
+    }
+
+    #[test]
+    fn action_sequence_binding() {
+        let binding: KeyBinding = toml::from_str(
+            "key = \"C\"\naction = [\"ClearSelection\", \"ScrollToBottom\", { chars = \
+             \"clear\\n\" }, { command = \"true\" }, \"Left\", { hint = { regex = \"a+\", action \
+             = \"Copy\" } }]",
+        )
+        .unwrap();
+
+        let actions = binding.action.actions();
+        assert_eq!(actions.len(), 6);
+        assert_eq!(actions[..3], [
+            Action::ClearSelection,
+            Action::ScrollToBottom,
+            Action::Esc("clear\n".into())
+        ]);
+        assert_eq!(actions[3], Action::Command(Program::Just("true".into())));
+        assert_eq!(actions[4], Action::ViMotion(ViMotion::Left));
+        assert!(matches!(actions[5], Action::Hint(_)));
+
+        let error = |action: &str| {
+            let binding = format!("key = \"C\"\naction = {action}");
+            toml::from_str::<KeyBinding>(&binding).unwrap_err().message().to_owned()
+        };
+        assert_eq!(error("[\"Copy\", \"Cpy\"]"), "action[1]: unknown keyboard action `Cpy`");
+        assert!(error("[\"Copy\", [\"Paste\"]]").starts_with("action[1]: "));
+        assert_eq!(
+            error("[\"Copy\", \"ExpandSelection\"]"),
+            "action `ExpandSelection` is only available for mouse bindings"
+        );
+    }
+
+    #[test]
+    fn macro_actions() {
+        let action = |action: &str| {
+            let binding = format!("key = \"M\"\naction = {action}");
+            toml::from_str::<KeyBinding>(&binding).map(|binding| binding.action)
+        };
+
+        assert_eq!(
+            action("{ StartMacroRecording = \"a\" }").unwrap(),
+            Action::StartMacroRecording("a".into())
+        );
+        assert_eq!(action("\"StopMacroRecording\"").unwrap(), Action::StopMacroRecording);
+        assert_eq!(action("{ PlayMacro = \"a\" }").unwrap(), Action::PlayMacro("a".into(), 1));
+        assert_eq!(
+            action("{ PlayMacro = { register = \"a\", count = 3 } }").unwrap(),
+            Action::PlayMacro("a".into(), 3)
+        );
+        assert!(action("{ PlayMacro = { register = \"a\", count = 0 } }").is_err());
+        assert!(action("{ PlayMacro = { register = \"a\", count = 1001 } }").is_err());
+    }
 }
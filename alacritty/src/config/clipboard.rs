@@ -0,0 +1,21 @@
+use serde::Serialize;
+
+use alacritty_config_derive::ConfigDeserialize;
+
+#[derive(ConfigDeserialize, Serialize, Clone, Debug, PartialEq, Eq)]
+pub struct ClipboardConfig {
+    /// Maximum number of copied texts kept in the history.
+    pub history: usize,
+
+    /// Add text copied by applications using OSC 52 to the history.
+    pub history_osc52: bool,
+
+    /// Add selected text to the history, without explicitly copying it.
+    pub history_selection: bool,
+}
+
+impl Default for ClipboardConfig {
+    fn default() -> Self {
+        Self { history: 50, history_osc52: true, history_selection: false }
+    }
+}
@@ -57,4 +57,7 @@ pub enum RendererPreference {
 
     /// Pure GLES 2 renderer.
     Gles2Pure,
+
+    /// Renderer drawing on the CPU, without OpenGL.
+    Software,
 }
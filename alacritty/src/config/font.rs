@@ -1,11 +1,18 @@
 use std::fmt;
+use std::ops::RangeInclusive;
+use std::str::FromStr;
 
 use crossfont::Size as FontSize;
+#[cfg(any(target_os = "macos", windows))]
+use log::error;
+use rustybuzz::Feature;
 use serde::de::{self, Visitor};
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
 
 use alacritty_config_derive::{ConfigDeserialize, SerdeReplace};
 
+#[cfg(any(target_os = "macos", windows))]
+use crate::config::LOG_TARGET_CONFIG;
 use crate::config::ui_config::Delta;
 
 /// Font config.
@@ -42,6 +49,18 @@ pub struct Font {
 
     /// Whether to use the built-in font for box drawing characters.
     pub builtin_box_drawing: bool,
+
+    /// Whether to shape text to render font ligatures.
+    pub ligatures: bool,
+
+    /// OpenType features applied when shaping text.
+    pub features: Vec<FontFeature>,
+
+    /// Fonts used for specific codepoint ranges.
+    pub symbol_map: Vec<SymbolMap>,
+
+    /// Fonts tried before the system fallback fonts.
+    pub fallback: Vec<FontDescription>,
 }
 
 impl Font {
@@ -74,6 +93,27 @@ impl Font {
     pub fn bold_italic(&self) -> FontDescription {
         self.bold_italic.desc(&self.normal)
     }
+
+    /// Reset options which are not supported on this platform.
+    pub fn remove_unsupported(&mut self) {
+        #[cfg(any(target_os = "macos", windows))]
+        if self.ligatures {
+            error!(
+                target: LOG_TARGET_CONFIG,
+                "Config error: font.ligatures is only supported on Linux/BSD"
+            );
+            self.ligatures = false;
+        }
+
+        #[cfg(any(target_os = "macos", windows))]
+        if !self.fallback.is_empty() {
+            error!(
+                target: LOG_TARGET_CONFIG,
+                "Config error: font.fallback is only supported on Linux/BSD"
+            );
+            self.fallback.clear();
+        }
+    }
 }
 
 impl Default for Font {
@@ -81,6 +121,10 @@ impl Default for Font {
         Self {
             builtin_box_drawing: true,
             glyph_offset: Default::default(),
+            ligatures: Default::default(),
+            features: Default::default(),
+            symbol_map: Default::default(),
+            fallback: Default::default(),
             use_thin_strokes: Default::default(),
             bold_italic: Default::default(),
             italic: Default::default(),
@@ -129,6 +173,114 @@ impl SecondaryFontDescription {
     }
 }
 
+/// OpenType feature, like `"ss01"` or `"-calt"`.
+#[derive(SerdeReplace, Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct FontFeature(String);
+
+impl FontFeature {
+    pub fn feature(&self) -> Feature {
+        // Features are validated during deserialization.
+        Feature::from_str(&self.0).unwrap()
+    }
+}
+
+impl<'de> Deserialize<'de> for FontFeature {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let feature = String::deserialize(deserializer)?;
+        match Feature::from_str(&feature) {
+            Ok(_) => Ok(Self(feature)),
+            Err(err) => Err(de::Error::custom(format!("invalid font feature {feature:?}: {err}"))),
+        }
+    }
+}
+
+/// Font used for a set of codepoint ranges.
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct SymbolMap {
+    /// Codepoint ranges rendered with this font.
+    pub ranges: Vec<CodepointRange>,
+
+    /// Font family.
+    pub family: String,
+
+    /// Font style.
+    #[serde(default)]
+    pub style: Option<String>,
+}
+
+impl SymbolMap {
+    pub fn desc(&self) -> FontDescription {
+        FontDescription { family: self.family.clone(), style: self.style.clone() }
+    }
+
+    /// Check if the character is part of any of the ranges.
+    pub fn contains(&self, c: char) -> bool {
+        self.ranges.iter().any(|range| range.0.contains(&c))
+    }
+}
+
+/// Inclusive range of Unicode codepoints, like `"U+E000-U+F8FF"` or `"U+2713"`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CodepointRange(RangeInclusive<char>);
+
+impl FromStr for CodepointRange {
+    type Err = String;
+
+    fn from_str(range: &str) -> Result<Self, Self::Err> {
+        let codepoint = |codepoint: &str| {
+            let hex = codepoint
+                .strip_prefix("U+")
+                .or_else(|| codepoint.strip_prefix("u+"))
+                .ok_or_else(|| format!("codepoint {codepoint:?} must start with \"U+\""))?;
+            u32::from_str_radix(hex, 16)
+                .ok()
+                .and_then(char::from_u32)
+                .ok_or_else(|| format!("invalid codepoint {codepoint:?}"))
+        };
+
+        let (start, end) = match range.split_once('-') {
+            Some((start, end)) => (codepoint(start.trim())?, codepoint(end.trim())?),
+            None => {
+                let codepoint = codepoint(range.trim())?;
+                (codepoint, codepoint)
+            },
+        };
+
+        if start > end {
+            return Err(format!("codepoint range {range:?} ends before it starts"));
+        }
+
+        Ok(Self(start..=end))
+    }
+}
+
+impl<'de> Deserialize<'de> for CodepointRange {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let range = String::deserialize(deserializer)?;
+        Self::from_str(&range).map_err(de::Error::custom)
+    }
+}
+
+impl Serialize for CodepointRange {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let (start, end) = (*self.0.start() as u32, *self.0.end() as u32);
+        if start == end {
+            serializer.serialize_str(&format!("U+{start:04X}"))
+        } else {
+            serializer.serialize_str(&format!("U+{start:04X}-U+{end:04X}"))
+        }
+    }
+}
+
 #[derive(SerdeReplace, Debug, Clone, PartialEq, Eq)]
 struct Size(FontSize);
 
@@ -172,3 +324,37 @@ impl Serialize for Size {
         serializer.serialize_f32(self.0.as_pt())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn codepoint_range() {
+        let range = CodepointRange::from_str("U+E000-U+F8FF").unwrap();
+        assert_eq!(range.0, '\u{e000}'..='\u{f8ff}');
+
+        let range = CodepointRange::from_str("u+2713").unwrap();
+        assert_eq!(range.0, '\u{2713}'..='\u{2713}');
+
+        assert!(CodepointRange::from_str("E000").is_err());
+        assert!(CodepointRange::from_str("U+F8FF-U+E000").is_err());
+        assert!(CodepointRange::from_str("U+D800").is_err());
+    }
+
+    #[test]
+    fn symbol_map() {
+        let symbols: SymbolMap = toml::from_str(
+            r#"
+            ranges = ["U+E0A0-U+E0A3", "U+2713"]
+            family = "Symbols Nerd Font Mono"
+            "#,
+        )
+        .unwrap();
+
+        assert!(symbols.contains('\u{e0a1}'));
+        assert!(symbols.contains('✓'));
+        assert!(!symbols.contains('a'));
+        assert_eq!(symbols.desc().style, None);
+    }
+}
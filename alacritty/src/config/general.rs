@@ -1,5 +1,8 @@
 //! Miscellaneous configuration options.
 
+#[cfg(windows)]
+use std::fs;
+use std::io;
 use std::path::PathBuf;
 
 use serde::Serialize;
@@ -21,12 +24,19 @@ pub struct General {
     /// Shell startup directory.
     pub working_directory: Option<PathBuf>,
 
+    /// Directory for screenshots and saved scrollback history.
+    pub save_directory: Option<PathBuf>,
+
     /// Live config reload.
     pub live_config_reload: bool,
 
     /// Offer IPC through a unix socket.
     #[allow(unused)]
     pub ipc_socket: bool,
+
+    /// Keep the terminals of closed windows running in daemon mode.
+    #[allow(unused)]
+    pub detach_on_close: bool,
 }
 
 impl Default for General {
@@ -34,8 +44,36 @@ impl Default for General {
         Self {
             live_config_reload: true,
             ipc_socket: true,
+            detach_on_close: Default::default(),
             working_directory: Default::default(),
+            save_directory: Default::default(),
             import: Default::default(),
         }
     }
 }
+
+impl General {
+    /// Directory for saved files, creating Alacritty's state directory when none is configured.
+    pub fn save_directory(&self) -> io::Result<PathBuf> {
+        match &self.save_directory {
+            Some(directory) => Ok(directory.clone()),
+            None => state_directory(),
+        }
+    }
+}
+
+/// Create Alacritty's directory for persistent state.
+#[cfg(not(windows))]
+fn state_directory() -> io::Result<PathBuf> {
+    xdg::BaseDirectories::with_prefix("alacritty").create_state_directory("")
+}
+
+/// Create Alacritty's directory for persistent state.
+#[cfg(windows)]
+fn state_directory() -> io::Result<PathBuf> {
+    let directory = dirs::data_local_dir()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no local data directory"))?
+        .join("alacritty");
+    fs::create_dir_all(&directory)?;
+    Ok(directory)
+}
@@ -0,0 +1,11 @@
+use std::path::PathBuf;
+
+use serde::Serialize;
+
+use alacritty_config_derive::ConfigDeserialize;
+
+#[derive(ConfigDeserialize, Serialize, Default, Clone, Debug, PartialEq, Eq)]
+pub struct MacrosConfig {
+    /// File the macro registers are persisted to.
+    pub file: Option<PathBuf>,
+}
@@ -11,15 +11,20 @@ use toml::ser::Error as TomlSeError;
 use toml::{Table, Value};
 
 pub mod bell;
+pub mod clipboard;
 pub mod color;
 pub mod cursor;
 pub mod debug;
 pub mod font;
 pub mod general;
+pub mod macros;
 pub mod monitor;
+pub mod notifications;
+pub mod paste;
 pub mod scrolling;
 pub mod selection;
 pub mod serde_utils;
+pub mod session;
 pub mod terminal;
 pub mod ui_config;
 pub mod window;
@@ -31,7 +36,8 @@ use crate::cli::Options;
 #[cfg(test)]
 pub use crate::config::bindings::Binding;
 pub use crate::config::bindings::{
-    Action, BindingKey, BindingMode, KeyBinding, MouseAction, SearchAction, ViAction,
+    Action, BindingKey, BindingMode, ChordKey, ChordStep, KeyBinding, MouseAction, SearchAction,
+    ViAction,
 };
 pub use crate::config::ui_config::UiConfig;
 use crate::logging::LOG_TARGET_CONFIG;
@@ -162,6 +168,11 @@ pub fn reload(config_path: &Path, options: &mut Options) -> Result<UiConfig> {
 fn after_loading(config: &mut UiConfig, options: &mut Options) {
     // Override config with CLI options.
     options.override_config(config);
+
+    // Drop bindings which can never be triggered.
+    config.validate_binding_modes();
+
+    config.font.remove_unsupported();
 }
 
 /// Load configuration file and log errors.
@@ -26,6 +26,10 @@ impl<'de> Deserialize<'de> for MouseBindings {
     where
         D: Deserializer<'de>,
     {
-        Ok(Self(ui_config::deserialize_bindings(deserializer, Self::default().0)?))
+        Ok(Self(ui_config::deserialize_bindings(
+            deserializer,
+            "mouse.bindings",
+            Self::default().0,
+        )?))
     }
 }
@@ -0,0 +1,25 @@
+use serde::Serialize;
+
+use alacritty_config_derive::ConfigDeserialize;
+
+use crate::config::ui_config::Program;
+
+#[derive(ConfigDeserialize, Serialize, Clone, Debug, PartialEq, Eq)]
+pub struct NotificationsConfig {
+    /// Command to run for desktop notifications.
+    ///
+    /// The notification's title and body are appended as the last two arguments.
+    pub command: Option<Program>,
+
+    /// Set the window urgency hint for notifications while unfocused.
+    pub urgency_hint: bool,
+
+    /// Maximum number of notifications per minute.
+    pub rate_limit: u8,
+}
+
+impl Default for NotificationsConfig {
+    fn default() -> Self {
+        Self { command: Default::default(), urgency_hint: Default::default(), rate_limit: 10 }
+    }
+}
@@ -0,0 +1,29 @@
+use serde::Serialize;
+
+use alacritty_config_derive::ConfigDeserialize;
+
+#[derive(ConfigDeserialize, Serialize, Clone, Debug, PartialEq, Eq)]
+pub struct PasteConfig {
+    /// Confirm pastes with newlines or control characters without bracketed paste.
+    pub confirm_unsafe: bool,
+
+    /// Confirm pastes larger than this number of bytes.
+    pub confirm_size: usize,
+
+    /// Remove escape sequences from pasted text.
+    pub strip_escapes: bool,
+
+    /// Remove newlines at the end of pasted text.
+    pub strip_trailing_newlines: bool,
+}
+
+impl Default for PasteConfig {
+    fn default() -> Self {
+        Self {
+            confirm_unsafe: true,
+            confirm_size: 1024 * 1024,
+            strip_escapes: Default::default(),
+            strip_trailing_newlines: Default::default(),
+        }
+    }
+}
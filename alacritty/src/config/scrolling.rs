@@ -1,10 +1,13 @@
-use serde::de::Error as SerdeError;
-use serde::{Deserialize, Deserializer, Serialize};
+use std::fmt::{self, Formatter};
+
+use serde::de::{self, Unexpected, Visitor};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 
 use alacritty_config_derive::{ConfigDeserialize, SerdeReplace};
+use alacritty_terminal::grid::UNLIMITED_HISTORY;
 
 /// Maximum scrollback amount configurable.
-pub const MAX_SCROLLBACK_LINES: u32 = 100_000;
+pub const MAX_SCROLLBACK_LINES: u32 = 10_000_000;
 
 /// Struct for scrolling related settings.
 #[derive(ConfigDeserialize, Serialize, Copy, Clone, Debug, PartialEq, Eq)]
@@ -21,17 +24,35 @@ impl Default for Scrolling {
 }
 
 impl Scrolling {
-    pub fn history(self) -> u32 {
-        self.history.0
+    pub fn history(self) -> usize {
+        match self.history {
+            ScrollingHistory::Lines(lines) => lines as usize,
+            ScrollingHistory::Unlimited => UNLIMITED_HISTORY,
+        }
     }
 }
 
-#[derive(SerdeReplace, Serialize, Copy, Clone, Debug, PartialEq, Eq)]
-struct ScrollingHistory(u32);
+#[derive(SerdeReplace, Copy, Clone, Debug, PartialEq, Eq)]
+enum ScrollingHistory {
+    Lines(u32),
+    Unlimited,
+}
 
 impl Default for ScrollingHistory {
     fn default() -> Self {
-        Self(10_000)
+        Self::Lines(10_000)
+    }
+}
+
+impl Serialize for ScrollingHistory {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match self {
+            Self::Lines(lines) => serializer.serialize_u32(*lines),
+            Self::Unlimited => serializer.serialize_str("unlimited"),
+        }
     }
 }
 
@@ -40,14 +61,70 @@ impl<'de> Deserialize<'de> for ScrollingHistory {
     where
         D: Deserializer<'de>,
     {
-        let lines = u32::deserialize(deserializer)?;
-
-        if lines > MAX_SCROLLBACK_LINES {
-            Err(SerdeError::custom(format!(
-                "exceeded maximum scrolling history ({lines}/{MAX_SCROLLBACK_LINES})"
-            )))
-        } else {
-            Ok(Self(lines))
+        struct ScrollingHistoryVisitor;
+
+        impl Visitor<'_> for ScrollingHistoryVisitor {
+            type Value = ScrollingHistory;
+
+            fn expecting(&self, f: &mut Formatter<'_>) -> fmt::Result {
+                f.write_str("a number of lines or \"unlimited\"")
+            }
+
+            fn visit_i64<E>(self, value: i64) -> Result<ScrollingHistory, E>
+            where
+                E: de::Error,
+            {
+                match u64::try_from(value) {
+                    Ok(value) => self.visit_u64(value),
+                    Err(_) => Err(E::invalid_value(Unexpected::Signed(value), &self)),
+                }
+            }
+
+            fn visit_u64<E>(self, lines: u64) -> Result<ScrollingHistory, E>
+            where
+                E: de::Error,
+            {
+                if lines > MAX_SCROLLBACK_LINES as u64 {
+                    Err(E::custom(format!(
+                        "exceeded maximum scrolling history ({lines}/{MAX_SCROLLBACK_LINES})"
+                    )))
+                } else {
+                    Ok(ScrollingHistory::Lines(lines as u32))
+                }
+            }
+
+            fn visit_str<E>(self, value: &str) -> Result<ScrollingHistory, E>
+            where
+                E: de::Error,
+            {
+                match value {
+                    "unlimited" => Ok(ScrollingHistory::Unlimited),
+                    _ => Err(E::invalid_value(Unexpected::Str(value), &self)),
+                }
+            }
         }
+
+        deserializer.deserialize_any(ScrollingHistoryVisitor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unlimited_history() {
+        let scrolling: Scrolling = toml::from_str("history = \"unlimited\"").unwrap();
+        assert_eq!(scrolling.history(), UNLIMITED_HISTORY);
+
+        let scrolling: Scrolling = toml::from_str("history = 1000").unwrap();
+        assert_eq!(scrolling.history(), 1000);
+
+        // Invalid values fall back to the default.
+        let scrolling: Scrolling = toml::from_str("history = \"infinite\"").unwrap();
+        assert_eq!(scrolling.history(), 10_000);
+
+        let scrolling: Scrolling = toml::from_str("history = -1").unwrap();
+        assert_eq!(scrolling.history(), 10_000);
     }
 }
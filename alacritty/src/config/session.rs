@@ -0,0 +1,9 @@
+use serde::Serialize;
+
+use alacritty_config_derive::ConfigDeserialize;
+
+#[derive(ConfigDeserialize, Serialize, Default, Clone, Debug, PartialEq, Eq)]
+pub struct SessionConfig {
+    /// Restore windows and their scrollback from the previous session.
+    pub restore: bool,
+}
@@ -5,6 +5,7 @@ use std::fmt::{self, Formatter};
 use std::mem;
 use std::path::PathBuf;
 use std::rc::Rc;
+use std::time::Duration;
 
 use log::{error, warn};
 use serde::de::{Error as SerdeError, MapAccess, Visitor};
@@ -21,17 +22,22 @@ use alacritty_terminal::tty::{Options as PtyOptions, Shell};
 use crate::config::LOG_TARGET_CONFIG;
 use crate::config::bell::BellConfig;
 use crate::config::bindings::{
-    self, Action, Binding, BindingKey, KeyBinding, KeyLocation, ModeWrapper, ModsWrapper,
+    self, Action, Binding, BindingKey, ChordKey, KeyBinding, KeyLocation, ModeWrapper, ModsWrapper,
     MouseBinding,
 };
+use crate::config::clipboard::ClipboardConfig;
 use crate::config::color::Colors;
 use crate::config::cursor::Cursor;
 use crate::config::debug::Debug;
 use crate::config::font::Font;
 use crate::config::general::General;
+use crate::config::macros::MacrosConfig;
 use crate::config::mouse::Mouse;
+use crate::config::notifications::NotificationsConfig;
+use crate::config::paste::PasteConfig;
 use crate::config::scrolling::Scrolling;
 use crate::config::selection::Selection;
+use crate::config::session::SessionConfig;
 use crate::config::terminal::Terminal;
 use crate::config::window::WindowConfig;
 
@@ -57,6 +63,12 @@ pub struct UiConfig {
     /// Selection configuration.
     pub selection: Selection,
 
+    /// Clipboard history configuration.
+    pub clipboard: ClipboardConfig,
+
+    /// Paste safety configuration.
+    pub paste: PasteConfig,
+
     /// Font configuration.
     pub font: Font,
 
@@ -72,6 +84,15 @@ pub struct UiConfig {
     /// Bell configuration.
     pub bell: BellConfig,
 
+    /// Desktop notification configuration.
+    pub notifications: NotificationsConfig,
+
+    /// Session persistence configuration.
+    pub session: SessionConfig,
+
+    /// Keyboard macro configuration.
+    pub macros: MacrosConfig,
+
     /// RGB values for colors.
     pub colors: Colors,
 
@@ -119,7 +140,7 @@ impl UiConfig {
     pub fn term_options(&self) -> TermConfig {
         TermConfig {
             semantic_escape_chars: self.selection.semantic_escape_chars.clone(),
-            scrolling_history: self.scrolling.history() as usize,
+            scrolling_history: self.scrolling.history(),
             vi_mode_cursor_style: self.cursor.vi_mode_style(),
             default_cursor_style: self.cursor.style(),
             osc52: self.terminal.osc52.0,
@@ -152,11 +173,32 @@ impl UiConfig {
         &self.keyboard.bindings.0
     }
 
+    /// Key used for `Leader` in key sequences.
+    #[inline]
+    pub fn leader(&self) -> Option<&ChordKey> {
+        self.keyboard.leader.as_ref()
+    }
+
+    /// Time to wait for the next key of a key sequence.
+    ///
+    /// Returns `None` when key sequences never time out.
+    #[inline]
+    pub fn chord_timeout(&self) -> Option<Duration> {
+        Some(self.keyboard.chord_timeout).filter(|timeout| *timeout != 0).map(Duration::from_millis)
+    }
+
     #[inline]
     pub fn mouse_bindings(&self) -> &[MouseBinding] {
         &self.mouse.bindings.0
     }
 
+    /// Remove bindings using binding modes which were not declared in `keyboard.modes`.
+    pub fn validate_binding_modes(&mut self) {
+        let modes = &self.keyboard.modes;
+        retain_declared_modes(&mut self.keyboard.bindings.0, modes);
+        retain_declared_modes(&mut self.mouse.bindings.0, modes);
+    }
+
     #[inline]
     pub fn live_config_reload(&self) -> bool {
         self.live_config_reload.unwrap_or(self.general.live_config_reload)
@@ -170,11 +212,32 @@ impl UiConfig {
 }
 
 /// Keyboard configuration.
-#[derive(ConfigDeserialize, Serialize, Default, Clone, Debug, PartialEq)]
+#[derive(ConfigDeserialize, Serialize, Clone, Debug, PartialEq)]
 struct Keyboard {
     /// Keybindings.
     #[serde(skip_serializing)]
     bindings: KeyBindings,
+
+    /// Key which can be used as `Leader` in key sequences.
+    #[serde(skip_serializing)]
+    leader: Option<ChordKey>,
+
+    /// Time in milliseconds to wait for the next key of a key sequence.
+    chord_timeout: u64,
+
+    /// Names of the user-defined binding modes.
+    modes: Vec<String>,
+}
+
+impl Default for Keyboard {
+    fn default() -> Self {
+        Self {
+            bindings: Default::default(),
+            leader: Default::default(),
+            chord_timeout: 1000,
+            modes: Default::default(),
+        }
+    }
 }
 
 #[derive(SerdeReplace, Clone, Debug, PartialEq, Eq)]
@@ -191,12 +254,47 @@ impl<'de> Deserialize<'de> for KeyBindings {
     where
         D: Deserializer<'de>,
     {
-        Ok(Self(deserialize_bindings(deserializer, Self::default().0)?))
+        Ok(Self(deserialize_bindings(deserializer, "keyboard.bindings", Self::default().0)?))
     }
 }
 
+/// Remove bindings using undeclared binding modes, with a config error for each of them.
+fn retain_declared_modes<T: fmt::Debug>(bindings: &mut Vec<Binding<T>>, modes: &[String]) {
+    let is_declared = |mode: &String| modes.contains(mode);
+
+    bindings.retain(|binding| {
+        if let Some(mode) = binding.custom_modes.iter().find(|mode| !is_declared(mode)) {
+            error!(
+                target: LOG_TARGET_CONFIG,
+                "Config error: binding mode `{mode}` of {:?} is not declared in keyboard.modes",
+                binding.trigger,
+            );
+            return false;
+        }
+
+        for action in binding.action.actions() {
+            let (Action::EnterMode(mode) | Action::LeaveMode(mode) | Action::ToggleMode(mode)) =
+                action
+            else {
+                continue;
+            };
+
+            if !is_declared(mode) {
+                warn!(
+                    target: LOG_TARGET_CONFIG,
+                    "Config warning: binding mode `{mode}` of {action} is not declared in \
+                     keyboard.modes",
+                );
+            }
+        }
+
+        true
+    });
+}
+
 pub fn deserialize_bindings<'a, D, T>(
     deserializer: D,
+    section: &str,
     mut default: Vec<Binding<T>>,
 ) -> Result<Vec<Binding<T>>, D::Error>
 where
@@ -208,11 +306,14 @@ where
 
     // Skip all invalid values.
     let mut bindings = Vec::with_capacity(values.len());
-    for value in values {
+    for (i, value) in values.into_iter().enumerate() {
         match Binding::<T>::deserialize(value) {
             Ok(binding) => bindings.push(binding),
             Err(err) => {
-                error!(target: LOG_TARGET_CONFIG, "Config error: {err}; ignoring binding");
+                error!(
+                    target: LOG_TARGET_CONFIG,
+                    "Config error: {section}[{i}]: {err}; ignoring binding"
+                );
             },
         }
     }
@@ -474,7 +575,9 @@ impl HintBinding {
             mods: self.mods.0,
             mode: self.mode.mode,
             notmode: self.mode.not_mode,
+            custom_modes: self.mode.custom.clone(),
             action: Action::Hint(hint.clone()),
+            prefix: Vec::new(),
         })
     }
 }
@@ -1,7 +1,7 @@
 #[cfg(target_os = "openbsd")]
 use std::ffi::CStr;
 use std::ffi::OsStr;
-#[cfg(not(any(target_os = "macos", target_os = "openbsd", windows)))]
+#[cfg(not(any(target_os = "macos", windows)))]
 use std::fs;
 use std::io;
 #[cfg(windows)]
@@ -20,6 +20,8 @@ use {
     std::path::PathBuf,
 };
 
+#[cfg(not(windows))]
+use alacritty_terminal::term::WorkingDirectory;
 #[cfg(not(windows))]
 use libc::pid_t;
 #[cfg(windows)]
@@ -54,8 +56,7 @@ where
 pub fn spawn_daemon<I, S>(
     program: &str,
     args: I,
-    master_fd: RawFd,
-    shell_pid: u32,
+    working_directory: Option<PathBuf>,
 ) -> io::Result<()>
 where
     I: IntoIterator<Item = S> + Copy,
@@ -64,7 +65,6 @@ where
     let mut command = Command::new(program);
     command.args(args).stdin(Stdio::null()).stdout(Stdio::null()).stderr(Stdio::null());
 
-    let working_directory = foreground_process_path(master_fd, shell_pid).ok();
     unsafe {
         command
             .pre_exec(move || {
@@ -91,16 +91,72 @@ where
     }
 }
 
+/// Get the working directory of the shell.
+///
+/// The directory reported by the shell is preferred, as long as it is located on this machine.
+/// Otherwise the working directory of the foreground process is used.
+#[cfg(not(windows))]
+pub fn working_directory(
+    reported: Option<&WorkingDirectory>,
+    master_fd: RawFd,
+    shell_pid: u32,
+) -> Option<PathBuf> {
+    match reported.filter(|directory| is_local_hostname(&directory.hostname)) {
+        Some(directory) => Some(directory.path.clone()),
+        None => foreground_process_path(master_fd, shell_pid).ok(),
+    }
+}
+
+/// Check if a hostname refers to this machine.
+#[cfg(not(windows))]
+fn is_local_hostname(hostname: &str) -> bool {
+    if hostname.is_empty() || hostname == "localhost" {
+        return true;
+    }
+
+    let mut buf = [0u8; 256];
+    let result = unsafe { libc::gethostname(buf.as_mut_ptr().cast(), buf.len()) };
+    if result != 0 {
+        return false;
+    }
+
+    let len = buf.iter().position(|&byte| byte == 0).unwrap_or(buf.len());
+    buf[..len] == *hostname.as_bytes()
+}
+
+/// Get the process ID of the controlling process.
+#[cfg(not(windows))]
+fn foreground_process_id(master_fd: RawFd, shell_pid: u32) -> pid_t {
+    let pid = unsafe { libc::tcgetpgrp(master_fd) };
+    if pid < 0 { shell_pid as pid_t } else { pid }
+}
+
+/// Get name of controlling process.
+#[cfg(not(windows))]
+pub fn foreground_process_name(master_fd: RawFd, shell_pid: u32) -> Result<String, Box<dyn Error>> {
+    let pid = foreground_process_id(master_fd, shell_pid);
+
+    #[cfg(not(any(target_os = "macos", target_os = "freebsd")))]
+    let comm_path = format!("/proc/{pid}/comm");
+    #[cfg(target_os = "freebsd")]
+    let comm_path = format!("/compat/linux/proc/{}/comm", pid);
+
+    #[cfg(not(target_os = "macos"))]
+    let name = fs::read_to_string(comm_path)?.trim_end().to_owned();
+
+    #[cfg(target_os = "macos")]
+    let name = macos::proc::name(pid)?;
+
+    Ok(name)
+}
+
 /// Get working directory of controlling process.
 #[cfg(not(any(windows, target_os = "openbsd")))]
 pub fn foreground_process_path(
     master_fd: RawFd,
     shell_pid: u32,
 ) -> Result<PathBuf, Box<dyn Error>> {
-    let mut pid = unsafe { libc::tcgetpgrp(master_fd) };
-    if pid < 0 {
-        pid = shell_pid as pid_t;
-    }
+    let pid = foreground_process_id(master_fd, shell_pid);
 
     #[cfg(not(any(target_os = "macos", target_os = "freebsd")))]
     let link_path = format!("/proc/{pid}/cwd");
@@ -121,10 +177,7 @@ pub fn foreground_process_path(
     master_fd: RawFd,
     shell_pid: u32,
 ) -> Result<PathBuf, Box<dyn Error>> {
-    let mut pid = unsafe { libc::tcgetpgrp(master_fd) };
-    if pid < 0 {
-        pid = shell_pid as pid_t;
-    }
+    let pid = foreground_process_id(master_fd, shell_pid);
     let name = [libc::CTL_KERN, libc::KERN_PROC_CWD, pid];
     let mut buf = [0u8; libc::PATH_MAX as usize];
     let result = unsafe {
@@ -1,9 +1,12 @@
 use std::borrow::Cow;
+use std::collections::VecDeque;
 use std::num::NonZeroU32;
 use std::ops::Deref;
 use std::{cmp, mem};
 
 use alacritty_terminal::event::EventListener;
+use alacritty_terminal::graphics::kitty::{PLACEHOLDER, Placeholder};
+use alacritty_terminal::graphics::{GraphicCell, Graphics};
 use alacritty_terminal::grid::{Dimensions, Indexed};
 use alacritty_terminal::index::{Column, Line, Point};
 use alacritty_terminal::selection::SelectionRange;
@@ -17,6 +20,7 @@ use crate::display::color::{CellRgb, DIM_FACTOR, List, Rgb};
 use crate::display::hint::{self, HintState};
 use crate::display::{Display, SizeInfo};
 use crate::event::SearchState;
+use crate::renderer::{ShapedGlyph, Shaper};
 
 /// Minimum contrast between a fixed cursor color and the cell's background.
 pub const MIN_CURSOR_CONTRAST: f64 = 1.5;
@@ -35,6 +39,19 @@ pub struct RenderableContent<'a> {
     colors: &'a List,
     focused_match: Option<&'a Match>,
     size: &'a SizeInfo,
+    graphics: &'a Graphics,
+
+    /// Last kitty Unicode placeholder, used to infer omitted placeholder positions.
+    placeholder: Option<(Point, Placeholder)>,
+
+    /// Text shaper, when font ligatures are enabled.
+    shaper: Option<&'a mut Shaper>,
+
+    /// Shaped cells of the current line.
+    line: VecDeque<RenderableCell>,
+
+    /// First cell of the line after the current line.
+    next_line: Option<RenderableCell>,
 }
 
 impl<'a> RenderableContent<'a> {
@@ -43,6 +60,36 @@ impl<'a> RenderableContent<'a> {
         display: &'a mut Display,
         term: &'a Term<T>,
         search_state: &'a mut SearchState,
+    ) -> Self {
+        let hint = if display.hint_state.active() {
+            display.hint_state.update_matches(term);
+            Some(Hint::from(&display.hint_state))
+        } else {
+            None
+        };
+
+        let shaper = Some(&mut display.glyph_cache.shaper).filter(|shaper| shaper.enabled());
+
+        let mut content =
+            Self::headless(config, &display.colors, &display.size_info, shaper, term, search_state);
+        content.hint = hint;
+
+        // Hide the cursor while it's blinking or the IME is composing text.
+        if display.cursor_hidden || display.ime.preedit().is_some() {
+            content.cursor_shape = CursorShape::Hidden;
+        }
+
+        content
+    }
+
+    /// Create renderable content without any of the window's UI state.
+    pub fn headless<T: EventListener>(
+        config: &'a UiConfig,
+        colors: &'a List,
+        size: &'a SizeInfo,
+        shaper: Option<&'a mut Shaper>,
+        term: &'a Term<T>,
+        search_state: &'a mut SearchState,
     ) -> Self {
         let search = search_state.dfas().map(|dfas| HintMatches::visible_regex_matches(term, dfas));
         let focused_match = search_state.focused_match();
@@ -50,9 +97,7 @@ impl<'a> RenderableContent<'a> {
 
         // Find terminal cursor shape.
         let cursor_shape = if terminal_content.cursor.shape == CursorShape::Hidden
-            || display.cursor_hidden
             || search_state.regex().is_some()
-            || display.ime.preedit().is_some()
         {
             CursorShape::Hidden
         } else if !term.is_focused && config.cursor.unfocused_hollow {
@@ -66,24 +111,22 @@ impl<'a> RenderableContent<'a> {
         let display_offset = terminal_content.display_offset;
         let cursor_point = term::point_to_viewport(display_offset, cursor_point).unwrap();
 
-        let hint = if display.hint_state.active() {
-            display.hint_state.update_matches(term);
-            Some(Hint::from(&display.hint_state))
-        } else {
-            None
-        };
-
         Self {
-            colors: &display.colors,
-            size: &display.size_info,
+            colors,
+            size,
             cursor: RenderableCursor::new_hidden(),
+            graphics: term.graphics(),
+            placeholder: None,
+            line: Default::default(),
+            next_line: None,
+            hint: None,
+            shaper,
             terminal_content,
             focused_match,
             cursor_shape,
             cursor_point,
             search,
             config,
-            hint,
         }
     }
 
@@ -150,15 +193,13 @@ impl<'a> RenderableContent<'a> {
     }
 }
 
-impl Iterator for RenderableContent<'_> {
-    type Item = RenderableCell;
-
-    /// Gets the next renderable cell.
+impl RenderableContent<'_> {
+    /// Get the next renderable cell, without text shaping.
     ///
     /// Skips empty (background) cells and applies any flags to the cell state
     /// (eg. invert fg and bg colors).
     #[inline]
-    fn next(&mut self) -> Option<Self::Item> {
+    fn next_cell(&mut self) -> Option<RenderableCell> {
         loop {
             let cell = self.terminal_content.display_iter.next()?;
             let mut cell = RenderableCell::new(self, cell);
@@ -182,6 +223,99 @@ impl Iterator for RenderableContent<'_> {
             }
         }
     }
+
+    /// Shape runs of cells with identical attributes in the current line.
+    ///
+    /// Runs are interrupted by the cursor and selection boundaries, to prevent ligatures from
+    /// hiding the characters below them.
+    fn shape_line(&mut self) {
+        let shaper = match self.shaper.as_deref_mut() {
+            Some(shaper) => shaper,
+            None => return,
+        };
+
+        let display_offset = self.terminal_content.display_offset;
+        let selection = self.terminal_content.selection;
+        let cursor_point = (self.cursor_shape != CursorShape::Hidden).then_some(self.cursor_point);
+        let is_selected = |cell: &RenderableCell| {
+            let point = term::viewport_to_point(display_offset, cell.point);
+            selection.is_some_and(|selection| selection.contains(point))
+        };
+        let is_shapeable = |cell: &RenderableCell| {
+            cell.extra.is_none()
+                && !cell.flags.intersects(Flags::WIDE_CHAR | Flags::HIDDEN)
+                && Some(cell.point) != cursor_point
+        };
+
+        let cells = self.line.make_contiguous();
+        let mut start = 0;
+        for end in 1..=cells.len() {
+            let (previous, cell) = (&cells[end - 1], cells.get(end));
+            let is_boundary = cell.is_none_or(|cell| {
+                cell.point.column != previous.point.column + 1
+                    || cell.fg != previous.fg
+                    || cell.bg != previous.bg
+                    || cell.bg_alpha != previous.bg_alpha
+                    || cell.flags != previous.flags
+                    || !is_shapeable(cell)
+                    || !is_shapeable(previous)
+                    || is_selected(cell) != is_selected(previous)
+            });
+            if !is_boundary {
+                continue;
+            }
+
+            let run = &mut cells[start..end];
+            start = end;
+
+            // Ligatures require at least two characters.
+            if run.len() < 2 {
+                continue;
+            }
+
+            let text: Vec<char> = run.iter().map(|cell| cell.character).collect();
+            if let Some(glyphs) = shaper.shape(run[0].flags, &text) {
+                for (cell, glyph) in run.iter_mut().zip(glyphs.iter()) {
+                    cell.glyph = *glyph;
+                }
+            }
+        }
+    }
+}
+
+impl Iterator for RenderableContent<'_> {
+    type Item = RenderableCell;
+
+    /// Gets the next renderable cell.
+    ///
+    /// With text shaping enabled, an entire line is read before any of its cells are returned.
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.shaper.is_none() {
+            return self.next_cell();
+        }
+
+        if let Some(cell) = self.line.pop_front() {
+            return Some(cell);
+        }
+
+        // Read the next line.
+        let first_cell = self.next_line.take().or_else(|| self.next_cell())?;
+        let line = first_cell.point.line;
+        self.line.push_back(first_cell);
+        while let Some(cell) = self.next_cell() {
+            if cell.point.line != line {
+                self.next_line = Some(cell);
+                break;
+            }
+
+            self.line.push_back(cell);
+        }
+
+        self.shape_line();
+
+        self.line.pop_front()
+    }
 }
 
 /// Cell ready for rendering.
@@ -195,6 +329,9 @@ pub struct RenderableCell {
     pub underline: Rgb,
     pub flags: Flags,
     pub extra: Option<Box<RenderableCellExtra>>,
+
+    /// Glyph replacing the character after text shaping.
+    pub glyph: Option<ShapedGlyph>,
 }
 
 /// Extra storage with rarely present fields for [`RenderableCell`], to reduce the cell size we
@@ -203,13 +340,15 @@ pub struct RenderableCell {
 pub struct RenderableCellExtra {
     pub zerowidth: Option<Vec<char>>,
     pub hyperlink: Option<Hyperlink>,
+    pub graphic: Option<GraphicCell>,
 }
 
 impl RenderableCell {
     fn new(content: &mut RenderableContent<'_>, cell: Indexed<&Cell>) -> Self {
         // Lookup RGB values.
-        let mut fg = Self::compute_fg_rgb(content, cell.fg, cell.flags);
-        let mut bg = Self::compute_bg_rgb(content, cell.bg);
+        let color = |index| content.color(index);
+        let mut fg = compute_fg_rgb(content.config, color, cell.fg, cell.flags);
+        let mut bg = compute_bg_rgb(color, cell.bg);
 
         let mut bg_alpha = if cell.flags.contains(Flags::INVERSE) {
             mem::swap(&mut fg, &mut bg);
@@ -281,21 +420,40 @@ impl RenderableCell {
         let cell_point = cell.point;
         let point = term::point_to_viewport(display_offset, cell_point).unwrap();
 
-        let underline = cell
-            .underline_color()
-            .map_or(fg, |underline| Self::compute_fg_rgb(content, underline, flags));
+        let underline = cell.underline_color().map_or(fg, |underline| {
+            compute_fg_rgb(content.config, |index| content.color(index), underline, flags)
+        });
 
-        let zerowidth = cell.zerowidth();
+        let mut zerowidth = cell.zerowidth();
         let hyperlink = cell.hyperlink();
+        let mut graphic = cell.graphic().cloned();
+
+        // Replace kitty Unicode placeholders with the image they reference.
+        let previous = content
+            .placeholder
+            .take()
+            .filter(|(point, _)| {
+                point.line == cell_point.line && point.column + 1 == cell_point.column
+            })
+            .map(|(_, placeholder)| placeholder);
+        if let Some(placeholder) = Placeholder::from_cell(&cell, previous) {
+            content.placeholder = Some((cell_point, placeholder));
+            graphic = content.graphics.placeholder_graphic(&placeholder).or(graphic);
+            zerowidth = None;
+            if character == PLACEHOLDER {
+                character = ' ';
+            }
+        }
 
-        let extra = (zerowidth.is_some() || hyperlink.is_some()).then(|| {
+        let extra = (zerowidth.is_some() || hyperlink.is_some() || graphic.is_some()).then(|| {
             Box::new(RenderableCellExtra {
                 zerowidth: zerowidth.map(|zerowidth| zerowidth.to_vec()),
                 hyperlink,
+                graphic,
             })
         });
 
-        RenderableCell { flags, character, bg_alpha, point, fg, bg, underline, extra }
+        RenderableCell { flags, character, bg_alpha, point, fg, bg, underline, extra, glyph: None }
     }
 
     /// Check if cell contains any renderable content.
@@ -322,63 +480,6 @@ impl RenderableCell {
         }
     }
 
-    /// Get the RGB color from a cell's foreground color.
-    fn compute_fg_rgb(content: &RenderableContent<'_>, fg: Color, flags: Flags) -> Rgb {
-        let config = &content.config;
-        match fg {
-            Color::Spec(rgb) => match flags & Flags::DIM {
-                Flags::DIM => {
-                    let rgb: Rgb = rgb.into();
-                    rgb * DIM_FACTOR
-                },
-                _ => rgb.into(),
-            },
-            Color::Named(ansi) => {
-                match (config.colors.draw_bold_text_with_bright_colors, flags & Flags::DIM_BOLD) {
-                    // If no bright foreground is set, treat it like the BOLD flag doesn't exist.
-                    (_, Flags::DIM_BOLD)
-                        if ansi == NamedColor::Foreground
-                            && config.colors.primary.bright_foreground.is_none() =>
-                    {
-                        content.color(NamedColor::DimForeground as usize)
-                    },
-                    // Draw bold text in bright colors *and* contains bold flag.
-                    (true, Flags::BOLD) => content.color(ansi.to_bright() as usize),
-                    // Cell is marked as dim and not bold.
-                    (_, Flags::DIM) | (false, Flags::DIM_BOLD) => {
-                        content.color(ansi.to_dim() as usize)
-                    },
-                    // None of the above, keep original color..
-                    _ => content.color(ansi as usize),
-                }
-            },
-            Color::Indexed(idx) => {
-                let idx = match (
-                    config.colors.draw_bold_text_with_bright_colors,
-                    flags & Flags::DIM_BOLD,
-                    idx,
-                ) {
-                    (true, Flags::BOLD, 0..=7) => idx as usize + 8,
-                    (false, Flags::DIM, 8..=15) => idx as usize - 8,
-                    (false, Flags::DIM, 0..=7) => NamedColor::DimBlack as usize + idx as usize,
-                    _ => idx as usize,
-                };
-
-                content.color(idx)
-            },
-        }
-    }
-
-    /// Get the RGB color from a cell's background color.
-    #[inline]
-    fn compute_bg_rgb(content: &RenderableContent<'_>, bg: Color) -> Rgb {
-        match bg {
-            Color::Spec(rgb) => rgb.into(),
-            Color::Named(ansi) => content.color(ansi as usize),
-            Color::Indexed(idx) => content.color(idx as usize),
-        }
-    }
-
     /// Compute background alpha based on cell's original color.
     ///
     /// Since an RGB color matching the background should not be transparent, this is computed
@@ -396,6 +497,63 @@ impl RenderableCell {
     }
 }
 
+/// Get the RGB color from a cell's foreground color.
+pub fn compute_fg_rgb<C>(config: &UiConfig, color: C, fg: Color, flags: Flags) -> Rgb
+where
+    C: Fn(usize) -> Rgb,
+{
+    match fg {
+        Color::Spec(rgb) => match flags & Flags::DIM {
+            Flags::DIM => {
+                let rgb: Rgb = rgb.into();
+                rgb * DIM_FACTOR
+            },
+            _ => rgb.into(),
+        },
+        Color::Named(ansi) => {
+            match (config.colors.draw_bold_text_with_bright_colors, flags & Flags::DIM_BOLD) {
+                // If no bright foreground is set, treat it like the BOLD flag doesn't exist.
+                (_, Flags::DIM_BOLD)
+                    if ansi == NamedColor::Foreground
+                        && config.colors.primary.bright_foreground.is_none() =>
+                {
+                    color(NamedColor::DimForeground as usize)
+                },
+                // Draw bold text in bright colors *and* contains bold flag.
+                (true, Flags::BOLD) => color(ansi.to_bright() as usize),
+                // Cell is marked as dim and not bold.
+                (_, Flags::DIM) | (false, Flags::DIM_BOLD) => color(ansi.to_dim() as usize),
+                // None of the above, keep original color..
+                _ => color(ansi as usize),
+            }
+        },
+        Color::Indexed(idx) => {
+            let idx = match (
+                config.colors.draw_bold_text_with_bright_colors,
+                flags & Flags::DIM_BOLD,
+                idx,
+            ) {
+                (true, Flags::BOLD, 0..=7) => idx as usize + 8,
+                (false, Flags::DIM, 8..=15) => idx as usize - 8,
+                (false, Flags::DIM, 0..=7) => NamedColor::DimBlack as usize + idx as usize,
+                _ => idx as usize,
+            };
+
+            color(idx)
+        },
+    }
+}
+
+/// Get the RGB color from a cell's background color.
+#[inline]
+pub fn compute_bg_rgb<C: Fn(usize) -> Rgb>(color: C, bg: Color) -> Rgb {
+    match bg {
+        Color::Spec(rgb) => rgb.into(),
+        Color::Named(ansi) => color(ansi as usize),
+        Color::Indexed(idx) => color(idx as usize),
+    }
+}
+
 /// Cursor storing all information relevant for rendering.
 #[derive(Debug, Eq, PartialEq, Copy, Clone)]
 pub struct RenderableCursor {
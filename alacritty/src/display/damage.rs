@@ -92,7 +92,8 @@ impl DamageTracker {
     /// Get shaped frame damage for the active frame.
     pub fn shape_frame_damage(&self, size_info: SizeInfo<u32>) -> Vec<Rect> {
         if self.frames[0].full {
-            vec![Rect::new(0, 0, size_info.width() as i32, size_info.height() as i32)]
+            let (width, height) = (size_info.window_width(), size_info.window_height());
+            vec![Rect::new(0, 0, width as i32, height as i32)]
         } else {
             let lines_damage = RenderDamageIterator::new(
                 TermDamageIterator::new(&self.frames[0].lines, 0),
@@ -176,6 +177,7 @@ impl FrameDamage {
         width: i32,
         height: i32,
     ) {
+        let x = size_info.x() as i32 + x;
         let y = viewport_y_to_damage_y(size_info, y, height);
         self.rects.push(Rect { x, y, width, height });
     }
@@ -204,12 +206,12 @@ impl FrameDamage {
 
 /// Convert viewport `y` coordinate to [`Rect`] damage coordinate.
 pub fn viewport_y_to_damage_y(size_info: &SizeInfo, y: i32, height: i32) -> i32 {
-    size_info.height() as i32 - y - height
+    (size_info.window_height() - size_info.y()) as i32 - y - height
 }
 
 /// Convert viewport `y` coordinate to [`Rect`] damage coordinate.
 pub fn damage_y_to_viewport_y(size_info: &SizeInfo, rect: &Rect) -> i32 {
-    size_info.height() as i32 - rect.y - rect.height
+    (size_info.window_height() - size_info.y()) as i32 - rect.y - rect.height
 }
 
 /// Iterator which converts `alacritty_terminal` damage information into renderer damaged rects.
@@ -226,8 +228,10 @@ impl<'a> RenderDamageIterator<'a> {
     #[inline]
     fn rect_for_line(&self, line_damage: LineDamageBounds) -> Rect {
         let size_info = &self.size_info;
-        let y_top = size_info.height() - size_info.padding_y();
-        let x = size_info.padding_x() + line_damage.left as u32 * size_info.cell_width();
+        let y_top = size_info.window_height() - size_info.y() - size_info.padding_y();
+        let x = size_info.x()
+            + size_info.padding_x()
+            + line_damage.left as u32 * size_info.cell_width();
         let y = y_top - (line_damage.line + 1) as u32 * size_info.cell_height();
         let width = (line_damage.right - line_damage.left + 1) as u32 * size_info.cell_width();
         Rect::new(x as i32, y as i32, width as i32, size_info.cell_height() as i32)
@@ -238,12 +242,12 @@ impl<'a> RenderDamageIterator<'a> {
     fn overdamage(size_info: &SizeInfo<u32>, mut rect: Rect) -> Rect {
         rect.x = (rect.x - size_info.cell_width() as i32).max(0);
         rect.width = cmp::min(
-            (size_info.width() as i32 - rect.x).max(0),
+            (size_info.window_width() as i32 - rect.x).max(0),
             rect.width + 2 * size_info.cell_width() as i32,
         );
         rect.y = (rect.y - size_info.cell_height() as i32 / 2).max(0);
         rect.height = cmp::min(
-            (size_info.height() as i32 - rect.y).max(0),
+            (size_info.window_height() as i32 - rect.y).max(0),
             rect.height + size_info.cell_height() as i32,
         );
 
@@ -5,7 +5,6 @@ use std::cmp;
 use std::fmt::{self, Formatter};
 use std::mem::{self, ManuallyDrop};
 use std::num::NonZeroU32;
-use std::ops::Deref;
 use std::time::{Duration, Instant};
 
 use glutin::config::GetGlConfig;
@@ -15,7 +14,7 @@ use glutin::error::ErrorKind;
 use glutin::prelude::*;
 use glutin::surface::{Surface, SwapInterval, WindowSurface};
 
-use log::{debug, info};
+use log::{debug, error, info};
 use parking_lot::MutexGuard;
 use serde::{Deserialize, Serialize};
 use winit::dpi::PhysicalSize;
@@ -49,18 +48,28 @@ use crate::display::cursor::IntoRects;
 use crate::display::damage::{DamageTracker, damage_y_to_viewport_y};
 use crate::display::hint::{HintMatch, HintState};
 use crate::display::meter::Meter;
+use crate::display::picker::ClipboardPicker;
 use crate::display::window::Window;
 use crate::event::{Event, EventType, Mouse, SearchState};
+use crate::input::keyboard::PendingKey;
+use crate::macros::MacroRecording;
 use crate::message_bar::{MessageBuffer, MessageType};
+use crate::pane::{Layout, Pane, PaneId};
+use crate::paste::PendingPaste;
+use crate::renderer::graphics::RenderGraphic;
+use crate::renderer::platform::{GraphicsPlatform, SoftwareSurface};
 use crate::renderer::rects::{RenderLine, RenderLines, RenderRect};
+use crate::renderer::software::SoftwareRenderer;
 use crate::renderer::{self, GlyphCache, Renderer, platform};
 use crate::scheduler::{Scheduler, TimerId, Topic};
+use crate::screenshot::Screenshot;
 use crate::string::{ShortenDirection, StrShortener};
 
 pub mod color;
 pub mod content;
 pub mod cursor;
 pub mod hint;
+pub mod picker;
 pub mod window;
 
 mod bell;
@@ -73,9 +82,18 @@ const FORWARD_SEARCH_LABEL: &str = "Search: ";
 /// Label for the backward terminal search bar.
 const BACKWARD_SEARCH_LABEL: &str = "Backward Search: ";
 
+/// Label for the clipboard history picker.
+const CLIPBOARD_PICKER_LABEL: &str = "Clipboard History: ";
+
+/// Maximum number of entries visible in the clipboard history picker.
+const CLIPBOARD_PICKER_LINES: usize = 10;
+
 /// The character used to shorten the visible text like uri preview or search regex.
 const SHORTENER: char = '…';
 
+/// Opacity of the dividers between panes.
+const DIVIDER_ALPHA: f32 = 0.5;
+
 /// Color which is used to highlight damaged rects when debugging.
 const DAMAGE_RECT_COLOR: Rgb = Rgb::new(255, 0, 255);
 
@@ -92,6 +110,9 @@ pub enum Error {
 
     /// Error during context operations.
     Context(glutin::error::Error),
+
+    /// Error creating the software renderer's surface.
+    Software(softbuffer::SoftBufferError),
 }
 
 impl std::error::Error for Error {
@@ -101,6 +122,7 @@ impl std::error::Error for Error {
             Error::Font(err) => err.source(),
             Error::Render(err) => err.source(),
             Error::Context(err) => err.source(),
+            Error::Software(err) => err.source(),
         }
     }
 }
@@ -112,6 +134,7 @@ impl fmt::Display for Error {
             Error::Font(err) => err.fmt(f),
             Error::Render(err) => err.fmt(f),
             Error::Context(err) => err.fmt(f),
+            Error::Software(err) => err.fmt(f),
         }
     }
 }
@@ -140,6 +163,12 @@ impl From<glutin::error::Error> for Error {
     }
 }
 
+impl From<softbuffer::SoftBufferError> for Error {
+    fn from(val: softbuffer::SoftBufferError) -> Self {
+        Error::Software(val)
+    }
+}
+
 /// Terminal size info.
 #[derive(Serialize, Deserialize, Debug, Copy, Clone, PartialEq, Eq)]
 pub struct SizeInfo<T = f32> {
@@ -166,6 +195,18 @@ pub struct SizeInfo<T = f32> {
 
     /// Number of columns in the viewport.
     columns: usize,
+
+    /// Horizontal offset of the terminal inside the window.
+    x: T,
+
+    /// Vertical offset of the terminal inside the window.
+    y: T,
+
+    /// Width of the entire window.
+    window_width: T,
+
+    /// Height of the entire window.
+    window_height: T,
 }
 
 impl From<SizeInfo<f32>> for SizeInfo<u32> {
@@ -179,6 +220,10 @@ impl From<SizeInfo<f32>> for SizeInfo<u32> {
             padding_y: size_info.padding_y as u32,
             screen_lines: size_info.screen_lines,
             columns: size_info.screen_lines,
+            x: size_info.x as u32,
+            y: size_info.y as u32,
+            window_width: size_info.window_width as u32,
+            window_height: size_info.window_height as u32,
         }
     }
 }
@@ -224,6 +269,26 @@ impl<T: Clone + Copy> SizeInfo<T> {
     pub fn padding_y(&self) -> T {
         self.padding_y
     }
+
+    #[inline]
+    pub fn x(&self) -> T {
+        self.x
+    }
+
+    #[inline]
+    pub fn y(&self) -> T {
+        self.y
+    }
+
+    #[inline]
+    pub fn window_width(&self) -> T {
+        self.window_width
+    }
+
+    #[inline]
+    pub fn window_height(&self) -> T {
+        self.window_height
+    }
 }
 
 impl SizeInfo<f32> {
@@ -257,14 +322,35 @@ impl SizeInfo<f32> {
             padding_y: padding_y.floor(),
             screen_lines,
             columns,
+            x: 0.,
+            y: 0.,
+            window_width: width,
+            window_height: height,
         }
     }
 
+    /// Place the terminal at an offset inside a larger window.
+    #[inline]
+    pub fn with_window(mut self, x: f32, y: f32, window_width: f32, window_height: f32) -> Self {
+        self.x = x;
+        self.y = y;
+        self.window_width = window_width;
+        self.window_height = window_height;
+        self
+    }
+
     #[inline]
     pub fn reserve_lines(&mut self, count: usize) {
         self.screen_lines = cmp::max(self.screen_lines.saturating_sub(count), MIN_SCREEN_LINES);
     }
 
+    /// Check if the minimum terminal grid fits inside the available space.
+    #[inline]
+    pub fn fits_grid(&self) -> bool {
+        self.padding_x * 2. + self.columns as f32 * self.cell_width <= self.width
+            && self.padding_y * 2. + self.screen_lines as f32 * self.cell_height <= self.height
+    }
+
     /// Check if coordinates are inside the terminal grid.
     ///
     /// The padding, message bar or search are not counted as part of the grid.
@@ -367,6 +453,21 @@ pub struct Display {
     /// State of the keyboard hints.
     pub hint_state: HintState,
 
+    /// Clipboard history picker, while it is open.
+    pub clipboard_picker: Option<ClipboardPicker>,
+
+    /// Paste waiting for confirmation by the user.
+    pub pending_paste: Option<PendingPaste>,
+
+    /// Keys of an incomplete key sequence.
+    pub pending_chord: Vec<PendingKey>,
+
+    /// Active user-defined binding modes.
+    pub custom_modes: Vec<String>,
+
+    /// Keyboard macro being recorded in this window.
+    pub macro_recording: Option<MacroRecording>,
+
     /// Unprocessed display updates.
     pub pending_update: DisplayUpdate,
 
@@ -385,15 +486,19 @@ pub struct Display {
     /// Font size used by the window.
     pub font_size: FontSize,
 
+    /// Split panes inside the window.
+    pub layout: Layout,
+
+    /// Screenshots saved with the next frame.
+    pub pending_screenshots: Vec<Screenshot>,
+
     // Mouse point position when highlighting hints.
     hint_mouse_point: Option<Point>,
 
     renderer: ManuallyDrop<Renderer>,
     renderer_preference: Option<RendererPreference>,
 
-    surface: ManuallyDrop<Surface<WindowSurface>>,
-
-    context: ManuallyDrop<PossiblyCurrentContext>,
+    surface: ManuallyDrop<RenderSurface>,
 
     glyph_cache: GlyphCache,
     meter: Meter,
@@ -402,7 +507,7 @@ pub struct Display {
 impl Display {
     pub fn new(
         window: Window,
-        gl_context: NotCurrentContext,
+        gl_context: Option<NotCurrentContext>,
         config: &UiConfig,
         _tabbed: bool,
     ) -> Result<Display, Error> {
@@ -425,18 +530,31 @@ impl Display {
             window.request_inner_size(size);
         }
 
-        // Create the GL surface to draw into.
-        let surface = platform::create_gl_surface(
-            &gl_context,
-            window.inner_size(),
-            window.raw_window_handle(),
-        )?;
+        let (mut surface, mut renderer) = match gl_context {
+            Some(gl_context) => {
+                // Create the GL surface to draw into.
+                let surface = platform::create_gl_surface(
+                    &gl_context,
+                    window.inner_size(),
+                    window.raw_window_handle(),
+                )?;
 
-        // Make the context current.
-        let context = gl_context.make_current(&surface)?;
+                // Make the context current.
+                let context = gl_context.make_current(&surface)?;
 
-        // Create renderer.
-        let mut renderer = Renderer::new(&context, config.debug.renderer)?;
+                // Create renderer.
+                let renderer = Renderer::new(&context, config.debug.renderer)?;
+
+                (RenderSurface::Gl { context, surface }, renderer)
+            },
+            None => {
+                let surface = platform::create_software_surface(
+                    window.raw_display_handle(),
+                    window.raw_window_handle(),
+                )?;
+                (RenderSurface::Software(surface), Renderer::Software(SoftwareRenderer::new()))
+            },
+        };
 
         // Load font common glyphs to accelerate rendering.
         debug!("Filling glyph cache with common glyphs");
@@ -478,7 +596,12 @@ impl Display {
         // On Wayland we can safely ignore this call, since the window isn't visible until you
         // actually draw something into it and commit those changes.
         if !is_wayland {
-            surface.swap_buffers(&context).expect("failed to swap buffers.");
+            match &mut surface {
+                RenderSurface::Gl { context, surface } => {
+                    surface.swap_buffers(context).expect("failed to swap buffers.")
+                },
+                RenderSurface::Software(surface) => renderer.present(surface),
+            }
             renderer.finish();
         }
 
@@ -510,12 +633,13 @@ impl Display {
         damage_tracker.debug = config.debug.highlight_damage;
 
         // Disable vsync.
-        if let Err(err) = surface.set_swap_interval(&context, SwapInterval::DontWait) {
-            info!("Failed to disable vsync: {err}");
+        if let RenderSurface::Gl { context, surface } = &surface {
+            if let Err(err) = surface.set_swap_interval(context, SwapInterval::DontWait) {
+                info!("Failed to disable vsync: {err}");
+            }
         }
 
         Ok(Self {
-            context: ManuallyDrop::new(context),
             visual_bell: VisualBell::from(&config.bell),
             renderer: ManuallyDrop::new(renderer),
             renderer_preference: config.debug.renderer,
@@ -526,7 +650,14 @@ impl Display {
             damage_tracker,
             glyph_cache,
             hint_state,
+            clipboard_picker: Default::default(),
+            pending_paste: Default::default(),
+            pending_chord: Default::default(),
+            custom_modes: Default::default(),
+            macro_recording: Default::default(),
             size_info,
+            layout: Layout::new(PaneId::next()),
+            pending_screenshots: Vec::new(),
             font_size,
             window,
             pending_renderer_update: Default::default(),
@@ -542,25 +673,40 @@ impl Display {
         })
     }
 
-    #[inline]
-    pub fn gl_context(&self) -> &PossiblyCurrentContext {
-        &self.context
+    /// Graphics platform used by the window.
+    pub fn graphics_platform(&self) -> GraphicsPlatform {
+        match &*self.surface {
+            RenderSurface::Gl { context, .. } => GraphicsPlatform::Gl(context.config()),
+            RenderSurface::Software(_) => GraphicsPlatform::Software,
+        }
+    }
+
+    /// Request a window size fitting the specified terminal dimensions.
+    pub fn request_terminal_size(&self, config: &UiConfig, dimensions: Dimensions) {
+        let (cell_width, cell_height) = (self.size_info.cell_width(), self.size_info.cell_height());
+        let scale_factor = self.window.scale_factor as f32;
+        let size = window_size(config, dimensions, cell_width, cell_height, scale_factor);
+        self.window.request_inner_size(size);
     }
 
     pub fn make_not_current(&mut self) {
-        if self.context.is_current() {
-            self.context.make_not_current_in_place().expect("failed to disable context");
+        if let RenderSurface::Gl { context, .. } = &mut *self.surface {
+            if context.is_current() {
+                context.make_not_current_in_place().expect("failed to disable context");
+            }
         }
     }
 
     pub fn make_current(&mut self) {
-        let is_current = self.context.is_current();
+        let RenderSurface::Gl { context, surface } = &mut *self.surface else {
+            return;
+        };
 
         // Attempt to make the context current if it's not.
-        let context_loss = if is_current {
+        let context_loss = if context.is_current() {
             self.renderer.was_context_reset()
         } else {
-            match self.context.make_current(&self.surface) {
+            match context.make_current(surface) {
                 Err(err) if err.error_kind() == ErrorKind::ContextLost => {
                     info!("Context lost for window {:?}", self.window.id());
                     true
@@ -573,25 +719,23 @@ impl Display {
             return;
         }
 
-        let gl_display = self.context.display();
-        let gl_config = self.context.config();
+        let gl_display = context.display();
+        let gl_config = context.config();
         let raw_window_handle = Some(self.window.raw_window_handle());
-        let context = platform::create_gl_context(&gl_display, &gl_config, raw_window_handle)
+        let new_context = platform::create_gl_context(&gl_display, &gl_config, raw_window_handle)
             .expect("failed to recreate context.");
 
-        // Drop the old context and renderer.
+        // Drop the old renderer.
         unsafe {
             ManuallyDrop::drop(&mut self.renderer);
-            ManuallyDrop::drop(&mut self.context);
         }
 
-        // Activate new context.
-        let context = context.treat_as_possibly_current();
-        self.context = ManuallyDrop::new(context);
-        self.context.make_current(&self.surface).expect("failed to reativate context after reset.");
+        // Activate new context, dropping the old one.
+        *context = new_context.treat_as_possibly_current();
+        context.make_current(surface).expect("failed to reativate context after reset.");
 
         // Recreate renderer.
-        let renderer = Renderer::new(&self.context, self.renderer_preference)
+        let renderer = Renderer::new(context, self.renderer_preference)
             .expect("failed to recreate renderer after reset");
         self.renderer = ManuallyDrop::new(renderer);
 
@@ -604,18 +748,24 @@ impl Display {
         debug!("Recovered window {:?} from gpu reset", self.window.id());
     }
 
-    fn swap_buffers(&self) {
+    fn swap_buffers(&mut self) {
         #[allow(clippy::single_match)]
-        let res = match (self.surface.deref(), &self.context.deref()) {
+        let res = match &mut *self.surface {
             #[cfg(not(any(target_os = "macos", windows)))]
-            (Surface::Egl(surface), PossiblyCurrentContext::Egl(context))
-                if matches!(self.raw_window_handle, RawWindowHandle::Wayland(_))
-                    && !self.damage_tracker.debug =>
+            RenderSurface::Gl {
+                surface: Surface::Egl(surface),
+                context: PossiblyCurrentContext::Egl(context),
+            } if matches!(self.raw_window_handle, RawWindowHandle::Wayland(_))
+                && !self.damage_tracker.debug =>
             {
                 let damage = self.damage_tracker.shape_frame_damage(self.size_info.into());
                 surface.swap_buffers_with_damage(context, &damage)
             },
-            (surface, context) => surface.swap_buffers(context),
+            RenderSurface::Gl { surface, context } => surface.swap_buffers(context),
+            RenderSurface::Software(surface) => {
+                self.renderer.present(surface);
+                Ok(())
+            },
         };
         if let Err(err) = res {
             debug!("error calling swap_buffers: {err}");
@@ -681,23 +831,22 @@ impl Display {
             self.damage_tracker.frame().mark_fully_damaged();
         }
 
-        let (mut width, mut height) = (self.size_info.width(), self.size_info.height());
+        let (mut width, mut height) =
+            (self.size_info.window_width(), self.size_info.window_height());
         if let Some(dimensions) = pending_update.dimensions() {
             width = dimensions.width as f32;
             height = dimensions.height as f32;
         }
 
-        let padding = config.window.padding(self.window.scale_factor as f32);
-
-        let mut new_size = SizeInfo::new(
-            width,
-            height,
-            cell_width,
-            cell_height,
-            padding.0,
-            padding.1,
-            config.window.dynamic_padding,
-        );
+        let focused = self.layout.focused();
+        let mut new_size =
+            match self.pane_size(config, focused, width, height, cell_width, cell_height) {
+                Some(size_info) => size_info,
+                None => {
+                    error!("Focused pane {focused:?} is not visible");
+                    return;
+                },
+            };
 
         // Update number of column/lines in the viewport.
         let search_active = search_state.history_index.is_some();
@@ -726,6 +875,9 @@ impl Display {
 
         // Check if dimensions have changed.
         if new_size != self.size_info {
+            // Update the text area size used for placing graphics.
+            terminal.set_window_size(new_size.into());
+
             // Queue renderer update.
             let renderer_update = self.pending_renderer_update.get_or_insert(Default::default());
             renderer_update.resize = true;
@@ -736,6 +888,44 @@ impl Display {
         self.size_info = new_size;
     }
 
+    /// Width of the dividers between panes.
+    pub fn pane_gap(&self) -> f32 {
+        (self.window.scale_factor as f32).round().max(1.)
+    }
+
+    /// Size of a visible pane inside the window.
+    pub fn pane_size_info(&self, config: &UiConfig, id: PaneId) -> Option<SizeInfo> {
+        let (width, height) = (self.size_info.window_width(), self.size_info.window_height());
+        let (cell_width, cell_height) = (self.size_info.cell_width(), self.size_info.cell_height());
+        self.pane_size(config, id, width, height, cell_width, cell_height)
+    }
+
+    /// Size of a visible pane inside a window with the specified dimensions.
+    fn pane_size(
+        &self,
+        config: &UiConfig,
+        id: PaneId,
+        width: f32,
+        height: f32,
+        cell_width: f32,
+        cell_height: f32,
+    ) -> Option<SizeInfo> {
+        let rect = self.layout.rect(id, width, height, self.pane_gap())?;
+        let padding = config.window.padding(self.window.scale_factor as f32);
+
+        let size_info = SizeInfo::new(
+            rect.width,
+            rect.height,
+            cell_width,
+            cell_height,
+            padding.0,
+            padding.1,
+            config.window.dynamic_padding,
+        );
+
+        Some(size_info.with_window(rect.x, rect.y, width, height))
+    }
+
     // NOTE: Renderer updates are split off, since platforms like Wayland require resize and other
     // OpenGL operations to be performed right before rendering. Otherwise they could lock the
     // back buffer and render with the previous state. This also solves flickering during resizes.
@@ -749,9 +939,11 @@ impl Display {
 
         // Resize renderer.
         if renderer_update.resize {
-            let width = NonZeroU32::new(self.size_info.width() as u32).unwrap();
-            let height = NonZeroU32::new(self.size_info.height() as u32).unwrap();
-            self.surface.resize(&self.context, width, height);
+            if let RenderSurface::Gl { context, surface } = &*self.surface {
+                let width = NonZeroU32::new(self.size_info.window_width() as u32).unwrap();
+                let height = NonZeroU32::new(self.size_info.window_height() as u32).unwrap();
+                surface.resize(context, width, height);
+            }
         }
 
         // Ensure we're modifying the correct OpenGL context.
@@ -779,11 +971,23 @@ impl Display {
         message_buffer: &MessageBuffer,
         config: &UiConfig,
         search_state: &mut SearchState,
+        panes: &mut [&mut Pane],
     ) {
         // Collect renderable content before the terminal is dropped.
         let mut content = RenderableContent::new(config, self, &terminal, search_state);
         let mut grid_cells = Vec::new();
+        let mut graphics = Vec::new();
         for cell in &mut content {
+            if let Some(graphic) = cell.extra.as_ref().and_then(|extra| extra.graphic.as_ref()) {
+                graphics.push(RenderGraphic {
+                    id: graphic.texture.id,
+                    point: cell.point,
+                    placement: graphic.placement,
+                    offset_x: graphic.offset_x,
+                    offset_y: graphic.offset_y,
+                });
+            }
+
             grid_cells.push(cell);
         }
         let selection_range = content.selection_range();
@@ -811,6 +1015,8 @@ impl Display {
         }
         terminal.reset_damage();
 
+        let graphics_queues = terminal.graphics_mut().take_queues();
+
         // Drop terminal as early as possible to free lock.
         drop(terminal);
 
@@ -820,8 +1026,12 @@ impl Display {
         // Add damage from alacritty's UI elements overlapping terminal.
 
         let requires_full_damage = self.visual_bell.intensity() != 0.
+            || self.layout.is_split()
             || self.hint_state.active()
-            || search_state.regex().is_some();
+            || self.clipboard_picker.is_some()
+            || self.pending_paste.is_some()
+            || search_state.regex().is_some()
+            || self.glyph_cache.shaper.enabled();
         if requires_full_damage {
             self.damage_tracker.frame().mark_fully_damaged();
             self.damage_tracker.next_frame().mark_fully_damaged();
@@ -836,6 +1046,28 @@ impl Display {
         self.make_current();
 
         self.renderer.clear(background_color, config.window_opacity());
+
+        if let Some(graphics_queues) = graphics_queues {
+            self.renderer.update_graphics(graphics_queues);
+        }
+
+        // Draw the unfocused panes and the dividers between them.
+        if self.layout.is_split() {
+            for pane in panes.iter_mut() {
+                self.draw_pane(pane, config);
+            }
+
+            self.draw_dividers(foreground_color, &metrics);
+
+            // Restore the renderer state for the focused pane.
+            self.renderer.resize(&size_info);
+        }
+
+        // Draw graphics with a negative Z-index below the text.
+        let (graphics_below, graphics): (Vec<_>, Vec<_>) =
+            graphics.into_iter().partition(|graphic| graphic.placement.z_index < 0);
+        self.renderer.draw_graphics(&size_info, graphics_below);
+
         let mut lines = RenderLines::new();
 
         // Optimize loop hint comparator.
@@ -878,6 +1110,8 @@ impl Display {
             self.renderer.draw_cells(&size_info, glyph_cache, cells);
         }
 
+        self.renderer.draw_graphics(&size_info, graphics);
+
         let mut rects = lines.rects(&metrics, &size_info);
 
         if let Some(vi_cursor_point) = vi_cursor_point {
@@ -961,6 +1195,9 @@ impl Display {
             }
         }
 
+        self.draw_clipboard_picker(config, &mut rects);
+        self.draw_paste_confirmation(config);
+
         if let Some(message) = message_buffer.message() {
             let search_offset = usize::from(search_state.regex().is_some());
             let text = message.text(&size_info);
@@ -1009,6 +1246,7 @@ impl Display {
         }
 
         self.draw_render_timer(config);
+        self.draw_binding_state(config);
 
         // Draw hyperlink uri preview.
         if has_highlighted_hint {
@@ -1016,6 +1254,14 @@ impl Display {
             self.draw_hyperlink_preview(config, cursor_point, display_offset);
         }
 
+        // Capture the frame before it is presented.
+        if !self.pending_screenshots.is_empty() {
+            let image = self.renderer.image(&self.size_info);
+            for screenshot in self.pending_screenshots.drain(..) {
+                screenshot.save(&image);
+            }
+        }
+
         // Notify winit that we're about to present.
         self.window.pre_present_notify();
 
@@ -1046,6 +1292,81 @@ impl Display {
         self.damage_tracker.swap_damage();
     }
 
+    /// Draw the content of an unfocused pane.
+    fn draw_pane(&mut self, pane: &mut Pane, config: &UiConfig) {
+        // Render the pane without the focused pane's UI state.
+        let size_info = mem::replace(&mut self.size_info, pane.size_info);
+        let hint_state = mem::replace(&mut self.hint_state, HintState::new(""));
+        let cursor_hidden = mem::take(&mut self.cursor_hidden);
+        let ime = mem::take(&mut self.ime);
+
+        let mut terminal = pane.terminal.lock();
+        let mut content = RenderableContent::new(config, self, &terminal, &mut pane.search_state);
+        let mut cells = Vec::new();
+        let mut graphics = Vec::new();
+        for cell in &mut content {
+            if let Some(graphic) = cell.extra.as_ref().and_then(|extra| extra.graphic.as_ref()) {
+                graphics.push(RenderGraphic {
+                    id: graphic.texture.id,
+                    point: cell.point,
+                    placement: graphic.placement,
+                    offset_x: graphic.offset_x,
+                    offset_y: graphic.offset_y,
+                });
+            }
+
+            cells.push(cell);
+        }
+        let cursor = content.cursor();
+
+        terminal.reset_damage();
+        let graphics_queues = terminal.graphics_mut().take_queues();
+        drop(terminal);
+
+        self.size_info = size_info;
+        self.hint_state = hint_state;
+        self.cursor_hidden = cursor_hidden;
+        self.ime = ime;
+
+        if let Some(graphics_queues) = graphics_queues {
+            self.renderer.update_graphics(graphics_queues);
+        }
+
+        let size_info = pane.size_info;
+        let metrics = self.glyph_cache.font_metrics();
+        self.renderer.resize(&size_info);
+
+        let (graphics_below, graphics): (Vec<_>, Vec<_>) =
+            graphics.into_iter().partition(|graphic| graphic.placement.z_index < 0);
+        self.renderer.draw_graphics(&size_info, graphics_below);
+
+        let mut lines = RenderLines::new();
+        let cells = cells.into_iter().inspect(|cell| lines.update(cell));
+        self.renderer.draw_cells(&size_info, &mut self.glyph_cache, cells);
+
+        self.renderer.draw_graphics(&size_info, graphics);
+
+        let mut rects = lines.rects(&metrics, &size_info);
+        rects.extend(cursor.rects(&size_info, config.cursor.thickness()));
+        self.renderer.draw_rects(&size_info, &metrics, rects);
+    }
+
+    /// Draw the dividers between panes.
+    fn draw_dividers(&mut self, color: Rgb, metrics: &crossfont::Metrics) {
+        let (width, height) = (self.size_info.window_width(), self.size_info.window_height());
+        let (cell_width, cell_height) = (self.size_info.cell_width(), self.size_info.cell_height());
+        let window = SizeInfo::new(width, height, cell_width, cell_height, 0., 0., false);
+
+        let dividers = self.layout.dividers(width, height, self.pane_gap());
+        let rects = dividers
+            .into_iter()
+            .map(|rect| {
+                RenderRect::new(rect.x, rect.y, rect.width, rect.height, color, DIVIDER_ALPHA)
+            })
+            .collect();
+        self.renderer.draw_rects(&window, metrics, rects);
+    }
+
     /// Update to a new configuration.
     pub fn update_config(&mut self, config: &UiConfig) {
         self.damage_tracker.debug = config.debug.highlight_damage;
@@ -1325,6 +1646,123 @@ impl Display {
         );
     }
 
+    /// Draw the clipboard history picker over the bottom of the terminal.
+    #[inline(never)]
+    fn draw_clipboard_picker(&mut self, config: &UiConfig, rects: &mut Vec<RenderRect>) {
+        let picker = match &self.clipboard_picker {
+            Some(picker) => picker,
+            None => return,
+        };
+
+        let num_cols = self.size_info.columns();
+        let bar_line = self.size_info.screen_lines().saturating_sub(1);
+        let fg = config.colors.footer_bar_foreground();
+        let bg = config.colors.footer_bar_background();
+
+        // Render the query like the search bar, with the best matches right above it.
+        let bar_text = Self::format_search(picker.query(), CLIPBOARD_PICKER_LABEL, num_cols);
+        let cursor_column = Column(bar_text.chars().count() - 1);
+        let bar_text = format!("{bar_text:<num_cols$}");
+        let glyph_cache = &mut self.glyph_cache;
+        let point = Point::new(bar_line, Column(0));
+        self.renderer.draw_string(point, fg, bg, bar_text.chars(), &self.size_info, glyph_cache);
+
+        let cursor_point = Point::new(bar_line, cursor_column);
+        let cursor_width = NonZeroU32::new(1).unwrap();
+        let cursor = RenderableCursor::new(cursor_point, CursorShape::Underline, fg, cursor_width);
+        rects.extend(cursor.rects(&self.size_info, config.cursor.thickness()));
+
+        // Scroll the list to keep the selected entry visible.
+        let matches: Vec<_> = picker.matches().collect();
+        let num_lines = CLIPBOARD_PICKER_LINES.min(bar_line);
+        let selected = matches.iter().position(|(_, selected)| *selected).unwrap_or_default();
+        let offset = (selected + 1).saturating_sub(num_lines);
+
+        for (i, (text, selected)) in matches.into_iter().skip(offset).take(num_lines).enumerate() {
+            // Show the entry on a single line.
+            let text: String = text
+                .chars()
+                .map(|c| match c {
+                    '\n' => '↵',
+                    c if c.is_control() => ' ',
+                    c => c,
+                })
+                .collect();
+            let text: String =
+                StrShortener::new(&text, num_cols, ShortenDirection::Right, Some(SHORTENER))
+                    .collect();
+            let text = format!("{text:<num_cols$}");
+
+            let (fg, bg) = if selected { (bg, fg) } else { (fg, bg) };
+            let point = Point::new(bar_line - 1 - i, Column(0));
+            let glyph_cache = &mut self.glyph_cache;
+            self.renderer.draw_string(point, fg, bg, text.chars(), &self.size_info, glyph_cache);
+        }
+    }
+
+    /// Draw the question for a paste waiting for confirmation.
+    #[inline(never)]
+    fn draw_paste_confirmation(&mut self, config: &UiConfig) {
+        let prompt = match &self.pending_paste {
+            Some(paste) => paste.prompt(),
+            None => return,
+        };
+
+        let num_cols = self.size_info.columns();
+        let text: String =
+            StrShortener::new(&prompt, num_cols, ShortenDirection::Right, Some(SHORTENER))
+                .collect();
+        let text = format!("{text:<num_cols$}");
+
+        let point = Point::new(self.size_info.screen_lines().saturating_sub(1), Column(0));
+        let fg = config.colors.footer_bar_foreground();
+        let bg = config.colors.footer_bar_background();
+
+        let glyph_cache = &mut self.glyph_cache;
+        self.renderer.draw_string(point, fg, bg, text.chars(), &self.size_info, glyph_cache);
+    }
+
+    /// Draw the macro recording, active binding modes and the keys of an incomplete key sequence.
+    #[inline(never)]
+    fn draw_binding_state(&mut self, config: &UiConfig) {
+        if self.pending_chord.is_empty()
+            && self.custom_modes.is_empty()
+            && self.macro_recording.is_none()
+        {
+            return;
+        }
+
+        let mut text = String::from(" ");
+        if let Some(recording) = &self.macro_recording {
+            text.push_str(&format!("recording @{} ", recording.register()));
+        }
+        for mode in &self.custom_modes {
+            text.push_str(&format!("[{mode}] "));
+        }
+        if !self.pending_chord.is_empty() {
+            let keys: Vec<_> = self.pending_chord.iter().map(PendingKey::label).collect();
+            text.push_str(&format!("{}, … ", keys.join(", ")));
+        }
+        let text: String =
+            StrShortener::new(&text, self.size_info.columns(), ShortenDirection::Left, None)
+                .collect();
+
+        let width = text.chars().count();
+        let column = Column(self.size_info.columns().saturating_sub(width));
+        let point = Point::new(self.size_info.screen_lines().saturating_sub(1), column);
+
+        // Damage the indicator for current and next frame.
+        let damage =
+            LineDamageBounds::new(point.line, point.column.0, self.size_info.last_column().0);
+        self.damage_tracker.frame().damage_line(damage);
+        self.damage_tracker.next_frame().damage_line(damage);
+
+        let fg = config.colors.footer_bar_foreground();
+        let bg = config.colors.footer_bar_background();
+        let glyph_cache = &mut self.glyph_cache;
+        self.renderer.draw_string(point, fg, bg, text.chars(), &self.size_info, glyph_cache);
+    }
+
     /// Draw render timer.
     #[inline(never)]
     fn draw_render_timer(&mut self, config: &UiConfig) {
@@ -1381,7 +1819,7 @@ impl Display {
     /// This function is for debug purposes only.
     fn highlight_damage(&self, render_rects: &mut Vec<RenderRect>) {
         for damage_rect in &self.damage_tracker.shape_frame_damage(self.size_info.into()) {
-            let x = damage_rect.x as f32;
+            let x = damage_rect.x as f32 - self.size_info.x();
             let height = damage_rect.height as f32;
             let width = damage_rect.width as f32;
             let y = damage_y_to_viewport_y(&self.size_info, damage_rect) as f32;
@@ -1465,12 +1903,20 @@ impl Drop for Display {
         self.make_current();
         unsafe {
             ManuallyDrop::drop(&mut self.renderer);
-            ManuallyDrop::drop(&mut self.context);
             ManuallyDrop::drop(&mut self.surface);
         }
     }
 }
 
+/// Surface the frames are presented to.
+enum RenderSurface {
+    /// OpenGL surface with its context.
+    Gl { context: PossiblyCurrentContext, surface: Surface<WindowSurface> },
+
+    /// Surface filled by the software renderer.
+    Software(SoftwareSurface),
+}
+
 /// Input method state.
 #[derive(Debug, Default)]
 pub struct Ime {
@@ -1605,7 +2051,7 @@ impl FrameTimer {
 ///
 /// This will return a tuple of the cell width and height.
 #[inline]
-fn compute_cell_size(config: &UiConfig, metrics: &crossfont::Metrics) -> (f32, f32) {
+pub fn compute_cell_size(config: &UiConfig, metrics: &crossfont::Metrics) -> (f32, f32) {
     let offset_x = f64::from(config.font.offset.x);
     let offset_y = f64::from(config.font.offset.y);
     (
@@ -1615,7 +2061,7 @@ fn compute_cell_size(config: &UiConfig, metrics: &crossfont::Metrics) -> (f32, f
 }
 
 /// Calculate the size of the window given padding, terminal dimensions and cell size.
-fn window_size(
+pub fn window_size(
     config: &UiConfig,
     dimensions: Dimensions,
     cell_width: f32,
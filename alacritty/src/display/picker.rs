@@ -0,0 +1,154 @@
+//! Overlay for picking an entry of the clipboard history.
+
+use std::cmp::Reverse;
+
+/// Fuzzy picker over the clipboard history.
+pub struct ClipboardPicker {
+    /// History entries, starting with the most recent one.
+    entries: Vec<String>,
+
+    /// Text typed to filter the entries.
+    query: String,
+
+    /// Indices of the entries matching the query, starting with the best match.
+    matches: Vec<usize>,
+
+    /// Index of the selected match.
+    selected: usize,
+}
+
+impl ClipboardPicker {
+    pub fn new(entries: Vec<String>) -> Self {
+        let matches = (0..entries.len()).collect();
+        Self { entries, matches, query: Default::default(), selected: Default::default() }
+    }
+
+    /// Text typed to filter the entries.
+    pub fn query(&self) -> &str {
+        &self.query
+    }
+
+    /// Append a character to the query.
+    pub fn push(&mut self, c: char) {
+        self.query.push(c);
+        self.update_matches();
+    }
+
+    /// Remove the last character of the query.
+    pub fn pop(&mut self) {
+        self.query.pop();
+        self.update_matches();
+    }
+
+    /// Move the selection to the next worse match.
+    pub fn select_next(&mut self) {
+        self.selected = (self.selected + 1).min(self.matches.len().saturating_sub(1));
+    }
+
+    /// Move the selection to the next better match.
+    pub fn select_previous(&mut self) {
+        self.selected = self.selected.saturating_sub(1);
+    }
+
+    /// Text of the selected entry.
+    pub fn selected(&self) -> Option<&str> {
+        self.matches.get(self.selected).map(|&index| self.entries[index].as_str())
+    }
+
+    /// Entries matching the query, starting with the best match.
+    ///
+    /// The boolean is `true` for the selected entry.
+    pub fn matches(&self) -> impl Iterator<Item = (&str, bool)> {
+        self.matches
+            .iter()
+            .enumerate()
+            .map(|(i, &index)| (self.entries[index].as_str(), i == self.selected))
+    }
+
+    /// Filter the entries with the current query.
+    fn update_matches(&mut self) {
+        let mut scored: Vec<_> = self
+            .entries
+            .iter()
+            .enumerate()
+            .filter_map(|(index, entry)| Some((fuzzy_score(&self.query, entry)?, index)))
+            .collect();
+
+        // Sorting is stable, so more recent entries win ties.
+        scored.sort_by_key(|&(score, _)| Reverse(score));
+
+        self.matches = scored.into_iter().map(|(_, index)| index).collect();
+        self.selected = 0;
+    }
+}
+
+/// Score how well text matches a query, ignoring case.
+///
+/// All characters of the query must appear in the text in order. Consecutive characters and
+/// matches close to the start of the text are preferred.
+fn fuzzy_score(query: &str, text: &str) -> Option<i64> {
+    let mut query = query.chars().flat_map(char::to_lowercase).peekable();
+    let mut score = 0;
+    let mut last_match = None;
+
+    for (i, c) in text.chars().flat_map(char::to_lowercase).enumerate() {
+        let Some(&next) = query.peek() else { break };
+        if c != next {
+            continue;
+        }
+
+        score += match last_match {
+            Some(last) if last + 1 == i => 8,
+            Some(_) => 1,
+            None => 4 - (i as i64).min(4),
+        };
+
+        last_match = Some(i);
+        query.next();
+    }
+
+    query.peek().is_none().then_some(score)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn picker(entries: &[&str]) -> ClipboardPicker {
+        ClipboardPicker::new(entries.iter().map(|entry| entry.to_string()).collect())
+    }
+
+    #[test]
+    fn fuzzy_matching() {
+        assert_eq!(fuzzy_score("", "abc"), Some(0));
+        assert!(fuzzy_score("AC", "abc").is_some());
+        assert_eq!(fuzzy_score("ca", "abc"), None);
+        assert!(fuzzy_score("ab", "abc") > fuzzy_score("ac", "abc"));
+        assert!(fuzzy_score("bc", "bcd") > fuzzy_score("bc", "abc"));
+    }
+
+    #[test]
+    fn filter_entries() {
+        let mut picker = picker(&["cargo test", "git status", "cargo build"]);
+        assert_eq!(picker.selected(), Some("cargo test"));
+        assert_eq!(picker.matches().count(), 3);
+
+        picker.push('b');
+        let matches: Vec<_> = picker.matches().map(|(text, _)| text).collect();
+        assert_eq!(matches, ["cargo build"]);
+
+        picker.pop();
+        picker.push('s');
+        picker.push('t');
+        picker.select_next();
+        let matches: Vec<_> = picker.matches().collect();
+        assert_eq!(matches, [("cargo test", false), ("git status", true)]);
+        assert_eq!(picker.selected(), Some("git status"));
+
+        picker.push('x');
+        assert_eq!(picker.selected(), None);
+        picker.select_next();
+        picker.select_previous();
+        assert_eq!(picker.selected(), None);
+    }
+}
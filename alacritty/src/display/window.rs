@@ -32,7 +32,9 @@ use winit::event_loop::ActiveEventLoop;
 use winit::monitor::MonitorHandle;
 #[cfg(windows)]
 use winit::platform::windows::{IconExtWindows, WindowAttributesExtWindows};
-use winit::raw_window_handle::{HasWindowHandle, RawWindowHandle};
+use winit::raw_window_handle::{
+    HasDisplayHandle, HasWindowHandle, RawDisplayHandle, RawWindowHandle,
+};
 use winit::window::{
     CursorIcon, Fullscreen, ImePurpose, Theme, UserAttentionType, Window as WinitWindow,
     WindowAttributes, WindowId,
@@ -219,6 +221,11 @@ impl Window {
         self.window.window_handle().unwrap().as_raw()
     }
 
+    #[inline]
+    pub fn raw_display_handle(&self) -> RawDisplayHandle {
+        self.window.display_handle().unwrap().as_raw()
+    }
+
     #[inline]
     pub fn request_inner_size(&self, size: PhysicalSize<u32>) {
         let _ = self.window.request_inner_size(size);
@@ -445,9 +452,11 @@ impl Window {
         // NOTE: X11 doesn't support cursor area, so we need to offset manually to not obscure
         // the text.
         let offset = if self.is_x11 { 1 } else { 0 };
-        let nspot_x = f64::from(size.padding_x() + point.column.0 as f32 * size.cell_width());
-        let nspot_y =
-            f64::from(size.padding_y() + (point.line + offset) as f32 * size.cell_height());
+        let nspot_x =
+            f64::from(size.x() + size.padding_x() + point.column.0 as f32 * size.cell_width());
+        let nspot_y = f64::from(
+            size.y() + size.padding_y() + (point.line + offset) as f32 * size.cell_height(),
+        );
 
         // NOTE: some compositors don't like excluding too much and try to render popup at the
         // bottom right corner of the provided area, so exclude just the full-width char to not
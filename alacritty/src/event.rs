@@ -1,7 +1,6 @@
 //! Process window events.
 
 use crate::ConfigMonitor;
-use glutin::config::GetGlConfig;
 use std::borrow::Cow;
 use std::cmp::min;
 use std::collections::hash_map::Entry;
@@ -15,14 +14,13 @@ use std::os::unix::io::RawFd;
 use std::os::unix::net::UnixStream;
 use std::path::PathBuf;
 use std::rc::Rc;
-#[cfg(unix)]
 use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::time::{Duration, Instant};
-use std::{env, f32, mem};
+use std::{env, f32, iter, mem};
 
 use ahash::RandomState;
 use crossfont::Size as FontSize;
-use glutin::config::Config as GlutinConfig;
 use glutin::display::GetGlDisplay;
 use log::{debug, error, info, warn};
 use winit::application::ApplicationHandler;
@@ -41,28 +39,42 @@ use alacritty_terminal::index::{Boundary, Column, Direction, Line, Point, Side};
 use alacritty_terminal::selection::{Selection, SelectionType};
 use alacritty_terminal::term::cell::Flags;
 use alacritty_terminal::term::search::{Match, RegexSearch};
-use alacritty_terminal::term::{self, ClipboardType, Term, TermMode};
+use alacritty_terminal::term::{self, ClipboardType, Term, TermMode, Urgency};
 use alacritty_terminal::vte::ansi::NamedColor;
 
 #[cfg(unix)]
-use crate::cli::{IpcConfig, ParsedOptions};
-use crate::cli::{Options as CliOptions, WindowOptions};
+use crate::cli::{IpcConfig, IpcGetText, ParsedOptions, SubscriptionKind};
+use crate::cli::{Options as CliOptions, Subcommands, WindowOptions};
 use crate::clipboard::Clipboard;
 use crate::config::ui_config::{HintAction, HintInternalAction};
 use crate::config::{self, UiConfig};
-#[cfg(not(windows))]
-use crate::daemon::foreground_process_path;
 use crate::daemon::spawn_daemon;
+#[cfg(not(windows))]
+use crate::daemon::working_directory;
+#[cfg(unix)]
+use crate::display::color::List;
 use crate::display::color::Rgb;
 use crate::display::hint::HintMatch;
 use crate::display::window::Window;
 use crate::display::{Display, Preedit, SizeInfo};
+#[cfg(unix)]
+use crate::export::{Format, Region};
 use crate::input::{self, ActionContext as _, FONT_SIZE_STEP};
 #[cfg(unix)]
-use crate::ipc::{self, SocketReply};
+use crate::ipc::{self, IpcEvent, SocketReply, Subscriptions};
 use crate::logging::{LOG_TARGET_CONFIG, LOG_TARGET_WINIT};
+use crate::macros::{MacroRecording, Macros};
 use crate::message_bar::{Message, MessageBuffer};
+use crate::notification::{self, RateLimiter};
+#[cfg(unix)]
+use crate::pane::Pane;
+use crate::pane::{PaneAction, PaneId};
+use crate::paste::{self, PendingPaste};
+use crate::recording::Recording;
+use crate::renderer::platform::GraphicsPlatform;
 use crate::scheduler::{Scheduler, TimerId, Topic};
+use crate::screenshot::Screenshot;
+use crate::session::{self, SavedWindow};
 use crate::window_context::WindowContext;
 
 /// Duration after the last user input until an unlimited search is performed.
@@ -88,14 +100,19 @@ pub struct Processor {
     pub config_monitor: Option<ConfigMonitor>,
 
     clipboard: Clipboard,
+    macros: Macros,
     scheduler: Scheduler,
     initial_window_options: Option<WindowOptions>,
     initial_window_error: Option<Box<dyn Error>>,
     windows: HashMap<WindowId, WindowContext, RandomState>,
     proxy: EventLoopProxy<Event>,
-    gl_config: Option<GlutinConfig>,
+    graphics_platform: Option<GraphicsPlatform>,
     #[cfg(unix)]
     global_ipc_options: ParsedOptions,
+    #[cfg(unix)]
+    sessions: HashMap<PaneId, Pane>,
+    #[cfg(unix)]
+    subscriptions: Option<Subscriptions>,
     cli_options: CliOptions,
     config: Rc<UiConfig>,
 }
@@ -118,6 +135,8 @@ impl Processor {
         // which is done in `loop_exiting`.
         let clipboard = unsafe { Clipboard::new(event_loop.display_handle().unwrap().as_raw()) };
 
+        let macros = Macros::new(&config.macros);
+
         // Create a config monitor.
         //
         // The monitor watches the config file for changes and reloads it. Pending
@@ -134,12 +153,17 @@ impl Processor {
             cli_options,
             proxy,
             scheduler,
-            gl_config: None,
+            graphics_platform: None,
             config: Rc::new(config),
             clipboard,
+            macros,
             windows: Default::default(),
             #[cfg(unix)]
             global_ipc_options: Default::default(),
+            #[cfg(unix)]
+            sessions: Default::default(),
+            #[cfg(unix)]
+            subscriptions: Default::default(),
             config_monitor,
         }
     }
@@ -152,16 +176,25 @@ impl Processor {
         &mut self,
         event_loop: &ActiveEventLoop,
         window_options: WindowOptions,
+        saved_window: Option<SavedWindow>,
     ) -> Result<(), Box<dyn Error>> {
+        // Replace the initial window's PTY with the replayed recording.
+        let replay = match self.cli_options.subcommands.take() {
+            Some(Subcommands::Replay(replay)) => Some(replay),
+            _ => None,
+        };
+
         let window_context = WindowContext::initial(
             event_loop,
             self.proxy.clone(),
             self.config.clone(),
             window_options,
+            replay,
+            saved_window,
         )?;
 
-        self.gl_config = Some(window_context.display.gl_context().config());
-        self.windows.insert(window_context.id(), window_context);
+        self.graphics_platform = Some(window_context.display.graphics_platform());
+        self.add_window(window_context);
 
         Ok(())
     }
@@ -171,8 +204,9 @@ impl Processor {
         &mut self,
         event_loop: &ActiveEventLoop,
         options: WindowOptions,
+        saved_window: Option<SavedWindow>,
     ) -> Result<(), Box<dyn Error>> {
-        let gl_config = self.gl_config.as_ref().unwrap();
+        let platform = self.graphics_platform.as_ref().unwrap();
 
         // Override config with CLI/IPC options.
         let mut config_overrides = options.config_overrides();
@@ -182,15 +216,188 @@ impl Processor {
         config = config_overrides.override_config_rc(config);
 
         let window_context = WindowContext::additional(
-            gl_config,
+            platform,
             event_loop,
             self.proxy.clone(),
             config,
             options,
             config_overrides,
+            saved_window,
         )?;
 
-        self.windows.insert(window_context.id(), window_context);
+        self.add_window(window_context);
+        Ok(())
+    }
+
+    /// Recreate the windows of the previous session.
+    ///
+    /// Returns `false` if there was no session to restore.
+    fn restore_session(
+        &mut self,
+        event_loop: &ActiveEventLoop,
+        window_options: &WindowOptions,
+    ) -> Result<bool, Box<dyn Error>> {
+        // Don't restore the session over an explicit command or replay.
+        if !self.config.session.restore
+            || window_options.terminal_options.command().is_some()
+            || self.cli_options.subcommands.is_some()
+        {
+            return Ok(false);
+        }
+
+        let saved_windows = match session::load() {
+            Ok(saved_windows) => saved_windows,
+            Err(err) => {
+                error!("Could not restore session: {err}");
+                return Ok(false);
+            },
+        };
+
+        for saved_window in saved_windows {
+            let mut options = window_options.clone();
+            if let Some(working_directory) = &saved_window.working_directory {
+                options.terminal_options.working_directory = Some(working_directory.clone());
+            }
+
+            if self.graphics_platform.is_none() {
+                self.create_initial_window(event_loop, options, Some(saved_window))?;
+                continue;
+            }
+
+            for window_context in self.windows.values_mut() {
+                window_context.display.make_not_current();
+            }
+
+            if let Err(err) = self.create_window(event_loop, options, Some(saved_window)) {
+                error!("Could not restore window: {err:?}");
+            }
+        }
+
+        Ok(self.graphics_platform.is_some())
+    }
+
+    /// Save windows for restoring them on the next start.
+    fn save_session<'a>(&self, windows: impl Iterator<Item = &'a WindowContext>) {
+        if !self.config.session.restore || self.cli_options.daemon {
+            return;
+        }
+
+        let saved_windows = windows.map(WindowContext::saved_window).collect();
+        if let Err(err) = session::save(saved_windows) {
+            error!("Could not save session: {err}");
+        }
+    }
+
+    /// Start tracking a new window.
+    fn add_window(&mut self, window_context: WindowContext) {
+        let window_id = window_context.id();
+        self.windows.insert(window_id, window_context);
+
+        #[cfg(unix)]
+        self.publish(IpcEvent::WindowCreated { window_id: window_id.into() });
+    }
+
+    /// Window targeted by an IPC request.
+    ///
+    /// Requests without a window ID use the focused window, or the only window if there is one.
+    #[cfg(unix)]
+    fn ipc_window(&mut self, window_id: Option<&WindowId>) -> Option<&mut WindowContext> {
+        match window_id {
+            Some(window_id) => self.windows.get_mut(window_id),
+            None if self.windows.len() == 1 => self.windows.values_mut().next(),
+            None => self.windows.values_mut().find(|window| window.is_focused()),
+        }
+    }
+
+    /// Send an event to all IPC subscribers.
+    #[cfg(unix)]
+    fn publish(&self, event: IpcEvent) {
+        if let Some(subscriptions) = &self.subscriptions {
+            subscriptions.publish(event);
+        }
+    }
+
+    /// Send terminal events relevant to IPC subscribers.
+    #[cfg(unix)]
+    fn publish_terminal_event(&self, window_id: WindowId, payload: &EventType) {
+        let window_context = match (&self.subscriptions, self.windows.get(&window_id)) {
+            (Some(_), Some(window_context)) => window_context,
+            _ => return,
+        };
+
+        let window_id = window_id.into();
+        let event = match payload {
+            EventType::Terminal(TerminalEvent::Title(title)) => {
+                IpcEvent::Title { window_id, title: title.clone() }
+            },
+            EventType::Terminal(TerminalEvent::ResetTitle) => {
+                let title = window_context.config().window.identity.title.clone();
+                IpcEvent::Title { window_id, title }
+            },
+            EventType::Terminal(TerminalEvent::Bell) => IpcEvent::Bell { window_id },
+            EventType::Terminal(TerminalEvent::ChildExit(code)) => {
+                IpcEvent::ChildExit { window_id, code: *code }
+            },
+            _ => return,
+        };
+
+        self.publish(event);
+    }
+
+    /// Close a window, keeping its terminals alive as detached sessions.
+    ///
+    /// Returns `false` if the window's terminals should be shut down instead.
+    #[cfg(unix)]
+    fn detach_window(&mut self, window_id: WindowId) -> bool {
+        // Terminals of windows which outlive their shell are never detached.
+        let window_context = match self.windows.entry(window_id) {
+            Entry::Occupied(window_context)
+                if self.cli_options.daemon
+                    && window_context.get().config().general.detach_on_close
+                    && !window_context.get().display.window.hold =>
+            {
+                window_context.remove()
+            },
+            _ => return false,
+        };
+
+        // Unschedule pending events.
+        self.scheduler.unschedule_window(window_id);
+
+        self.publish(IpcEvent::WindowClosed { window_id: window_id.into() });
+
+        for pane in window_context.detach() {
+            info!("Detached session {}", usize::from(pane.id));
+            self.sessions.insert(pane.id, pane);
+        }
+
+        true
+    }
+
+    /// Open a new window for a detached terminal.
+    #[cfg(unix)]
+    fn attach_session(
+        &mut self,
+        event_loop: &ActiveEventLoop,
+        session_id: PaneId,
+    ) -> Result<(), Box<dyn Error>> {
+        let platform = match &self.graphics_platform {
+            Some(platform) if self.sessions.contains_key(&session_id) => platform,
+            _ => return Err(format!("no detached session {}", usize::from(session_id)).into()),
+        };
+
+        // XXX Ensure that no context is current when creating a new window, see `CreateWindow`.
+        for window_context in self.windows.values_mut() {
+            window_context.display.make_not_current();
+        }
+
+        let config = self.global_ipc_options.override_config_rc(self.config.clone());
+        let window_context =
+            WindowContext::attach(platform, event_loop, config, &mut self.sessions, session_id)?;
+
+        info!("Attached session {}", usize::from(session_id));
+        self.add_window(window_context);
+
         Ok(())
     }
 
@@ -236,7 +443,12 @@ impl ApplicationHandler<Event> for Processor {
         }
 
         if let Some(window_options) = self.initial_window_options.take() {
-            if let Err(err) = self.create_initial_window(event_loop, window_options) {
+            let result = match self.restore_session(event_loop, &window_options) {
+                Ok(false) => self.create_initial_window(event_loop, window_options, None),
+                result => result.map(|_| ()),
+            };
+
+            if let Err(err) = result {
                 self.initial_window_error = Some(err);
                 event_loop.exit();
                 return;
@@ -261,6 +473,17 @@ impl ApplicationHandler<Event> for Processor {
             return;
         }
 
+        // Keep the terminals of closed windows running in the background.
+        #[cfg(unix)]
+        if matches!(event, WindowEvent::CloseRequested) && self.detach_window(window_id) {
+            return;
+        }
+
+        #[cfg(unix)]
+        if let WindowEvent::Focused(focused) = event {
+            self.publish(IpcEvent::WindowFocused { window_id: window_id.into(), focused });
+        }
+
         let window_context = match self.windows.get_mut(&window_id) {
             Some(window_context) => window_context,
             None => return,
@@ -273,6 +496,7 @@ impl ApplicationHandler<Event> for Processor {
             _event_loop,
             &self.proxy,
             &mut self.clipboard,
+            &mut self.macros,
             &mut self.scheduler,
             WinitEvent::WindowEvent { window_id, event },
         );
@@ -287,6 +511,40 @@ impl ApplicationHandler<Event> for Processor {
             info!(target: LOG_TARGET_WINIT, "{event:?}");
         }
 
+        let pane_id = event.pane_id;
+
+        // Handle events of detached terminals.
+        //
+        // Queries are answered like they would be by an unfocused window, all other events only
+        // affect a window and are dropped.
+        #[cfg(unix)]
+        if let Some(pane) = pane_id.and_then(|id| self.sessions.get(&id)) {
+            match event.payload {
+                EventType::Terminal(TerminalEvent::PtyWrite(text)) => {
+                    pane.notifier.notify(text.into_bytes());
+                },
+                EventType::Terminal(TerminalEvent::ColorRequest(index, format)) => {
+                    let color = match pane.terminal.lock().colors()[index] {
+                        Some(color) => Rgb(color),
+                        // Ignore cursor color requests unless it was changed.
+                        None if index == NamedColor::Cursor as usize => return,
+                        None => List::from(&self.config.colors)[index],
+                    };
+                    pane.notifier.notify(format(color.0).into_bytes());
+                },
+                EventType::Terminal(TerminalEvent::TextAreaSizeRequest(format)) => {
+                    pane.notifier.notify(format(pane.size_info.into()).into_bytes());
+                },
+                EventType::Terminal(TerminalEvent::Exit) => {
+                    let id = pane.id;
+                    info!("Detached session {} exited", usize::from(id));
+                    self.sessions.remove(&id);
+                },
+                _ => (),
+            }
+            return;
+        }
+
         // Handle events which don't mandate the WindowId.
         match (event.payload, event.window_id.as_ref()) {
             // Process IPC config update.
@@ -340,6 +598,129 @@ impl ApplicationHandler<Event> for Processor {
                     ipc::send_reply(&mut stream, SocketReply::GetConfig(config_json));
                 }
             },
+            #[cfg(unix)]
+            (EventType::IpcGetWorkingDirectory(stream), window_id) => {
+                let working_directory =
+                    self.ipc_window(window_id).and_then(|window| window.working_directory());
+
+                if let Ok(mut stream) = stream.try_clone() {
+                    let reply = SocketReply::GetWorkingDirectory(working_directory);
+                    ipc::send_reply(&mut stream, reply);
+                }
+            },
+            #[cfg(unix)]
+            (EventType::IpcSendText(text, stream), window_id) => match self.ipc_window(window_id) {
+                Some(window_context) => window_context.send_text(text),
+                None => {
+                    if let Ok(mut stream) = stream.try_clone() {
+                        let reply = SocketReply::Error("window not found".into());
+                        ipc::send_reply(&mut stream, reply);
+                    }
+                },
+            },
+            #[cfg(unix)]
+            (EventType::IpcGetText(get_text, stream), window_id) => {
+                let region = if get_text.selection {
+                    Region::Selection
+                } else if get_text.scrollback {
+                    Region::History
+                } else {
+                    Region::Viewport
+                };
+                let format = if get_text.html {
+                    Format::Html
+                } else if get_text.ansi {
+                    Format::Ansi
+                } else {
+                    Format::Text
+                };
+
+                let reply = match self.ipc_window(window_id) {
+                    Some(window_context) => match window_context.text(region, format) {
+                        Some(text) => SocketReply::GetText(text),
+                        None => SocketReply::Error("no text selected".into()),
+                    },
+                    None => SocketReply::Error("window not found".into()),
+                };
+
+                if let Ok(mut stream) = stream.try_clone() {
+                    ipc::send_reply(&mut stream, reply);
+                }
+            },
+            #[cfg(unix)]
+            (EventType::IpcScreenshot(path, stream), window_id) => {
+                match self.ipc_window(window_id) {
+                    // Occluded windows are not drawn, so the screenshot would never be taken.
+                    Some(window_context) if window_context.is_occluded() => {
+                        Screenshot::with_reply(path, stream).cancel();
+                    },
+                    Some(window_context) => {
+                        let screenshot = Screenshot::with_reply(path, stream);
+                        window_context.display.pending_screenshots.push(screenshot);
+                        window_context.dirty = true;
+                        if window_context.display.window.has_frame {
+                            window_context.display.window.request_redraw();
+                        }
+                    },
+                    None => {
+                        if let Ok(mut stream) = stream.try_clone() {
+                            let reply = SocketReply::Error("window not found".into());
+                            ipc::send_reply(&mut stream, reply);
+                        }
+                    },
+                }
+            },
+            #[cfg(unix)]
+            (EventType::IpcSubscribe(kinds, stream), _) => {
+                if let Ok(stream) = stream.try_clone() {
+                    let subscriptions = self.subscriptions.get_or_insert_with(Subscriptions::new);
+                    subscriptions.subscribe(stream, kinds);
+                }
+            },
+            #[cfg(unix)]
+            (EventType::IpcListWindows(stream), _) => {
+                let mut windows: Vec<_> = self.windows.values().map(WindowContext::info).collect();
+                windows.sort_unstable_by_key(|window| window.id);
+
+                if let Ok(mut stream) = stream.try_clone() {
+                    ipc::send_reply(&mut stream, SocketReply::ListWindows(windows));
+                }
+            },
+            #[cfg(unix)]
+            (EventType::IpcListSessions(stream), _) => {
+                let mut sessions: Vec<_> = self
+                    .sessions
+                    .values()
+                    .map(|pane| {
+                        let terminal = pane.terminal.lock();
+                        let working_directory = working_directory(
+                            terminal.working_directory(),
+                            pane.master_fd,
+                            pane.shell_pid,
+                        );
+
+                        ipc::Session {
+                            id: pane.id.into(),
+                            title: terminal.title().map(String::from),
+                            working_directory,
+                        }
+                    })
+                    .collect();
+                sessions.sort_unstable_by_key(|session| session.id);
+
+                if let Ok(mut stream) = stream.try_clone() {
+                    ipc::send_reply(&mut stream, SocketReply::ListSessions(sessions));
+                }
+            },
+            // Open a new window for a detached terminal.
+            #[cfg(unix)]
+            (EventType::IpcAttach(session_id, stream), _) => {
+                if let Err(err) = self.attach_session(event_loop, PaneId::from(session_id)) {
+                    if let Ok(mut stream) = stream.try_clone() {
+                        ipc::send_reply(&mut stream, SocketReply::Error(err.to_string()));
+                    }
+                }
+            },
             (EventType::ConfigReload(path), _) => {
                 // Clear config logs from message bar for all terminals.
                 for window_context in self.windows.values_mut() {
@@ -367,6 +748,9 @@ impl ApplicationHandler<Event> for Processor {
                     for window_context in self.windows.values_mut() {
                         window_context.update_config(self.config.clone());
                     }
+
+                    #[cfg(unix)]
+                    self.publish(IpcEvent::ConfigReload);
                 }
             },
             // Create a new terminal window.
@@ -379,16 +763,19 @@ impl ApplicationHandler<Event> for Processor {
                     window_context.display.make_not_current();
                 }
 
-                if self.gl_config.is_none() {
+                if self.graphics_platform.is_none() {
                     // Handle initial window creation in daemon mode.
-                    if let Err(err) = self.create_initial_window(event_loop, options) {
+                    if let Err(err) = self.create_initial_window(event_loop, options, None) {
                         self.initial_window_error = Some(err);
                         event_loop.exit();
                     }
-                } else if let Err(err) = self.create_window(event_loop, options) {
+                } else if let Err(err) = self.create_window(event_loop, options, None) {
                     error!("Could not open window: {err:?}");
                 }
             },
+            // Forward window events to IPC subscribers.
+            #[cfg(unix)]
+            (EventType::IpcEvent(event), _) => self.publish(event),
             // Process events affecting all windows.
             (payload, None) => {
                 let event = WinitEvent::UserEvent(Event::new(payload, None));
@@ -398,6 +785,7 @@ impl ApplicationHandler<Event> for Processor {
                         event_loop,
                         &self.proxy,
                         &mut self.clipboard,
+                        &mut self.macros,
                         &mut self.scheduler,
                         event.clone(),
                     );
@@ -412,6 +800,13 @@ impl ApplicationHandler<Event> for Processor {
                 }
             },
             (EventType::Terminal(TerminalEvent::Exit), Some(window_id)) => {
+                // Only close the exited pane if there are others left.
+                if let Some(window_context) = self.windows.get_mut(window_id) {
+                    if window_context.close_pane(pane_id) {
+                        return;
+                    }
+                }
+
                 // Remove the closed terminal.
                 let window_context = match self.windows.entry(*window_id) {
                     // Don't exit when terminal exits if user asked to hold the window.
@@ -426,6 +821,9 @@ impl ApplicationHandler<Event> for Processor {
                 // Unschedule pending events.
                 self.scheduler.unschedule_window(window_context.id());
 
+                #[cfg(unix)]
+                self.publish(IpcEvent::WindowClosed { window_id: (*window_id).into() });
+
                 // Shutdown if no more terminals are open.
                 if self.windows.is_empty() && !self.cli_options.daemon {
                     // Write ref tests of last window to disk.
@@ -433,6 +831,8 @@ impl ApplicationHandler<Event> for Processor {
                         window_context.write_ref_test_results();
                     }
 
+                    self.save_session(iter::once(&window_context));
+
                     event_loop.exit();
                 }
             },
@@ -446,14 +846,22 @@ impl ApplicationHandler<Event> for Processor {
                 }
             },
             (payload, Some(window_id)) => {
+                #[cfg(unix)]
+                self.publish_terminal_event(*window_id, &payload);
+
                 if let Some(window_context) = self.windows.get_mut(window_id) {
                     window_context.handle_event(
                         #[cfg(target_os = "macos")]
                         event_loop,
                         &self.proxy,
                         &mut self.clipboard,
+                        &mut self.macros,
                         &mut self.scheduler,
-                        WinitEvent::UserEvent(Event::new(payload, *window_id)),
+                        WinitEvent::UserEvent(Event {
+                            window_id: Some(*window_id),
+                            pane_id,
+                            payload,
+                        }),
                     );
                 }
             },
@@ -472,6 +880,7 @@ impl ApplicationHandler<Event> for Processor {
                 event_loop,
                 &self.proxy,
                 &mut self.clipboard,
+                &mut self.macros,
                 &mut self.scheduler,
                 WinitEvent::AboutToWait,
             );
@@ -491,7 +900,16 @@ impl ApplicationHandler<Event> for Processor {
             info!("Exiting the event loop");
         }
 
-        match self.gl_config.take().map(|config| config.display()) {
+        // Save windows which are still open, since the last closed window is saved on exit.
+        if !self.windows.is_empty() {
+            self.save_session(self.windows.values());
+        }
+
+        let gl_config = match self.graphics_platform.take() {
+            Some(GraphicsPlatform::Gl(gl_config)) => Some(gl_config),
+            _ => None,
+        };
+        match gl_config.map(|config| config.display()) {
             #[cfg(not(target_os = "macos"))]
             Some(glutin::display::Display::Egl(display)) => {
                 // Ensure that all the windows are dropped, so the destructors for
@@ -519,13 +937,26 @@ pub struct Event {
     /// Limit event to a specific window.
     window_id: Option<WindowId>,
 
+    /// Pane inside the window which sent the event.
+    pane_id: Option<PaneId>,
+
     /// Event payload.
     payload: EventType,
 }
 
 impl Event {
     pub fn new<I: Into<Option<WindowId>>>(payload: EventType, window_id: I) -> Self {
-        Self { window_id: window_id.into(), payload }
+        Self { window_id: window_id.into(), pane_id: None, payload }
+    }
+
+    /// Pane which sent the event.
+    pub fn pane_id(&self) -> Option<PaneId> {
+        self.pane_id
+    }
+
+    /// Event payload.
+    pub fn payload(&self) -> &EventType {
+        &self.payload
     }
 }
 
@@ -547,10 +978,31 @@ pub enum EventType {
     IpcConfig(IpcConfig),
     #[cfg(unix)]
     IpcGetConfig(Arc<UnixStream>),
+    #[cfg(unix)]
+    IpcGetWorkingDirectory(Arc<UnixStream>),
+    #[cfg(unix)]
+    IpcListSessions(Arc<UnixStream>),
+    #[cfg(unix)]
+    IpcAttach(usize, Arc<UnixStream>),
+    #[cfg(unix)]
+    IpcSendText(String, Arc<UnixStream>),
+    #[cfg(unix)]
+    IpcGetText(IpcGetText, Arc<UnixStream>),
+    #[cfg(unix)]
+    IpcScreenshot(PathBuf, Arc<UnixStream>),
+    #[cfg(unix)]
+    IpcListWindows(Arc<UnixStream>),
+    #[cfg(unix)]
+    IpcSubscribe(Vec<SubscriptionKind>, Arc<UnixStream>),
+    #[cfg(unix)]
+    IpcEvent(IpcEvent),
     BlinkCursor,
     BlinkCursorTimeout,
     SearchNext,
+    ChordTimeout,
     Frame,
+    ResizeTerminal(config::window::Dimensions),
+    Pane(PaneAction),
 }
 
 impl From<TerminalEvent> for EventType {
@@ -659,6 +1111,7 @@ pub struct ActionContext<'a, N, T> {
     pub notifier: &'a mut N,
     pub terminal: &'a mut Term<T>,
     pub clipboard: &'a mut Clipboard,
+    pub macros: &'a mut Macros,
     pub mouse: &'a mut Mouse,
     pub touch: &'a mut TouchPurpose,
     pub modifiers: &'a mut Modifiers,
@@ -667,6 +1120,8 @@ pub struct ActionContext<'a, N, T> {
     pub config: &'a UiConfig,
     pub cursor_blink_timed_out: &'a mut bool,
     pub prev_bell_cmd: &'a mut Option<Instant>,
+    pub notification_limiter: &'a mut RateLimiter,
+    pub recording: &'a mut Recording,
     #[cfg(target_os = "macos")]
     pub event_loop: &'a ActiveEventLoop,
     pub event_proxy: &'a EventLoopProxy<Event>,
@@ -742,6 +1197,12 @@ impl<'a, N: Notify + 'a, T: EventListener> input::ActionContext<T> for ActionCon
             None => return,
         };
 
+        // Every mouse selection would flood the history, so only copies are added by default.
+        let config = &self.config.clipboard;
+        if ty == ClipboardType::Clipboard || config.history_selection {
+            self.clipboard.push_history(&text, config.history);
+        }
+
         if ty == ClipboardType::Selection && self.config.selection.save_to_clipboard {
             self.clipboard.store(ClipboardType::Clipboard, text.clone());
         }
@@ -870,6 +1331,12 @@ impl<'a, N: Notify + 'a, T: EventListener> input::ActionContext<T> for ActionCon
                 continue;
             }
 
+            // Don't overwrite the recording of this instance.
+            if arg == "--record" {
+                let _ = env_args.next();
+                continue;
+            }
+
             args.push(arg);
         }
 
@@ -879,8 +1346,7 @@ impl<'a, N: Notify + 'a, T: EventListener> input::ActionContext<T> for ActionCon
     #[cfg(not(windows))]
     fn create_new_window(&mut self, #[cfg(target_os = "macos")] tabbing_id: Option<String>) {
         let mut options = WindowOptions::default();
-        options.terminal_options.working_directory =
-            foreground_process_path(self.master_fd, self.shell_pid).ok();
+        options.terminal_options.working_directory = self.working_directory();
 
         #[cfg(target_os = "macos")]
         {
@@ -897,13 +1363,68 @@ impl<'a, N: Notify + 'a, T: EventListener> input::ActionContext<T> for ActionCon
             .send_event(Event::new(EventType::CreateWindow(WindowOptions::default()), None));
     }
 
+    fn toggle_recording(&mut self) {
+        self.recording.toggle();
+    }
+
+    fn start_macro_recording(&mut self, register: String) {
+        self.stop_macro_recording();
+        self.display.macro_recording = Some(MacroRecording::new(register));
+        *self.dirty = true;
+    }
+
+    fn stop_macro_recording(&mut self) {
+        if let Some(recording) = self.display.macro_recording.take() {
+            let (register, input) = recording.finish();
+            self.macros.store(&self.config.macros, register, input);
+            *self.dirty = true;
+        }
+    }
+
+    fn play_macro(&mut self, register: &str, count: usize) {
+        let input = match self.macros.get(register) {
+            Some(input) if !input.is_empty() => input.to_owned(),
+            _ => {
+                info!("Macro register {register:?} is empty");
+                return;
+            },
+        };
+
+        self.on_terminal_input_start();
+        for _ in 0..count {
+            // Include the played input in the macro being recorded, so it is replayed as well.
+            if let Some(recording) = &mut self.display.macro_recording {
+                recording.push(input.as_bytes());
+            }
+            self.write_to_pty(input.clone().into_bytes());
+        }
+    }
+
+    fn screenshot(&mut self) {
+        let directory = match self.config.general.save_directory() {
+            Ok(directory) => directory,
+            Err(err) => {
+                error!("Unable to create screenshot directory: {err}");
+                return;
+            },
+        };
+
+        self.display.pending_screenshots.push(Screenshot::timestamped(&directory));
+        *self.dirty = true;
+    }
+
+    fn pane_action(&mut self, action: PaneAction) {
+        let event = Event::new(EventType::Pane(action), self.display.window.id());
+        let _ = self.event_proxy.send_event(event);
+    }
+
     fn spawn_daemon<I, S>(&self, program: &str, args: I)
     where
         I: IntoIterator<Item = S> + Debug + Copy,
         S: AsRef<OsStr>,
     {
         #[cfg(not(windows))]
-        let result = spawn_daemon(program, args, self.master_fd, self.shell_pid);
+        let result = spawn_daemon(program, args, self.working_directory());
         #[cfg(windows)]
         let result = spawn_daemon(program, args);
 
@@ -1170,10 +1691,16 @@ impl<'a, N: Notify + 'a, T: EventListener> input::ActionContext<T> for ActionCon
 
     /// Find the next search match.
     fn search_next(&mut self, origin: Point, direction: Direction, side: Side) -> Option<Match> {
-        self.search_state
+        let regex_match = self
+            .search_state
             .dfas
             .as_mut()
-            .and_then(|dfas| self.terminal.search_next(dfas, origin, direction, side, None))
+            .and_then(|dfas| self.terminal.search_next(dfas, origin, direction, side, None));
+
+        // Drop history lines decompressed by the search.
+        self.terminal.grid_mut().evict_history();
+
+        regex_match
     }
 
     #[inline]
@@ -1181,6 +1708,11 @@ impl<'a, N: Notify + 'a, T: EventListener> input::ActionContext<T> for ActionCon
         self.search_state.direction
     }
 
+    #[inline]
+    fn custom_modes(&self) -> &[String] {
+        &self.display.custom_modes
+    }
+
     #[inline]
     fn search_active(&self) -> bool {
         self.search_state.history_index.is_some()
@@ -1246,6 +1778,7 @@ impl<'a, N: Notify + 'a, T: EventListener> input::ActionContext<T> for ActionCon
             },
             // Copy the text to the clipboard.
             HintAction::Action(HintInternalAction::Copy) => {
+                self.clipboard.push_history(&text, self.config.clipboard.history);
                 self.clipboard.store(ClipboardType::Clipboard, text);
             },
             // Write the text to the PTY/search.
@@ -1368,41 +1901,30 @@ impl<'a, N: Notify + 'a, T: EventListener> input::ActionContext<T> for ActionCon
             }
         } else if self.inline_search_state.char_pending {
             self.inline_search_input(text);
-        } else if bracketed && self.terminal().mode().contains(TermMode::BRACKETED_PASTE) {
-            self.on_terminal_input_start();
-
-            self.write_to_pty(&b"\x1b[200~"[..]);
-
-            // Write filtered escape sequences.
-            //
-            // We remove `\x1b` to ensure it's impossible for the pasted text to write the bracketed
-            // paste end escape `\x1b[201~` and `\x03` since some shells incorrectly terminate
-            // bracketed paste when they receive it.
-            let filtered = text.replace(['\x1b', '\x03'], "");
-            self.write_to_pty(filtered.into_bytes());
-
-            self.write_to_pty(&b"\x1b[201~"[..]);
-        } else {
-            self.on_terminal_input_start();
+        } else if bracketed {
+            let text = paste::filter(&self.config.paste, text);
 
-            let payload = if bracketed {
-                // In non-bracketed (ie: normal) mode, terminal applications cannot distinguish
-                // pasted data from keystrokes.
-                //
-                // In theory, we should construct the keystrokes needed to produce the data we are
-                // pasting... since that's neither practical nor sensible (and probably an
-                // impossible task to solve in a general way), we'll just replace line breaks
-                // (windows and unix style) with a single carriage return (\r, which is what the
-                // Enter key produces).
-                text.replace("\r\n", "\r").replace('\n', "\r").into_bytes()
+            // Ask before pasting text which could run commands or take long to write.
+            let bracketed_mode = self.terminal.mode().contains(TermMode::BRACKETED_PASTE);
+            if paste::needs_confirmation(&self.config.paste, &text, bracketed_mode) {
+                self.display.pending_paste = Some(PendingPaste::new(text.into_owned()));
+                *self.dirty = true;
             } else {
-                // When we explicitly disable bracketed paste don't manipulate with the input,
-                // so we pass user input as is.
-                text.to_owned().into_bytes()
-            };
+                self.write_paste(&text, true);
+            }
+        } else {
+            self.write_paste(text, false);
+        }
+    }
 
-            self.write_to_pty(payload);
+    /// Process the user's answer to a paste confirmation.
+    fn confirm_paste(&mut self, confirmed: bool) {
+        if let Some(paste) = self.display.pending_paste.take() {
+            if confirmed {
+                self.write_paste(paste.text(), true);
+            }
         }
+        *self.dirty = true;
     }
 
     /// Toggle the vi mode status.
@@ -1495,6 +2017,51 @@ impl<'a, N: Notify + 'a, T: EventListener> input::ActionContext<T> for ActionCon
 }
 
 impl<'a, N: Notify + 'a, T: EventListener> ActionContext<'a, N, T> {
+    /// Write pasted text to the PTY.
+    fn write_paste(&mut self, text: &str, bracketed: bool) {
+        if bracketed && self.terminal().mode().contains(TermMode::BRACKETED_PASTE) {
+            self.on_terminal_input_start();
+
+            self.write_to_pty(&b"\x1b[200~"[..]);
+
+            // Write filtered escape sequences.
+            //
+            // We remove `\x1b` to ensure it's impossible for the pasted text to write the bracketed
+            // paste end escape `\x1b[201~` and `\x03` since some shells incorrectly terminate
+            // bracketed paste when they receive it.
+            let filtered = text.replace(['\x1b', '\x03'], "");
+            self.write_to_pty(filtered.into_bytes());
+
+            self.write_to_pty(&b"\x1b[201~"[..]);
+        } else {
+            self.on_terminal_input_start();
+
+            let payload = if bracketed {
+                // In non-bracketed (ie: normal) mode, terminal applications cannot distinguish
+                // pasted data from keystrokes.
+                //
+                // In theory, we should construct the keystrokes needed to produce the data we are
+                // pasting... since that's neither practical nor sensible (and probably an
+                // impossible task to solve in a general way), we'll just replace line breaks
+                // (windows and unix style) with a single carriage return (\r, which is what the
+                // Enter key produces).
+                text.replace("\r\n", "\r").replace('\n', "\r").into_bytes()
+            } else {
+                // When we explicitly disable bracketed paste don't manipulate with the input,
+                // so we pass user input as is.
+                text.to_owned().into_bytes()
+            };
+
+            self.write_to_pty(payload);
+        }
+    }
+
+    /// Working directory of the shell.
+    #[cfg(not(windows))]
+    fn working_directory(&self) -> Option<PathBuf> {
+        working_directory(self.terminal.working_directory(), self.master_fd, self.shell_pid)
+    }
+
     fn update_search(&mut self) {
         let regex = match self.search_state.regex() {
             Some(regex) => regex,
@@ -1558,7 +2125,13 @@ impl<'a, N: Notify + 'a, T: EventListener> ActionContext<'a, N, T> {
         // Jump to the next match.
         let direction = self.search_state.direction;
         let clamped_origin = self.search_state.origin.grid_clamp(self.terminal, Boundary::Grid);
-        match self.terminal.search_next(dfas, clamped_origin, direction, Side::Left, limit) {
+        let regex_match =
+            self.terminal.search_next(dfas, clamped_origin, direction, Side::Left, limit);
+
+        // Drop history lines decompressed by the search.
+        self.terminal.grid_mut().evict_history();
+
+        match regex_match {
             Some(regex_match) => {
                 let old_offset = self.terminal.grid().display_offset() as i32;
 
@@ -1837,6 +2410,10 @@ impl input::Processor<EventProxy, ActionContext<'_, Notifier, EventProxy>> {
         match event {
             WinitEvent::UserEvent(Event { payload, .. }) => match payload {
                 EventType::SearchNext => self.ctx.goto_match(None),
+                EventType::ChordTimeout => self.chord_timeout(),
+                EventType::ResizeTerminal(dimensions) => {
+                    self.ctx.display.request_terminal_size(self.ctx.config, dimensions);
+                },
                 EventType::Scroll(scroll) => self.ctx.scroll(scroll),
                 EventType::BlinkCursor => {
                     // Only change state when timeout isn't reached, since we could get
@@ -1894,8 +2471,39 @@ impl input::Processor<EventProxy, ActionContext<'_, Notifier, EventProxy>> {
                             }
                         }
                     },
+                    TerminalEvent::Notification { title, body, urgency } => {
+                        let config = &self.ctx.config.notifications;
+                        let limit = config.rate_limit;
+                        if !self.ctx.notification_limiter.record(limit, Instant::now()) {
+                            debug!("Dropping notification exceeding rate limit: {title:?}");
+                            return;
+                        }
+
+                        // Set window urgency hint when window is not focused.
+                        let focused = self.ctx.terminal.is_focused;
+                        if config.urgency_hint && !focused && urgency != Urgency::Low {
+                            self.ctx.window().set_urgent(true);
+                        }
+
+                        // Execute notification command.
+                        if let Some(command) = &config.command {
+                            // Fall back to the window title for notifications without one.
+                            let title = if title.is_empty() {
+                                &self.ctx.config.window.identity.title
+                            } else {
+                                &title
+                            };
+
+                            let args = notification::command_args(command, title, &body);
+                            self.ctx.spawn_daemon(command.program(), &args);
+                        }
+                    },
                     TerminalEvent::ClipboardStore(clipboard_type, content) => {
                         if self.ctx.terminal.is_focused {
+                            let config = &self.ctx.config.clipboard;
+                            if config.history_osc52 {
+                                self.ctx.clipboard.push_history(&content, config.history);
+                            }
                             self.ctx.clipboard.store(clipboard_type, content);
                         }
                     },
@@ -1921,19 +2529,34 @@ impl input::Processor<EventProxy, ActionContext<'_, Notifier, EventProxy>> {
                     TerminalEvent::PtyWrite(text) => self.ctx.write_to_pty(text.into_bytes()),
                     TerminalEvent::MouseCursorDirty => self.reset_mouse_cursor(),
                     TerminalEvent::CursorBlinkingChange => self.ctx.update_cursor_blinking(),
-                    TerminalEvent::Exit | TerminalEvent::ChildExit(_) | TerminalEvent::Wakeup => (),
+                    TerminalEvent::Exit
+                    | TerminalEvent::ChildExit(_)
+                    | TerminalEvent::WorkingDirectory(_)
+                    | TerminalEvent::Wakeup => (),
                 },
                 #[cfg(unix)]
-                EventType::IpcConfig(_) | EventType::IpcGetConfig(..) => (),
+                EventType::IpcConfig(_)
+                | EventType::IpcGetConfig(..)
+                | EventType::IpcGetWorkingDirectory(..)
+                | EventType::IpcListSessions(_)
+                | EventType::IpcAttach(..)
+                | EventType::IpcSendText(..)
+                | EventType::IpcGetText(..)
+                | EventType::IpcScreenshot(..)
+                | EventType::IpcListWindows(_)
+                | EventType::IpcSubscribe(..)
+                | EventType::IpcEvent(_) => (),
                 EventType::Message(_)
                 | EventType::ConfigReload(_)
                 | EventType::CreateWindow(_)
+                | EventType::Pane(_)
                 | EventType::Frame => (),
             },
             WinitEvent::WindowEvent { event, .. } => {
                 match event {
                     WindowEvent::CloseRequested => {
                         // User asked to close the window, so no need to hold it.
+                        self.ctx.display.layout.unsplit();
                         self.ctx.window().hold = false;
                         self.ctx.terminal.exit();
                     },
@@ -1995,6 +2618,13 @@ impl input::Processor<EventProxy, ActionContext<'_, Notifier, EventProxy>> {
                     },
                     WindowEvent::Occluded(occluded) => {
                         *self.ctx.occluded = occluded;
+
+                        // Screenshots waiting for the next frame won't be taken anymore.
+                        if occluded {
+                            for screenshot in self.ctx.display.pending_screenshots.drain(..) {
+                                screenshot.cancel();
+                            }
+                        }
                     },
                     WindowEvent::DroppedFile(path) => {
                         let path: String = path.to_string_lossy().into();
@@ -2010,6 +2640,9 @@ impl input::Processor<EventProxy, ActionContext<'_, Notifier, EventProxy>> {
                     WindowEvent::Ime(ime) => match ime {
                         Ime::Commit(text) => {
                             *self.ctx.dirty = true;
+                            if let Some(recording) = &mut self.ctx.display.macro_recording {
+                                recording.push(text.as_bytes());
+                            }
                             // Don't use bracketed paste for single char input.
                             self.ctx.paste(&text, text.chars().count() > 1);
                             self.ctx.update_cursor_blinking();
@@ -2064,22 +2697,32 @@ impl input::Processor<EventProxy, ActionContext<'_, Notifier, EventProxy>> {
 #[derive(Debug, Clone)]
 pub struct EventProxy {
     proxy: EventLoopProxy<Event>,
-    window_id: WindowId,
+    window_id: Arc<AtomicU64>,
+    pane_id: PaneId,
 }
 
 impl EventProxy {
-    pub fn new(proxy: EventLoopProxy<Event>, window_id: WindowId) -> Self {
-        Self { proxy, window_id }
+    pub fn new(proxy: EventLoopProxy<Event>, window_id: WindowId, pane_id: PaneId) -> Self {
+        Self { proxy, window_id: Arc::new(AtomicU64::new(window_id.into())), pane_id }
+    }
+
+    /// Move the terminal's events to another window.
+    #[cfg(unix)]
+    pub fn set_window_id(&self, window_id: WindowId) {
+        self.window_id.store(window_id.into(), Ordering::Relaxed);
     }
 
     /// Send an event to the event loop.
     pub fn send_event(&self, event: EventType) {
-        let _ = self.proxy.send_event(Event::new(event, self.window_id));
+        let window_id = WindowId::from(self.window_id.load(Ordering::Relaxed));
+        let event =
+            Event { window_id: Some(window_id), pane_id: Some(self.pane_id), payload: event };
+        let _ = self.proxy.send_event(event);
     }
 }
 
 impl EventListener for EventProxy {
     fn send_event(&self, event: TerminalEvent) {
-        let _ = self.proxy.send_event(Event::new(event.into(), self.window_id));
+        EventProxy::send_event(self, event.into());
     }
 }
@@ -0,0 +1,371 @@
+//! Export of the terminal's text with its colors and attributes.
+
+use std::fmt::Write as _;
+use std::fs::OpenOptions;
+use std::io::{self, Write};
+#[cfg(unix)]
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use log::{error, info};
+
+use alacritty_terminal::grid::Dimensions;
+use alacritty_terminal::index::{Column, Line, Point};
+use alacritty_terminal::term::Term;
+use alacritty_terminal::term::cell::{Cell, Flags, LineLength};
+use alacritty_terminal::thread;
+use alacritty_terminal::vte::ansi::{Color, NamedColor};
+
+use crate::config::UiConfig;
+use crate::display::color::{List, Rgb};
+use crate::display::content::{compute_bg_rgb, compute_fg_rgb};
+
+/// Text attributes with a CSS equivalent.
+const HTML_FLAGS: Flags =
+    Flags::BOLD.union(Flags::ITALIC).union(Flags::ALL_UNDERLINES).union(Flags::STRIKEOUT);
+
+/// Format of the exported text.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Format {
+    /// Plain text without any attributes.
+    Text,
+
+    /// Text with SGR escape sequences for its attributes.
+    Ansi,
+
+    /// HTML document with inline styles.
+    Html,
+}
+
+/// Part of the terminal which is exported.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Region {
+    /// Active selection.
+    Selection,
+
+    /// Lines visible in the window.
+    Viewport,
+
+    /// Entire scrollback history, including the visible lines.
+    History,
+}
+
+/// Serialize a region of the terminal.
+///
+/// Returns `None` when the selection is exported without any active selection.
+pub fn export<T>(
+    term: &Term<T>,
+    config: &UiConfig,
+    region: Region,
+    format: Format,
+) -> Option<String> {
+    let grid = term.grid();
+    let (start, end, is_block) = match region {
+        Region::Selection => {
+            let range = term.selection.as_ref()?.to_range(term)?;
+            (range.start, range.end, range.is_block)
+        },
+        Region::Viewport => {
+            let top = Line(-(grid.display_offset() as i32));
+            let bottom = top + (grid.screen_lines() as i32 - 1);
+            (Point::new(top, Column(0)), Point::new(bottom, grid.last_column()), false)
+        },
+        Region::History => {
+            let start = Point::new(grid.topmost_line(), Column(0));
+            (start, Point::new(grid.bottommost_line(), grid.last_column()), false)
+        },
+    };
+
+    // Split block selections into a separate range for every line.
+    let ranges: Vec<_> = if is_block {
+        (start.line.0..=end.line.0)
+            .map(Line)
+            .map(|line| (Point::new(line, start.column), Point::new(line, end.column)))
+            .collect()
+    } else {
+        vec![(start, end)]
+    };
+
+    let text = match format {
+        Format::Text if region == Region::Selection => term.selection_to_string()?,
+        Format::Text => term.bounds_to_string(start, end),
+        Format::Ansi => {
+            let lines: Vec<_> =
+                ranges.into_iter().map(|(start, end)| term.bounds_to_ansi(start, end)).collect();
+            lines.join("\n")
+        },
+        Format::Html => html(term, config, &ranges),
+    };
+
+    Some(text)
+}
+
+/// Save the scrollback history with its attributes to a file in the `general.save_directory`.
+///
+/// The file is written on a separate thread, since large histories would block the UI.
+pub fn save_history<T>(term: &Term<T>, config: &UiConfig) {
+    let directory = match config.general.save_directory() {
+        Ok(directory) => directory,
+        Err(err) => {
+            error!("Unable to create scrollback history directory: {err}");
+            return;
+        },
+    };
+
+    let text = export(term, config, Region::History, Format::Ansi).unwrap_or_default();
+    thread::spawn_named("scrollback history writer", move || {
+        match write_history(&directory, &text) {
+            Ok(path) => info!("Saved scrollback history to {path:?}"),
+            Err(err) => error!("Unable to save scrollback history in {directory:?}: {err}"),
+        }
+    });
+}
+
+/// Write history to a new file in `directory`, named after the current time.
+///
+/// The history can contain passwords or other secrets, so the file is only readable by its owner.
+fn write_history(directory: &Path, text: &str) -> io::Result<PathBuf> {
+    let timestamp = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+    let path = directory.join(format!("alacritty-{}.txt", timestamp.as_millis()));
+
+    let mut options = OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    options.mode(0o600);
+    options.open(&path)?.write_all(text.as_bytes())?;
+
+    Ok(path)
+}
+
+/// Convert ranges of the terminal to an HTML document.
+fn html<T>(term: &Term<T>, config: &UiConfig, ranges: &[(Point, Point)]) -> String {
+    let colors = List::from(&config.colors);
+    let color = |index: usize| term.colors()[index].map(Rgb).unwrap_or(colors[index]);
+    let foreground = color(NamedColor::Foreground as usize);
+    let background = color(NamedColor::Background as usize);
+
+    let mut html = String::from("<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n");
+    let _ = writeln!(
+        html,
+        "</head>\n<body style=\"background-color: {background};\">\n<pre style=\"color: \
+         {foreground}; background-color: {background}; font-family: monospace;\">"
+    );
+
+    let grid = term.grid();
+    let mut style = HtmlStyle::default();
+    for (i, &(start, end)) in ranges.iter().enumerate() {
+        if i != 0 {
+            style.close(&mut html);
+            html.push('\n');
+        }
+
+        for line in (start.line.0..=end.line.0).map(Line) {
+            let row = &grid[line];
+            let start_column = if line == start.line { start.column } else { Column(0) };
+            let end_column = if line == end.line { end.column } else { grid.last_column() };
+            let line_end = row.line_length().min(end_column + 1);
+
+            for column in (start_column.0..line_end.0).map(Column) {
+                let cell = &row[column];
+                if cell.flags.intersects(Flags::WIDE_CHAR_SPACER | Flags::LEADING_WIDE_CHAR_SPACER)
+                {
+                    continue;
+                }
+
+                let cell_style = HtmlStyle::new(config, &color, foreground, cell);
+                if cell_style != style {
+                    style.close(&mut html);
+                    cell_style.open(&mut html);
+                    style = cell_style;
+                }
+
+                push_escaped(&mut html, cell.c);
+                for c in cell.zerowidth().into_iter().flatten() {
+                    push_escaped(&mut html, *c);
+                }
+            }
+
+            // Join wrapped lines, like the plain text export.
+            let wrapped = line_end == grid.columns()
+                && row[grid.last_column()].flags.contains(Flags::WRAPLINE);
+            if line != end.line && !wrapped {
+                style.close(&mut html);
+                style = HtmlStyle::default();
+                html.push('\n');
+            }
+        }
+    }
+    style.close(&mut html);
+
+    html.push_str("</pre>\n</body>\n</html>\n");
+    html
+}
+
+/// Escape characters with a special meaning in HTML.
+fn push_escaped(html: &mut String, c: char) {
+    match c {
+        '&' => html.push_str("&amp;"),
+        '<' => html.push_str("&lt;"),
+        '>' => html.push_str("&gt;"),
+        '"' => html.push_str("&quot;"),
+        c => html.push(c),
+    }
+}
+
+/// Inline style of a `<span>` element.
+#[derive(Copy, Clone, PartialEq, Eq)]
+struct HtmlStyle {
+    fg: Option<Rgb>,
+    bg: Option<Rgb>,
+    flags: Flags,
+}
+
+impl Default for HtmlStyle {
+    fn default() -> Self {
+        Self { fg: None, bg: None, flags: Flags::empty() }
+    }
+}
+
+impl HtmlStyle {
+    fn new<C>(config: &UiConfig, color: &C, foreground: Rgb, cell: &Cell) -> Self
+    where
+        C: Fn(usize) -> Rgb,
+    {
+        let mut fg = compute_fg_rgb(config, color, cell.fg, cell.flags);
+        let mut bg = compute_bg_rgb(color, cell.bg);
+        let mut has_bg = cell.bg != Color::Named(NamedColor::Background);
+
+        if cell.flags.contains(Flags::INVERSE) {
+            std::mem::swap(&mut fg, &mut bg);
+            has_bg = true;
+        }
+
+        if cell.flags.contains(Flags::HIDDEN) {
+            fg = bg;
+        }
+
+        Self {
+            fg: Some(fg).filter(|fg| *fg != foreground),
+            bg: Some(bg).filter(|_| has_bg),
+            flags: cell.flags & HTML_FLAGS,
+        }
+    }
+
+    /// Start a `<span>` with this style.
+    fn open(&self, html: &mut String) {
+        if *self == Self::default() {
+            return;
+        }
+
+        html.push_str("<span style=\"");
+        if let Some(fg) = self.fg {
+            let _ = write!(html, "color: {fg}; ");
+        }
+        if let Some(bg) = self.bg {
+            let _ = write!(html, "background-color: {bg}; ");
+        }
+        if self.flags.contains(Flags::BOLD) {
+            html.push_str("font-weight: bold; ");
+        }
+        if self.flags.contains(Flags::ITALIC) {
+            html.push_str("font-style: italic; ");
+        }
+        match (self.flags.intersects(Flags::ALL_UNDERLINES), self.flags.contains(Flags::STRIKEOUT))
+        {
+            (true, true) => html.push_str("text-decoration: underline line-through; "),
+            (true, false) => html.push_str("text-decoration: underline; "),
+            (false, true) => html.push_str("text-decoration: line-through; "),
+            (false, false) => (),
+        }
+
+        // Remove trailing whitespace of the last property.
+        html.pop();
+        html.push_str("\">");
+    }
+
+    /// End the `<span>` of this style.
+    fn close(&self, html: &mut String) {
+        if *self != Self::default() {
+            html.push_str("</span>");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use alacritty_terminal::event::VoidListener;
+    use alacritty_terminal::index::Side;
+    use alacritty_terminal::selection::{Selection, SelectionType};
+    use alacritty_terminal::term::Config;
+    use alacritty_terminal::term::test::TermSize;
+    use alacritty_terminal::vte::ansi::{Attr, Handler};
+
+    fn term(text: &str) -> Term<VoidListener> {
+        let size = TermSize::new(10, 3);
+        let mut term = Term::new(Config::default(), &size, VoidListener);
+        for c in text.chars() {
+            match c {
+                '\n' => {
+                    term.carriage_return();
+                    term.linefeed();
+                },
+                c => term.input(c),
+            }
+        }
+        term
+    }
+
+    #[test]
+    fn export_html() {
+        let mut term = term("a<b\n");
+        term.terminal_attribute(Attr::Bold);
+        term.terminal_attribute(Attr::Foreground(Color::Named(NamedColor::Red)));
+        term.input('c');
+
+        let config = UiConfig::default();
+        let html = export(&term, &config, Region::Viewport, Format::Html).unwrap();
+
+        let red = List::from(&config.colors)[NamedColor::Red as usize];
+        let expected =
+            format!("a&lt;b\n<span style=\"color: {red}; font-weight: bold;\">c</span>\n</pre>");
+        assert!(html.contains(&expected), "{html}");
+    }
+
+    #[test]
+    fn export_selection() {
+        let mut term = term("abc\ndef");
+
+        let config = UiConfig::default();
+        assert_eq!(export(&term, &config, Region::Selection, Format::Text), None);
+
+        let start = Point::new(Line(0), Column(1));
+        let mut selection = Selection::new(SelectionType::Block, start, Side::Left);
+        selection.update(Point::new(Line(1), Column(1)), Side::Right);
+        term.selection = Some(selection);
+
+        let text = export(&term, &config, Region::Selection, Format::Text);
+        assert_eq!(text.as_deref(), Some("b\ne"));
+        let ansi = export(&term, &config, Region::Selection, Format::Ansi);
+        assert_eq!(ansi.as_deref(), Some("b\ne"));
+    }
+
+    #[test]
+    fn write_history_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = write_history(dir.path(), "\x1b[31mred").unwrap();
+
+        assert_eq!(path.parent(), Some(dir.path()));
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "\x1b[31mred");
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+
+            let metadata = std::fs::metadata(&path).unwrap();
+            assert_eq!(metadata.permissions().mode() & 0o777, 0o600);
+        }
+    }
+}
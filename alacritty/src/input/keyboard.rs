@@ -1,9 +1,10 @@
 use std::borrow::Cow;
+use std::{iter, mem};
 
 use winit::event::{ElementState, KeyEvent};
 #[cfg(target_os = "macos")]
 use winit::keyboard::ModifiersKeyState;
-use winit::keyboard::{Key, KeyLocation, ModifiersState, NamedKey};
+use winit::keyboard::{Key, KeyLocation, ModifiersState, NamedKey, PhysicalKey};
 #[cfg(target_os = "macos")]
 use winit::platform::macos::OptionAsAlt;
 
@@ -11,8 +12,8 @@ use alacritty_terminal::event::EventListener;
 use alacritty_terminal::term::TermMode;
 use winit::platform::modifier_supplement::KeyEventExtModifierSupplement;
 
-use crate::config::{Action, BindingKey, BindingMode, KeyBinding};
-use crate::event::TYPING_SEARCH_DELAY;
+use crate::config::{Action, BindingKey, BindingMode, ChordKey, ChordStep, KeyBinding};
+use crate::event::{Event, EventType, TYPING_SEARCH_DELAY};
 use crate::input::{ActionContext, Execute, Processor};
 use crate::scheduler::{TimerId, Topic};
 
@@ -45,6 +46,25 @@ impl<T: EventListener, A: ActionContext<T>> Processor<T, A> {
             return;
         }
 
+        // The clipboard history picker captures all keys while it is open.
+        if self.ctx.display().clipboard_picker.is_some() {
+            self.clipboard_picker_input(&key, text);
+            return;
+        }
+
+        // Pastes waiting for confirmation are only written after an explicit yes.
+        if self.ctx.display().pending_paste.is_some() {
+            let confirmed = match key.logical_key.as_ref() {
+                // Cancelling is the default, since the prompt is answered with `[y/N]`.
+                Key::Named(NamedKey::Enter | NamedKey::Escape) => false,
+                // Ignore keys without text, like modifiers.
+                _ if text.is_empty() => return,
+                _ => text.eq_ignore_ascii_case("y"),
+            };
+            self.ctx.confirm_paste(confirmed);
+            return;
+        }
+
         // First key after inline search is captured.
         let inline_state = self.ctx.inline_search_state();
         if inline_state.char_pending {
@@ -55,11 +75,34 @@ impl<T: EventListener, A: ActionContext<T>> Processor<T, A> {
         // Reset search delay when the user is still typing.
         self.reset_search_delay();
 
+        // Leave user-defined binding modes before Escape reaches the vi mode or search bindings.
+        if self.ctx.display().pending_chord.is_empty() {
+            let press = PendingKey::new(key.clone(), mods).press;
+            let bindings = self.ctx.config().key_bindings();
+            if self.binding_context().leaves_custom_modes(bindings, &press) {
+                self.ctx.display().custom_modes.clear();
+                self.ctx.mark_dirty();
+                return;
+            }
+        }
+
         // Key bindings suppress the character input.
         if self.process_key_bindings(&key) {
             return;
         }
 
+        // User-defined binding modes only accept keys with a binding.
+        if !self.ctx.custom_modes().is_empty() {
+            return;
+        }
+
+        self.unbound_key_input(key, mode, mods);
+    }
+
+    /// Send input of a key without any bindings to the search or the PTY.
+    fn unbound_key_input(&mut self, key: KeyEvent, mode: TermMode, mods: ModifiersState) {
+        let text = key.text_with_all_modifiers().unwrap_or_default();
+
         if self.ctx.search_active() {
             for character in text.chars() {
                 self.ctx.search_input(character);
@@ -97,6 +140,9 @@ impl<T: EventListener, A: ActionContext<T>> Processor<T, A> {
             if !is_modifier_key {
                 self.ctx.on_terminal_input_start();
             }
+            if let Some(recording) = &mut self.ctx.display().macro_recording {
+                recording.push(&bytes);
+            }
             self.ctx.write_to_pty(bytes);
         }
     }
@@ -174,77 +220,128 @@ impl<T: EventListener, A: ActionContext<T>> Processor<T, A> {
     ///
     /// The provided mode, mods, and key must match what is allowed by a binding
     /// for its action to be executed.
+    ///
+    /// Keys which start or continue a key sequence are held back until the sequence is
+    /// complete. When the sequence doesn't match any binding, they are processed on their own.
     fn process_key_bindings(&mut self, key: &KeyEvent) -> bool {
-        let mode = BindingMode::new(self.ctx.terminal().mode(), self.ctx.search_active());
         let mods = self.ctx.modifiers().state();
+        let pending_key = PendingKey::new(key.clone(), mods);
 
-        // Don't suppress char if no bindings were triggered.
-        let mut suppress_chars = None;
+        // Modifiers pressed for the next key of a sequence don't interrupt it.
+        let mut pending_chord = mem::take(&mut self.ctx.display().pending_chord);
+        if !pending_chord.is_empty() && Self::is_modifier_key(key) {
+            self.ctx.display().pending_chord = pending_chord;
+            return true;
+        }
 
-        // We don't want the key without modifier, because it means something else most of
-        // the time. However what we want is to manually lowercase the character to account
-        // for both small and capital letters on regular characters at the same time.
-        let logical_key = if let Key::Character(ch) = key.logical_key.as_ref() {
-            // Match `Alt` bindings without `Alt` being applied, otherwise they use the
-            // composed chars, which are not intuitive to bind.
-            //
-            // On Windows, the `Ctrl + Alt` mangles `logical_key` to unidentified values, thus
-            // preventing them from being used in bindings
-            //
-            // For more see https://github.com/rust-windowing/winit/issues/2945.
-            if (cfg!(target_os = "macos") || (cfg!(windows) && mods.control_key()))
-                && mods.alt_key()
-            {
-                key.key_without_modifiers()
-            } else {
-                Key::Character(ch.to_lowercase().into())
-            }
-        } else {
-            key.logical_key.clone()
-        };
+        let prefix: Vec<_> = pending_chord.iter().map(|pending| pending.press.clone()).collect();
 
-        // Get the action of a key binding.
-        let mut binding_action = |binding: &KeyBinding| {
-            let key = match (&binding.trigger, &logical_key) {
-                (BindingKey::Scancode(_), _) => BindingKey::Scancode(key.physical_key),
-                (_, code) => {
-                    BindingKey::Keycode { key: code.clone(), location: key.location.into() }
-                },
-            };
+        // Wait for the next key of the sequence.
+        let bindings = self.ctx.config().key_bindings();
+        if self.binding_context().continues_chord(bindings, &prefix, &pending_key.press) {
+            pending_chord.push(pending_key);
+            self.ctx.display().pending_chord = pending_chord;
+            self.schedule_chord_timeout();
+            self.ctx.mark_dirty();
+            return true;
+        }
 
-            if binding.is_triggered_by(mode, mods, &key) {
-                // Pass through the key if any of the bindings has the `ReceiveChar` action.
-                *suppress_chars.get_or_insert(true) &= binding.action != Action::ReceiveChar;
+        let actions = self.binding_actions(&prefix, &pending_key.press);
+        self.ctx.display().pending_chord = pending_chord;
 
-                // Binding was triggered; run the action.
-                Some(binding.action.clone())
-            } else {
-                None
-            }
+        let (actions, suppress_chars) = match actions {
+            Some(actions) => actions,
+            // Process the keys of sequences without a binding on their own.
+            None if !prefix.is_empty() => {
+                self.cancel_chord();
+                return self.process_key_bindings(key);
+            },
+            // Don't suppress char if no bindings were triggered.
+            None => (Vec::new(), false),
         };
 
-        // Trigger matching key bindings.
-        for i in 0..self.ctx.config().key_bindings().len() {
-            let binding = &self.ctx.config().key_bindings()[i];
-            if let Some(action) = binding_action(binding) {
-                action.execute(&mut self.ctx);
-            }
+        self.clear_chord();
+        for action in actions {
+            action.execute(&mut self.ctx);
         }
 
-        // Trigger key bindings for hints.
-        for i in 0..self.ctx.config().hints.enabled.len() {
-            let hint = &self.ctx.config().hints.enabled[i];
-            let binding = match hint.binding.as_ref() {
-                Some(binding) => binding.key_binding(hint),
-                None => continue,
-            };
+        suppress_chars
+    }
 
-            if let Some(action) = binding_action(binding) {
+    /// State required for matching key presses against bindings.
+    fn binding_context(&self) -> BindingContext<'_> {
+        let mode = BindingMode::new(self.ctx.terminal().mode(), self.ctx.search_active());
+        let custom_modes = self.ctx.custom_modes();
+        BindingContext { mode, custom_modes, leader: self.ctx.config().leader() }
+    }
+
+    /// Actions of the bindings triggered by a key press, including the ones for hints.
+    fn binding_actions(
+        &self,
+        prefix: &[KeyPress],
+        press: &KeyPress,
+    ) -> Option<(Vec<Action>, bool)> {
+        let config = self.ctx.config();
+        let hint_bindings = config
+            .hints
+            .enabled
+            .iter()
+            .filter_map(|hint| Some(hint.binding.as_ref()?.key_binding(hint)));
+        let bindings = config.key_bindings().iter().chain(hint_bindings);
+
+        self.binding_context().triggered_actions(bindings, prefix, press)
+    }
+
+    /// Process the keys of an expired key sequence on their own.
+    pub fn chord_timeout(&mut self) {
+        self.cancel_chord();
+    }
+
+    /// Abort the pending key sequence, processing its keys on their own.
+    ///
+    /// Held keys run their own bindings or are sent as regular input, but never start another
+    /// key sequence.
+    fn cancel_chord(&mut self) {
+        let pending_chord = mem::take(&mut self.ctx.display().pending_chord);
+        self.clear_chord();
+
+        for pending_key in pending_chord {
+            let (actions, suppress_chars) =
+                self.binding_actions(&[], &pending_key.press).unwrap_or_default();
+            for action in actions {
                 action.execute(&mut self.ctx);
             }
+
+            // User-defined binding modes only accept keys with a binding.
+            if !suppress_chars && self.ctx.custom_modes().is_empty() {
+                let mode = *self.ctx.terminal().mode();
+                self.unbound_key_input(pending_key.key, mode, pending_key.press.mods);
+            }
         }
+    }
+
+    /// Stop waiting for the next key of a key sequence.
+    fn clear_chord(&mut self) {
+        let timer_id = TimerId::new(Topic::ChordTimeout, self.ctx.window().id());
+        self.ctx.scheduler_mut().unschedule(timer_id);
 
-        suppress_chars.unwrap_or(false)
+        let display = self.ctx.display();
+        if !display.pending_chord.is_empty() {
+            display.pending_chord.clear();
+            self.ctx.mark_dirty();
+        }
+    }
+
+    /// Restart the timeout for the next key of a key sequence.
+    fn schedule_chord_timeout(&mut self) {
+        let window_id = self.ctx.window().id();
+        let timer_id = TimerId::new(Topic::ChordTimeout, window_id);
+        self.ctx.scheduler_mut().unschedule(timer_id);
+
+        if let Some(timeout) = self.ctx.config().chord_timeout() {
+            let event = Event::new(EventType::ChordTimeout, window_id);
+            self.ctx.scheduler_mut().schedule(event, timeout, false, timer_id);
+        }
     }
 
     /// Handle key release.
@@ -253,6 +350,9 @@ impl<T: EventListener, A: ActionContext<T>> Processor<T, A> {
             || mode.contains(TermMode::VI)
             || self.ctx.search_active()
             || self.ctx.display().hint_state.active()
+            || self.ctx.display().clipboard_picker.is_some()
+            || self.ctx.display().pending_paste.is_some()
+            || !self.ctx.display().pending_chord.is_empty()
         {
             return;
         }
@@ -275,6 +375,36 @@ impl<T: EventListener, A: ActionContext<T>> Processor<T, A> {
         self.ctx.write_to_pty(bytes);
     }
 
+    /// Handle key input while the clipboard history picker is open.
+    fn clipboard_picker_input(&mut self, key: &KeyEvent, text: &str) {
+        let display = self.ctx.display();
+        let picker = match &mut display.clipboard_picker {
+            Some(picker) => picker,
+            None => return,
+        };
+
+        match key.logical_key.as_ref() {
+            Key::Named(NamedKey::ArrowUp) => picker.select_next(),
+            Key::Named(NamedKey::ArrowDown) => picker.select_previous(),
+            Key::Named(NamedKey::Backspace) => picker.pop(),
+            Key::Named(NamedKey::Escape) => display.clipboard_picker = None,
+            Key::Named(NamedKey::Enter) => {
+                let text = picker.selected().map(str::to_owned);
+                display.clipboard_picker = None;
+                if let Some(text) = text {
+                    self.ctx.paste(&text, true);
+                }
+            },
+            _ => {
+                for c in text.chars().filter(|c| !c.is_control()) {
+                    picker.push(c);
+                }
+            },
+        }
+
+        self.ctx.mark_dirty();
+    }
+
     /// Reset search delay.
     fn reset_search_delay(&mut self) {
         if self.ctx.search_active() {
@@ -287,6 +417,188 @@ impl<T: EventListener, A: ActionContext<T>> Processor<T, A> {
     }
 }
 
+/// Key press which is part of an incomplete key sequence.
+#[derive(Debug)]
+pub struct PendingKey {
+    key: KeyEvent,
+    press: KeyPress,
+}
+
+impl PendingKey {
+    fn new(key: KeyEvent, mods: ModifiersState) -> Self {
+        // We don't want the key without modifier, because it means something else most of
+        // the time. However what we want is to manually lowercase the character to account
+        // for both small and capital letters on regular characters at the same time.
+        let logical_key = if let Key::Character(ch) = key.logical_key.as_ref() {
+            // Match `Alt` bindings without `Alt` being applied, otherwise they use the
+            // composed chars, which are not intuitive to bind.
+            //
+            // On Windows, the `Ctrl + Alt` mangles `logical_key` to unidentified values, thus
+            // preventing them from being used in bindings
+            //
+            // For more see https://github.com/rust-windowing/winit/issues/2945.
+            if (cfg!(target_os = "macos") || (cfg!(windows) && mods.control_key()))
+                && mods.alt_key()
+            {
+                key.key_without_modifiers()
+            } else {
+                Key::Character(ch.to_lowercase().into())
+            }
+        } else {
+            key.logical_key.clone()
+        };
+
+        let press =
+            KeyPress { mods, logical_key, physical_key: key.physical_key, location: key.location };
+
+        Self { key, press }
+    }
+
+    /// Human readable description of the key press, like `Control+a`.
+    pub fn label(&self) -> String {
+        self.press.label()
+    }
+}
+
+/// Key press in the representation used for matching bindings.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct KeyPress {
+    mods: ModifiersState,
+
+    /// Key used for matching bindings.
+    logical_key: Key,
+
+    physical_key: PhysicalKey,
+    location: KeyLocation,
+}
+
+impl KeyPress {
+    /// Key in the same representation as a binding's trigger.
+    fn binding_key(&self, trigger: &BindingKey) -> BindingKey {
+        match trigger {
+            BindingKey::Scancode(_) => BindingKey::Scancode(self.physical_key),
+            BindingKey::Keycode { .. } => BindingKey::Keycode {
+                key: self.logical_key.clone(),
+                location: self.location.into(),
+            },
+        }
+    }
+
+    /// Check if the key press matches a key of a key sequence.
+    fn matches_step(&self, step: &ChordStep, leader: Option<&ChordKey>) -> bool {
+        let chord_key = match step {
+            ChordStep::Leader => match leader {
+                Some(leader) => leader,
+                None => return false,
+            },
+            ChordStep::Key(chord_key) => chord_key,
+        };
+
+        chord_key.mods == self.mods && chord_key.key == self.binding_key(&chord_key.key)
+    }
+
+    /// Human readable description of the key press, like `Control+a`.
+    pub fn label(&self) -> String {
+        let mut label = String::new();
+        for (modifier, name) in [
+            (ModifiersState::CONTROL, "Control+"),
+            (ModifiersState::ALT, "Alt+"),
+            (ModifiersState::SHIFT, "Shift+"),
+            (ModifiersState::SUPER, "Super+"),
+        ] {
+            if self.mods.contains(modifier) {
+                label.push_str(name);
+            }
+        }
+
+        match &self.logical_key {
+            Key::Character(ch) => label.push_str(ch),
+            Key::Named(named) => label.push_str(&format!("{named:?}")),
+            key => label.push_str(&format!("{key:?}")),
+        }
+
+        label
+    }
+}
+
+/// State required for matching key presses against bindings.
+struct BindingContext<'a> {
+    mode: BindingMode,
+    custom_modes: &'a [String],
+    leader: Option<&'a ChordKey>,
+}
+
+impl BindingContext<'_> {
+    /// Check if any binding has more keys after `press` following the keys of `prefix`.
+    fn continues_chord<'a>(
+        &self,
+        bindings: impl IntoIterator<Item = &'a KeyBinding>,
+        prefix: &[KeyPress],
+        press: &KeyPress,
+    ) -> bool {
+        bindings.into_iter().any(|binding| {
+            binding.prefix.len() > prefix.len()
+                && self.mode.contains(binding.mode)
+                && !self.mode.intersects(binding.notmode)
+                && binding.custom_modes.matches(self.custom_modes)
+                && iter::zip(&binding.prefix, prefix.iter().chain([press]))
+                    .all(|(step, press)| press.matches_step(step, self.leader))
+        })
+    }
+
+    /// Check if `press` leaves all active user-defined binding modes.
+    ///
+    /// Escape leaves them, unless it is bound within one of the active modes. Bindings which
+    /// don't require any user-defined mode, like the defaults for vi mode, are ignored.
+    fn leaves_custom_modes<'a>(
+        &self,
+        bindings: impl IntoIterator<Item = &'a KeyBinding>,
+        press: &KeyPress,
+    ) -> bool {
+        if self.custom_modes.is_empty() || press.logical_key != Key::Named(NamedKey::Escape) {
+            return false;
+        }
+
+        let bindings = bindings.into_iter().filter(|binding| !binding.custom_modes.mode.is_empty());
+        self.triggered_actions(bindings, &[], press).is_none()
+    }
+
+    /// Actions of all bindings triggered by `press` after the keys of `prefix`.
+    ///
+    /// Returns `None` if no binding was triggered, otherwise the actions and whether the key's
+    /// text should be suppressed.
+    fn triggered_actions<'a>(
+        &self,
+        bindings: impl IntoIterator<Item = &'a KeyBinding>,
+        prefix: &[KeyPress],
+        press: &KeyPress,
+    ) -> Option<(Vec<Action>, bool)> {
+        let mut suppress_chars = None;
+
+        let actions = bindings
+            .into_iter()
+            .filter(|binding| {
+                binding.prefix.len() == prefix.len()
+                    && iter::zip(&binding.prefix, prefix)
+                        .all(|(step, key)| key.matches_step(step, self.leader))
+                    && binding.is_triggered_by(
+                        self.mode,
+                        press.mods,
+                        &press.binding_key(&binding.trigger),
+                    )
+                    && binding.custom_modes.matches(self.custom_modes)
+            })
+            .map(|binding| {
+                // Pass through the key if any of the bindings has the `ReceiveChar` action.
+                *suppress_chars.get_or_insert(true) &= binding.action != Action::ReceiveChar;
+                binding.action.clone()
+            })
+            .collect();
+
+        suppress_chars.map(|suppress_chars| (actions, suppress_chars))
+    }
+}
+
 /// Build a key's keyboard escape sequence based on the given `key`, `mods`, and `mode`.
 ///
 /// The key sequences for `APP_KEYPAD` and alike are handled inside the bindings.
@@ -715,3 +1027,89 @@ fn is_control_character(text: &str) -> bool {
     let codepoint = text.bytes().next().unwrap();
     text.len() == 1 && (codepoint < 0x20 || (0x7f..=0x9f).contains(&codepoint))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use winit::keyboard::KeyCode;
+
+    use crate::config::Binding;
+
+    fn press(code: KeyCode) -> KeyPress {
+        KeyPress {
+            mods: ModifiersState::empty(),
+            logical_key: Key::Unidentified(winit::keyboard::NativeKey::Unidentified),
+            physical_key: PhysicalKey::Code(code),
+            location: KeyLocation::Standard,
+        }
+    }
+
+    fn binding(prefix: &[KeyCode], trigger: KeyCode, action: Action) -> KeyBinding {
+        let chord_key = |code| ChordKey {
+            key: BindingKey::Scancode(PhysicalKey::Code(code)),
+            mods: ModifiersState::empty(),
+        };
+
+        Binding {
+            mods: ModifiersState::empty(),
+            action,
+            mode: BindingMode::empty(),
+            notmode: BindingMode::empty(),
+            custom_modes: Default::default(),
+            trigger: BindingKey::Scancode(PhysicalKey::Code(trigger)),
+            prefix: prefix.iter().map(|&code| ChordStep::Key(chord_key(code))).collect(),
+        }
+    }
+
+    #[test]
+    fn prefix_key_bound_alone() {
+        let bindings = [
+            binding(&[], KeyCode::KeyG, Action::ScrollToBottom),
+            binding(&[KeyCode::KeyG], KeyCode::KeyG, Action::ScrollToTop),
+        ];
+        let context =
+            BindingContext { mode: BindingMode::empty(), custom_modes: &[], leader: None };
+        let g = press(KeyCode::KeyG);
+        let x = press(KeyCode::KeyX);
+        let held = [g.clone()];
+
+        // The key is held back, since it starts a key sequence.
+        assert!(context.continues_chord(&bindings, &[], &g));
+        assert!(!context.continues_chord(&bindings, &held, &g));
+
+        // Completing the sequence runs only the sequence's binding.
+        let actions = context.triggered_actions(&bindings, &held, &g);
+        assert_eq!(actions, Some((vec![Action::ScrollToTop], true)));
+
+        // A mismatched sequence is cancelled and the held key runs its own binding.
+        assert_eq!(context.triggered_actions(&bindings, &held, &x), None);
+        let actions = context.triggered_actions(&bindings, &[], &g);
+        assert_eq!(actions, Some((vec![Action::ScrollToBottom], true)));
+        assert_eq!(context.triggered_actions(&bindings, &[], &x), None);
+    }
+
+    #[test]
+    fn escape_leaves_custom_modes() {
+        let escape =
+            KeyPress { logical_key: Key::Named(NamedKey::Escape), ..press(KeyCode::Escape) };
+
+        let mut vi_escape = binding(&[], KeyCode::Escape, Action::ClearSelection);
+        vi_escape.mode = BindingMode::VI;
+        let mut bindings = vec![vi_escape];
+
+        let modes = ["resize".to_owned()];
+        let context = BindingContext { mode: BindingMode::VI, custom_modes: &modes, leader: None };
+        let no_modes = BindingContext { mode: BindingMode::VI, custom_modes: &[], leader: None };
+
+        // Bindings without user-defined modes don't keep Escape from leaving them.
+        assert!(context.leaves_custom_modes(&bindings, &escape));
+        assert!(!context.leaves_custom_modes(&bindings, &press(KeyCode::KeyX)));
+        assert!(!no_modes.leaves_custom_modes(&bindings, &escape));
+
+        let mut resize_escape = binding(&[], KeyCode::Escape, Action::ToggleMode("resize".into()));
+        resize_escape.custom_modes.mode.push("resize".into());
+        bindings.push(resize_escape);
+        assert!(!context.leaves_custom_modes(&bindings, &escape));
+    }
+}
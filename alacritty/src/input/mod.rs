@@ -28,7 +28,7 @@ use winit::window::CursorIcon;
 
 use alacritty_terminal::event::EventListener;
 use alacritty_terminal::grid::{Dimensions, Scroll};
-use alacritty_terminal::index::{Boundary, Column, Direction, Point, Side};
+use alacritty_terminal::index::{Boundary, Column, Direction, Line, Point, Side};
 use alacritty_terminal::selection::SelectionType;
 use alacritty_terminal::term::search::Match;
 use alacritty_terminal::term::{ClipboardType, Term, TermMode};
@@ -40,12 +40,15 @@ use crate::clipboard::Clipboard;
 use crate::config::window::Decorations;
 use crate::config::{Action, BindingMode, MouseAction, SearchAction, UiConfig, ViAction};
 use crate::display::hint::HintMatch;
+use crate::display::picker::ClipboardPicker;
 use crate::display::window::Window;
 use crate::display::{Display, SizeInfo};
 use crate::event::{
     ClickState, Event, EventType, InlineSearchState, Mouse, TouchPurpose, TouchZoom,
 };
+use crate::export::{self, Format, Region};
 use crate::message_bar::{self, Message};
+use crate::pane::{Direction as PaneDirection, PaneAction, Split};
 use crate::scheduler::{Scheduler, TimerId, Topic};
 
 pub mod keyboard;
@@ -101,6 +104,9 @@ pub trait ActionContext<T: EventListener> {
     fn create_new_window(&mut self, _tabbing_id: Option<String>) {}
     #[cfg(not(target_os = "macos"))]
     fn create_new_window(&mut self) {}
+    fn toggle_recording(&mut self) {}
+    fn screenshot(&mut self) {}
+    fn pane_action(&mut self, _action: PaneAction) {}
     fn change_font_size(&mut self, _delta: f32) {}
     fn reset_font_size(&mut self) {}
     fn pop_message(&mut self) {}
@@ -109,6 +115,9 @@ pub trait ActionContext<T: EventListener> {
     #[cfg(target_os = "macos")]
     fn event_loop(&self) -> &ActiveEventLoop;
     fn mouse_mode(&self) -> bool;
+    fn custom_modes(&self) -> &[String] {
+        &[]
+    }
     fn clipboard_mut(&mut self) -> &mut Clipboard;
     fn scheduler_mut(&mut self) -> &mut Scheduler;
     fn start_search(&mut self, _direction: Direction) {}
@@ -136,6 +145,10 @@ pub trait ActionContext<T: EventListener> {
     fn semantic_word(&self, point: Point) -> String;
     fn on_terminal_input_start(&mut self) {}
     fn paste(&mut self, _text: &str, _bracketed: bool) {}
+    fn confirm_paste(&mut self, _confirmed: bool) {}
+    fn start_macro_recording(&mut self, _register: String) {}
+    fn stop_macro_recording(&mut self) {}
+    fn play_macro(&mut self, _register: &str, _count: usize) {}
     fn spawn_daemon<I, S>(&self, _program: &str, _args: I)
     where
         I: IntoIterator<Item = S> + Debug + Copy,
@@ -157,6 +170,48 @@ impl Action {
             selection.include_all();
         }
     }
+
+    fn scroll_to_prompt<T, A>(ctx: &mut A, direction: Direction)
+    where
+        A: ActionContext<T>,
+        T: EventListener,
+    {
+        // Move the vi mode cursor along with the viewport.
+        if ctx.terminal().mode().contains(TermMode::VI) {
+            let motion = match direction {
+                Direction::Left => ViMotion::PromptUp,
+                Direction::Right => ViMotion::PromptDown,
+            };
+            ctx.terminal_mut().vi_motion(motion);
+            ctx.mark_dirty();
+            return;
+        }
+
+        // Put the prompt at the top of the viewport.
+        let display_offset = ctx.terminal().grid().display_offset() as i32;
+        match ctx.terminal().prompt_line(Line(-display_offset), direction) {
+            Some(line) => ctx.scroll(Scroll::Delta(-line.0 - display_offset)),
+            None if direction == Direction::Right => ctx.scroll(Scroll::Bottom),
+            None => (),
+        }
+    }
+
+    fn select_last_command_output<T, A>(ctx: &mut A)
+    where
+        A: ActionContext<T>,
+        T: EventListener,
+    {
+        let output = match ctx.terminal().last_command_output() {
+            Some(output) => output,
+            None => return,
+        };
+
+        ctx.start_selection(SelectionType::Lines, *output.start(), Side::Left);
+        ctx.update_selection(*output.end(), Side::Right);
+        ctx.copy_selection(ClipboardType::Selection);
+        ctx.terminal_mut().scroll_to_point(*output.start());
+        ctx.mark_dirty();
+    }
 }
 
 trait Execute<T: EventListener> {
@@ -322,6 +377,25 @@ impl<T: EventListener> Execute<T> for Action {
             #[cfg(not(any(target_os = "macos", windows)))]
             Action::CopySelection => ctx.copy_selection(ClipboardType::Selection),
             Action::ClearSelection => ctx.clear_selection(),
+            Action::SelectLastCommandOutput => Self::select_last_command_output(ctx),
+            Action::CopyLastCommandOutput => {
+                if let Some(output) = ctx.terminal().last_command_output() {
+                    let text = ctx.terminal().bounds_to_string(*output.start(), *output.end());
+                    let size = ctx.config().clipboard.history;
+                    ctx.clipboard_mut().push_history(&text, size);
+                    ctx.clipboard_mut().store(ClipboardType::Clipboard, text);
+                }
+            },
+            Action::CopySelectionAsHtml => {
+                let html =
+                    export::export(ctx.terminal(), ctx.config(), Region::Selection, Format::Html);
+                if let Some(html) = html {
+                    let size = ctx.config().clipboard.history;
+                    ctx.clipboard_mut().push_history(&html, size);
+                    ctx.clipboard_mut().store(ClipboardType::Clipboard, html);
+                }
+            },
+            Action::SaveScrollback => export::save_history(ctx.terminal(), ctx.config()),
             Action::Paste => {
                 let text = ctx.clipboard_mut().load(ClipboardType::Clipboard);
                 ctx.paste(&text, true);
@@ -330,6 +404,13 @@ impl<T: EventListener> Execute<T> for Action {
                 let text = ctx.clipboard_mut().load(ClipboardType::Selection);
                 ctx.paste(&text, true);
             },
+            Action::PasteFromHistory => {
+                let entries: Vec<_> = ctx.clipboard_mut().history().iter().cloned().collect();
+                if !entries.is_empty() {
+                    ctx.display().clipboard_picker = Some(ClipboardPicker::new(entries));
+                    ctx.mark_dirty();
+                }
+            },
             Action::ToggleFullscreen => ctx.window().toggle_fullscreen(),
             Action::ToggleMaximized => ctx.window().toggle_maximized(),
             #[cfg(target_os = "macos")]
@@ -342,6 +423,8 @@ impl<T: EventListener> Execute<T> for Action {
             Action::Hide => ctx.window().set_visible(false),
             Action::Minimize => ctx.window().set_minimized(true),
             Action::Quit => {
+                // Close the entire window instead of just the focused pane.
+                ctx.display().layout.unsplit();
                 ctx.window().hold = false;
                 ctx.terminal_mut().exit();
             },
@@ -399,11 +482,58 @@ impl<T: EventListener> Execute<T> for Action {
                 term.vi_motion(ViMotion::FirstOccupied);
                 ctx.mark_dirty();
             },
+            Action::ScrollToPreviousPrompt => Self::scroll_to_prompt(ctx, Direction::Left),
+            Action::ScrollToNextPrompt => Self::scroll_to_prompt(ctx, Direction::Right),
             Action::ClearHistory => ctx.terminal_mut().clear_screen(ClearMode::Saved),
             Action::ClearLogNotice => ctx.pop_message(),
             #[cfg(not(target_os = "macos"))]
             Action::CreateNewWindow => ctx.create_new_window(),
             Action::SpawnNewInstance => ctx.spawn_new_instance(),
+            Action::ToggleRecording => ctx.toggle_recording(),
+            Action::Screenshot => ctx.screenshot(),
+            Action::EnterMode(name) => {
+                let custom_modes = &mut ctx.display().custom_modes;
+                if !custom_modes.contains(name) {
+                    custom_modes.push(name.clone());
+                    ctx.mark_dirty();
+                }
+            },
+            Action::LeaveMode(name) => {
+                let custom_modes = &mut ctx.display().custom_modes;
+                let len = custom_modes.len();
+                custom_modes.retain(|mode| mode != name);
+                if custom_modes.len() != len {
+                    ctx.mark_dirty();
+                }
+            },
+            Action::StartMacroRecording(register) => ctx.start_macro_recording(register.clone()),
+            Action::StopMacroRecording => ctx.stop_macro_recording(),
+            Action::PlayMacro(register, count) => ctx.play_macro(register, *count),
+            Action::Sequence(actions) => {
+                for action in actions {
+                    action.execute(ctx);
+                }
+            },
+            Action::ToggleMode(name) => {
+                let custom_modes = &mut ctx.display().custom_modes;
+                match custom_modes.iter().position(|mode| mode == name) {
+                    Some(index) => _ = custom_modes.remove(index),
+                    None => custom_modes.push(name.clone()),
+                }
+                ctx.mark_dirty();
+            },
+            Action::SplitHorizontal => ctx.pane_action(PaneAction::Split(Split::Horizontal)),
+            Action::SplitVertical => ctx.pane_action(PaneAction::Split(Split::Vertical)),
+            Action::FocusPaneLeft => ctx.pane_action(PaneAction::Focus(PaneDirection::Left)),
+            Action::FocusPaneRight => ctx.pane_action(PaneAction::Focus(PaneDirection::Right)),
+            Action::FocusPaneUp => ctx.pane_action(PaneAction::Focus(PaneDirection::Up)),
+            Action::FocusPaneDown => ctx.pane_action(PaneAction::Focus(PaneDirection::Down)),
+            Action::ResizePaneLeft => ctx.pane_action(PaneAction::Resize(PaneDirection::Left)),
+            Action::ResizePaneRight => ctx.pane_action(PaneAction::Resize(PaneDirection::Right)),
+            Action::ResizePaneUp => ctx.pane_action(PaneAction::Resize(PaneDirection::Up)),
+            Action::ResizePaneDown => ctx.pane_action(PaneAction::Resize(PaneDirection::Down)),
+            Action::ClosePane => ctx.pane_action(PaneAction::Close),
+            Action::ZoomPane => ctx.pane_action(PaneAction::Zoom),
             #[cfg(target_os = "macos")]
             Action::CreateNewWindow => ctx.create_new_window(None),
             #[cfg(target_os = "macos")]
@@ -1034,12 +1164,15 @@ impl<T: EventListener, A: ActionContext<T>> Processor<T, A> {
         let mouse_mode = self.ctx.mouse_mode();
         let mods = self.ctx.modifiers().state();
         let mouse_bindings = self.ctx.config().mouse_bindings().to_owned();
+        let custom_modes = self.ctx.custom_modes().to_vec();
 
         // If mouse mode is active, also look for bindings without shift.
         let fallback_allowed = mouse_mode && mods.contains(ModifiersState::SHIFT);
         let mut exact_match_found = false;
 
-        for binding in &mouse_bindings {
+        let mouse_bindings =
+            mouse_bindings.iter().filter(|binding| binding.custom_modes.matches(&custom_modes));
+        for binding in mouse_bindings.clone() {
             // Don't trigger normal bindings in mouse mode unless Shift is pressed.
             if binding.is_triggered_by(mode, mods, &button) && (fallback_allowed || !mouse_mode) {
                 binding.action.execute(&mut self.ctx);
@@ -1049,7 +1182,7 @@ impl<T: EventListener, A: ActionContext<T>> Processor<T, A> {
 
         if fallback_allowed && !exact_match_found {
             let fallback_mods = mods & !ModifiersState::SHIFT;
-            for binding in &mouse_bindings {
+            for binding in mouse_bindings {
                 if binding.is_triggered_by(mode, fallback_mods, &button) {
                     binding.action.execute(&mut self.ctx);
                 }
@@ -1485,7 +1618,7 @@ mod tests {
 
     test_process_binding! {
         name: process_binding_nomode_shiftmod_require_shift,
-        binding: Binding { trigger: KEY, mods: ModifiersState::SHIFT, action: Action::from("\x1b[1;2D"), mode: BindingMode::empty(), notmode: BindingMode::empty() },
+        binding: Binding { trigger: KEY, mods: ModifiersState::SHIFT, action: Action::from("\x1b[1;2D"), mode: BindingMode::empty(), notmode: BindingMode::empty(), custom_modes: Default::default(), prefix: Vec::new() },
         triggers: true,
         mode: BindingMode::empty(),
         mods: ModifiersState::SHIFT,
@@ -1493,7 +1626,7 @@ mod tests {
 
     test_process_binding! {
         name: process_binding_nomode_nomod_require_shift,
-        binding: Binding { trigger: KEY, mods: ModifiersState::SHIFT, action: Action::from("\x1b[1;2D"), mode: BindingMode::empty(), notmode: BindingMode::empty() },
+        binding: Binding { trigger: KEY, mods: ModifiersState::SHIFT, action: Action::from("\x1b[1;2D"), mode: BindingMode::empty(), notmode: BindingMode::empty(), custom_modes: Default::default(), prefix: Vec::new() },
         triggers: false,
         mode: BindingMode::empty(),
         mods: ModifiersState::empty(),
@@ -1501,7 +1634,7 @@ mod tests {
 
     test_process_binding! {
         name: process_binding_nomode_controlmod,
-        binding: Binding { trigger: KEY, mods: ModifiersState::CONTROL, action: Action::from("\x1b[1;5D"), mode: BindingMode::empty(), notmode: BindingMode::empty() },
+        binding: Binding { trigger: KEY, mods: ModifiersState::CONTROL, action: Action::from("\x1b[1;5D"), mode: BindingMode::empty(), notmode: BindingMode::empty(), custom_modes: Default::default(), prefix: Vec::new() },
         triggers: true,
         mode: BindingMode::empty(),
         mods: ModifiersState::CONTROL,
@@ -1509,7 +1642,7 @@ mod tests {
 
     test_process_binding! {
         name: process_binding_nomode_nomod_require_not_appcursor,
-        binding: Binding { trigger: KEY, mods: ModifiersState::empty(), action: Action::from("\x1b[D"), mode: BindingMode::empty(), notmode: BindingMode::APP_CURSOR },
+        binding: Binding { trigger: KEY, mods: ModifiersState::empty(), action: Action::from("\x1b[D"), mode: BindingMode::empty(), notmode: BindingMode::APP_CURSOR, custom_modes: Default::default(), prefix: Vec::new() },
         triggers: true,
         mode: BindingMode::empty(),
         mods: ModifiersState::empty(),
@@ -1517,7 +1650,7 @@ mod tests {
 
     test_process_binding! {
         name: process_binding_appcursormode_nomod_require_appcursor,
-        binding: Binding { trigger: KEY, mods: ModifiersState::empty(), action: Action::from("\x1bOD"), mode: BindingMode::APP_CURSOR, notmode: BindingMode::empty() },
+        binding: Binding { trigger: KEY, mods: ModifiersState::empty(), action: Action::from("\x1bOD"), mode: BindingMode::APP_CURSOR, notmode: BindingMode::empty(), custom_modes: Default::default(), prefix: Vec::new() },
         triggers: true,
         mode: BindingMode::APP_CURSOR,
         mods: ModifiersState::empty(),
@@ -1525,7 +1658,7 @@ mod tests {
 
     test_process_binding! {
         name: process_binding_nomode_nomod_require_appcursor,
-        binding: Binding { trigger: KEY, mods: ModifiersState::empty(), action: Action::from("\x1bOD"), mode: BindingMode::APP_CURSOR, notmode: BindingMode::empty() },
+        binding: Binding { trigger: KEY, mods: ModifiersState::empty(), action: Action::from("\x1bOD"), mode: BindingMode::APP_CURSOR, notmode: BindingMode::empty(), custom_modes: Default::default(), prefix: Vec::new() },
         triggers: false,
         mode: BindingMode::empty(),
         mods: ModifiersState::empty(),
@@ -1533,7 +1666,7 @@ mod tests {
 
     test_process_binding! {
         name: process_binding_appcursormode_appkeypadmode_nomod_require_appcursor,
-        binding: Binding { trigger: KEY, mods: ModifiersState::empty(), action: Action::from("\x1bOD"), mode: BindingMode::APP_CURSOR, notmode: BindingMode::empty() },
+        binding: Binding { trigger: KEY, mods: ModifiersState::empty(), action: Action::from("\x1bOD"), mode: BindingMode::APP_CURSOR, notmode: BindingMode::empty(), custom_modes: Default::default(), prefix: Vec::new() },
         triggers: true,
         mode: BindingMode::APP_CURSOR | BindingMode::APP_KEYPAD,
         mods: ModifiersState::empty(),
@@ -1541,7 +1674,7 @@ mod tests {
 
     test_process_binding! {
         name: process_binding_fail_with_extra_mods,
-        binding: Binding { trigger: KEY, mods: ModifiersState::SUPER, action: Action::from("arst"), mode: BindingMode::empty(), notmode: BindingMode::empty() },
+        binding: Binding { trigger: KEY, mods: ModifiersState::SUPER, action: Action::from("arst"), mode: BindingMode::empty(), notmode: BindingMode::empty(), custom_modes: Default::default(), prefix: Vec::new() },
         triggers: false,
         mode: BindingMode::empty(),
         mods: ModifiersState::ALT | ModifiersState::SUPER,
@@ -5,8 +5,10 @@ use std::ffi::OsStr;
 use std::io::{BufRead, BufReader, Error as IoError, ErrorKind, Result as IoResult, Write};
 use std::net::Shutdown;
 use std::os::unix::net::{UnixListener, UnixStream};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
+use std::sync::mpsc::{self, Sender};
+use std::time::Duration;
 use std::{env, fs, process};
 
 use log::{error, warn};
@@ -16,12 +18,18 @@ use winit::window::WindowId;
 
 use alacritty_terminal::thread;
 
-use crate::cli::{Options, SocketMessage};
+use crate::cli::{Options, SocketMessage, SubscriptionKind};
 use crate::event::{Event, EventType};
 
 /// Environment variable name for the IPC socket path.
 const ALACRITTY_SOCKET_ENV: &str = "ALACRITTY_SOCKET";
 
+/// Time after which subscribers not reading their events are dropped.
+const SUBSCRIBER_WRITE_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Time after which clients stop waiting for a reply to their request.
+const REPLY_READ_TIMEOUT: Duration = Duration::from_secs(10);
+
 /// Create an IPC socket.
 pub fn spawn_ipc_socket(
     options: &Options,
@@ -83,6 +91,58 @@ pub fn spawn_ipc_socket(
                     let event = Event::new(EventType::IpcGetConfig(Arc::new(stream)), window_id);
                     let _ = event_proxy.send_event(event);
                 },
+                SocketMessage::GetWorkingDirectory(options) => {
+                    let Ok(window_id) = requested_window(options.window_id, &stream) else {
+                        continue;
+                    };
+                    let stream = Arc::new(stream);
+                    let event = Event::new(EventType::IpcGetWorkingDirectory(stream), window_id);
+                    let _ = event_proxy.send_event(event);
+                },
+                SocketMessage::SendText(send_text) => {
+                    let Ok(window_id) = requested_window(send_text.window_id, &stream) else {
+                        continue;
+                    };
+                    let stream = Arc::new(stream);
+                    let event =
+                        Event::new(EventType::IpcSendText(send_text.text, stream), window_id);
+                    let _ = event_proxy.send_event(event);
+                },
+                SocketMessage::GetText(get_text) => {
+                    let Ok(window_id) = requested_window(get_text.window_id, &stream) else {
+                        continue;
+                    };
+                    let event =
+                        Event::new(EventType::IpcGetText(get_text, Arc::new(stream)), window_id);
+                    let _ = event_proxy.send_event(event);
+                },
+                SocketMessage::Screenshot(screenshot) => {
+                    let Ok(window_id) = requested_window(screenshot.window_id, &stream) else {
+                        continue;
+                    };
+                    let stream = Arc::new(stream);
+                    let event =
+                        Event::new(EventType::IpcScreenshot(screenshot.output, stream), window_id);
+                    let _ = event_proxy.send_event(event);
+                },
+                SocketMessage::ListWindows => {
+                    let event = Event::new(EventType::IpcListWindows(Arc::new(stream)), None);
+                    let _ = event_proxy.send_event(event);
+                },
+                SocketMessage::Subscribe(subscribe) => {
+                    let stream = Arc::new(stream);
+                    let event = Event::new(EventType::IpcSubscribe(subscribe.events, stream), None);
+                    let _ = event_proxy.send_event(event);
+                },
+                SocketMessage::ListSessions => {
+                    let event = Event::new(EventType::IpcListSessions(Arc::new(stream)), None);
+                    let _ = event_proxy.send_event(event);
+                },
+                SocketMessage::Attach(attach) => {
+                    let stream = Arc::new(stream);
+                    let event = Event::new(EventType::IpcAttach(attach.session_id, stream), None);
+                    let _ = event_proxy.send_event(event);
+                },
             }
         }
     });
@@ -90,6 +150,23 @@ pub fn spawn_ipc_socket(
     Ok(socket_path)
 }
 
+/// Get the window targeted by an IPC request.
+///
+/// Unlike a missing ID, which targets the focused window, an ID that can't belong to any window
+/// is rejected with an error reply to the client.
+fn requested_window(window_id: Option<i128>, stream: &UnixStream) -> Result<Option<WindowId>, ()> {
+    match window_id.map(u64::try_from) {
+        Some(Ok(window_id)) => Ok(Some(WindowId::from(window_id))),
+        Some(Err(_)) => {
+            if let Ok(mut stream) = stream.try_clone() {
+                send_reply(&mut stream, SocketReply::Error("window not found".into()));
+            }
+            Err(())
+        },
+        None => Ok(None),
+    }
+}
+
 /// Send a message to the active Alacritty socket.
 pub fn send_message(socket: Option<PathBuf>, message: SocketMessage) -> IoResult<()> {
     let mut socket = find_socket(socket)?;
@@ -102,6 +179,14 @@ pub fn send_message(socket: Option<PathBuf>, message: SocketMessage) -> IoResult
     // Shutdown write end, to allow reading.
     socket.shutdown(Shutdown::Write)?;
 
+    // Stream events until the socket is closed.
+    if let SocketMessage::Subscribe(_) = message {
+        for line in BufReader::new(&socket).lines() {
+            println!("{}", line?);
+        }
+        return Ok(());
+    }
+
     // Get matching IPC reply.
     handle_reply(&socket, &message)?;
 
@@ -112,9 +197,14 @@ pub fn send_message(socket: Option<PathBuf>, message: SocketMessage) -> IoResult
 fn handle_reply(stream: &UnixStream, message: &SocketMessage) -> IoResult<()> {
     // Read reply, returning early if there is none.
     let mut buffer = String::new();
+    stream.set_read_timeout(Some(REPLY_READ_TIMEOUT))?;
     let mut reader = BufReader::new(stream);
-    if let Ok(0) | Err(_) = reader.read_line(&mut buffer) {
-        return Ok(());
+    match reader.read_line(&mut buffer) {
+        Err(err) if matches!(err.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => {
+            return Err(IoError::new(ErrorKind::TimedOut, "timed out waiting for a reply"));
+        },
+        Ok(0) | Err(_) => return Ok(()),
+        Ok(_) => (),
     }
 
     // Parse IPC reply.
@@ -128,6 +218,38 @@ fn handle_reply(stream: &UnixStream, message: &SocketMessage) -> IoResult<()> {
             println!("{config}");
             Ok(())
         },
+        // Write working directory to STDOUT.
+        (SocketMessage::GetWorkingDirectory(..), SocketReply::GetWorkingDirectory(path)) => {
+            match path {
+                Some(path) => {
+                    println!("{}", path.display());
+                    Ok(())
+                },
+                None => Err(IoError::other("working directory unavailable")),
+            }
+        },
+        // Write detached sessions to STDOUT.
+        (SocketMessage::ListSessions, SocketReply::ListSessions(sessions)) => {
+            for session in sessions {
+                let title = session.title.as_deref().unwrap_or_default();
+                let directory = session.working_directory.as_deref().unwrap_or(Path::new(""));
+                println!("{}\t{title}\t{}", session.id, directory.display());
+            }
+            Ok(())
+        },
+        // Write window text to STDOUT.
+        (SocketMessage::GetText(..), SocketReply::GetText(text)) => {
+            println!("{text}");
+            Ok(())
+        },
+        // Write windows as JSON to STDOUT.
+        (SocketMessage::ListWindows, SocketReply::ListWindows(windows)) => {
+            let json = serde_json::to_string(windows).map_err(IoError::other)?;
+            println!("{json}");
+            Ok(())
+        },
+        // Forward errors to the caller.
+        (_, SocketReply::Error(err)) => Err(IoError::other(err.as_str())),
         // Ignore requests without reply.
         _ => Ok(()),
     }
@@ -148,6 +270,101 @@ fn send_reply_fallible(stream: &mut UnixStream, message: SocketReply) -> IoResul
     Ok(())
 }
 
+/// Event streamed to IPC subscribers.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum IpcEvent {
+    WindowCreated { window_id: u64 },
+    WindowClosed { window_id: u64 },
+    WindowFocused { window_id: u64, focused: bool },
+    Title { window_id: u64, title: String },
+    Bell { window_id: u64 },
+    ChildExit { window_id: u64, code: i32 },
+    ConfigReload,
+    Search { window_id: u64, active: bool },
+    ViMode { window_id: u64, active: bool },
+}
+
+impl IpcEvent {
+    /// Subscription kind of the event.
+    fn kind(&self) -> SubscriptionKind {
+        match self {
+            Self::WindowCreated { .. } => SubscriptionKind::WindowCreated,
+            Self::WindowClosed { .. } => SubscriptionKind::WindowClosed,
+            Self::WindowFocused { .. } => SubscriptionKind::WindowFocused,
+            Self::Title { .. } => SubscriptionKind::Title,
+            Self::Bell { .. } => SubscriptionKind::Bell,
+            Self::ChildExit { .. } => SubscriptionKind::ChildExit,
+            Self::ConfigReload => SubscriptionKind::ConfigReload,
+            Self::Search { .. } => SubscriptionKind::Search,
+            Self::ViMode { .. } => SubscriptionKind::ViMode,
+        }
+    }
+}
+
+/// Messages handled by the subscription thread.
+enum SubscriptionMessage {
+    Subscribe(UnixStream, Vec<SubscriptionKind>),
+    Event(IpcEvent),
+}
+
+/// Fan-out of events to all IPC subscribers.
+///
+/// Events are written on a separate thread, so slow subscribers cannot block the event loop.
+pub struct Subscriptions {
+    sender: Sender<SubscriptionMessage>,
+}
+
+impl Subscriptions {
+    pub fn new() -> Self {
+        let (sender, receiver) = mpsc::channel();
+
+        thread::spawn_named("ipc subscriptions", move || {
+            let mut subscribers: Vec<(UnixStream, Vec<SubscriptionKind>)> = Vec::new();
+
+            for message in receiver {
+                match message {
+                    SubscriptionMessage::Subscribe(stream, kinds) => {
+                        let _ = stream.set_write_timeout(Some(SUBSCRIBER_WRITE_TIMEOUT));
+                        subscribers.push((stream, kinds));
+                    },
+                    SubscriptionMessage::Event(event) => {
+                        let mut json = match serde_json::to_string(&event) {
+                            Ok(json) => json,
+                            Err(err) => {
+                                error!("Failed IPC event serialization: {err}");
+                                continue;
+                            },
+                        };
+                        json.push('\n');
+
+                        // Remove subscribers once their socket was closed.
+                        let kind = event.kind();
+                        subscribers.retain_mut(|(stream, kinds)| {
+                            (!kinds.is_empty() && !kinds.contains(&kind))
+                                || stream.write_all(json.as_bytes()).is_ok()
+                        });
+                    },
+                }
+            }
+        });
+
+        Self { sender }
+    }
+
+    /// Stream all future events of the requested kinds to the socket.
+    ///
+    /// All events are streamed if no kinds are specified.
+    pub fn subscribe(&self, stream: UnixStream, kinds: Vec<SubscriptionKind>) {
+        let _ = self.sender.send(SubscriptionMessage::Subscribe(stream, kinds));
+    }
+
+    /// Send an event to all interested subscribers.
+    pub fn publish(&self, event: IpcEvent) {
+        let _ = self.sender.send(SubscriptionMessage::Event(event));
+    }
+}
+
 /// Directory for the IPC socket file.
 #[cfg(not(target_os = "macos"))]
 fn socket_dir() -> PathBuf {
@@ -234,4 +451,69 @@ fn socket_prefix() -> String {
 #[derive(Serialize, Deserialize, Debug)]
 pub enum SocketReply {
     GetConfig(String),
+    GetWorkingDirectory(Option<PathBuf>),
+    ListSessions(Vec<Session>),
+    GetText(String),
+    ListWindows(Vec<WindowInfo>),
+    Error(String),
+}
+
+/// Description of a terminal window.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct WindowInfo {
+    pub id: u64,
+    pub title: String,
+    pub columns: usize,
+    pub lines: usize,
+    pub width: u32,
+    pub height: u32,
+    pub focused: bool,
+    pub working_directory: Option<PathBuf>,
+    pub foreground_process: Option<String>,
+}
+
+/// Terminal detached from its window.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Session {
+    pub id: usize,
+    pub title: Option<String>,
+    pub working_directory: Option<PathBuf>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn subscription_fan_out() {
+        let subscriptions = Subscriptions::new();
+
+        let (all, all_reader) = UnixStream::pair().unwrap();
+        let (bells, bells_reader) = UnixStream::pair().unwrap();
+        subscriptions.subscribe(all, Vec::new());
+        subscriptions.subscribe(bells, vec![SubscriptionKind::Bell]);
+
+        subscriptions.publish(IpcEvent::ConfigReload);
+        subscriptions.publish(IpcEvent::Bell { window_id: 1 });
+        drop(subscriptions);
+
+        let all: Vec<_> = BufReader::new(all_reader).lines().map(Result::unwrap).collect();
+        assert_eq!(all, [r#"{"event":"config_reload"}"#, r#"{"event":"bell","window_id":1}"#]);
+
+        let bells: Vec<_> = BufReader::new(bells_reader).lines().map(Result::unwrap).collect();
+        assert_eq!(bells, [r#"{"event":"bell","window_id":1}"#]);
+    }
+
+    #[test]
+    fn requested_window_ids() {
+        let (stream, reader) = UnixStream::pair().unwrap();
+
+        assert_eq!(requested_window(None, &stream), Ok(None));
+        assert_eq!(requested_window(Some(3), &stream), Ok(Some(WindowId::from(3))));
+        assert_eq!(requested_window(Some(-1), &stream), Err(()));
+        drop(stream);
+
+        let replies: Vec<_> = BufReader::new(reader).lines().map(Result::unwrap).collect();
+        assert_eq!(replies, [r#"{"Error":"window not found"}"#]);
+    }
 }
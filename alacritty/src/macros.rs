@@ -0,0 +1,191 @@
+//! Recording and playback of keyboard input.
+
+use std::collections::BTreeMap;
+use std::fmt::{self, Display, Formatter};
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+#[cfg(unix)]
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::{Path, PathBuf};
+
+use log::{error, info};
+
+use crate::config::macros::MacrosConfig;
+
+/// Keyboard macros stored in named registers.
+#[derive(Default, Debug)]
+pub struct Macros {
+    registers: BTreeMap<String, String>,
+}
+
+impl Macros {
+    /// Load the registers persisted in the configured file.
+    pub fn new(config: &MacrosConfig) -> Self {
+        let path = match config.file.as_deref() {
+            Some(path) => expand_home(path),
+            None => return Self::default(),
+        };
+
+        match load(&path) {
+            Ok(registers) => Self { registers },
+            Err(Error::Io(err)) if err.kind() == io::ErrorKind::NotFound => Self::default(),
+            Err(err) => {
+                error!("Unable to load macros {path:?}: {err}");
+                Self::default()
+            },
+        }
+    }
+
+    /// Input recorded into a register.
+    pub fn get(&self, register: &str) -> Option<&str> {
+        self.registers.get(register).map(String::as_str)
+    }
+
+    /// Replace the input of a register, persisting all registers to the configured file.
+    pub fn store(&mut self, config: &MacrosConfig, register: String, input: String) {
+        self.registers.insert(register, input);
+
+        let path = match config.file.as_deref() {
+            Some(path) => expand_home(path),
+            None => return,
+        };
+
+        match save(&path, &self.registers) {
+            Ok(()) => info!("Saved macros to {path:?}"),
+            Err(err) => error!("Unable to save macros {path:?}: {err}"),
+        }
+    }
+}
+
+/// Macro which is currently being recorded.
+#[derive(Debug)]
+pub struct MacroRecording {
+    register: String,
+    input: String,
+}
+
+impl MacroRecording {
+    pub fn new(register: String) -> Self {
+        Self { register, input: String::new() }
+    }
+
+    /// Register the macro is recorded into.
+    pub fn register(&self) -> &str {
+        &self.register
+    }
+
+    /// Append input written to the terminal.
+    pub fn push(&mut self, bytes: &[u8]) {
+        self.input.push_str(&String::from_utf8_lossy(bytes));
+    }
+
+    /// Register and recorded input.
+    pub fn finish(self) -> (String, String) {
+        (self.register, self.input)
+    }
+}
+
+/// Read registers from a TOML file.
+fn load(path: &Path) -> Result<BTreeMap<String, String>, Error> {
+    let registers = fs::read_to_string(path)?;
+    Ok(toml::from_str(&registers)?)
+}
+
+/// Write registers to a TOML file, creating its parent directories.
+///
+/// Recorded input can contain passwords, so the file is only readable by its owner.
+fn save(path: &Path, registers: &BTreeMap<String, String>) -> Result<(), Error> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    let registers = toml::to_string(registers)?;
+
+    let mut options = OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    options.mode(0o600);
+    options.open(path)?.write_all(registers.as_bytes())?;
+
+    Ok(())
+}
+
+/// Replace a leading `~` in a path with the home directory.
+fn expand_home(path: &Path) -> PathBuf {
+    match (path.strip_prefix("~"), home::home_dir()) {
+        (Ok(stripped), Some(home_dir)) => home_dir.join(stripped),
+        _ => path.to_owned(),
+    }
+}
+
+#[derive(Debug)]
+enum Error {
+    /// Error accessing the macros file.
+    Io(io::Error),
+
+    /// Invalid TOML in the macros file.
+    Deserialize(toml::de::Error),
+
+    /// Unable to serialize the registers.
+    Serialize(toml::ser::Error),
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Io(err) => err.fmt(f),
+            Error::Deserialize(err) => err.fmt(f),
+            Error::Serialize(err) => err.fmt(f),
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(val: io::Error) -> Self {
+        Error::Io(val)
+    }
+}
+
+impl From<toml::de::Error> for Error {
+    fn from(val: toml::de::Error) -> Self {
+        Error::Deserialize(val)
+    }
+}
+
+impl From<toml::ser::Error> for Error {
+    fn from(val: toml::ser::Error) -> Self {
+        Error::Serialize(val)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn persist_registers() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = MacrosConfig { file: Some(dir.path().join("state").join("macros.toml")) };
+
+        let mut recording = MacroRecording::new(String::from("a"));
+        recording.push(b"ls");
+        recording.push(b"\x1b[A\r");
+        let (register, input) = recording.finish();
+
+        let mut macros = Macros::new(&config);
+        assert_eq!(macros.get("a"), None);
+        macros.store(&config, register, input);
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+
+            let metadata = fs::metadata(config.file.as_ref().unwrap()).unwrap();
+            assert_eq!(metadata.permissions().mode() & 0o777, 0o600);
+        }
+
+        let macros = Macros::new(&config);
+        assert_eq!(macros.get("a"), Some("ls\x1b[A\r"));
+        assert_eq!(Macros::new(&MacrosConfig::default()).get("a"), None);
+    }
+}
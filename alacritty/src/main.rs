@@ -33,18 +33,26 @@ mod config;
 mod daemon;
 mod display;
 mod event;
+mod export;
 mod input;
 #[cfg(unix)]
 mod ipc;
 mod logging;
 #[cfg(target_os = "macos")]
 mod macos;
+mod macros;
 mod message_bar;
 mod migrate;
+mod notification;
+mod pane;
 #[cfg(windows)]
 mod panic;
+mod paste;
+mod recording;
 mod renderer;
 mod scheduler;
+mod screenshot;
+mod session;
 mod string;
 mod window_context;
 
@@ -55,7 +63,7 @@ mod gl {
 
 #[cfg(unix)]
 use crate::cli::MessageOptions;
-#[cfg(not(any(target_os = "macos", windows)))]
+#[cfg(unix)]
 use crate::cli::SocketMessage;
 use crate::cli::{Options, Subcommands};
 use crate::config::UiConfig;
@@ -83,7 +91,8 @@ fn main() -> Result<(), Box<dyn Error>> {
         #[cfg(unix)]
         Some(Subcommands::Msg(options)) => msg(options)?,
         Some(Subcommands::Migrate(options)) => migrate::migrate(options),
-        None => alacritty(options)?,
+        Some(Subcommands::Render(_)) => render(options)?,
+        Some(Subcommands::Replay(_)) | None => alacritty(options)?,
     }
 
     Ok(())
@@ -91,16 +100,29 @@ fn main() -> Result<(), Box<dyn Error>> {
 
 /// `msg` subcommand entrypoint.
 #[cfg(unix)]
-#[allow(unused_mut)]
 fn msg(mut options: MessageOptions) -> Result<(), Box<dyn Error>> {
     #[cfg(not(any(target_os = "macos", windows)))]
     if let SocketMessage::CreateWindow(window_options) = &mut options.message {
         window_options.activation_token =
             env::var("XDG_ACTIVATION_TOKEN").or_else(|_| env::var("DESKTOP_STARTUP_ID")).ok();
     }
+
+    // Resolve the screenshot path relative to the client's working directory.
+    if let SocketMessage::Screenshot(screenshot) = &mut options.message {
+        screenshot.output = env::current_dir()?.join(&screenshot.output);
+    }
     ipc::send_message(options.socket, options.message).map_err(|err| err.into())
 }
 
+/// `render` subcommand entrypoint.
+fn render(mut options: Options) -> Result<(), Box<dyn Error>> {
+    let config = config::load(&mut options);
+    if let Some(Subcommands::Render(render_options)) = &options.subcommands {
+        screenshot::render(&config, render_options)?;
+    }
+    Ok(())
+}
+
 /// Temporary files stored for Alacritty.
 ///
 /// This stores temporary files to automate their destruction through its `Drop` implementation.
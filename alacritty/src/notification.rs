@@ -0,0 +1,90 @@
+//! Desktop notifications requested by applications through escape sequences.
+
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+use crate::config::ui_config::Program;
+
+/// Interval over which the notification rate is limited.
+const RATE_LIMIT_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Limit the number of notifications a window can send.
+#[derive(Debug, Default)]
+pub struct RateLimiter {
+    sent: VecDeque<Instant>,
+}
+
+impl RateLimiter {
+    /// Record a new notification.
+    ///
+    /// Returns `false` if the notification exceeds the rate limit and should be dropped.
+    pub fn record(&mut self, limit: u8, now: Instant) -> bool {
+        while self.sent.front().is_some_and(|sent| now - *sent >= RATE_LIMIT_INTERVAL) {
+            self.sent.pop_front();
+        }
+
+        if self.sent.len() >= limit as usize {
+            return false;
+        }
+
+        self.sent.push_back(now);
+        true
+    }
+}
+
+/// Arguments passed to the notification command.
+pub fn command_args(command: &Program, title: &str, body: &str) -> Vec<String> {
+    let mut args = command.args().to_vec();
+    args.push(title.into());
+    args.push(body.into());
+    args
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rate_limit() {
+        let mut limiter = RateLimiter::default();
+        let start = Instant::now();
+
+        assert!(limiter.record(2, start));
+        assert!(limiter.record(2, start + Duration::from_secs(30)));
+        assert!(!limiter.record(2, start + Duration::from_secs(59)));
+        assert!(limiter.record(2, start + Duration::from_secs(60)));
+        assert!(!limiter.record(2, start + Duration::from_secs(61)));
+
+        assert!(!RateLimiter::default().record(0, start));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn stub_command() {
+        use std::{fs, thread};
+
+        use crate::daemon::spawn_daemon;
+
+        let output = std::env::temp_dir().join(format!("alacritty-notify-{}", std::process::id()));
+        let _ = fs::remove_file(&output);
+
+        let script =
+            format!("printf '%s|%s' \"$1\" \"$2\" > {}.tmp && mv {0}.tmp {0}", output.display());
+        let command = Program::WithArgs {
+            program: "sh".into(),
+            args: vec!["-c".into(), script, "sh".into()],
+        };
+
+        let args = command_args(&command, "Title", "Body; with spaces");
+        spawn_daemon(command.program(), &args, None).unwrap();
+
+        // Wait for the detached process to finish writing.
+        let start = Instant::now();
+        while !output.exists() && start.elapsed() < Duration::from_secs(10) {
+            thread::sleep(Duration::from_millis(10));
+        }
+
+        assert_eq!(fs::read_to_string(&output).unwrap(), "Title|Body; with spaces");
+        fs::remove_file(&output).unwrap();
+    }
+}
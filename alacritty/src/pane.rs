@@ -0,0 +1,670 @@
+//! Split panes inside a single window.
+
+#[cfg(not(windows))]
+use std::os::unix::io::RawFd;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use alacritty_terminal::event_loop::{Msg, Notifier};
+use alacritty_terminal::sync::FairMutex;
+use alacritty_terminal::term::Term;
+
+use crate::display::SizeInfo;
+use crate::event::{EventProxy, InlineSearchState, SearchState};
+use crate::recording::Recording;
+
+/// Minimum share of its parent split a pane can be resized to.
+const MIN_RATIO: f32 = 0.05;
+
+/// Distance from a divider in pixels at which it can still be dragged.
+const DIVIDER_GRAB_DISTANCE: f32 = 4.;
+
+/// Last pane ID, shared by all windows.
+static NEXT_PANE_ID: AtomicUsize = AtomicUsize::new(0);
+
+/// Unique identifier of a pane.
+///
+/// Pane IDs are unique across all windows, allowing them to be used as session IDs when a pane's
+/// terminal is detached from its window.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct PaneId(usize);
+
+impl PaneId {
+    /// Allocate a new pane ID.
+    pub fn next() -> Self {
+        Self(NEXT_PANE_ID.fetch_add(1, Ordering::Relaxed))
+    }
+}
+
+impl From<usize> for PaneId {
+    fn from(id: usize) -> Self {
+        Self(id)
+    }
+}
+
+impl From<PaneId> for usize {
+    fn from(id: PaneId) -> Self {
+        id.0
+    }
+}
+
+/// Orientation of a split.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Split {
+    /// Panes placed next to each other.
+    Horizontal,
+
+    /// Panes placed above each other.
+    Vertical,
+}
+
+/// Direction of a pane relative to the focused pane.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Direction {
+    Left,
+    Right,
+    Up,
+    Down,
+}
+
+impl Direction {
+    /// Split orientation moving along this direction.
+    pub fn split(self) -> Split {
+        match self {
+            Direction::Left | Direction::Right => Split::Horizontal,
+            Direction::Up | Direction::Down => Split::Vertical,
+        }
+    }
+}
+
+/// Operation on the panes of a window.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum PaneAction {
+    /// Split the focused pane.
+    Split(Split),
+
+    /// Focus the closest pane in a direction.
+    Focus(Direction),
+
+    /// Move the closest divider of the focused pane in a direction.
+    Resize(Direction),
+
+    /// Close the focused pane.
+    Close,
+
+    /// Toggle the focused pane filling the entire window.
+    Zoom,
+}
+
+/// Rectangle inside the window in physical pixels.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Rect {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+}
+
+impl Rect {
+    pub fn new(x: f32, y: f32, width: f32, height: f32) -> Self {
+        Self { x, y, width, height }
+    }
+
+    /// Check if a point is inside the rectangle.
+    pub fn contains(&self, x: f32, y: f32) -> bool {
+        x >= self.x && x < self.x + self.width && y >= self.y && y < self.y + self.height
+    }
+
+    /// Split the rectangle, leaving `gap` pixels between both halves.
+    fn split(&self, split: Split, ratio: f32, gap: f32) -> (Rect, Rect, Rect) {
+        match split {
+            Split::Horizontal => {
+                let first = ((self.width - gap).max(0.) * ratio).floor();
+                let second = (self.width - first - gap).max(0.);
+                (
+                    Rect::new(self.x, self.y, first, self.height),
+                    Rect::new(self.x + first, self.y, gap, self.height),
+                    Rect::new(self.x + first + gap, self.y, second, self.height),
+                )
+            },
+            Split::Vertical => {
+                let first = ((self.height - gap).max(0.) * ratio).floor();
+                let second = (self.height - first - gap).max(0.);
+                (
+                    Rect::new(self.x, self.y, self.width, first),
+                    Rect::new(self.x, self.y + first, self.width, gap),
+                    Rect::new(self.x, self.y + first + gap, self.width, second),
+                )
+            },
+        }
+    }
+}
+
+/// Divider between two panes, identified by its path from the root of the layout.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Divider(Vec<bool>);
+
+/// Node of the split tree.
+#[derive(Debug, Clone, PartialEq)]
+enum Node {
+    Pane(PaneId),
+    Split { split: Split, ratio: f32, first: Box<Node>, second: Box<Node> },
+}
+
+impl Node {
+    fn contains(&self, id: PaneId) -> bool {
+        match self {
+            Node::Pane(pane) => *pane == id,
+            Node::Split { first, second, .. } => first.contains(id) || second.contains(id),
+        }
+    }
+
+    fn first_pane(&self) -> PaneId {
+        match self {
+            Node::Pane(pane) => *pane,
+            Node::Split { first, .. } => first.first_pane(),
+        }
+    }
+
+    fn rects(
+        &self,
+        rect: Rect,
+        gap: f32,
+        panes: &mut Vec<(PaneId, Rect)>,
+        dividers: &mut Vec<Rect>,
+    ) {
+        match self {
+            Node::Pane(pane) => panes.push((*pane, rect)),
+            Node::Split { split, ratio, first, second } => {
+                let (first_rect, divider, second_rect) = rect.split(*split, *ratio, gap);
+                dividers.push(divider);
+                first.rects(first_rect, gap, panes, dividers);
+                second.rects(second_rect, gap, panes, dividers);
+            },
+        }
+    }
+
+    /// Find the node at `path`, returning its rectangle.
+    fn node_at(&mut self, path: &[bool], rect: Rect, gap: f32) -> Option<(&mut Node, Rect)> {
+        let (&step, path) = match path.split_first() {
+            Some(split) => split,
+            None => return Some((self, rect)),
+        };
+
+        match self {
+            Node::Split { split, ratio, first, second } => {
+                let (first_rect, _, second_rect) = rect.split(*split, *ratio, gap);
+                if step {
+                    second.node_at(path, second_rect, gap)
+                } else {
+                    first.node_at(path, first_rect, gap)
+                }
+            },
+            Node::Pane(_) => None,
+        }
+    }
+
+    /// Path to the pane with the specified ID.
+    fn path(&self, id: PaneId, path: &mut Vec<bool>) -> bool {
+        match self {
+            Node::Pane(pane) => *pane == id,
+            Node::Split { first, second, .. } => {
+                path.push(false);
+                if first.path(id, path) {
+                    return true;
+                }
+                path.pop();
+
+                path.push(true);
+                if second.path(id, path) {
+                    return true;
+                }
+                path.pop();
+
+                false
+            },
+        }
+    }
+
+    fn divider_at(&self, rect: Rect, gap: f32, x: f32, y: f32, path: &mut Vec<bool>) -> bool {
+        let Node::Split { split, ratio, first, second } = self else {
+            return false;
+        };
+
+        let (first_rect, divider, second_rect) = rect.split(*split, *ratio, gap);
+        let grab = Rect {
+            x: divider.x - DIVIDER_GRAB_DISTANCE,
+            y: divider.y - DIVIDER_GRAB_DISTANCE,
+            width: divider.width + 2. * DIVIDER_GRAB_DISTANCE,
+            height: divider.height + 2. * DIVIDER_GRAB_DISTANCE,
+        };
+
+        // Prefer nested dividers, since they are closer to the cursor.
+        for (step, node, rect) in [(false, first, first_rect), (true, second, second_rect)] {
+            path.push(step);
+            if rect.contains(x, y) && node.divider_at(rect, gap, x, y, path) {
+                return true;
+            }
+            path.pop();
+        }
+
+        grab.contains(x, y)
+    }
+}
+
+/// Tree of splits inside a window.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Layout {
+    root: Node,
+    focused: PaneId,
+    zoomed: bool,
+}
+
+impl Layout {
+    /// Create a layout with a single pane.
+    pub fn new(id: PaneId) -> Self {
+        Self { root: Node::Pane(id), focused: id, zoomed: false }
+    }
+
+    /// ID of the focused pane.
+    pub fn focused(&self) -> PaneId {
+        self.focused
+    }
+
+    /// Check if there is more than one pane.
+    pub fn is_split(&self) -> bool {
+        matches!(self.root, Node::Split { .. })
+    }
+
+    /// Split the focused pane in half, returning the ID of the new pane.
+    ///
+    /// The new pane is placed to the right of or below the focused pane and receives focus.
+    pub fn split(&mut self, split: Split) -> PaneId {
+        let id = PaneId::next();
+
+        let mut path = Vec::new();
+        self.root.path(self.focused, &mut path);
+
+        let node = self.node_mut(&path);
+        let focused = Box::new(node.clone());
+        *node = Node::Split { split, ratio: 0.5, first: focused, second: Box::new(Node::Pane(id)) };
+
+        self.focused = id;
+        self.zoomed = false;
+
+        id
+    }
+
+    /// Remove a pane from the layout.
+    ///
+    /// Returns `false` if the pane is not part of the layout or the last remaining pane.
+    pub fn remove(&mut self, id: PaneId) -> bool {
+        let mut path = Vec::new();
+        if !self.root.path(id, &mut path) || path.is_empty() {
+            return false;
+        }
+
+        // Replace the parent split with the removed pane's sibling.
+        let removed_second = path.pop().unwrap();
+        let parent = self.node_mut(&path);
+        let sibling = match parent {
+            Node::Split { first, second, .. } if removed_second => first.as_ref().clone(),
+            Node::Split { second, .. } => second.as_ref().clone(),
+            Node::Pane(_) => unreachable!(),
+        };
+        let sibling_pane = sibling.first_pane();
+        *parent = sibling;
+
+        if self.focused == id {
+            self.focused = sibling_pane;
+            self.zoomed = false;
+        }
+
+        true
+    }
+
+    /// Remove all panes except the focused one.
+    pub fn unsplit(&mut self) {
+        self.root = Node::Pane(self.focused);
+        self.zoomed = false;
+    }
+
+    /// Focus a pane.
+    pub fn focus(&mut self, id: PaneId) {
+        if self.root.contains(id) {
+            self.focused = id;
+            self.zoomed = false;
+        }
+    }
+
+    /// Toggle the focused pane filling the entire window.
+    pub fn toggle_zoom(&mut self) {
+        self.zoomed = !self.zoomed && self.is_split();
+    }
+
+    /// Visible panes and their position inside a window of the specified size.
+    pub fn panes(&self, width: f32, height: f32, gap: f32) -> Vec<(PaneId, Rect)> {
+        self.compute(width, height, gap).0
+    }
+
+    /// Dividers between visible panes.
+    pub fn dividers(&self, width: f32, height: f32, gap: f32) -> Vec<Rect> {
+        self.compute(width, height, gap).1
+    }
+
+    /// Position of a visible pane.
+    pub fn rect(&self, id: PaneId, width: f32, height: f32, gap: f32) -> Option<Rect> {
+        let panes = self.panes(width, height, gap);
+        panes.into_iter().find(|(pane, _)| *pane == id).map(|(_, rect)| rect)
+    }
+
+    /// Visible pane at the specified position.
+    pub fn pane_at(&self, x: f32, y: f32, width: f32, height: f32, gap: f32) -> Option<PaneId> {
+        let panes = self.panes(width, height, gap);
+        panes.into_iter().find(|(_, rect)| rect.contains(x, y)).map(|(pane, _)| pane)
+    }
+
+    /// Find the closest pane in a direction from the focused pane.
+    pub fn neighbor(&self, direction: Direction, width: f32, height: f32) -> Option<PaneId> {
+        // Compute the unzoomed layout, so hidden panes can be found.
+        let mut panes = Vec::new();
+        let window = Rect::new(0., 0., width, height);
+        self.root.rects(window, 0., &mut panes, &mut Vec::new());
+
+        let (_, focused) = panes.iter().find(|(pane, _)| *pane == self.focused)?;
+
+        // Distance to the pane along the direction, and the overlap orthogonal to it.
+        let placement = |rect: &Rect| {
+            let (distance, start, end, rect_start, rect_end) = match direction {
+                Direction::Left => (
+                    focused.x - (rect.x + rect.width),
+                    focused.y,
+                    focused.y + focused.height,
+                    rect.y,
+                    rect.y + rect.height,
+                ),
+                Direction::Right => (
+                    rect.x - (focused.x + focused.width),
+                    focused.y,
+                    focused.y + focused.height,
+                    rect.y,
+                    rect.y + rect.height,
+                ),
+                Direction::Up => (
+                    focused.y - (rect.y + rect.height),
+                    focused.x,
+                    focused.x + focused.width,
+                    rect.x,
+                    rect.x + rect.width,
+                ),
+                Direction::Down => (
+                    rect.y - (focused.y + focused.height),
+                    focused.x,
+                    focused.x + focused.width,
+                    rect.x,
+                    rect.x + rect.width,
+                ),
+            };
+            let overlap = end.min(rect_end) - start.max(rect_start);
+            (distance, overlap)
+        };
+
+        panes
+            .iter()
+            .filter(|(pane, _)| *pane != self.focused)
+            .map(|(pane, rect)| (pane, placement(rect)))
+            .filter(|(_, (distance, overlap))| *distance >= 0. && *overlap > 0.)
+            .min_by(|(_, (a_dist, a_overlap)), (_, (b_dist, b_overlap))| {
+                a_dist.total_cmp(b_dist).then(b_overlap.total_cmp(a_overlap))
+            })
+            .map(|(pane, _)| *pane)
+    }
+
+    /// Move the closest divider of the focused pane in a direction by `amount` pixels.
+    pub fn resize(&mut self, direction: Direction, amount: f32, width: f32, height: f32, gap: f32) {
+        let mut path = Vec::new();
+        self.root.path(self.focused, &mut path);
+
+        // Find the closest ancestor splitting along the direction.
+        while !path.is_empty() {
+            path.pop();
+
+            let window = Rect::new(0., 0., width, height);
+            let (node, rect) = match self.root.node_at(&path, window, gap) {
+                Some(node) => node,
+                None => return,
+            };
+
+            if let Node::Split { split, ratio, .. } = node {
+                if *split != direction.split() {
+                    continue;
+                }
+
+                let size = match split {
+                    Split::Horizontal => rect.width - gap,
+                    Split::Vertical => rect.height - gap,
+                };
+                if size <= 0. {
+                    return;
+                }
+
+                let amount = match direction {
+                    Direction::Left | Direction::Up => -amount,
+                    Direction::Right | Direction::Down => amount,
+                };
+                *ratio = (*ratio + amount / size).clamp(MIN_RATIO, 1. - MIN_RATIO);
+
+                return;
+            }
+        }
+    }
+
+    /// Find the divider at the specified position.
+    pub fn divider_at(&self, x: f32, y: f32, width: f32, height: f32, gap: f32) -> Option<Divider> {
+        if self.zoomed {
+            return None;
+        }
+
+        let mut path = Vec::new();
+        let window = Rect::new(0., 0., width, height);
+        self.root.divider_at(window, gap, x, y, &mut path).then_some(Divider(path))
+    }
+
+    /// Move a divider to the specified position.
+    pub fn drag_divider(
+        &mut self,
+        divider: &Divider,
+        x: f32,
+        y: f32,
+        width: f32,
+        height: f32,
+        gap: f32,
+    ) {
+        let window = Rect::new(0., 0., width, height);
+        if let Some((Node::Split { split, ratio, .. }, rect)) =
+            self.root.node_at(&divider.0, window, gap)
+        {
+            let (position, start, size) = match split {
+                Split::Horizontal => (x, rect.x, rect.width - gap),
+                Split::Vertical => (y, rect.y, rect.height - gap),
+            };
+
+            if size > 0. {
+                *ratio = ((position - start) / size).clamp(MIN_RATIO, 1. - MIN_RATIO);
+            }
+        }
+    }
+
+    fn node_mut(&mut self, path: &[bool]) -> &mut Node {
+        let mut node = &mut self.root;
+        for &step in path {
+            node = match node {
+                Node::Split { second, .. } if step => second,
+                Node::Split { first, .. } => first,
+                Node::Pane(_) => unreachable!("invalid pane path"),
+            };
+        }
+        node
+    }
+
+    fn compute(&self, width: f32, height: f32, gap: f32) -> (Vec<(PaneId, Rect)>, Vec<Rect>) {
+        let window = Rect::new(0., 0., width, height);
+        if self.zoomed {
+            return (vec![(self.focused, window)], Vec::new());
+        }
+
+        let mut panes = Vec::new();
+        let mut dividers = Vec::new();
+        self.root.rects(window, gap, &mut panes, &mut dividers);
+        (panes, dividers)
+    }
+}
+
+/// Terminal running inside a pane.
+pub struct Pane {
+    pub id: PaneId,
+    pub terminal: Arc<FairMutex<Term<EventProxy>>>,
+    pub notifier: Notifier,
+    pub recording: Recording,
+    #[cfg(unix)]
+    pub event_proxy: EventProxy,
+    pub search_state: SearchState,
+    pub inline_search_state: InlineSearchState,
+    #[cfg(not(windows))]
+    pub master_fd: RawFd,
+    #[cfg(not(windows))]
+    pub shell_pid: u32,
+
+    /// Size of the pane while it's not focused.
+    ///
+    /// The focused pane's size is stored in the display.
+    pub size_info: SizeInfo,
+}
+
+impl Drop for Pane {
+    fn drop(&mut self) {
+        // Shutdown the terminal's PTY.
+        let _ = self.notifier.0.send(Msg::Shutdown);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn split_layout() {
+        let root = PaneId::next();
+        let mut layout = Layout::new(root);
+        assert_eq!(layout.panes(101., 50., 1.), [(root, Rect::new(0., 0., 101., 50.))]);
+        assert!(layout.dividers(101., 50., 1.).is_empty());
+
+        let right = layout.split(Split::Horizontal);
+        assert_eq!(layout.focused(), right);
+        assert_eq!(layout.panes(101., 50., 1.), [
+            (root, Rect::new(0., 0., 50., 50.)),
+            (right, Rect::new(51., 0., 50., 50.)),
+        ]);
+        assert_eq!(layout.dividers(101., 50., 1.), [Rect::new(50., 0., 1., 50.)]);
+
+        let bottom = layout.split(Split::Vertical);
+        assert_eq!(layout.panes(101., 51., 1.), [
+            (root, Rect::new(0., 0., 50., 51.)),
+            (right, Rect::new(51., 0., 50., 25.)),
+            (bottom, Rect::new(51., 26., 50., 25.)),
+        ]);
+    }
+
+    #[test]
+    fn remove_pane() {
+        let root = PaneId::next();
+        let mut layout = Layout::new(root);
+        let right = layout.split(Split::Horizontal);
+        let bottom = layout.split(Split::Vertical);
+
+        assert!(layout.remove(bottom));
+        assert_eq!(layout.focused(), right);
+        assert_eq!(layout.panes(10., 10., 0.), [
+            (root, Rect::new(0., 0., 5., 10.)),
+            (right, Rect::new(5., 0., 5., 10.)),
+        ]);
+
+        assert!(layout.remove(root));
+        assert_eq!(layout.panes(10., 10., 0.), [(right, Rect::new(0., 0., 10., 10.))]);
+
+        assert!(!layout.remove(right));
+        assert!(!layout.remove(bottom));
+    }
+
+    #[test]
+    fn zoom_pane() {
+        let root = PaneId::next();
+        let mut layout = Layout::new(root);
+        layout.toggle_zoom();
+        assert!(!layout.zoomed);
+
+        let right = layout.split(Split::Horizontal);
+        layout.toggle_zoom();
+        assert_eq!(layout.panes(10., 10., 1.), [(right, Rect::new(0., 0., 10., 10.))]);
+        assert!(layout.dividers(10., 10., 1.).is_empty());
+
+        layout.focus(root);
+        assert_eq!(layout.panes(10., 10., 1.).len(), 2);
+    }
+
+    #[test]
+    fn neighbor() {
+        let root = PaneId::next();
+        let mut layout = Layout::new(root);
+        let right = layout.split(Split::Horizontal);
+        let bottom_right = layout.split(Split::Vertical);
+
+        assert_eq!(layout.neighbor(Direction::Left, 100., 100.), Some(root));
+        assert_eq!(layout.neighbor(Direction::Up, 100., 100.), Some(right));
+        assert_eq!(layout.neighbor(Direction::Right, 100., 100.), None);
+        assert_eq!(layout.neighbor(Direction::Down, 100., 100.), None);
+
+        layout.focus(root);
+        assert_eq!(layout.neighbor(Direction::Right, 100., 100.), Some(right));
+
+        layout.focus(bottom_right);
+        layout.toggle_zoom();
+        assert_eq!(layout.neighbor(Direction::Up, 100., 100.), Some(right));
+    }
+
+    #[test]
+    fn resize_pane() {
+        let root = PaneId::next();
+        let mut layout = Layout::new(root);
+        let right = layout.split(Split::Horizontal);
+        layout.split(Split::Vertical);
+
+        layout.resize(Direction::Left, 10., 100., 100., 0.);
+        assert_eq!(layout.rect(right, 100., 100., 0.), Some(Rect::new(40., 0., 60., 50.)));
+
+        layout.resize(Direction::Down, 25., 100., 100., 0.);
+        assert_eq!(layout.rect(right, 100., 100., 0.), Some(Rect::new(40., 0., 60., 75.)));
+
+        layout.resize(Direction::Right, 1000., 100., 100., 0.);
+        assert_eq!(layout.rect(right, 100., 100., 0.), Some(Rect::new(95., 0., 5., 75.)));
+    }
+
+    #[test]
+    fn drag_divider() {
+        let root = PaneId::next();
+        let mut layout = Layout::new(root);
+        let right = layout.split(Split::Horizontal);
+        let bottom = layout.split(Split::Vertical);
+
+        assert_eq!(layout.divider_at(20., 20., 100., 100., 0.), None);
+
+        let divider = layout.divider_at(49., 20., 100., 100., 0.).unwrap();
+        layout.drag_divider(&divider, 30., 20., 100., 100., 0.);
+        assert_eq!(layout.rect(root, 100., 100., 0.), Some(Rect::new(0., 0., 30., 100.)));
+
+        let divider = layout.divider_at(80., 52., 100., 100., 0.).unwrap();
+        layout.drag_divider(&divider, 80., 60., 100., 100., 0.);
+        assert_eq!(layout.rect(right, 100., 100., 0.), Some(Rect::new(30., 0., 70., 60.)));
+        assert_eq!(layout.rect(bottom, 100., 100., 0.), Some(Rect::new(30., 60., 70., 40.)));
+    }
+}
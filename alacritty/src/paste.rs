@@ -0,0 +1,176 @@
+//! Filtering and confirmation of pasted text.
+
+use std::borrow::Cow;
+
+use crate::config::paste::PasteConfig;
+
+/// Paste waiting for confirmation by the user.
+pub struct PendingPaste {
+    text: String,
+}
+
+impl PendingPaste {
+    pub fn new(text: String) -> Self {
+        Self { text }
+    }
+
+    /// Text which will be pasted once confirmed.
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Question shown to the user before pasting.
+    pub fn prompt(&self) -> String {
+        let lines = self.text.lines().count().max(1);
+        let lines = if lines == 1 { String::from("1 line") } else { format!("{lines} lines") };
+
+        let size = format_size(self.text.len());
+        let control = if has_control_chars(&self.text) { " with control characters" } else { "" };
+
+        format!("Paste {lines} ({size}){control}? [y/N] ")
+    }
+}
+
+/// Apply the configured filters to pasted text.
+pub fn filter<'a>(config: &PasteConfig, text: &'a str) -> Cow<'a, str> {
+    let mut text = Cow::Borrowed(text);
+
+    if config.strip_escapes && text.contains('\x1b') {
+        text = Cow::Owned(strip_escapes(&text));
+    }
+
+    if config.strip_trailing_newlines {
+        let len = text.trim_end_matches(['\r', '\n']).len();
+        match &mut text {
+            Cow::Borrowed(borrowed) => *borrowed = &borrowed[..len],
+            Cow::Owned(owned) => owned.truncate(len),
+        }
+    }
+
+    text
+}
+
+/// Check whether pasting text must be confirmed by the user first.
+pub fn needs_confirmation(config: &PasteConfig, text: &str, bracketed: bool) -> bool {
+    let too_large = config.confirm_size != 0 && text.len() > config.confirm_size;
+    let unsafe_text = config.confirm_unsafe && !bracketed && has_control_chars(text);
+    too_large || unsafe_text
+}
+
+/// Check for line breaks and other control characters, except for tabs.
+fn has_control_chars(text: &str) -> bool {
+    text.chars().any(|c| c.is_control() && c != '\t')
+}
+
+/// Remove escape sequences from text.
+fn strip_escapes(text: &str) -> String {
+    let mut stripped = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if c != '\x1b' {
+            stripped.push(c);
+            continue;
+        }
+
+        match chars.next() {
+            // CSI sequences end with a character in the range `@` to `~`.
+            Some('[') => {
+                for c in chars.by_ref() {
+                    if ('@'..='~').contains(&c) {
+                        break;
+                    }
+                }
+            },
+            // String sequences end with BEL or ST.
+            Some(']' | 'P' | 'X' | '^' | '_') => {
+                while let Some(c) = chars.next() {
+                    if c == '\x07' {
+                        break;
+                    } else if c == '\x1b' && chars.peek() == Some(&'\\') {
+                        chars.next();
+                        break;
+                    }
+                }
+            },
+            // Other escapes have optional intermediates and a single final character.
+            Some(' '..='/') => {
+                for c in chars.by_ref() {
+                    if !(' '..='/').contains(&c) {
+                        break;
+                    }
+                }
+            },
+            _ => (),
+        }
+    }
+
+    stripped
+}
+
+/// Format a number of bytes for humans.
+fn format_size(bytes: usize) -> String {
+    const UNITS: [&str; 3] = ["KiB", "MiB", "GiB"];
+
+    if bytes < 1024 {
+        return format!("{bytes} bytes");
+    }
+
+    let mut size = bytes as f64 / 1024.;
+    let mut unit = UNITS[0];
+    for next_unit in &UNITS[1..] {
+        if size < 1024. {
+            break;
+        }
+        size /= 1024.;
+        unit = next_unit;
+    }
+
+    format!("{size:.1} {unit}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn strip_escape_sequences() {
+        assert_eq!(strip_escapes("a\x1b[1;31mb\x1b[0mc"), "abc");
+        assert_eq!(strip_escapes("a\x1b]0;title\x07b\x1b]8;;x\x1b\\c"), "abc");
+        assert_eq!(strip_escapes("a\x1b(Bb\x1bMc"), "abc");
+        assert_eq!(strip_escapes("a\x1b"), "a");
+    }
+
+    #[test]
+    fn filter_text() {
+        let mut config = PasteConfig::default();
+        assert_eq!(filter(&config, "\x1b[1mls\n\n"), "\x1b[1mls\n\n");
+
+        config.strip_escapes = true;
+        config.strip_trailing_newlines = true;
+        assert_eq!(filter(&config, "\x1b[1mls\r\n\n"), "ls");
+        assert_eq!(filter(&config, "ls\n\n"), "ls");
+    }
+
+    #[test]
+    fn confirmation() {
+        let mut config = PasteConfig::default();
+        assert!(!needs_confirmation(&config, "ls\t-l", false));
+        assert!(needs_confirmation(&config, "ls\n", false));
+        assert!(!needs_confirmation(&config, "ls\n", true));
+
+        config.confirm_size = 2;
+        assert!(needs_confirmation(&config, "abc", true));
+
+        config.confirm_size = 0;
+        config.confirm_unsafe = false;
+        assert!(!needs_confirmation(&config, "ls\n", false));
+    }
+
+    #[test]
+    fn prompt() {
+        let paste = PendingPaste::new(String::from("ls\nrm -rf /tmp/x\n"));
+        assert_eq!(paste.prompt(), "Paste 2 lines (17 bytes) with control characters? [y/N] ");
+        assert_eq!(format_size(3 * 1024 * 1024 / 2), "1.5 MiB");
+    }
+}
@@ -0,0 +1,201 @@
+//! Session recording and replay.
+
+use std::fmt::{self, Display, Formatter};
+use std::fs::{self, File};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use log::{error, info};
+use serde::Deserialize;
+use serde_json as json;
+
+use alacritty_terminal::event_loop::{EventLoopSender, Msg};
+use alacritty_terminal::recording::{Record, RecordKind};
+
+/// Recording state of a terminal.
+pub struct Recording {
+    sender: EventLoopSender,
+    path: Option<PathBuf>,
+}
+
+impl Recording {
+    pub fn new(sender: EventLoopSender) -> Self {
+        Self { sender, path: None }
+    }
+
+    /// Start recording to the specified file.
+    pub fn start(&mut self, path: PathBuf) {
+        self.stop();
+
+        let file = match File::create(&path) {
+            Ok(file) => file,
+            Err(err) => {
+                error!("Unable to create recording {path:?}: {err}");
+                return;
+            },
+        };
+
+        info!("Recording session to {path:?}");
+
+        let _ = self.sender.send(Msg::StartRecording(file));
+        self.path = Some(path);
+    }
+
+    /// Stop the active recording.
+    pub fn stop(&mut self) {
+        if let Some(path) = self.path.take() {
+            info!("Stopped recording session to {path:?}");
+            let _ = self.sender.send(Msg::StopRecording);
+        }
+    }
+
+    /// Toggle recording to a new file in the current directory.
+    pub fn toggle(&mut self) {
+        if self.path.is_some() {
+            self.stop();
+            return;
+        }
+
+        let timestamp = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+        self.start(PathBuf::from(format!("alacritty-{}.cast", timestamp.as_secs())));
+    }
+}
+
+/// Header of an asciicast v2 recording.
+#[derive(Deserialize, Debug)]
+struct Header {
+    version: u32,
+    width: usize,
+    height: usize,
+}
+
+/// Load an asciicast v2 recording.
+///
+/// The recording's initial dimensions are returned as its first record.
+pub fn load(path: &Path) -> Result<Vec<Record>, Error> {
+    parse(&fs::read_to_string(path)?)
+}
+
+/// Parse an asciicast v2 recording.
+fn parse(recording: &str) -> Result<Vec<Record>, Error> {
+    let mut lines = recording.lines().enumerate().filter(|(_, line)| !line.trim().is_empty());
+
+    let (_, header) = lines.next().ok_or(Error::Format(0, "missing header"))?;
+    let header: Header = json::from_str(header)?;
+    if header.version != 2 {
+        return Err(Error::Version(header.version));
+    }
+
+    let mut records = vec![Record {
+        time: Duration::ZERO,
+        kind: RecordKind::Resize(header.width, header.height),
+    }];
+
+    for (i, line) in lines {
+        let (time, code, data): (f64, String, String) = json::from_str(line)?;
+        let time = Duration::try_from_secs_f64(time).map_err(|_| Error::Format(i, "bad time"))?;
+
+        let kind = match code.as_str() {
+            "o" => RecordKind::Output(data),
+            "r" => {
+                let size = data.split_once('x').and_then(|(columns, lines)| {
+                    Some(RecordKind::Resize(columns.parse().ok()?, lines.parse().ok()?))
+                });
+                size.ok_or(Error::Format(i, "bad resize"))?
+            },
+            // Ignore input, markers and unknown events.
+            _ => continue,
+        };
+
+        records.push(Record { time, kind });
+    }
+
+    Ok(records)
+}
+
+#[derive(Debug)]
+pub enum Error {
+    /// Error reading the recording.
+    Io(io::Error),
+
+    /// Invalid JSON in the recording.
+    Json(json::Error),
+
+    /// Unsupported asciicast version.
+    Version(u32),
+
+    /// Invalid event at the specified line index.
+    Format(usize, &'static str),
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io(err) => err.source(),
+            Error::Json(err) => err.source(),
+            Error::Version(_) | Error::Format(..) => None,
+        }
+    }
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Io(err) => write!(f, "Unable to read recording: {err}"),
+            Error::Json(err) => write!(f, "Invalid recording: {err}"),
+            Error::Version(version) => write!(f, "Unsupported asciicast version {version}"),
+            Error::Format(i, err) => write!(f, "Invalid recording event on line {}: {err}", i + 1),
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(val: io::Error) -> Self {
+        Error::Io(val)
+    }
+}
+
+impl From<json::Error> for Error {
+    fn from(val: json::Error) -> Self {
+        Error::Json(val)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_recording() {
+        let recording = r#"{"version": 2, "width": 80, "height": 24, "env": {"TERM": "alacritty"}}
+[0.5, "o", "hello\r\n"]
+[1.0, "i", "x"]
+
+[1.25, "r", "100x30"]
+[2.0, "m", ""]
+"#;
+
+        let records = parse(recording).unwrap();
+        assert_eq!(records, [
+            Record { time: Duration::ZERO, kind: RecordKind::Resize(80, 24) },
+            Record {
+                time: Duration::from_millis(500),
+                kind: RecordKind::Output("hello\r\n".into())
+            },
+            Record { time: Duration::from_millis(1250), kind: RecordKind::Resize(100, 30) },
+        ]);
+    }
+
+    #[test]
+    fn parse_invalid_recording() {
+        assert!(matches!(parse(""), Err(Error::Format(0, _))));
+        assert!(matches!(parse(r#"{"version":1,"width":1,"height":1}"#), Err(Error::Version(1))));
+
+        let recording = "{\"version\":2,\"width\":1,\"height\":1}\n[0, \"r\", \"1y1\"]";
+        assert!(matches!(parse(recording), Err(Error::Format(1, "bad resize"))));
+
+        let recording = "{\"version\":2,\"width\":1,\"height\":1}\n[-1, \"o\", \"\"]";
+        assert!(matches!(parse(recording), Err(Error::Format(1, "bad time"))));
+    }
+}
@@ -0,0 +1,286 @@
+use std::collections::HashMap;
+use std::mem;
+
+use ahash::RandomState;
+use log::debug;
+
+use alacritty_terminal::graphics::{GraphicId, Placement, UpdateQueues};
+use alacritty_terminal::index::Point;
+
+use crate::display::SizeInfo;
+use crate::gl::types::*;
+use crate::renderer::shader::{ShaderProgram, ShaderVersion};
+use crate::{gl, renderer};
+
+/// Shader sources for graphics rendering program.
+const GRAPHICS_SHADER_F: &str = include_str!("../../res/graphics.f.glsl");
+const GRAPHICS_SHADER_V: &str = include_str!("../../res/graphics.v.glsl");
+
+/// Part of a graphic visible in a single cell.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct RenderGraphic {
+    /// Graphic which should be drawn.
+    pub id: GraphicId,
+
+    /// Position of the cell in the viewport.
+    pub point: Point<usize>,
+
+    /// Placement the cell is part of.
+    pub placement: Placement,
+
+    /// Horizontal offset of the cell's origin from the placement's origin in pixels.
+    pub offset_x: i32,
+
+    /// Vertical offset of the cell's origin from the placement's origin in pixels.
+    pub offset_y: i32,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+struct Vertex {
+    // Normalized screen coordinates.
+    x: f32,
+    y: f32,
+
+    // Texture coordinates.
+    u: f32,
+    v: f32,
+}
+
+/// Texture storing a graphic.
+#[derive(Debug)]
+struct GraphicTexture {
+    id: GLuint,
+    width: usize,
+    height: usize,
+}
+
+#[derive(Debug)]
+pub struct GraphicsRenderer {
+    // GL buffer objects.
+    vao: GLuint,
+    vbo: GLuint,
+
+    program: ShaderProgram,
+    vertices: Vec<Vertex>,
+
+    /// Textures of all graphics which are still referenced by the terminal.
+    textures: HashMap<GraphicId, GraphicTexture, RandomState>,
+}
+
+impl GraphicsRenderer {
+    pub fn new(shader_version: ShaderVersion) -> Result<Self, renderer::Error> {
+        let program =
+            ShaderProgram::new(shader_version, None, GRAPHICS_SHADER_V, GRAPHICS_SHADER_F)?;
+
+        let mut vao: GLuint = 0;
+        let mut vbo: GLuint = 0;
+
+        unsafe {
+            // Allocate buffers.
+            gl::GenVertexArrays(1, &mut vao);
+            gl::GenBuffers(1, &mut vbo);
+
+            gl::BindVertexArray(vao);
+            gl::BindBuffer(gl::ARRAY_BUFFER, vbo);
+
+            // Position.
+            gl::VertexAttribPointer(
+                0,
+                2,
+                gl::FLOAT,
+                gl::FALSE,
+                mem::size_of::<Vertex>() as i32,
+                std::ptr::null(),
+            );
+            gl::EnableVertexAttribArray(0);
+
+            // Texture coordinates.
+            gl::VertexAttribPointer(
+                1,
+                2,
+                gl::FLOAT,
+                gl::FALSE,
+                mem::size_of::<Vertex>() as i32,
+                (mem::size_of::<f32>() * 2) as *const _,
+            );
+            gl::EnableVertexAttribArray(1);
+
+            // Reset buffer bindings.
+            gl::BindVertexArray(0);
+            gl::BindBuffer(gl::ARRAY_BUFFER, 0);
+        }
+
+        Ok(Self { vao, vbo, program, vertices: Vec::new(), textures: Default::default() })
+    }
+
+    /// Upload new graphics and free the textures of removed ones.
+    pub fn update(&mut self, queues: UpdateQueues) {
+        let bound_texture = bound_texture();
+
+        for id in queues.remove_queue {
+            if let Some(texture) = self.textures.remove(&id) {
+                unsafe { gl::DeleteTextures(1, &texture.id) };
+            }
+        }
+
+        for (id, graphic) in queues.pending {
+            let mut texture = 0;
+            unsafe {
+                gl::GenTextures(1, &mut texture);
+                gl::BindTexture(gl::TEXTURE_2D, texture);
+
+                gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MIN_FILTER, gl::LINEAR as GLint);
+                gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAG_FILTER, gl::LINEAR as GLint);
+                gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_S, gl::CLAMP_TO_EDGE as GLint);
+                gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_T, gl::CLAMP_TO_EDGE as GLint);
+
+                gl::PixelStorei(gl::UNPACK_ALIGNMENT, 1);
+                gl::TexImage2D(
+                    gl::TEXTURE_2D,
+                    0,
+                    gl::RGBA as GLint,
+                    graphic.width as GLint,
+                    graphic.height as GLint,
+                    0,
+                    gl::RGBA,
+                    gl::UNSIGNED_BYTE,
+                    graphic.pixels.as_ptr() as *const _,
+                );
+            }
+
+            debug!("Uploaded {}x{} graphic {id:?}", graphic.width, graphic.height);
+
+            let texture =
+                GraphicTexture { id: texture, width: graphic.width, height: graphic.height };
+            self.textures.insert(id, texture);
+        }
+
+        unsafe { gl::BindTexture(gl::TEXTURE_2D, bound_texture) };
+    }
+
+    /// Draw graphics in the viewport.
+    pub fn draw(&mut self, size_info: &SizeInfo, mut graphics: Vec<RenderGraphic>) {
+        // Draw graphics in stacking order, grouping cells to bind every texture only once.
+        graphics.sort_by_key(|graphic| (graphic.placement.z_index, graphic.placement.id));
+
+        let half_width = size_info.width() / 2.;
+        let half_height = size_info.height() / 2.;
+        let cell_width = size_info.cell_width();
+        let cell_height = size_info.cell_height();
+
+        // Build vertices and the vertex range of every texture.
+        let mut batches: Vec<(GLuint, usize)> = Vec::new();
+        self.vertices.clear();
+        for graphic in &graphics {
+            let texture = match self.textures.get(&graphic.id) {
+                Some(texture) => texture,
+                None => continue,
+            };
+
+            // Clip the cell to the placement's dimensions.
+            let placement = &graphic.placement;
+            let (offset_x, offset_y) = (graphic.offset_x as f32, graphic.offset_y as f32);
+            let clip_left = offset_x.max(0.);
+            let clip_top = offset_y.max(0.);
+            let clip_right = (offset_x + cell_width).min(placement.width as f32);
+            let clip_bottom = (offset_y + cell_height).min(placement.height as f32);
+            if clip_right <= clip_left || clip_bottom <= clip_top {
+                continue;
+            }
+
+            // Calculate quad vertices positions in normalized device coordinates.
+            // NDC range from -1 to +1, with Y pointing up.
+            let x = size_info.padding_x() + graphic.point.column.0 as f32 * cell_width;
+            let y = size_info.padding_y() + graphic.point.line as f32 * cell_height;
+            let left = (x + clip_left - offset_x) / half_width - 1.;
+            let right = (x + clip_right - offset_x) / half_width - 1.;
+            let top = -(y + clip_top - offset_y) / half_height + 1.;
+            let bottom = -(y + clip_bottom - offset_y) / half_height + 1.;
+
+            // Map the clipped region to the displayed part of the texture.
+            let source = &placement.source;
+            let scale_x = source.width as f32 / placement.width as f32;
+            let scale_y = source.height as f32 / placement.height as f32;
+            let u = |x: f32| (source.x as f32 + x * scale_x) / texture.width as f32;
+            let v = |y: f32| (source.y as f32 + y * scale_y) / texture.height as f32;
+            let (u_left, u_right) = (u(clip_left), u(clip_right));
+            let (v_top, v_bottom) = (v(clip_top), v(clip_bottom));
+
+            let quad = [
+                Vertex { x: left, y: top, u: u_left, v: v_top },
+                Vertex { x: left, y: bottom, u: u_left, v: v_bottom },
+                Vertex { x: right, y: top, u: u_right, v: v_top },
+                Vertex { x: right, y: bottom, u: u_right, v: v_bottom },
+            ];
+
+            // Append the vertices to form two triangles.
+            self.vertices
+                .extend_from_slice(&[quad[0], quad[1], quad[2], quad[2], quad[3], quad[1]]);
+
+            match batches.last_mut() {
+                Some((id, count)) if *id == texture.id => *count += 6,
+                _ => batches.push((texture.id, 6)),
+            }
+        }
+
+        if self.vertices.is_empty() {
+            return;
+        }
+
+        let bound_texture = bound_texture();
+
+        unsafe {
+            // Remove padding from viewport.
+            renderer::set_pane_viewport(size_info);
+            gl::BlendFuncSeparate(gl::SRC_ALPHA, gl::ONE_MINUS_SRC_ALPHA, gl::SRC_ALPHA, gl::ONE);
+
+            gl::UseProgram(self.program.id());
+            gl::BindVertexArray(self.vao);
+            gl::BindBuffer(gl::ARRAY_BUFFER, self.vbo);
+            gl::ActiveTexture(gl::TEXTURE0);
+
+            gl::BufferData(
+                gl::ARRAY_BUFFER,
+                (self.vertices.len() * mem::size_of::<Vertex>()) as isize,
+                self.vertices.as_ptr() as *const _,
+                gl::STREAM_DRAW,
+            );
+
+            let mut start = 0;
+            for (texture, count) in batches {
+                gl::BindTexture(gl::TEXTURE_2D, texture);
+                gl::DrawArrays(gl::TRIANGLES, start as i32, count as i32);
+                start += count;
+            }
+
+            // Reset state.
+            gl::BindTexture(gl::TEXTURE_2D, bound_texture);
+            gl::BindBuffer(gl::ARRAY_BUFFER, 0);
+            gl::BindVertexArray(0);
+            gl::UseProgram(0);
+        }
+    }
+}
+
+/// Get the currently bound texture.
+///
+/// The text renderer caches its bound atlas, so the binding must be restored after drawing.
+fn bound_texture() -> GLuint {
+    let mut texture = 0;
+    unsafe { gl::GetIntegerv(gl::TEXTURE_BINDING_2D, &mut texture) };
+    texture as GLuint
+}
+
+impl Drop for GraphicsRenderer {
+    fn drop(&mut self) {
+        unsafe {
+            for texture in self.textures.values() {
+                gl::DeleteTextures(1, &texture.id);
+            }
+
+            gl::DeleteBuffers(1, &self.vbo);
+            gl::DeleteVertexArrays(1, &self.vao);
+        }
+    }
+}
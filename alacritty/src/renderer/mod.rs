@@ -12,6 +12,7 @@ use glutin::display::{GetGlDisplay, GlDisplay};
 use log::{LevelFilter, debug, info};
 use unicode_width::UnicodeWidthChar;
 
+use alacritty_terminal::graphics::UpdateQueues;
 use alacritty_terminal::index::Point;
 use alacritty_terminal::term::cell::Flags;
 
@@ -20,14 +21,21 @@ use crate::display::SizeInfo;
 use crate::display::color::Rgb;
 use crate::display::content::RenderableCell;
 use crate::gl;
+use crate::renderer::graphics::{GraphicsRenderer, RenderGraphic};
+use crate::renderer::platform::SoftwareSurface;
 use crate::renderer::rects::{RectRenderer, RenderRect};
 use crate::renderer::shader::ShaderError;
+use crate::renderer::software::SoftwareRenderer;
+use crate::screenshot::Image;
 
+pub mod graphics;
 pub mod platform;
 pub mod rects;
 mod shader;
+pub mod software;
 mod text;
 
+pub use text::shaper::{ShapedGlyph, Shaper};
 pub use text::{GlyphCache, LoaderApi};
 
 use shader::ShaderVersion;
@@ -85,10 +93,21 @@ enum TextRendererProvider {
     Glsl3(Glsl3Renderer),
 }
 
+#[allow(clippy::large_enum_variant)]
 #[derive(Debug)]
-pub struct Renderer {
+pub enum Renderer {
+    /// Renderer using OpenGL.
+    Gl(GlRenderer),
+
+    /// Renderer drawing on the CPU.
+    Software(SoftwareRenderer),
+}
+
+#[derive(Debug)]
+pub struct GlRenderer {
     text_renderer: TextRendererProvider,
     rect_renderer: RectRenderer,
+    graphics_renderer: GraphicsRenderer,
     robustness: bool,
 }
 
@@ -112,6 +131,155 @@ fn gl_get_string(
 }
 
 impl Renderer {
+    /// Create a new OpenGL renderer.
+    pub fn new(
+        context: &PossiblyCurrentContext,
+        renderer_preference: Option<RendererPreference>,
+    ) -> Result<Self, Error> {
+        Ok(Self::Gl(GlRenderer::new(context, renderer_preference)?))
+    }
+
+    pub fn draw_cells<I: Iterator<Item = RenderableCell>>(
+        &mut self,
+        size_info: &SizeInfo,
+        glyph_cache: &mut GlyphCache,
+        cells: I,
+    ) {
+        match self {
+            Self::Gl(renderer) => renderer.draw_cells(size_info, glyph_cache, cells),
+            Self::Software(renderer) => renderer.draw_cells(size_info, glyph_cache, cells),
+        }
+    }
+
+    /// Draw a string in a variable location. Used for printing the render timer, warnings and
+    /// errors.
+    pub fn draw_string(
+        &mut self,
+        point: Point<usize>,
+        fg: Rgb,
+        bg: Rgb,
+        string_chars: impl Iterator<Item = char>,
+        size_info: &SizeInfo,
+        glyph_cache: &mut GlyphCache,
+    ) {
+        let mut wide_char_spacer = false;
+        let cells = string_chars.enumerate().filter_map(|(i, character)| {
+            let flags = if wide_char_spacer {
+                wide_char_spacer = false;
+                return None;
+            } else if character.width() == Some(2) {
+                // The spacer is always following the wide char.
+                wide_char_spacer = true;
+                Flags::WIDE_CHAR
+            } else {
+                Flags::empty()
+            };
+
+            Some(RenderableCell {
+                point: Point::new(point.line, point.column + i),
+                character,
+                extra: None,
+                glyph: None,
+                flags,
+                bg_alpha: 1.0,
+                fg,
+                bg,
+                underline: fg,
+            })
+        });
+
+        self.draw_cells(size_info, glyph_cache, cells);
+    }
+
+    pub fn with_loader<F, T>(&mut self, func: F) -> T
+    where
+        F: FnOnce(LoaderApi<'_>) -> T,
+    {
+        match self {
+            Self::Gl(renderer) => renderer.with_loader(func),
+            Self::Software(renderer) => renderer.with_loader(func),
+        }
+    }
+
+    /// Draw all rectangles simultaneously to prevent excessive program swaps.
+    pub fn draw_rects(&mut self, size_info: &SizeInfo, metrics: &Metrics, rects: Vec<RenderRect>) {
+        match self {
+            Self::Gl(renderer) => renderer.draw_rects(size_info, metrics, rects),
+            Self::Software(renderer) => renderer.draw_rects(size_info, metrics, rects),
+        }
+    }
+
+    /// Upload new graphics and free the ones which are no longer used.
+    pub fn update_graphics(&mut self, queues: UpdateQueues) {
+        match self {
+            Self::Gl(renderer) => renderer.graphics_renderer.update(queues),
+            Self::Software(renderer) => renderer.update_graphics(queues),
+        }
+    }
+
+    /// Draw all graphics visible in the viewport.
+    pub fn draw_graphics(&mut self, size_info: &SizeInfo, graphics: Vec<RenderGraphic>) {
+        match self {
+            Self::Gl(renderer) => renderer.draw_graphics(size_info, graphics),
+            Self::Software(renderer) => renderer.draw_graphics(size_info, graphics),
+        }
+    }
+
+    /// Fill the window with `color` and `alpha`.
+    pub fn clear(&mut self, color: Rgb, alpha: f32) {
+        match self {
+            Self::Gl(renderer) => renderer.clear(color, alpha),
+            Self::Software(renderer) => renderer.clear(color),
+        }
+    }
+
+    /// Get the context reset status.
+    pub fn was_context_reset(&self) -> bool {
+        match self {
+            Self::Gl(renderer) => renderer.was_context_reset(),
+            Self::Software(_) => false,
+        }
+    }
+
+    pub fn finish(&self) {
+        if let Self::Gl(renderer) = self {
+            renderer.finish();
+        }
+    }
+
+    /// Set the viewport for cell rendering.
+    #[cfg(target_os = "macos")]
+    pub fn set_viewport(&self, size: &SizeInfo) {
+        if let Self::Gl(renderer) = self {
+            renderer.set_viewport(size);
+        }
+    }
+
+    /// Resize the renderer.
+    pub fn resize(&mut self, size_info: &SizeInfo) {
+        match self {
+            Self::Gl(renderer) => renderer.resize(size_info),
+            Self::Software(renderer) => renderer.resize(size_info),
+        }
+    }
+
+    /// Image of the current frame, before it is presented.
+    pub fn image(&self, size_info: &SizeInfo) -> Image {
+        match self {
+            Self::Gl(renderer) => renderer.image(size_info),
+            Self::Software(renderer) => renderer.image(),
+        }
+    }
+
+    /// Copy the software renderer's frame to the window.
+    pub fn present(&self, surface: &mut SoftwareSurface) {
+        if let Self::Software(renderer) = self {
+            renderer.present(surface);
+        }
+    }
+}
+
+impl GlRenderer {
     /// Create a new renderer.
     ///
     /// This will automatically pick between the GLES2 and GLSL3 renderer based on the GPU's
@@ -147,19 +315,21 @@ impl Renderer {
             Some(RendererPreference::Glsl3) => (true, true),
             Some(RendererPreference::Gles2) => (false, true),
             Some(RendererPreference::Gles2Pure) => (false, false),
-            None => (shader_version.as_ref() >= "3.3" && !is_gles_context, true),
+            Some(RendererPreference::Software) | None => {
+                (shader_version.as_ref() >= "3.3" && !is_gles_context, true)
+            },
         };
 
-        let (text_renderer, rect_renderer) = if use_glsl3 {
+        let (text_renderer, shader_version) = if use_glsl3 {
             let text_renderer = TextRendererProvider::Glsl3(Glsl3Renderer::new()?);
-            let rect_renderer = RectRenderer::new(ShaderVersion::Glsl3)?;
-            (text_renderer, rect_renderer)
+            (text_renderer, ShaderVersion::Glsl3)
         } else {
             let text_renderer =
                 TextRendererProvider::Gles2(Gles2Renderer::new(allow_dsb, is_gles_context)?);
-            let rect_renderer = RectRenderer::new(ShaderVersion::Gles2)?;
-            (text_renderer, rect_renderer)
+            (text_renderer, ShaderVersion::Gles2)
         };
+        let rect_renderer = RectRenderer::new(shader_version)?;
+        let graphics_renderer = GraphicsRenderer::new(shader_version)?;
 
         // Enable debug logging for OpenGL as well.
         if log::max_level() >= LevelFilter::Debug && GlExtensions::contains("GL_KHR_debug") {
@@ -171,7 +341,7 @@ impl Renderer {
             }
         }
 
-        Ok(Self { text_renderer, rect_renderer, robustness })
+        Ok(Self { text_renderer, rect_renderer, graphics_renderer, robustness })
     }
 
     pub fn draw_cells<I: Iterator<Item = RenderableCell>>(
@@ -190,45 +360,6 @@ impl Renderer {
         }
     }
 
-    /// Draw a string in a variable location. Used for printing the render timer, warnings and
-    /// errors.
-    pub fn draw_string(
-        &mut self,
-        point: Point<usize>,
-        fg: Rgb,
-        bg: Rgb,
-        string_chars: impl Iterator<Item = char>,
-        size_info: &SizeInfo,
-        glyph_cache: &mut GlyphCache,
-    ) {
-        let mut wide_char_spacer = false;
-        let cells = string_chars.enumerate().filter_map(|(i, character)| {
-            let flags = if wide_char_spacer {
-                wide_char_spacer = false;
-                return None;
-            } else if character.width() == Some(2) {
-                // The spacer is always following the wide char.
-                wide_char_spacer = true;
-                Flags::WIDE_CHAR
-            } else {
-                Flags::empty()
-            };
-
-            Some(RenderableCell {
-                point: Point::new(point.line, point.column + i),
-                character,
-                extra: None,
-                flags,
-                bg_alpha: 1.0,
-                fg,
-                bg,
-                underline: fg,
-            })
-        });
-
-        self.draw_cells(size_info, glyph_cache, cells);
-    }
-
     pub fn with_loader<F, T>(&mut self, func: F) -> T
     where
         F: FnOnce(LoaderApi<'_>) -> T,
@@ -248,7 +379,7 @@ impl Renderer {
         // Prepare rect rendering state.
         unsafe {
             // Remove padding from viewport.
-            gl::Viewport(0, 0, size_info.width() as i32, size_info.height() as i32);
+            set_pane_viewport(size_info);
             gl::BlendFuncSeparate(gl::SRC_ALPHA, gl::ONE_MINUS_SRC_ALPHA, gl::SRC_ALPHA, gl::ONE);
         }
 
@@ -264,6 +395,24 @@ impl Renderer {
         }
     }
 
+    /// Draw all graphics visible in the viewport.
+    pub fn draw_graphics(&mut self, size_info: &SizeInfo, graphics: Vec<RenderGraphic>) {
+        if graphics.is_empty() {
+            return;
+        }
+
+        self.graphics_renderer.draw(size_info, graphics);
+
+        // Activate regular state again.
+        unsafe {
+            // Reset blending strategy.
+            gl::BlendFunc(gl::SRC1_COLOR, gl::ONE_MINUS_SRC1_COLOR);
+
+            // Restore viewport with padding.
+            self.set_viewport(size_info);
+        }
+    }
+
     /// Fill the window with `color` and `alpha`.
     pub fn clear(&self, color: Rgb, alpha: f32) {
         unsafe {
@@ -326,13 +475,44 @@ impl Renderer {
         }
     }
 
+    /// Read the window's pixels from the back buffer.
+    pub fn image(&self, size_info: &SizeInfo) -> Image {
+        let width = size_info.window_width() as usize;
+        let height = size_info.window_height() as usize;
+
+        let mut rgba = vec![0u8; width * height * 4];
+        unsafe {
+            gl::PixelStorei(gl::PACK_ALIGNMENT, 1);
+            gl::ReadPixels(
+                0,
+                0,
+                width as i32,
+                height as i32,
+                gl::RGBA,
+                gl::UNSIGNED_BYTE,
+                rgba.as_mut_ptr() as *mut _,
+            );
+        }
+
+        // OpenGL's rows start at the bottom of the window.
+        let pixels = rgba
+            .chunks_exact(width * 4)
+            .rev()
+            .flat_map(|row| row.chunks_exact(4).flat_map(|pixel| [pixel[0], pixel[1], pixel[2]]))
+            .collect();
+
+        Image::new(width as u32, height as u32, pixels)
+    }
+
     /// Set the viewport for cell rendering.
     #[inline]
     pub fn set_viewport(&self, size: &SizeInfo) {
+        // OpenGL's viewport origin is at the bottom of the window.
+        let y = size.window_height() - size.y() - size.height();
         unsafe {
             gl::Viewport(
-                size.padding_x() as i32,
-                size.padding_y() as i32,
+                (size.x() + size.padding_x()) as i32,
+                (y + size.padding_y()) as i32,
                 size.width() as i32 - 2 * size.padding_x() as i32,
                 size.height() as i32 - 2 * size.padding_y() as i32,
             );
@@ -349,6 +529,14 @@ impl Renderer {
     }
 }
 
+/// Set the viewport to the terminal's area inside the window, including its padding.
+fn set_pane_viewport(size: &SizeInfo) {
+    let y = size.window_height() - size.y() - size.height();
+    unsafe {
+        gl::Viewport(size.x() as i32, y as i32, size.width() as i32, size.height() as i32);
+    }
+}
+
 struct GlExtensions;
 
 impl GlExtensions {
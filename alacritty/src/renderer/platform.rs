@@ -8,14 +8,60 @@ use glutin::context::{
 };
 use glutin::display::{Display, DisplayApiPreference, DisplayFeatures, GetGlDisplay};
 use glutin::error::Result as GlutinResult;
+#[cfg(all(feature = "x11", not(any(target_os = "macos", windows))))]
+use glutin::platform::x11::{X11GlConfigExt, X11VisualInfo};
 use glutin::prelude::*;
 use glutin::surface::{Surface, SurfaceAttributesBuilder, WindowSurface};
 use log::{LevelFilter, debug};
+use softbuffer::SoftBufferError;
 
 use winit::dpi::PhysicalSize;
 #[cfg(all(feature = "x11", not(any(target_os = "macos", windows))))]
 use winit::platform::x11;
-use winit::raw_window_handle::{RawDisplayHandle, RawWindowHandle};
+use winit::raw_window_handle::{
+    DisplayHandle, HandleError, HasDisplayHandle, HasWindowHandle, RawDisplayHandle,
+    RawWindowHandle, WindowHandle,
+};
+
+/// Window surface the software renderer presents its frames to.
+pub type SoftwareSurface = softbuffer::Surface<RawHandles, RawHandles>;
+
+/// Graphics platform shared by all windows.
+#[derive(Clone, Debug)]
+pub enum GraphicsPlatform {
+    /// OpenGL, with the config used to create the context of every window.
+    Gl(Config),
+
+    /// Software rendering on the CPU.
+    Software,
+}
+
+impl GraphicsPlatform {
+    /// Create the OpenGL context for a window.
+    ///
+    /// Software rendering doesn't use any context.
+    pub fn create_context(
+        &self,
+        raw_window_handle: Option<RawWindowHandle>,
+    ) -> GlutinResult<Option<NotCurrentContext>> {
+        match self {
+            Self::Gl(gl_config) => {
+                let gl_display = gl_config.display();
+                create_gl_context(&gl_display, gl_config, raw_window_handle).map(Some)
+            },
+            Self::Software => Ok(None),
+        }
+    }
+
+    /// X11 visual which must be used by the windows.
+    #[cfg(all(feature = "x11", not(any(target_os = "macos", windows))))]
+    pub fn x11_visual(&self) -> Option<X11VisualInfo> {
+        match self {
+            Self::Gl(gl_config) => gl_config.x11_visual(),
+            Self::Software => None,
+        }
+    }
+}
 
 /// Create the GL display.
 pub fn create_gl_display(
@@ -166,3 +212,36 @@ pub fn create_gl_surface(
     // Create the GL surface to draw into.
     unsafe { gl_display.create_window_surface(&gl_config, &surface_attributes) }
 }
+
+/// Create the surface for the software renderer.
+///
+/// The surface must be dropped before its window, since it doesn't borrow the window's handles.
+pub fn create_software_surface(
+    raw_display_handle: RawDisplayHandle,
+    raw_window_handle: RawWindowHandle,
+) -> Result<SoftwareSurface, SoftBufferError> {
+    let handles = RawHandles { display: raw_display_handle, window: raw_window_handle };
+    let context = softbuffer::Context::new(handles)?;
+    softbuffer::Surface::new(&context, handles)
+}
+
+/// Raw handles of a window and its display.
+#[derive(Copy, Clone, Debug)]
+pub struct RawHandles {
+    display: RawDisplayHandle,
+    window: RawWindowHandle,
+}
+
+impl HasDisplayHandle for RawHandles {
+    fn display_handle(&self) -> Result<DisplayHandle<'_>, HandleError> {
+        // SAFETY: The surface using the handles is dropped before the window.
+        Ok(unsafe { DisplayHandle::borrow_raw(self.display) })
+    }
+}
+
+impl HasWindowHandle for RawHandles {
+    fn window_handle(&self) -> Result<WindowHandle<'_>, HandleError> {
+        // SAFETY: The surface using the handles is dropped before the window.
+        Ok(unsafe { WindowHandle::borrow_raw(self.window) })
+    }
+}
@@ -0,0 +1,456 @@
+//! Renderer drawing into a framebuffer on the CPU, without any OpenGL.
+
+use std::collections::HashMap;
+use std::num::NonZeroU32;
+use std::ops::Range;
+
+use ahash::RandomState;
+use crossfont::Metrics;
+use log::{debug, info};
+
+use alacritty_terminal::graphics::{GraphicData, GraphicId, UpdateQueues};
+
+use crate::display::SizeInfo;
+use crate::display::color::Rgb;
+use crate::display::content::RenderableCell;
+use crate::renderer::graphics::RenderGraphic;
+use crate::renderer::platform::SoftwareSurface;
+use crate::renderer::rects::{RectKind, RenderRect};
+use crate::renderer::text::{GlyphCache, LoaderApi, SoftwareTextRenderer};
+use crate::screenshot::Image;
+
+/// Pixels of the window in the `0RGB` format.
+#[derive(Debug, Default)]
+pub struct Framebuffer {
+    pixels: Vec<u32>,
+    width: usize,
+    height: usize,
+}
+
+impl Framebuffer {
+    /// Resize the framebuffer, discarding its content.
+    pub fn resize(&mut self, width: usize, height: usize) {
+        self.width = width;
+        self.height = height;
+        self.pixels.clear();
+        self.pixels.resize(width * height, 0);
+    }
+
+    /// Fill the entire framebuffer with `color`.
+    pub fn fill(&mut self, color: Rgb) {
+        self.pixels.fill(pack(color.r as f32, color.g as f32, color.b as f32));
+    }
+
+    /// Color of a single pixel.
+    #[cfg(test)]
+    pub fn pixel(&self, x: usize, y: usize) -> Rgb {
+        let pixel = self.pixels[y * self.width + x];
+        Rgb::new((pixel >> 16) as u8, (pixel >> 8) as u8, pixel as u8)
+    }
+
+    /// Blend `color` over a pixel, with a separate alpha value for every channel.
+    ///
+    /// Pixels outside of the framebuffer are ignored.
+    #[inline]
+    pub fn blend(&mut self, x: i32, y: i32, color: [f32; 3], alpha: [f32; 3]) {
+        if x < 0 || y < 0 || x as usize >= self.width || y as usize >= self.height {
+            return;
+        }
+
+        let pixel = &mut self.pixels[y as usize * self.width + x as usize];
+        let dst = [(*pixel >> 16) as u8, (*pixel >> 8) as u8, *pixel as u8];
+        let channel = |i: usize| color[i] * alpha[i] + dst[i] as f32 * (1. - alpha[i]);
+        *pixel = pack(channel(0), channel(1), channel(2));
+    }
+
+    /// Horizontal pixel range of the framebuffer.
+    pub fn columns(&self) -> Range<i32> {
+        0..self.width as i32
+    }
+
+    /// Vertical pixel range of the framebuffer.
+    pub fn lines(&self) -> Range<i32> {
+        0..self.height as i32
+    }
+}
+
+/// Pack color channels into a `0RGB` pixel.
+#[inline]
+fn pack(r: f32, g: f32, b: f32) -> u32 {
+    let channel = |value: f32| value.round().clamp(0., 255.) as u32;
+    (channel(r) << 16) | (channel(g) << 8) | channel(b)
+}
+
+/// Intersection of two pixel ranges.
+#[inline]
+pub fn clip(range: Range<i32>, bounds: Range<i32>) -> Range<i32> {
+    range.start.max(bounds.start)..range.end.min(bounds.end)
+}
+
+/// Pixel range covered by the pixel centers between `start` and `end`.
+///
+/// This matches the pixels OpenGL rasterizes for a quad.
+#[inline]
+fn pixel_range(start: f32, end: f32) -> Range<i32> {
+    (start - 0.5).ceil() as i32..(end - 0.5).ceil() as i32
+}
+
+#[derive(Debug, Default)]
+pub struct SoftwareRenderer {
+    framebuffer: Framebuffer,
+    text_renderer: SoftwareTextRenderer,
+
+    /// Pixels of all graphics which are still referenced by the terminal.
+    graphics: HashMap<GraphicId, GraphicData, RandomState>,
+}
+
+impl SoftwareRenderer {
+    pub fn new() -> Self {
+        info!("Using software renderer");
+
+        Self::default()
+    }
+
+    pub fn draw_cells<I: Iterator<Item = RenderableCell>>(
+        &mut self,
+        size_info: &SizeInfo,
+        glyph_cache: &mut GlyphCache,
+        cells: I,
+    ) {
+        self.text_renderer.draw_cells(&mut self.framebuffer, size_info, glyph_cache, cells);
+    }
+
+    pub fn with_loader<F, T>(&mut self, func: F) -> T
+    where
+        F: FnOnce(LoaderApi<'_>) -> T,
+    {
+        self.text_renderer.with_loader(func)
+    }
+
+    /// Draw rectangles relative to the pane's origin.
+    ///
+    /// Underlines are drawn like the OpenGL rect shaders, to get identical results.
+    pub fn draw_rects(
+        &mut self,
+        size_info: &SizeInfo,
+        metrics: &Metrics,
+        mut rects: Vec<RenderRect>,
+    ) {
+        // Draw plain rects at the end, since visual bell or damage rects should be above lines.
+        rects.sort_by_key(|rect| std::cmp::Reverse(rect.kind as u8));
+
+        let underline = UnderlineStyle::new(size_info, metrics);
+        let columns = clip(
+            size_info.x() as i32..(size_info.x() + size_info.width()) as i32,
+            self.framebuffer.columns(),
+        );
+        let lines = clip(
+            size_info.y() as i32..(size_info.y() + size_info.height()) as i32,
+            self.framebuffer.lines(),
+        );
+
+        for rect in rects {
+            let (r, g, b) = rect.color.as_tuple();
+            let color = [r as f32, g as f32, b as f32];
+
+            let x = size_info.x() + rect.x;
+            let y = size_info.y() + rect.y;
+            for py in clip(pixel_range(y, y + rect.height), lines.clone()) {
+                for px in clip(pixel_range(x, x + rect.width), columns.clone()) {
+                    let alpha = match rect.kind {
+                        RectKind::Normal | RectKind::NumKinds => rect.alpha,
+                        kind => underline.alpha(kind, px, py),
+                    };
+                    let alpha = alpha.clamp(0., 1.);
+                    self.framebuffer.blend(px, py, color, [alpha; 3]);
+                }
+            }
+        }
+    }
+
+    /// Store new graphics and free the ones which are no longer used.
+    pub fn update_graphics(&mut self, queues: UpdateQueues) {
+        for id in queues.remove_queue {
+            self.graphics.remove(&id);
+        }
+
+        for (id, graphic) in queues.pending {
+            debug!("Stored {}x{} graphic {id:?}", graphic.width, graphic.height);
+            self.graphics.insert(id, graphic);
+        }
+    }
+
+    /// Draw all graphics visible in the viewport.
+    pub fn draw_graphics(&mut self, size_info: &SizeInfo, mut graphics: Vec<RenderGraphic>) {
+        graphics.sort_by_key(|graphic| (graphic.placement.z_index, graphic.placement.id));
+
+        let cell_width = size_info.cell_width();
+        let cell_height = size_info.cell_height();
+        let columns = clip(
+            size_info.x() as i32..(size_info.x() + size_info.width()) as i32,
+            self.framebuffer.columns(),
+        );
+        let lines = clip(
+            size_info.y() as i32..(size_info.y() + size_info.height()) as i32,
+            self.framebuffer.lines(),
+        );
+
+        for graphic in &graphics {
+            let data = match self.graphics.get(&graphic.id) {
+                Some(data) => data,
+                None => continue,
+            };
+
+            // Clip the cell to the placement's dimensions.
+            let placement = &graphic.placement;
+            let (offset_x, offset_y) = (graphic.offset_x as f32, graphic.offset_y as f32);
+            let clip_left = offset_x.max(0.);
+            let clip_top = offset_y.max(0.);
+            let clip_right = (offset_x + cell_width).min(placement.width as f32);
+            let clip_bottom = (offset_y + cell_height).min(placement.height as f32);
+            if clip_right <= clip_left || clip_bottom <= clip_top {
+                continue;
+            }
+
+            // Origin of the placement in the window.
+            let x =
+                size_info.x() + size_info.padding_x() + graphic.point.column.0 as f32 * cell_width
+                    - offset_x;
+            let y = size_info.y() + size_info.padding_y() + graphic.point.line as f32 * cell_height
+                - offset_y;
+
+            // Map the placement's pixels to the displayed part of the graphic.
+            let source = &placement.source;
+            let scale_x = source.width as f32 / placement.width as f32;
+            let scale_y = source.height as f32 / placement.height as f32;
+
+            let pixel_lines = clip(pixel_range(y + clip_top, y + clip_bottom), lines.clone());
+            let pixel_columns = clip(pixel_range(x + clip_left, x + clip_right), columns.clone());
+            for py in pixel_lines {
+                let v = source.y as f32 + (py as f32 + 0.5 - y) * scale_y;
+                let v = (v as usize).min(data.height.saturating_sub(1));
+                for px in pixel_columns.clone() {
+                    let u = source.x as f32 + (px as f32 + 0.5 - x) * scale_x;
+                    let u = (u as usize).min(data.width.saturating_sub(1));
+
+                    let index = (v * data.width + u) * 4;
+                    let [r, g, b, a] = match data.pixels.get(index..index + 4) {
+                        Some(&[r, g, b, a]) => [r, g, b, a],
+                        _ => continue,
+                    };
+
+                    let alpha = a as f32 / 255.;
+                    self.framebuffer.blend(px, py, [r as f32, g as f32, b as f32], [alpha; 3]);
+                }
+            }
+        }
+    }
+
+    /// Fill the window with `color`.
+    ///
+    /// Transparency is not supported, so the window is always opaque.
+    pub fn clear(&mut self, color: Rgb) {
+        self.framebuffer.fill(color);
+    }
+
+    /// Resize the framebuffer to the window.
+    pub fn resize(&mut self, size_info: &SizeInfo) {
+        let width = size_info.window_width() as usize;
+        let height = size_info.window_height() as usize;
+        if (width, height) != (self.framebuffer.width, self.framebuffer.height) {
+            self.framebuffer.resize(width, height);
+        }
+    }
+
+    /// Copy the framebuffer to the window's surface.
+    pub fn present(&self, surface: &mut SoftwareSurface) {
+        let width = NonZeroU32::new(self.framebuffer.width as u32);
+        let height = NonZeroU32::new(self.framebuffer.height as u32);
+        let (width, height) = match width.zip(height) {
+            Some(size) => size,
+            None => return,
+        };
+
+        let result = surface.resize(width, height).and_then(|_| {
+            let mut buffer = surface.buffer_mut()?;
+            buffer.copy_from_slice(&self.framebuffer.pixels);
+            buffer.present()
+        });
+
+        if let Err(err) = result {
+            debug!("error presenting software framebuffer: {err}");
+        }
+    }
+
+    /// Image of the framebuffer.
+    pub fn image(&self) -> Image {
+        let pixels = self
+            .framebuffer
+            .pixels
+            .iter()
+            .flat_map(|pixel| [(pixel >> 16) as u8, (pixel >> 8) as u8, *pixel as u8])
+            .collect();
+        Image::new(self.framebuffer.width as u32, self.framebuffer.height as u32, pixels)
+    }
+
+    /// Rendered window content.
+    #[cfg(test)]
+    pub fn framebuffer(&self) -> &Framebuffer {
+        &self.framebuffer
+    }
+}
+
+/// Uniforms of the OpenGL rect shaders.
+struct UnderlineStyle {
+    window_height: f32,
+    cell_width: f32,
+    cell_height: f32,
+    padding_x: f32,
+    padding_y: f32,
+    underline_position: f32,
+    underline_thickness: f32,
+    undercurl_position: f32,
+}
+
+impl UnderlineStyle {
+    fn new(size_info: &SizeInfo, metrics: &Metrics) -> Self {
+        // Fragment coordinates are relative to the bottom left corner of the window.
+        let padding_x = size_info.x() + size_info.padding_x();
+        let viewport_height = size_info.window_height() - size_info.y() - size_info.padding_y();
+        let padding_y = viewport_height
+            - (viewport_height / size_info.cell_height()).floor() * size_info.cell_height();
+
+        Self {
+            window_height: size_info.window_height(),
+            cell_width: size_info.cell_width(),
+            cell_height: size_info.cell_height(),
+            padding_x,
+            padding_y,
+            underline_position: metrics.descent.abs() - metrics.underline_position.abs(),
+            underline_thickness: metrics.underline_thickness,
+            undercurl_position: (0.5 * metrics.descent).abs(),
+        }
+    }
+
+    /// Alpha of a pixel in an underline rect.
+    fn alpha(&self, kind: RectKind, px: i32, py: i32) -> f32 {
+        // Position of the pixel inside its cell, with Y pointing up.
+        let frag_x = px as f32 + 0.5;
+        let frag_y = self.window_height - py as f32 - 0.5;
+        let x = (frag_x - self.padding_x).rem_euclid(self.cell_width).floor();
+        let y = (frag_y - self.padding_y).rem_euclid(self.cell_height).floor();
+
+        match kind {
+            RectKind::Undercurl => self.undercurl(x, y),
+            RectKind::DottedUnderline if self.underline_thickness < 2. => self.dotted(frag_x, x, y),
+            RectKind::DottedUnderline => self.dotted_aliased(x, y),
+            RectKind::DashedUnderline => self.dashed(x),
+            RectKind::Normal | RectKind::NumKinds => 1.,
+        }
+    }
+
+    fn undercurl(&self, x: f32, y: f32) -> f32 {
+        let undercurl = self.undercurl_position / 2.
+            * ((x + 0.5) * 2. * std::f32::consts::PI / self.cell_width).cos()
+            + self.undercurl_position
+            - 1.;
+
+        let undercurl_top = undercurl + (self.underline_thickness - 1.).max(0.) / 2.;
+        let undercurl_bottom = undercurl - (self.underline_thickness - 1.).max(0.) / 2.;
+
+        let dst = (y - undercurl_top).max((undercurl_bottom - y).max(0.));
+        1. - dst * dst
+    }
+
+    fn dotted_aliased(&self, x: f32, y: f32) -> f32 {
+        let dot_number = (x / self.underline_thickness).floor();
+
+        let radius = self.underline_thickness / 2.;
+        let center_y = self.underline_position - 1.;
+
+        let left_center =
+            (dot_number - dot_number.rem_euclid(2.)) * self.underline_thickness + radius;
+        let right_center = left_center + 2. * self.underline_thickness;
+
+        let distance_left = ((x - left_center).powi(2) + (y - center_y).powi(2)).sqrt();
+        let distance_right = ((x - right_center).powi(2) + (y - center_y).powi(2)).sqrt();
+
+        (1. - (distance_left.min(distance_right) - radius)).max(0.)
+    }
+
+    fn dotted(&self, frag_x: f32, x: f32, y: f32) -> f32 {
+        // Invert the pattern every two cells, to keep the spacing even for odd cell widths.
+        let mut cell_even = 0.;
+        if self.cell_width.rem_euclid(2.) as i32 != 0 {
+            cell_even = ((frag_x - self.padding_x) / self.cell_width).rem_euclid(2.);
+        }
+
+        if x.rem_euclid(2.) as i32 != cell_even as i32 {
+            return 0.;
+        }
+
+        1. - (self.underline_position.floor() - y).abs()
+    }
+
+    fn dashed(&self, x: f32) -> f32 {
+        let half_dash_len = (self.cell_width / 4. + 0.5).floor();
+
+        if x > half_dash_len - 1. && x < self.cell_width - half_dash_len { 0. } else { 1. }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const METRICS: Metrics = Metrics {
+        average_advance: 5.,
+        line_height: 10.,
+        descent: -2.,
+        underline_position: -1.,
+        underline_thickness: 1.,
+        strikeout_position: 4.,
+        strikeout_thickness: 1.,
+    };
+
+    #[test]
+    fn draw_rects() {
+        let mut renderer = SoftwareRenderer::new();
+        let size_info = SizeInfo::new(20., 10., 5., 10., 0., 0., false);
+        renderer.resize(&size_info);
+        renderer.clear(Rgb::new(0, 0, 0));
+
+        let rects = vec![
+            RenderRect::new(2., 1., 4., 3., Rgb::new(255, 0, 0), 1.),
+            RenderRect::new(4., 2., 10., 1., Rgb::new(0, 0, 255), 0.5),
+        ];
+        renderer.draw_rects(&size_info, &METRICS, rects);
+
+        let framebuffer = renderer.framebuffer();
+        assert_eq!(framebuffer.pixel(1, 1), Rgb::new(0, 0, 0));
+        assert_eq!(framebuffer.pixel(2, 1), Rgb::new(255, 0, 0));
+        assert_eq!(framebuffer.pixel(5, 3), Rgb::new(255, 0, 0));
+        assert_eq!(framebuffer.pixel(6, 3), Rgb::new(0, 0, 0));
+        assert_eq!(framebuffer.pixel(5, 2), Rgb::new(128, 0, 128));
+        assert_eq!(framebuffer.pixel(13, 2), Rgb::new(0, 0, 128));
+        assert_eq!(framebuffer.pixel(14, 2), Rgb::new(0, 0, 0));
+    }
+
+    #[test]
+    fn draw_dashed_underline() {
+        let mut renderer = SoftwareRenderer::new();
+        let size_info = SizeInfo::new(20., 10., 5., 10., 0., 0., false);
+        renderer.resize(&size_info);
+        renderer.clear(Rgb::new(0, 0, 0));
+
+        let white = Rgb::new(255, 255, 255);
+        let mut rect = RenderRect::new(0., 8., 10., 1., white, 1.);
+        rect.kind = RectKind::DashedUnderline;
+        renderer.draw_rects(&size_info, &METRICS, vec![rect]);
+
+        let framebuffer = renderer.framebuffer();
+        let dashes: Vec<_> = (0..10).map(|x| framebuffer.pixel(x, 8) == white).collect();
+        let expected = [true, false, false, false, true, true, false, false, false, true];
+        assert_eq!(dashes, expected);
+    }
+}
@@ -58,6 +58,9 @@ pub struct Atlas {
     ///
     /// This affects the texture loading.
     is_gles_context: bool,
+
+    /// RGBA pixels of atlases kept in memory instead of an OpenGL texture.
+    pixels: Option<Vec<u8>>,
 }
 
 /// Error that can happen when inserting a texture to the Atlas.
@@ -106,9 +109,36 @@ impl Atlas {
             row_baseline: 0,
             row_tallest: 0,
             is_gles_context,
+            pixels: None,
         }
     }
 
+    /// Create an atlas in memory, used by the software renderer.
+    ///
+    /// The `id` is used as texture id of the atlas' glyphs.
+    pub fn new_software(size: i32, id: GLuint) -> Self {
+        Self {
+            id,
+            width: size,
+            height: size,
+            row_extent: 0,
+            row_baseline: 0,
+            row_tallest: 0,
+            is_gles_context: false,
+            pixels: Some(vec![0; size as usize * size as usize * 4]),
+        }
+    }
+
+    /// RGBA pixels of an atlas in memory.
+    pub fn pixels(&self) -> Option<&[u8]> {
+        self.pixels.as_deref()
+    }
+
+    /// Width of the atlas.
+    pub fn width(&self) -> i32 {
+        self.width
+    }
+
     pub fn clear(&mut self) {
         self.row_extent = 0;
         self.row_baseline = 0;
@@ -149,15 +179,54 @@ impl Atlas {
         let offset_x = self.row_extent;
         let height = glyph.height;
         let width = glyph.width;
-        let multicolor;
+        let multicolor = matches!(glyph.buffer, BitmapBuffer::Rgba(_));
+
+        if let Some(pixels) = &mut self.pixels {
+            Self::copy_glyph(pixels, self.width, offset_x, offset_y, glyph);
+        } else {
+            self.upload_glyph(offset_x, offset_y, glyph, active_tex);
+        }
+
+        // Update Atlas state.
+        self.row_extent = offset_x + width;
+        if height > self.row_tallest {
+            self.row_tallest = height;
+        }
+
+        // Generate UV coordinates.
+        let uv_bot = offset_y as f32 / self.height as f32;
+        let uv_left = offset_x as f32 / self.width as f32;
+        let uv_height = height as f32 / self.height as f32;
+        let uv_width = width as f32 / self.width as f32;
+
+        Glyph {
+            tex_id: self.id,
+            multicolor,
+            top: glyph.top as i16,
+            left: glyph.left as i16,
+            width: width as i16,
+            height: height as i16,
+            uv_bot,
+            uv_left,
+            uv_width,
+            uv_height,
+        }
+    }
 
+    /// Load the glyph into the atlas' OpenGL texture.
+    fn upload_glyph(
+        &self,
+        offset_x: i32,
+        offset_y: i32,
+        glyph: &RasterizedGlyph,
+        active_tex: &mut u32,
+    ) {
         unsafe {
             gl::BindTexture(gl::TEXTURE_2D, self.id);
 
             // Load data into OpenGL.
             let (format, buffer) = match &glyph.buffer {
                 BitmapBuffer::Rgb(buffer) => {
-                    multicolor = false;
                     // Gles context doesn't allow uploading RGB data into RGBA texture, so need
                     // explicit copy.
                     if self.is_gles_context {
@@ -173,10 +242,7 @@ impl Atlas {
                         (gl::RGB, Cow::Borrowed(buffer))
                     }
                 },
-                BitmapBuffer::Rgba(buffer) => {
-                    multicolor = true;
-                    (gl::RGBA, Cow::Borrowed(buffer))
-                },
+                BitmapBuffer::Rgba(buffer) => (gl::RGBA, Cow::Borrowed(buffer)),
             };
 
             gl::TexSubImage2D(
@@ -184,8 +250,8 @@ impl Atlas {
                 0,
                 offset_x,
                 offset_y,
-                width,
-                height,
+                glyph.width,
+                glyph.height,
                 format,
                 gl::UNSIGNED_BYTE,
                 buffer.as_ptr() as *const _,
@@ -194,30 +260,36 @@ impl Atlas {
             gl::BindTexture(gl::TEXTURE_2D, 0);
             *active_tex = 0;
         }
+    }
 
-        // Update Atlas state.
-        self.row_extent = offset_x + width;
-        if height > self.row_tallest {
-            self.row_tallest = height;
+    /// Copy the glyph into the atlas' RGBA pixels.
+    fn copy_glyph(
+        pixels: &mut [u8],
+        atlas_width: i32,
+        offset_x: i32,
+        offset_y: i32,
+        glyph: &RasterizedGlyph,
+    ) {
+        let (buffer, channels) = match &glyph.buffer {
+            BitmapBuffer::Rgb(buffer) => (buffer, 3),
+            BitmapBuffer::Rgba(buffer) => (buffer, 4),
+        };
+
+        // Empty glyphs like spaces have no pixels to copy.
+        let width = glyph.width as usize;
+        if width == 0 {
+            return;
         }
 
-        // Generate UV coordinates.
-        let uv_bot = offset_y as f32 / self.height as f32;
-        let uv_left = offset_x as f32 / self.width as f32;
-        let uv_height = height as f32 / self.height as f32;
-        let uv_width = width as f32 / self.width as f32;
-
-        Glyph {
-            tex_id: self.id,
-            multicolor,
-            top: glyph.top as i16,
-            left: glyph.left as i16,
-            width: width as i16,
-            height: height as i16,
-            uv_bot,
-            uv_left,
-            uv_width,
-            uv_height,
+        for (y, row) in buffer.chunks_exact(width * channels).enumerate() {
+            let start = ((offset_y as usize + y) * atlas_width as usize + offset_x as usize) * 4;
+            let atlas_row = &mut pixels[start..start + width * 4];
+            for (dst, src) in atlas_row.chunks_exact_mut(4).zip(row.chunks_exact(channels)) {
+                dst[..channels].copy_from_slice(src);
+                if channels == 3 {
+                    dst[3] = u8::MAX;
+                }
+            }
         }
     }
 
@@ -261,11 +333,16 @@ impl Atlas {
             Err(AtlasInsertError::Full) => {
                 // Get the context type before adding a new Atlas.
                 let is_gles_context = atlas[*current_atlas].is_gles_context;
+                let is_software = atlas[*current_atlas].pixels.is_some();
 
                 // Advance the current Atlas index.
                 *current_atlas += 1;
                 if *current_atlas == atlas.len() {
-                    let new = Atlas::new(ATLAS_SIZE, is_gles_context);
+                    let new = if is_software {
+                        Atlas::new_software(ATLAS_SIZE, atlas.len() as GLuint)
+                    } else {
+                        Atlas::new(ATLAS_SIZE, is_gles_context)
+                    };
                     *active_tex = 0; // Atlas::new binds a texture. Ugh this is sloppy.
                     atlas.push(new);
                 }
@@ -297,8 +374,45 @@ impl Atlas {
 
 impl Drop for Atlas {
     fn drop(&mut self) {
+        if self.pixels.is_some() {
+            return;
+        }
+
         unsafe {
             gl::DeleteTextures(1, &self.id);
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn glyph(width: i32, height: i32) -> RasterizedGlyph {
+        RasterizedGlyph {
+            character: ' ',
+            top: 0,
+            left: 0,
+            width,
+            height,
+            buffer: BitmapBuffer::Rgb(vec![u8::MAX; width as usize * height as usize * 3]),
+            advance: (width, height),
+        }
+    }
+
+    #[test]
+    fn insert_empty_glyph() {
+        let mut atlas = Atlas::new_software(16, 0);
+        let mut active_tex = 0;
+
+        let empty = atlas.insert(&glyph(0, 0), &mut active_tex).ok().unwrap();
+        assert_eq!((empty.width, empty.height), (0, 0));
+
+        // Empty glyphs don't take up any space in the atlas.
+        let filled = atlas.insert(&glyph(2, 1), &mut active_tex).ok().unwrap();
+        assert_eq!(filled.uv_left, 0.);
+        let pixels = atlas.pixels().unwrap();
+        assert_eq!(pixels[..8], [u8::MAX; 8]);
+        assert_eq!(pixels[8..12], [0; 4]);
+    }
+}
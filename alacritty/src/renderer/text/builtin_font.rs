@@ -18,6 +18,47 @@ const POWERLINE_TRIANGLE_LTR: char = '\u{e0b0}';
 const POWERLINE_ARROW_LTR: char = '\u{e0b1}';
 const POWERLINE_TRIANGLE_RTL: char = '\u{e0b2}';
 const POWERLINE_ARROW_RTL: char = '\u{e0b3}';
+const POWERLINE_HALF_CIRCLE_LTR: char = '\u{e0b4}';
+const POWERLINE_HALF_CIRCLE_ARC_LTR: char = '\u{e0b5}';
+const POWERLINE_HALF_CIRCLE_RTL: char = '\u{e0b6}';
+const POWERLINE_HALF_CIRCLE_ARC_RTL: char = '\u{e0b7}';
+
+/// Number of samples per pixel axis used for antialiasing of shapes.
+const SAMPLES: usize = 4;
+
+/// Polygons of the smooth mosaic diagonals, relative to the cell size.
+///
+/// The remaining smooth mosaics are the inverse of these polygons.
+const SMOOTH_MOSAICS: [&[(f32, f32)]; 22] = [
+    &[(0., 2. / 3.), (0., 1.), (0.5, 1.)],
+    &[(0., 2. / 3.), (0., 1.), (1., 1.)],
+    &[(0., 1. / 3.), (0., 1.), (0.5, 1.)],
+    &[(0., 1. / 3.), (0., 1.), (1., 1.)],
+    &[(0., 0.), (0., 1.), (0.5, 1.)],
+    &[(0., 1. / 3.), (0.5, 0.), (1., 0.), (1., 1.), (0., 1.)],
+    &[(0., 1. / 3.), (1., 0.), (1., 1.), (0., 1.)],
+    &[(0., 2. / 3.), (0.5, 0.), (1., 0.), (1., 1.), (0., 1.)],
+    &[(0., 2. / 3.), (1., 0.), (1., 1.), (0., 1.)],
+    &[(0., 1.), (0.5, 0.), (1., 0.), (1., 1.)],
+    &[(0., 2. / 3.), (1., 1. / 3.), (1., 1.), (0., 1.)],
+    &[(0.5, 1.), (1., 2. / 3.), (1., 1.)],
+    &[(0., 1.), (1., 2. / 3.), (1., 1.)],
+    &[(0.5, 1.), (1., 1. / 3.), (1., 1.)],
+    &[(0., 1.), (1., 1. / 3.), (1., 1.)],
+    &[(0.5, 1.), (1., 0.), (1., 1.)],
+    &[(0., 0.), (0.5, 0.), (1., 1. / 3.), (1., 1.), (0., 1.)],
+    &[(0., 0.), (1., 1. / 3.), (1., 1.), (0., 1.)],
+    &[(0., 0.), (0.5, 0.), (1., 2. / 3.), (1., 1.), (0., 1.)],
+    &[(0., 0.), (1., 2. / 3.), (1., 1.), (0., 1.)],
+    &[(0., 0.), (0.5, 0.), (1., 1.), (0., 1.)],
+    &[(0., 1. / 3.), (1., 2. / 3.), (1., 1.), (0., 1.)],
+];
+
+/// Octant patterns which are encoded outside of the octant range, like quadrants and half blocks.
+const OCTANTS_ENCODED_ELSEWHERE: [u8; 26] = [
+    0x00, 0x01, 0x02, 0x03, 0x05, 0x0a, 0x0f, 0x14, 0x28, 0x3f, 0x40, 0x50, 0x55, 0x5a, 0x5f, 0x80,
+    0xa0, 0xa5, 0xaa, 0xaf, 0xc0, 0xf0, 0xf5, 0xfa, 0xfc, 0xff,
+];
 
 /// Returns the rasterized glyph if the character is part of the built-in font.
 pub fn builtin_glyph(
@@ -28,13 +69,19 @@ pub fn builtin_glyph(
 ) -> Option<RasterizedGlyph> {
     let mut glyph = match character {
         // Box drawing characters and block elements.
-        '\u{2500}'..='\u{259f}' | '\u{1fb00}'..='\u{1fb3b}' => {
+        '\u{2500}'..='\u{259f}' | '\u{1fb00}'..='\u{1fb8b}' | '\u{1cd00}'..='\u{1cde5}' => {
             box_drawing(character, metrics, offset)
         },
+        // Braille patterns.
+        '\u{2800}'..='\u{28ff}' => box_drawing(character, metrics, offset),
         // Powerline symbols: '','','',''
         POWERLINE_TRIANGLE_LTR..=POWERLINE_ARROW_RTL => {
             powerline_drawing(character, metrics, offset)?
         },
+        // Rounded powerline symbols: '','','',''
+        POWERLINE_HALF_CIRCLE_LTR..=POWERLINE_HALF_CIRCLE_ARC_RTL => {
+            powerline_rounded_drawing(character, metrics, offset)
+        },
         _ => return None,
     };
 
@@ -571,6 +618,132 @@ fn box_drawing(character: char, metrics: &Metrics, offset: &Delta<i8>) -> Raster
             canvas.draw_rect(0., y_third * 2., w_bottom_left, h_bottom_left, COLOR_FILL);
             canvas.draw_rect(x_center, y_third * 2., w_bottom_right, h_bottom_right, COLOR_FILL);
         },
+        // Smooth mosaic diagonals: '🬼', '🬽', '🬾', '🬿', '🭀', '🭁', '🭂', '🭃', '🭄', '🭅', '🭆', '🭇',
+        // '🭈', '🭉', '🭊', '🭋', '🭌', '🭍', '🭎', '🭏', '🭐', '🭑', '🭒', '🭓', '🭔', '🭕', '🭖', '🭗',
+        // '🭘', '🭙', '🭚', '🭛', '🭜', '🭝', '🭞', '🭟', '🭠', '🭡', '🭢', '🭣', '🭤', '🭥', '🭦', '🭧'.
+        '\u{1fb3c}'..='\u{1fb67}' => {
+            // The second half of the range are the inverse of the first half.
+            let index = (character as u32 - 0x1fb3c) as usize;
+            let inverse = index >= SMOOTH_MOSAICS.len();
+            let polygon = SMOOTH_MOSAICS[index % SMOOTH_MOSAICS.len()];
+
+            let (width, height) = (width as f32, height as f32);
+            let polygon: Vec<_> = polygon.iter().map(|&(x, y)| (x * width, y * height)).collect();
+            canvas.fill_shape(|x, y| point_in_polygon(&polygon, x, y) != inverse);
+        },
+        // Triangular blocks: '🭨', '🭩', '🭪', '🭫', '🭬', '🭭', '🭮', '🭯'.
+        '\u{1fb68}'..='\u{1fb6f}' => {
+            let (width, height) = (width as f32, height as f32);
+            let center = (width / 2., height / 2.);
+            let triangle = match character {
+                '\u{1fb68}' | '\u{1fb6c}' => [(0., 0.), center, (0., height)],
+                '\u{1fb69}' | '\u{1fb6d}' => [(0., 0.), (width, 0.), center],
+                '\u{1fb6a}' | '\u{1fb6e}' => [(width, 0.), (width, height), center],
+                _ => [(0., height), center, (width, height)],
+            };
+
+            // The three quarter blocks are the inverse of the one quarter blocks.
+            let inverse = character < '\u{1fb6c}';
+            canvas.fill_shape(|x, y| point_in_polygon(&triangle, x, y) != inverse);
+        },
+        // Eighth blocks: '🭰', '🭱', '🭲', '🭳', '🭴', '🭵', '🭶', '🭷', '🭸', '🭹', '🭺', '🭻', '🭼', '🭽',
+        // '🭾', '🭿', '🮀', '🮁', '🮂', '🮃', '🮄', '🮅', '🮆', '🮇', '🮈', '🮉', '🮊', '🮋'.
+        '\u{1fb70}'..='\u{1fb8b}' => {
+            let x_eighth = |eighths: u32| (width as f32 * eighths as f32 / 8.).round();
+            let y_eighth = |eighths: u32| (height as f32 * eighths as f32 / 8.).round();
+
+            // Draw the columns and rows of eighths from `start` to `end`.
+            let columns = |canvas: &mut Canvas, start: u32, end: u32| {
+                let x = x_eighth(start);
+                canvas.draw_rect(x, 0., (x_eighth(end) - x).max(1.), height as f32, COLOR_FILL);
+            };
+            let rows = |canvas: &mut Canvas, start: u32, end: u32| {
+                let y = y_eighth(start);
+                canvas.draw_rect(0., y, width as f32, (y_eighth(end) - y).max(1.), COLOR_FILL);
+            };
+
+            let index = character as u32;
+            match character {
+                '\u{1fb70}'..='\u{1fb75}' => columns(&mut canvas, index - 0x1fb6f, index - 0x1fb6e),
+                '\u{1fb76}'..='\u{1fb7b}' => rows(&mut canvas, index - 0x1fb75, index - 0x1fb74),
+                '\u{1fb7c}' => {
+                    columns(&mut canvas, 0, 1);
+                    rows(&mut canvas, 7, 8);
+                },
+                '\u{1fb7d}' => {
+                    columns(&mut canvas, 0, 1);
+                    rows(&mut canvas, 0, 1);
+                },
+                '\u{1fb7e}' => {
+                    columns(&mut canvas, 7, 8);
+                    rows(&mut canvas, 0, 1);
+                },
+                '\u{1fb7f}' => {
+                    columns(&mut canvas, 7, 8);
+                    rows(&mut canvas, 7, 8);
+                },
+                '\u{1fb80}' => {
+                    rows(&mut canvas, 0, 1);
+                    rows(&mut canvas, 7, 8);
+                },
+                '\u{1fb81}' => {
+                    for row in [0, 2, 4, 7] {
+                        rows(&mut canvas, row, row + 1);
+                    }
+                },
+                '\u{1fb82}'..='\u{1fb86}' => {
+                    let eighths = [2, 3, 5, 6, 7][(index - 0x1fb82) as usize];
+                    rows(&mut canvas, 0, eighths);
+                },
+                _ => {
+                    let eighths = [2, 3, 5, 6, 7][(index - 0x1fb87) as usize];
+                    columns(&mut canvas, 8 - eighths, 8);
+                },
+            }
+        },
+        // Braille patterns.
+        '\u{2800}'..='\u{28ff}' => {
+            let dots = character as u32 - 0x2800;
+
+            // Dots are numbered column by column, with the last row added separately.
+            const DOTS: [(usize, usize); 8] =
+                [(0, 0), (0, 1), (0, 2), (1, 0), (1, 1), (1, 2), (0, 3), (1, 3)];
+
+            let columns = [0., canvas.x_center().round(), width as f32];
+            let rows = [0, 1, 2, 3, 4].map(|row| (height as f32 * row as f32 / 4.).round());
+            let dot_size = (columns[1].min(rows[1]) / 2.).floor().max(1.);
+
+            for (dot, (column, row)) in DOTS.into_iter().enumerate() {
+                if dots & (1 << dot) == 0 {
+                    continue;
+                }
+
+                let cell_width = columns[column + 1] - columns[column];
+                let cell_height = rows[row + 1] - rows[row];
+                let x = columns[column] + ((cell_width - dot_size) / 2.).floor();
+                let y = rows[row] + ((cell_height - dot_size) / 2.).floor();
+                canvas.draw_rect(x, y, dot_size, dot_size, COLOR_FILL);
+            }
+        },
+        // Octants.
+        '\u{1cd00}'..='\u{1cde5}' => {
+            let octants = octant_mask(character);
+
+            let columns = [0., canvas.x_center().round().max(1.), width as f32];
+            let rows = [0, 1, 2, 3, 4].map(|row| (height as f32 * row as f32 / 4.).round());
+
+            for octant in 0..8 {
+                if octants & (1 << octant) == 0 {
+                    continue;
+                }
+
+                let (column, row) = (octant % 2, octant / 2);
+                let x = columns[column];
+                let y = rows[row];
+                let rect_height = (rows[row + 1] - y).max(1.);
+                canvas.draw_rect(x, y, columns[column + 1] - x, rect_height, COLOR_FILL);
+            }
+        },
         _ => unreachable!(),
     }
 
@@ -660,6 +833,49 @@ fn powerline_drawing(
     })
 }
 
+fn powerline_rounded_drawing(
+    character: char,
+    metrics: &Metrics,
+    offset: &Delta<i8>,
+) -> RasterizedGlyph {
+    let height = (metrics.line_height as i32 + offset.y as i32).max(1) as usize;
+    let width = (metrics.average_advance as i32 + offset.x as i32).max(1) as usize;
+    let stroke_size = calculate_stroke_size(width) as f32;
+
+    let mut canvas = Canvas::new(width, height);
+
+    // Half of an ellipse centered on the left edge of the cell.
+    let radius_x = width as f32;
+    let radius_y = height as f32 / 2.;
+    let ellipse = |x: f32, y: f32, shrink: f32| {
+        let x = x / (radius_x - shrink);
+        let y = (y - radius_y) / (radius_y - shrink);
+        x * x + y * y <= 1.
+    };
+
+    if character == POWERLINE_HALF_CIRCLE_ARC_LTR || character == POWERLINE_HALF_CIRCLE_ARC_RTL {
+        canvas.fill_shape(|x, y| ellipse(x, y, 0.) && !ellipse(x, y, stroke_size));
+    } else {
+        canvas.fill_shape(|x, y| ellipse(x, y, 0.));
+    }
+
+    if character == POWERLINE_HALF_CIRCLE_RTL || character == POWERLINE_HALF_CIRCLE_ARC_RTL {
+        canvas.flip_horizontal();
+    }
+
+    let top = height as i32 + metrics.descent as i32;
+    let buffer = BitmapBuffer::Rgb(canvas.into_raw());
+    RasterizedGlyph {
+        character,
+        top,
+        left: 0,
+        height: height as i32,
+        width: width as i32,
+        buffer,
+        advance: (width as i32, height as i32),
+    }
+}
+
 #[repr(C, packed)]
 #[derive(Clone, Copy, Debug, Default)]
 struct Pixel {
@@ -945,6 +1161,29 @@ impl Canvas {
         }
     }
 
+    /// Fills all pixels inside of a shape, antialiasing its edges.
+    ///
+    /// The `inside` function is called with canvas coordinates to check if they are part of the
+    /// shape.
+    fn fill_shape(&mut self, inside: impl Fn(f32, f32) -> bool) {
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let mut covered = 0;
+                for sample_y in 0..SAMPLES {
+                    for sample_x in 0..SAMPLES {
+                        let sample_x = x as f32 + (sample_x as f32 + 0.5) / SAMPLES as f32;
+                        let sample_y = y as f32 + (sample_y as f32 + 0.5) / SAMPLES as f32;
+                        covered += inside(sample_x, sample_y) as usize;
+                    }
+                }
+
+                let value = covered as f32 / (SAMPLES * SAMPLES) as f32;
+                let color = Pixel::gray((COLOR_FILL._r as f32 * value).round() as u8);
+                self.put_pixel(x as f32, y as f32, color);
+            }
+        }
+    }
+
     /// Fills the `Canvas` with the given `Color`.
     fn fill(&mut self, color: Pixel) {
         self.buffer.fill(color);
@@ -970,6 +1209,29 @@ fn calculate_stroke_size(cell_width: usize) -> usize {
     cmp::max((cell_width as f32 / 8.).round() as usize, 1)
 }
 
+/// Check if a point is inside of a polygon, using the even-odd rule.
+fn point_in_polygon(polygon: &[(f32, f32)], x: f32, y: f32) -> bool {
+    let mut inside = false;
+    let mut previous = polygon[polygon.len() - 1];
+    for &point in polygon {
+        if (point.1 > y) != (previous.1 > y) {
+            let intersection_x =
+                point.0 + (y - point.1) / (previous.1 - point.1) * (previous.0 - point.0);
+            if x < intersection_x {
+                inside = !inside;
+            }
+        }
+        previous = point;
+    }
+    inside
+}
+
+/// Bitmask of the filled octants, from left to right and top to bottom.
+fn octant_mask(character: char) -> u8 {
+    let index = character as usize - 0x1cd00;
+    (0..=u8::MAX).filter(|mask| !OCTANTS_ENCODED_ELSEWHERE.contains(mask)).nth(index).unwrap()
+}
+
 /// `f(x) = slope * x + offset` equation.
 fn line_equation(slope: i32, x: i32, offset: i32) -> (f32, f32) {
     (x as f32, (slope * x + offset) as f32)
@@ -997,11 +1259,19 @@ mod tests {
         let glyph_offset = Default::default();
 
         // Test coverage of box drawing characters.
-        for character in ('\u{2500}'..='\u{259f}').chain('\u{1fb00}'..='\u{1fb3b}') {
+        for character in ('\u{2500}'..='\u{259f}')
+            .chain('\u{2800}'..='\u{28ff}')
+            .chain('\u{1fb00}'..='\u{1fb8b}')
+            .chain('\u{1cd00}'..='\u{1cde5}')
+        {
             assert!(builtin_glyph(character, &METRICS, &offset, &glyph_offset).is_some());
         }
 
-        for character in ('\u{2450}'..'\u{2500}').chain('\u{25a0}'..'\u{2600}') {
+        for character in ('\u{2450}'..'\u{2500}')
+            .chain('\u{25a0}'..'\u{2600}')
+            .chain('\u{1fb8c}'..'\u{1fc00}')
+            .chain('\u{1cde6}'..'\u{1ce00}')
+        {
             assert!(builtin_glyph(character, &METRICS, &offset, &glyph_offset).is_none());
         }
     }
@@ -1012,12 +1282,89 @@ mod tests {
         let glyph_offset = Default::default();
 
         // Test coverage of box drawing characters.
-        for character in '\u{e0b0}'..='\u{e0b3}' {
+        for character in '\u{e0b0}'..='\u{e0b7}' {
             assert!(builtin_glyph(character, &METRICS, &offset, &glyph_offset).is_some());
         }
 
-        for character in ('\u{e0a0}'..'\u{e0b0}').chain('\u{e0b4}'..'\u{e0c0}') {
+        for character in ('\u{e0a0}'..'\u{e0b0}').chain('\u{e0b8}'..'\u{e0c0}') {
             assert!(builtin_glyph(character, &METRICS, &offset, &glyph_offset).is_none());
         }
     }
+
+    /// Render a glyph as text, with `#` for filled, `+` for partially filled and `.` for empty
+    /// pixels.
+    fn render(character: char, width: f64, height: f64) -> String {
+        let metrics = Metrics { average_advance: width, line_height: height, ..METRICS };
+        let glyph = builtin_glyph(character, &metrics, &Default::default(), &Default::default());
+        let buffer = match glyph.unwrap().buffer {
+            BitmapBuffer::Rgb(buffer) => buffer,
+            BitmapBuffer::Rgba(_) => unreachable!(),
+        };
+
+        let rows = buffer.chunks(width as usize * 3).map(|row| {
+            row.chunks(3)
+                .map(|pixel| match pixel[0] {
+                    0 => '.',
+                    255 => '#',
+                    _ => '+',
+                })
+                .collect::<String>()
+        });
+        rows.collect::<Vec<_>>().join("\n")
+    }
+
+    #[test]
+    fn builtin_braille() {
+        let expected = [
+            "........", ".##.....", ".##.....", "........", "........", ".##.....", ".##.....",
+            "........", "........", ".##.....", ".##.....", "........", "........", ".##.....",
+            ".##.....", "........",
+        ];
+        assert_eq!(render('\u{2847}', 8., 16.), expected.join("\n"));
+    }
+
+    #[test]
+    fn builtin_octant() {
+        let expected = [
+            "......", "......", "......", "...###", "...###", "...###", "###...", "###...",
+            "###...", "###...", "###...", "###...",
+        ];
+        assert_eq!(render('\u{1cd4b}', 6., 12.), expected.join("\n"));
+    }
+
+    #[test]
+    fn builtin_smooth_mosaic() {
+        let expected = [
+            "......", "......", "......", "......", "....++", "...++#", ".++###", "++####",
+            "######", "######", "######", "######",
+        ];
+        assert_eq!(render('\u{1fb46}', 6., 12.), expected.join("\n"));
+    }
+
+    #[test]
+    fn builtin_triangular_block() {
+        let expected = [
+            "+.....", "+.....", "#+....", "#+....", "##+...", "##+...", "##+...", "##+...",
+            "#+....", "#+....", "+.....", "+.....",
+        ];
+        assert_eq!(render('\u{1fb6c}', 6., 12.), expected.join("\n"));
+    }
+
+    #[test]
+    fn builtin_eighth_block() {
+        let expected = [
+            "#.....", "#.....", "#.....", "#.....", "#.....", "#.....", "#.....", "#.....",
+            "#.....", "#.....", "#.....", "######",
+        ];
+        assert_eq!(render('\u{1fb7c}', 6., 12.), expected.join("\n"));
+    }
+
+    #[test]
+    fn builtin_powerline_half_circle() {
+        let expected = [
+            "#++...", "###++.", "####+.", "#####+", "#####+", "######", "######", "#####+",
+            "#####+", "####+.", "###++.", "#++...",
+        ];
+        assert_eq!(render('\u{e0b4}', 6., 12.), expected.join("\n"));
+    }
 }
@@ -0,0 +1,152 @@
+//! Font selection for characters before the rasterizer's system fallback.
+
+use std::collections::HashMap;
+
+use crossfont::{FontKey, Rasterize, Rasterizer, Slant, Weight};
+use log::warn;
+
+use crate::config::font::{Font, FontDescription, SymbolMap};
+
+use super::glyph_cache::GlyphCache;
+
+/// Fonts configured for specific characters.
+#[derive(Default)]
+pub struct FontFallback {
+    /// Fonts for the codepoint ranges of the symbol map.
+    symbol_map: Vec<(SymbolMap, FontKey)>,
+
+    /// Fonts tried in order for characters missing from the primary fonts.
+    fallback: Vec<(FontKey, Coverage)>,
+
+    /// Character coverage of the primary fonts.
+    primary: HashMap<FontKey, Coverage>,
+}
+
+impl FontFallback {
+    /// Load the configured fonts.
+    ///
+    /// The primary fonts are the regular, bold, italic and bold italic fonts used for the
+    /// characters of each cell.
+    pub fn new(rasterizer: &mut Rasterizer, font: &Font, primary: [FontKey; 4]) -> Self {
+        let size = font.size();
+        let mut load = |desc: &FontDescription| {
+            let font_desc = GlyphCache::make_desc(desc, Slant::Normal, Weight::Normal);
+            match rasterizer.load_font(&font_desc, size) {
+                Ok(font_key) => Some(font_key),
+                Err(err) => {
+                    warn!("Unable to load font {:?}: {err}", desc.family);
+                    None
+                },
+            }
+        };
+
+        let symbol_map = font
+            .symbol_map
+            .iter()
+            .filter_map(|symbols| Some((symbols.clone(), load(&symbols.desc())?)))
+            .collect();
+
+        if font.fallback.is_empty() {
+            return Self { symbol_map, ..Default::default() };
+        }
+
+        if !Coverage::SUPPORTED {
+            warn!("Fallback fonts are not supported on this platform");
+            return Self { symbol_map, ..Default::default() };
+        }
+
+        let fallback = font
+            .fallback
+            .iter()
+            .filter_map(|desc| {
+                let coverage = Coverage::load(desc, Slant::Normal, Weight::Normal, size)?;
+                Some((load(desc)?, coverage))
+            })
+            .collect();
+
+        let primary_descs = [
+            (font.normal().clone(), Slant::Normal, Weight::Normal),
+            (font.bold(), Slant::Normal, Weight::Bold),
+            (font.italic(), Slant::Italic, Weight::Normal),
+            (font.bold_italic(), Slant::Italic, Weight::Bold),
+        ];
+        let primary = primary
+            .into_iter()
+            .zip(primary_descs)
+            .filter_map(|(font_key, (desc, slant, weight))| {
+                Some((font_key, Coverage::load(&desc, slant, weight, size)?))
+            })
+            .collect();
+
+        Self { symbol_map, fallback, primary }
+    }
+
+    /// Font configured for a character in the symbol map.
+    pub fn symbol_font(&self, c: char) -> Option<FontKey> {
+        self.symbol_map.iter().find(|(symbols, _)| symbols.contains(c)).map(|(_, key)| *key)
+    }
+
+    /// Font used to rasterize a character of the primary font.
+    ///
+    /// Characters without a configured font are left to the rasterizer's system fallback.
+    pub fn font_key(&self, primary: FontKey, c: char) -> FontKey {
+        if let Some(font_key) = self.symbol_font(c) {
+            return font_key;
+        }
+
+        // Keep the primary font if it has the character, or its coverage is unknown.
+        if self.primary.get(&primary).is_none_or(|coverage| coverage.has_char(c)) {
+            return primary;
+        }
+
+        self.fallback
+            .iter()
+            .find(|(_, coverage)| coverage.has_char(c))
+            .map_or(primary, |(font_key, _)| *font_key)
+    }
+}
+
+/// Characters present in a font.
+#[cfg(not(any(target_os = "macos", windows)))]
+struct Coverage(crossfont::ft::fc::CharSet);
+
+#[cfg(not(any(target_os = "macos", windows)))]
+impl Coverage {
+    const SUPPORTED: bool = true;
+
+    fn load(
+        desc: &FontDescription,
+        slant: Slant,
+        weight: Weight,
+        size: crossfont::Size,
+    ) -> Option<Self> {
+        let font = super::fontconfig::match_font(desc, slant, weight, size)?;
+        Some(Self(font.get_charset()?.to_owned()))
+    }
+
+    fn has_char(&self, c: char) -> bool {
+        self.0.has_char(c)
+    }
+}
+
+/// Characters present in a font.
+#[cfg(any(target_os = "macos", windows))]
+struct Coverage;
+
+#[cfg(any(target_os = "macos", windows))]
+impl Coverage {
+    const SUPPORTED: bool = false;
+
+    fn load(
+        _desc: &FontDescription,
+        _slant: Slant,
+        _weight: Weight,
+        _size: crossfont::Size,
+    ) -> Option<Self> {
+        None
+    }
+
+    fn has_char(&self, _c: char) -> bool {
+        false
+    }
+}
@@ -0,0 +1,34 @@
+//! Font matching through fontconfig, mirroring the font selection of the rasterizer.
+
+use crossfont::ft::fc::{self, Pattern};
+use crossfont::{Slant, Weight};
+
+use crate::config::font::FontDescription;
+
+/// Find the font the rasterizer uses as primary font for a font description.
+pub fn match_font(
+    desc: &FontDescription,
+    slant: Slant,
+    weight: Weight,
+    size: crossfont::Size,
+) -> Option<Pattern> {
+    let config = fc::Config::get_current();
+    let mut pattern = Pattern::new();
+    pattern.add_family(&desc.family);
+    pattern.add_pixelsize(f64::from(size.as_px()));
+    match &desc.style {
+        Some(style) => {
+            pattern.add_style(style);
+        },
+        None => {
+            pattern.set_weight(weight.into());
+            pattern.set_slant(slant.into());
+        },
+    }
+
+    pattern.config_substitute(config, fc::MatchKind::Pattern);
+    pattern.default_substitute();
+
+    let fonts = fc::font_sort(config, &pattern)?;
+    Some(pattern.render_prepare(config, fonts.into_iter().next()?))
+}
@@ -8,11 +8,15 @@ use crossfont::{
 use log::{error, info};
 use unicode_width::UnicodeWidthChar;
 
+use alacritty_terminal::term::cell::Flags;
+
 use crate::config::font::{Font, FontDescription};
 use crate::config::ui_config::Delta;
 use crate::gl::types::*;
 
 use super::builtin_font;
+use super::fallback::FontFallback;
+use super::shaper::Shaper;
 
 /// `LoadGlyph` allows for copying a rasterized glyph into graphics memory.
 pub trait LoadGlyph {
@@ -47,9 +51,18 @@ pub struct GlyphCache {
     /// Cache of buffered glyphs.
     cache: HashMap<GlyphKey, Glyph, RandomState>,
 
+    /// Cache of buffered glyphs substituted by text shaping.
+    shaped_cache: HashMap<(FontKey, u16), Option<Glyph>, RandomState>,
+
     /// Rasterizer for loading new glyphs.
     rasterizer: Rasterizer,
 
+    /// Text shaper for font ligatures.
+    pub shaper: Shaper,
+
+    /// Fonts configured for specific characters.
+    fallback: FontFallback,
+
     /// Regular font.
     pub font_key: FontKey,
 
@@ -83,8 +96,13 @@ impl GlyphCache {
         let (regular, bold, italic, bold_italic) = Self::compute_font_keys(font, &mut rasterizer)?;
 
         let metrics = GlyphCache::load_font_metrics(&mut rasterizer, font, regular)?;
+        let fallback =
+            FontFallback::new(&mut rasterizer, font, [regular, bold, italic, bold_italic]);
         Ok(Self {
+            fallback,
             cache: Default::default(),
+            shaped_cache: Default::default(),
+            shaper: Shaper::new(font),
             rasterizer,
             font_size: font.size(),
             font_key: regular,
@@ -179,7 +197,7 @@ impl GlyphCache {
         }
     }
 
-    fn make_desc(desc: &FontDescription, slant: Slant, weight: Weight) -> FontDesc {
+    pub fn make_desc(desc: &FontDescription, slant: Slant, weight: Weight) -> FontDesc {
         let style = if let Some(ref spec) = desc.style {
             Style::Specific(spec.to_owned())
         } else {
@@ -206,10 +224,10 @@ impl GlyphCache {
             return *glyph;
         };
 
-        // Rasterize the glyph using the built-in font for special characters or the user's font
-        // for everything else.
-        let rasterized = self
-            .builtin_box_drawing
+        // Rasterize the glyph using the symbol map or built-in font for special characters and the
+        // user's font for everything else.
+        let symbol_font = self.fallback.symbol_font(glyph_key.character);
+        let rasterized = (self.builtin_box_drawing && symbol_font.is_none())
             .then(|| {
                 builtin_font::builtin_glyph(
                     glyph_key.character,
@@ -219,7 +237,13 @@ impl GlyphCache {
                 )
             })
             .flatten()
-            .map_or_else(|| self.rasterizer.get_glyph(glyph_key), Ok);
+            .map_or_else(
+                || {
+                    let font_key = self.fallback.font_key(glyph_key.font_key, glyph_key.character);
+                    self.rasterizer.get_glyph(GlyphKey { font_key, ..glyph_key })
+                },
+                Ok,
+            );
 
         let glyph = match rasterized {
             Ok(rasterized) => self.load_glyph(loader, rasterized),
@@ -244,6 +268,31 @@ impl GlyphCache {
         *self.cache.entry(glyph_key).or_insert(glyph)
     }
 
+    /// Get a glyph substituted by text shaping.
+    ///
+    /// Returns [`None`] if the glyph could not be rasterized.
+    pub fn get_shaped<L>(&mut self, flags: Flags, glyph_id: u16, loader: &mut L) -> Option<Glyph>
+    where
+        L: LoadGlyph + ?Sized,
+    {
+        let font_key = match flags & Flags::BOLD_ITALIC {
+            Flags::BOLD_ITALIC => self.bold_italic_key,
+            Flags::ITALIC => self.italic_key,
+            Flags::BOLD => self.bold_key,
+            _ => self.font_key,
+        };
+
+        if let Some(glyph) = self.shaped_cache.get(&(font_key, glyph_id)) {
+            return *glyph;
+        }
+
+        let rasterized = self.shaper.rasterize(flags, glyph_id, self.font_size);
+        let glyph = rasterized.map(|rasterized| self.load_glyph(loader, rasterized));
+        self.shaped_cache.insert((font_key, glyph_id), glyph);
+
+        glyph
+    }
+
     /// Load glyph into the atlas.
     ///
     /// This will apply all transforms defined for the glyph cache to the rasterized glyph before
@@ -272,6 +321,7 @@ impl GlyphCache {
     pub fn reset_glyph_cache<L: LoadGlyph>(&mut self, loader: &mut L) {
         loader.clear();
         self.cache = Default::default();
+        self.shaped_cache = Default::default();
 
         self.load_common_glyphs(loader);
     }
@@ -300,6 +350,9 @@ impl GlyphCache {
         self.bold_italic_key = bold_italic;
         self.metrics = metrics;
         self.builtin_box_drawing = font.builtin_box_drawing;
+        self.shaper = Shaper::new(font);
+        self.fallback =
+            FontFallback::new(&mut self.rasterizer, font, [regular, bold, italic, bold_italic]);
 
         Ok(())
     }
@@ -10,15 +10,22 @@ use crate::gl::types::*;
 
 mod atlas;
 mod builtin_font;
+mod fallback;
+#[cfg(not(any(target_os = "macos", windows)))]
+mod fontconfig;
 mod gles2;
 mod glsl3;
 pub mod glyph_cache;
+pub mod shaper;
+mod software;
 
 use atlas::Atlas;
 pub use gles2::Gles2Renderer;
 pub use glsl3::Glsl3Renderer;
 pub use glyph_cache::GlyphCache;
 use glyph_cache::{Glyph, LoadGlyph};
+use shaper::ShapedGlyph;
+pub use software::SoftwareTextRenderer;
 
 // NOTE: These flags must be in sync with their usage in the text.*.glsl shaders.
 bitflags! {
@@ -155,8 +162,19 @@ pub trait TextRenderApi<T: TextRenderBatch>: LoadGlyph {
         let mut glyph_key =
             GlyphKey { font_key, size: glyph_cache.font_size, character: cell.character };
 
+        // Use glyphs substituted by text shaping, falling back to the cell's character.
+        let shaped_glyph = match cell.glyph.filter(|_| !hidden) {
+            Some(ShapedGlyph::Glyph(glyph_id)) => {
+                glyph_cache.get_shaped(cell.flags, glyph_id, self)
+            },
+            Some(ShapedGlyph::Continuation) => {
+                Some(glyph_cache.get(GlyphKey { character: ' ', ..glyph_key }, self, true))
+            },
+            None => None,
+        };
+
         // Add cell to batch.
-        let glyph = glyph_cache.get(glyph_key, self, true);
+        let glyph = shaped_glyph.unwrap_or_else(|| glyph_cache.get(glyph_key, self, true));
         self.add_render_item(&cell, &glyph, size_info);
 
         // Render visible zero-width characters.
@@ -0,0 +1,488 @@
+//! Text shaping for font ligatures.
+
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use ahash::RandomState;
+use crossfont::RasterizedGlyph;
+use log::warn;
+use rustybuzz::{Direction, Feature, UnicodeBuffer};
+
+use alacritty_terminal::term::cell::Flags;
+
+use crate::config::font::Font;
+
+/// Maximum number of shaped runs kept in the cache.
+const MAX_CACHE_SIZE: usize = 4096;
+
+/// Glyph replacing a cell's character after shaping.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ShapedGlyph {
+    /// Glyph ID in the font of the cell.
+    Glyph(u16),
+
+    /// Cell covered by a ligature starting in one of the previous cells.
+    Continuation,
+}
+
+/// Text shaper for the primary fonts.
+///
+/// Shaping is only performed for the configured fonts and not for their fallbacks, glyphs which
+/// aren't substituted by the font are left to the rasterizer instead.
+pub struct Shaper {
+    /// Faces for regular, bold, italic and bold italic text.
+    faces: [Option<Face>; 4],
+
+    /// OpenType features applied to all text.
+    features: Vec<Feature>,
+
+    /// Previously shaped runs.
+    cache: HashMap<(usize, String), Rc<[Option<ShapedGlyph>]>, RandomState>,
+}
+
+impl Shaper {
+    pub fn new(font: &Font) -> Self {
+        let features = font.features.iter().map(|feature| feature.feature()).collect();
+        let faces = if font.ligatures { Face::load_all(font) } else { Default::default() };
+
+        Self { faces, features, cache: Default::default() }
+    }
+
+    /// Check if there are any fonts available for shaping.
+    pub fn enabled(&self) -> bool {
+        self.faces.iter().any(Option::is_some)
+    }
+
+    /// Shape a run of single-width characters sharing the same font.
+    ///
+    /// Returns the glyph replacing each character, or [`None`] for characters which should be
+    /// rendered normally.
+    pub fn shape(&mut self, flags: Flags, text: &[char]) -> Option<Rc<[Option<ShapedGlyph>]>> {
+        let style = style(flags);
+        let face = self.faces[style].as_ref()?;
+
+        let key = (style, text.iter().collect::<String>());
+        if let Some(glyphs) = self.cache.get(&key) {
+            return Some(glyphs.clone());
+        }
+
+        let glyphs: Rc<[_]> = face.shape(&key.1, text, &self.features)?.into();
+
+        if self.cache.len() >= MAX_CACHE_SIZE {
+            self.cache.clear();
+        }
+        self.cache.insert(key, glyphs.clone());
+
+        Some(glyphs)
+    }
+
+    /// Rasterize a shaped glyph.
+    pub fn rasterize(
+        &mut self,
+        flags: Flags,
+        glyph_id: u16,
+        size: crossfont::Size,
+    ) -> Option<RasterizedGlyph> {
+        self.faces[style(flags)].as_mut()?.rasterize(glyph_id, size)
+    }
+}
+
+/// Index of the face used for cells with the specified flags.
+fn style(flags: Flags) -> usize {
+    match flags & Flags::BOLD_ITALIC {
+        Flags::BOLD_ITALIC => 3,
+        Flags::ITALIC => 2,
+        Flags::BOLD => 1,
+        _ => 0,
+    }
+}
+
+/// Map shaped glyphs onto the characters they were shaped from.
+///
+/// The glyphs are pairs of glyph ID and the index of the first character in their cluster.
+fn map_glyphs(
+    glyphs: &[(u16, usize)],
+    chars: &[char],
+    nominal: impl Fn(char) -> Option<u16>,
+) -> Vec<Option<ShapedGlyph>> {
+    let mut mapped = vec![None; chars.len()];
+    for (i, &(glyph_id, start)) in glyphs.iter().enumerate() {
+        let previous = i.checked_sub(1).map(|i| glyphs[i].1);
+        let next = glyphs.get(i + 1).map(|glyph| glyph.1);
+
+        // Clusters with multiple glyphs cannot be mapped onto the grid.
+        if previous == Some(start) || next == Some(start) {
+            continue;
+        }
+
+        // Leave missing glyphs to the rasterizer's fallback fonts.
+        let end = next.unwrap_or(chars.len());
+        if glyph_id == 0 || end <= start {
+            continue;
+        }
+
+        // Skip glyphs which weren't substituted.
+        if end - start == 1 && nominal(chars[start]) == Some(glyph_id) {
+            continue;
+        }
+
+        mapped[start] = Some(ShapedGlyph::Glyph(glyph_id));
+        for glyph in &mut mapped[start + 1..end] {
+            *glyph = Some(ShapedGlyph::Continuation);
+        }
+    }
+
+    mapped
+}
+
+/// Font face used for shaping and rasterization of shaped glyphs.
+struct Face {
+    /// Font file contents.
+    data: Vec<u8>,
+
+    /// Index of the face within the font file.
+    index: u32,
+
+    #[cfg(not(any(target_os = "macos", windows)))]
+    rasterizer: ft::FaceRasterizer,
+}
+
+impl Face {
+    /// Shape text, mapping the resulting glyphs onto the characters.
+    fn shape(
+        &self,
+        text: &str,
+        chars: &[char],
+        features: &[Feature],
+    ) -> Option<Vec<Option<ShapedGlyph>>> {
+        let face = rustybuzz::Face::from_slice(&self.data, self.index)?;
+
+        let mut buffer = UnicodeBuffer::new();
+        buffer.push_str(text);
+        buffer.guess_segment_properties();
+        buffer.set_direction(Direction::LeftToRight);
+        let output = rustybuzz::shape(&face, features, buffer);
+
+        // Map cluster byte offsets to the characters.
+        let offsets: Vec<usize> = text.char_indices().map(|(offset, _)| offset).collect();
+        let glyphs = output
+            .glyph_infos()
+            .iter()
+            .map(|info| {
+                let index = offsets.binary_search(&(info.cluster as usize)).ok()?;
+                Some((info.glyph_id as u16, index))
+            })
+            .collect::<Option<Vec<_>>>()?;
+
+        let nominal = |c| face.glyph_index(c).map(|glyph| glyph.0);
+        Some(map_glyphs(&glyphs, chars, nominal))
+    }
+
+    #[cfg(not(any(target_os = "macos", windows)))]
+    fn load_all(font: &Font) -> [Option<Self>; 4] {
+        use crossfont::{Slant, Weight};
+
+        let mut rasterizer = match ft::Rasterizer::new() {
+            Ok(rasterizer) => rasterizer,
+            Err(err) => {
+                warn!("Unable to load fonts for ligatures: {err}");
+                return Default::default();
+            },
+        };
+
+        [
+            (font.normal().clone(), Slant::Normal, Weight::Normal),
+            (font.bold(), Slant::Normal, Weight::Bold),
+            (font.italic(), Slant::Italic, Weight::Normal),
+            (font.bold_italic(), Slant::Italic, Weight::Bold),
+        ]
+        .map(|(desc, slant, weight)| {
+            let (data, index, face) = rasterizer.load(&desc, slant, weight, font.size())?;
+            let face = Self { data, index, rasterizer: face };
+
+            // Ensure the font can actually be used for shaping.
+            rustybuzz::Face::from_slice(&face.data, face.index)?;
+
+            Some(face)
+        })
+    }
+
+    #[cfg(any(target_os = "macos", windows))]
+    fn load_all(_font: &Font) -> [Option<Self>; 4] {
+        warn!("Font ligatures are not supported on this platform");
+        Default::default()
+    }
+
+    #[cfg(not(any(target_os = "macos", windows)))]
+    fn rasterize(&mut self, glyph_id: u16, size: crossfont::Size) -> Option<RasterizedGlyph> {
+        self.rasterizer.rasterize(glyph_id, size)
+    }
+
+    #[cfg(any(target_os = "macos", windows))]
+    fn rasterize(&mut self, _glyph_id: u16, _size: crossfont::Size) -> Option<RasterizedGlyph> {
+        None
+    }
+}
+
+/// FreeType rasterization of glyph IDs, matching the rasterizer used for characters.
+#[cfg(not(any(target_os = "macos", windows)))]
+mod ft {
+    use std::fs;
+    use std::rc::Rc;
+
+    use crossfont::ft::fc::{self, HintStyle, PatternRef, Rgba};
+    use crossfont::{BitmapBuffer, RasterizedGlyph, Slant, Weight};
+    use freetype::bitmap::PixelMode;
+    use freetype::face::LoadFlag;
+    use freetype::{FtResult, Library, RenderMode};
+    use log::{debug, warn};
+
+    use crate::config::font::FontDescription;
+    use crate::renderer::text::fontconfig;
+
+    pub struct Rasterizer {
+        library: Rc<Library>,
+    }
+
+    impl Rasterizer {
+        pub fn new() -> FtResult<Self> {
+            Ok(Self { library: Rc::new(Library::init()?) })
+        }
+
+        /// Load the font file and FreeType face for a font description.
+        ///
+        /// Fonts which require synthetic styles or aren't scalable are not supported, since the
+        /// shaped glyphs wouldn't match the glyphs of the rasterizer.
+        pub fn load(
+            &mut self,
+            desc: &FontDescription,
+            slant: Slant,
+            weight: Weight,
+            size: crossfont::Size,
+        ) -> Option<(Vec<u8>, u32, FaceRasterizer)> {
+            let font = fontconfig::match_font(desc, slant, weight, size)?;
+
+            if !font.scalable().next().unwrap_or(true)
+                || font.embolden().next().unwrap_or(false)
+                || font.get_matrix().is_some()
+            {
+                debug!("Ligatures are not supported for font {:?}", desc.family);
+                return None;
+            }
+
+            let location = font.ft_face_location(0)?;
+            let data = match fs::read(&location.path) {
+                Ok(data) => data,
+                Err(err) => {
+                    warn!("Unable to read font {:?}: {err}", location.path);
+                    return None;
+                },
+            };
+
+            let face = self.library.new_face(&location.path, location.index).ok()?;
+            let rasterizer = FaceRasterizer {
+                load_flags: load_flags(&font),
+                render_mode: render_mode(&font),
+                lcd_filter: lcd_filter(&font),
+                rgba: font.rgba().next().unwrap_or(Rgba::Unknown),
+                library: self.library.clone(),
+                face,
+            };
+
+            Some((data, location.index as u32, rasterizer))
+        }
+    }
+
+    pub struct FaceRasterizer {
+        library: Rc<Library>,
+        face: freetype::Face,
+        load_flags: LoadFlag,
+        render_mode: RenderMode,
+        lcd_filter: freetype::LcdFilter,
+        rgba: Rgba,
+    }
+
+    impl FaceRasterizer {
+        pub fn rasterize(
+            &mut self,
+            glyph_id: u16,
+            size: crossfont::Size,
+        ) -> Option<RasterizedGlyph> {
+            let pixelsize = (size.as_px() * 64.).round() as isize;
+            self.face.set_char_size(pixelsize, 0, 0, 0).ok()?;
+            let _ = self.library.set_lcd_filter(self.lcd_filter);
+
+            self.face.load_glyph(u32::from(glyph_id), self.load_flags).ok()?;
+            let glyph = self.face.glyph();
+            glyph.render_glyph(self.render_mode).ok()?;
+
+            let (height, width, buffer) = normalize_buffer(&glyph.bitmap(), &self.rgba)?;
+
+            Some(RasterizedGlyph {
+                character: '\0',
+                top: glyph.bitmap_top(),
+                left: glyph.bitmap_left(),
+                advance: ((glyph.advance().x >> 6) as i32, (glyph.advance().y >> 6) as i32),
+                width,
+                height,
+                buffer,
+            })
+        }
+    }
+
+    /// Glyph loading flags for the font.
+    fn load_flags(pattern: &PatternRef) -> LoadFlag {
+        let antialias = pattern.antialias().next().unwrap_or(true);
+        let autohint = pattern.autohint().next().unwrap_or(false);
+        let hinting = pattern.hinting().next().unwrap_or(true);
+        let rgba = pattern.rgba().next().unwrap_or(Rgba::Unknown);
+
+        let hintstyle = if hinting {
+            pattern.hintstyle().next().unwrap_or(HintStyle::Full)
+        } else {
+            HintStyle::None
+        };
+
+        let mut flags = match (antialias, hintstyle, rgba) {
+            (false, HintStyle::None, _) => LoadFlag::NO_HINTING | LoadFlag::MONOCHROME,
+            (false, ..) => LoadFlag::TARGET_MONO | LoadFlag::MONOCHROME,
+            (true, HintStyle::None, _) => LoadFlag::NO_HINTING,
+            (true, HintStyle::Slight, _) => LoadFlag::TARGET_LIGHT,
+            (true, HintStyle::Medium, _) => LoadFlag::TARGET_NORMAL,
+            (true, HintStyle::Full, Rgba::Rgb | Rgba::Bgr) => LoadFlag::TARGET_LCD,
+            (true, HintStyle::Full, Rgba::Vrgb | Rgba::Vbgr) => LoadFlag::TARGET_LCD_V,
+            (true, HintStyle::Full, Rgba::Unknown | Rgba::None) => LoadFlag::TARGET_NORMAL,
+        };
+
+        if autohint {
+            flags |= LoadFlag::FORCE_AUTOHINT;
+        }
+
+        flags
+    }
+
+    /// Glyph render mode for the font.
+    fn render_mode(pattern: &PatternRef) -> RenderMode {
+        let antialias = pattern.antialias().next().unwrap_or(true);
+        let rgba = pattern.rgba().next().unwrap_or(Rgba::Unknown);
+
+        match (antialias, rgba) {
+            (false, _) => RenderMode::Mono,
+            (_, Rgba::Rgb | Rgba::Bgr) => RenderMode::Lcd,
+            (_, Rgba::Vrgb | Rgba::Vbgr) => RenderMode::LcdV,
+            (true, _) => RenderMode::Normal,
+        }
+    }
+
+    /// LCD filter for the font.
+    fn lcd_filter(pattern: &PatternRef) -> freetype::LcdFilter {
+        match pattern.lcdfilter().next().unwrap_or(fc::LcdFilter::Default) {
+            fc::LcdFilter::None => freetype::LcdFilter::LcdFilterNone,
+            fc::LcdFilter::Default => freetype::LcdFilter::LcdFilterDefault,
+            fc::LcdFilter::Light => freetype::LcdFilter::LcdFilterLight,
+            fc::LcdFilter::Legacy => freetype::LcdFilter::LcdFilterLegacy,
+        }
+    }
+
+    /// Convert a FreeType bitmap to an RGB alphamask.
+    ///
+    /// Returns the height and width in pixels together with the buffer.
+    fn normalize_buffer(
+        bitmap: &freetype::Bitmap,
+        rgba: &Rgba,
+    ) -> Option<(i32, i32, BitmapBuffer)> {
+        let buffer = bitmap.buffer();
+        let pitch = bitmap.pitch().unsigned_abs() as usize;
+        let (rows, width) = (bitmap.rows() as usize, bitmap.width() as usize);
+
+        let mut packed = Vec::with_capacity(rows * width * 3);
+        match bitmap.pixel_mode().ok()? {
+            PixelMode::Lcd => {
+                for row in buffer.chunks(pitch).take(rows) {
+                    for pixel in row[..width].chunks_exact(3) {
+                        match rgba {
+                            Rgba::Bgr => packed.extend([pixel[2], pixel[1], pixel[0]]),
+                            _ => packed.extend_from_slice(pixel),
+                        }
+                    }
+                }
+                Some((rows as i32, width as i32 / 3, BitmapBuffer::Rgb(packed)))
+            },
+            PixelMode::LcdV => {
+                for row in 0..rows / 3 {
+                    for column in 0..width {
+                        for channel in 0..3 {
+                            let channel =
+                                if matches!(rgba, Rgba::Vbgr) { 2 - channel } else { channel };
+                            packed.push(buffer[(row * 3 + channel) * pitch + column]);
+                        }
+                    }
+                }
+                Some((rows as i32 / 3, width as i32, BitmapBuffer::Rgb(packed)))
+            },
+            PixelMode::Mono => {
+                for row in buffer.chunks(pitch).take(rows) {
+                    for column in 0..width {
+                        let value = ((row[column / 8] >> (7 - column % 8)) & 1) * 255;
+                        packed.extend([value; 3]);
+                    }
+                }
+                Some((rows as i32, width as i32, BitmapBuffer::Rgb(packed)))
+            },
+            PixelMode::Gray => {
+                for row in buffer.chunks(pitch).take(rows) {
+                    for &value in &row[..width] {
+                        packed.extend([value; 3]);
+                    }
+                }
+                Some((rows as i32, width as i32, BitmapBuffer::Rgb(packed)))
+            },
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn map_contextual_alternates() {
+        // Every character is replaced by its own glyph, like `calt` based ligatures.
+        let chars = ['a', '-', '>', 'b'];
+        let nominal = |c| Some(c as u16);
+        let glyphs = map_glyphs(&[(97, 0), (300, 1), (301, 2), (98, 3)], &chars, nominal);
+
+        assert_eq!(glyphs, [
+            None,
+            Some(ShapedGlyph::Glyph(300)),
+            Some(ShapedGlyph::Glyph(301)),
+            None
+        ]);
+    }
+
+    #[test]
+    fn map_ligature_clusters() {
+        // A single glyph replaces multiple characters.
+        let chars = ['=', '=', '=', 'x'];
+        let nominal = |c| Some(c as u16);
+        let glyphs = map_glyphs(&[(400, 0), (120, 3)], &chars, nominal);
+
+        assert_eq!(glyphs, [
+            Some(ShapedGlyph::Glyph(400)),
+            Some(ShapedGlyph::Continuation),
+            Some(ShapedGlyph::Continuation),
+            None
+        ]);
+    }
+
+    #[test]
+    fn map_unsupported_clusters() {
+        // Missing glyphs and clusters with multiple glyphs are rendered without shaping.
+        let chars = ['a', 'b', 'c'];
+        let nominal = |_| None;
+        let glyphs = map_glyphs(&[(0, 0), (5, 1), (6, 1), (7, 2)], &chars, nominal);
+
+        assert_eq!(glyphs, [None, None, Some(ShapedGlyph::Glyph(7))]);
+    }
+}
@@ -0,0 +1,229 @@
+use crossfont::RasterizedGlyph;
+
+use alacritty_terminal::term::cell::Flags;
+
+use crate::display::SizeInfo;
+use crate::display::color::Rgb;
+use crate::display::content::RenderableCell;
+use crate::gl::types::*;
+use crate::renderer::software::{self, Framebuffer};
+
+use super::atlas::{ATLAS_SIZE, Atlas};
+use super::{Glyph, GlyphCache, LoadGlyph, LoaderApi, TextRenderApi, TextRenderBatch};
+
+/// Maximum items to be drawn in a batch.
+const BATCH_MAX: usize = 0x1_0000;
+
+/// Text renderer drawing glyphs from atlases in memory.
+#[derive(Debug)]
+pub struct SoftwareTextRenderer {
+    atlas: Vec<Atlas>,
+    current_atlas: usize,
+    active_tex: GLuint,
+    batch: Batch,
+}
+
+impl Default for SoftwareTextRenderer {
+    fn default() -> Self {
+        Self {
+            atlas: vec![Atlas::new_software(ATLAS_SIZE, 0)],
+            current_atlas: 0,
+            active_tex: 0,
+            batch: Default::default(),
+        }
+    }
+}
+
+impl SoftwareTextRenderer {
+    /// Draw cells into the framebuffer.
+    pub fn draw_cells<I: Iterator<Item = RenderableCell>>(
+        &mut self,
+        framebuffer: &mut Framebuffer,
+        size_info: &SizeInfo,
+        glyph_cache: &mut GlyphCache,
+        cells: I,
+    ) {
+        let mut api = RenderApi {
+            active_tex: &mut self.active_tex,
+            batch: &mut self.batch,
+            atlas: &mut self.atlas,
+            current_atlas: &mut self.current_atlas,
+            framebuffer,
+            size_info,
+        };
+
+        for cell in cells {
+            api.draw_cell(cell, glyph_cache, size_info);
+        }
+    }
+
+    /// Invoke renderer with the loader.
+    pub fn with_loader<F: FnOnce(LoaderApi<'_>) -> T, T>(&mut self, func: F) -> T {
+        func(LoaderApi {
+            active_tex: &mut self.active_tex,
+            atlas: &mut self.atlas,
+            current_atlas: &mut self.current_atlas,
+        })
+    }
+}
+
+#[derive(Debug)]
+struct RenderApi<'a> {
+    active_tex: &'a mut GLuint,
+    batch: &'a mut Batch,
+    atlas: &'a mut Vec<Atlas>,
+    current_atlas: &'a mut usize,
+    framebuffer: &'a mut Framebuffer,
+    size_info: &'a SizeInfo,
+}
+
+impl TextRenderApi<Batch> for RenderApi<'_> {
+    fn batch(&mut self) -> &mut Batch {
+        self.batch
+    }
+
+    fn render_batch(&mut self) {
+        let size_info = self.size_info;
+        let cell_width = size_info.cell_width();
+        let cell_height = size_info.cell_height();
+        let origin_x = size_info.x() + size_info.padding_x();
+        let origin_y = size_info.y() + size_info.padding_y();
+
+        // Limit drawing to the viewport without padding, like OpenGL's viewport.
+        let columns =
+            (origin_x as i32)..(origin_x + size_info.width() - 2. * size_info.padding_x()) as i32;
+        let lines =
+            (origin_y as i32)..(origin_y + size_info.height() - 2. * size_info.padding_y()) as i32;
+        let columns = software::clip(columns, self.framebuffer.columns());
+        let lines = software::clip(lines, self.framebuffer.lines());
+
+        // Draw backgrounds first, so glyphs can overlap neighboring cells.
+        for item in &self.batch.items {
+            if item.bg_alpha == 0. {
+                continue;
+            }
+
+            let occupied_cells = if item.wide { 2. } else { 1. };
+            let x = (origin_x + item.column as f32 * cell_width) as i32;
+            let y = (origin_y + item.line as f32 * cell_height) as i32;
+            let width = (cell_width * occupied_cells) as i32;
+            let (r, g, b) = item.bg.as_tuple();
+            let color = [r as f32, g as f32, b as f32];
+
+            for py in software::clip(y..y + cell_height as i32, lines.clone()) {
+                for px in software::clip(x..x + width, columns.clone()) {
+                    self.framebuffer.blend(px, py, color, [item.bg_alpha; 3]);
+                }
+            }
+        }
+
+        let atlas = &self.atlas[self.batch.tex as usize];
+        let atlas_pixels = atlas.pixels().unwrap_or_default();
+        let atlas_width = atlas.width() as usize;
+
+        for item in &self.batch.items {
+            let glyph = &item.glyph;
+            let x = (origin_x + item.column as f32 * cell_width) as i32 + glyph.left as i32;
+            let y = (origin_y + (item.line + 1) as f32 * cell_height) as i32 - glyph.top as i32;
+            let atlas_x = (glyph.uv_left * atlas_width as f32).round() as usize;
+            let atlas_y = (glyph.uv_bot * atlas_width as f32).round() as usize;
+            let (r, g, b) = item.fg.as_tuple();
+            let fg = [r as f32, g as f32, b as f32];
+
+            let glyph_lines = software::clip(y..y + glyph.height as i32, lines.clone());
+            let glyph_columns = software::clip(x..x + glyph.width as i32, columns.clone());
+            for py in glyph_lines {
+                let row = atlas_y + (py - y) as usize;
+                for px in glyph_columns.clone() {
+                    let index = (row * atlas_width + atlas_x + (px - x) as usize) * 4;
+                    let texel = &atlas_pixels[index..index + 4];
+                    let [r, g, b, a] = [texel[0], texel[1], texel[2], texel[3]].map(f32::from);
+
+                    if glyph.multicolor {
+                        // Color glyphs, like emojis, are stored with premultiplied alpha.
+                        if a == 0. {
+                            continue;
+                        }
+
+                        let color = [r * 255. / a, g * 255. / a, b * 255. / a];
+                        self.framebuffer.blend(px, py, color, [a / 255.; 3]);
+                    } else {
+                        // Regular text glyphs use a separate alpha for every subpixel.
+                        self.framebuffer.blend(px, py, fg, [r / 255., g / 255., b / 255.]);
+                    }
+                }
+            }
+        }
+
+        self.batch.items.clear();
+    }
+}
+
+impl LoadGlyph for RenderApi<'_> {
+    fn load_glyph(&mut self, rasterized: &RasterizedGlyph) -> Glyph {
+        Atlas::load_glyph(self.active_tex, self.atlas, self.current_atlas, rasterized)
+    }
+
+    fn clear(&mut self) {
+        Atlas::clear_atlas(self.atlas, self.current_atlas)
+    }
+}
+
+impl Drop for RenderApi<'_> {
+    fn drop(&mut self) {
+        if !self.batch.is_empty() {
+            self.render_batch();
+        }
+    }
+}
+
+/// Cell drawn by the software renderer.
+#[derive(Debug)]
+struct BatchItem {
+    column: usize,
+    line: usize,
+    wide: bool,
+    fg: Rgb,
+    bg: Rgb,
+    bg_alpha: f32,
+    glyph: Glyph,
+}
+
+#[derive(Debug, Default)]
+struct Batch {
+    tex: GLuint,
+    items: Vec<BatchItem>,
+}
+
+impl TextRenderBatch for Batch {
+    #[inline]
+    fn tex(&self) -> GLuint {
+        self.tex
+    }
+
+    #[inline]
+    fn full(&self) -> bool {
+        self.items.len() == BATCH_MAX
+    }
+
+    #[inline]
+    fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    fn add_item(&mut self, cell: &RenderableCell, glyph: &Glyph, _: &SizeInfo) {
+        if self.is_empty() {
+            self.tex = glyph.tex_id;
+        }
+
+        self.items.push(BatchItem {
+            column: cell.point.column.0,
+            line: cell.point.line,
+            wide: cell.flags.contains(Flags::WIDE_CHAR),
+            fg: cell.fg,
+            bg: cell.bg,
+            bg_alpha: cell.bg_alpha,
+            glyph: *glyph,
+        });
+    }
+}
@@ -26,6 +26,7 @@ impl TimerId {
 pub enum Topic {
     SelectionScrolling,
     DelayedSearch,
+    ChordTimeout,
     BlinkCursor,
     BlinkTimeout,
     Frame,
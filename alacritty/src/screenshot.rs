@@ -0,0 +1,332 @@
+//! Export of the terminal's content as PNG images.
+
+use std::fmt::{self, Display, Formatter};
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Read};
+use std::path::{Path, PathBuf};
+#[cfg(unix)]
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+#[cfg(unix)]
+use std::os::unix::net::UnixStream;
+
+use crossfont::{Rasterize, Rasterizer};
+use log::{error, info};
+
+use alacritty_terminal::event::VoidListener;
+use alacritty_terminal::parser::Processor;
+use alacritty_terminal::term::Term;
+use alacritty_terminal::vte::ansi::NamedColor;
+
+use crate::cli::RenderOptions;
+use crate::config::UiConfig;
+use crate::config::window::Dimensions;
+use crate::display::color::List;
+use crate::display::content::RenderableContent;
+use crate::display::cursor::IntoRects;
+use crate::display::{self, SizeInfo};
+use crate::event::SearchState;
+#[cfg(unix)]
+use crate::ipc::{self, SocketReply};
+use crate::renderer::GlyphCache;
+use crate::renderer::graphics::RenderGraphic;
+use crate::renderer::rects::RenderLines;
+use crate::renderer::software::SoftwareRenderer;
+
+/// Terminal size used when neither the CLI nor the config specify one.
+const DEFAULT_DIMENSIONS: Dimensions = Dimensions { columns: 80, lines: 24 };
+
+/// RGB image of a rendered frame.
+pub struct Image {
+    width: u32,
+    height: u32,
+
+    /// Pixel rows from top to bottom, with 3 bytes per pixel.
+    pixels: Vec<u8>,
+}
+
+impl Image {
+    pub fn new(width: u32, height: u32, pixels: Vec<u8>) -> Self {
+        debug_assert_eq!(pixels.len(), width as usize * height as usize * 3);
+        Self { width, height, pixels }
+    }
+
+    /// Write the image to a PNG file.
+    pub fn write_png(&self, path: &Path) -> Result<(), Error> {
+        let file = BufWriter::new(File::create(path)?);
+        let mut encoder = png::Encoder::new(file, self.width, self.height);
+        encoder.set_color(png::ColorType::Rgb);
+        encoder.set_depth(png::BitDepth::Eight);
+
+        let mut writer = encoder.write_header()?;
+        writer.write_image_data(&self.pixels)?;
+        writer.finish()?;
+
+        Ok(())
+    }
+}
+
+/// Screenshot waiting for the next frame of a window.
+pub struct Screenshot {
+    path: PathBuf,
+
+    /// IPC client waiting for the screenshot.
+    #[cfg(unix)]
+    stream: Option<Arc<UnixStream>>,
+}
+
+impl Screenshot {
+    pub fn new(path: PathBuf) -> Self {
+        Self {
+            path,
+            #[cfg(unix)]
+            stream: None,
+        }
+    }
+
+    /// Screenshot in `directory`, named after the current time.
+    pub fn timestamped(directory: &Path) -> Self {
+        let timestamp = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+        Self::new(directory.join(format!("alacritty-{}.png", timestamp.as_millis())))
+    }
+
+    /// Screenshot requested over IPC, which reports errors to the client.
+    #[cfg(unix)]
+    pub fn with_reply(path: PathBuf, stream: Arc<UnixStream>) -> Self {
+        Self { path, stream: Some(stream) }
+    }
+
+    /// Abort the screenshot, since occluded windows are not drawn.
+    pub fn cancel(self) {
+        error!("Unable to save screenshot {:?}: window is not visible", self.path);
+
+        #[cfg(unix)]
+        if let Some(stream) = self.stream {
+            if let Ok(mut stream) = stream.try_clone() {
+                ipc::send_reply(&mut stream, SocketReply::Error("window is not visible".into()));
+            }
+        }
+    }
+
+    /// Save the frame to the screenshot's file.
+    pub fn save(self, image: &Image) {
+        let result = image.write_png(&self.path);
+        match &result {
+            Ok(()) => info!("Saved screenshot to {:?}", self.path),
+            Err(err) => error!("Unable to save screenshot {:?}: {err}", self.path),
+        }
+
+        // Closing the stream without a reply signals success to the client.
+        #[cfg(unix)]
+        if let (Some(stream), Err(err)) = (self.stream, result) {
+            if let Ok(mut stream) = stream.try_clone() {
+                ipc::send_reply(&mut stream, SocketReply::Error(err.to_string()));
+            }
+        }
+    }
+}
+
+/// Render terminal output to a PNG image, without opening a window.
+pub fn render(config: &UiConfig, options: &RenderOptions) -> Result<(), Error> {
+    let input = match &options.input {
+        Some(path) => fs::read(path)?,
+        None => {
+            let mut input = Vec::new();
+            io::stdin().read_to_end(&mut input)?;
+            input
+        },
+    };
+
+    let mut glyph_cache = GlyphCache::new(Rasterizer::new()?, &config.font)?;
+    let metrics = glyph_cache.font_metrics();
+    let (cell_width, cell_height) = display::compute_cell_size(config, &metrics);
+
+    // Size the image like a window with the requested dimensions.
+    let dimensions = config.window.dimensions().unwrap_or(DEFAULT_DIMENSIONS);
+    let dimensions = Dimensions {
+        columns: options.columns.unwrap_or(dimensions.columns),
+        lines: options.lines.unwrap_or(dimensions.lines),
+    };
+    let size = display::window_size(config, dimensions, cell_width, cell_height, 1.);
+    let padding = config.window.padding(1.);
+    let size_info = SizeInfo::new(
+        size.width as f32,
+        size.height as f32,
+        cell_width,
+        cell_height,
+        padding.0,
+        padding.1,
+        false,
+    );
+
+    // Replay the output through the terminal.
+    let mut terminal = Term::new(config.term_options(), &size_info, VoidListener);
+    terminal.is_focused = true;
+    let mut parser: Processor = Processor::new();
+    parser.advance(&mut terminal, &input);
+    parser.stop_sync(&mut terminal);
+
+    let colors = List::from(&config.colors);
+    let mut search_state = SearchState::default();
+    let shaper = Some(&mut glyph_cache.shaper).filter(|shaper| shaper.enabled());
+    let mut content = RenderableContent::headless(
+        config,
+        &colors,
+        &size_info,
+        shaper,
+        &terminal,
+        &mut search_state,
+    );
+    let mut cells = Vec::new();
+    let mut graphics = Vec::new();
+    for cell in &mut content {
+        if let Some(graphic) = cell.extra.as_ref().and_then(|extra| extra.graphic.as_ref()) {
+            graphics.push(RenderGraphic {
+                id: graphic.texture.id,
+                point: cell.point,
+                placement: graphic.placement,
+                offset_x: graphic.offset_x,
+                offset_y: graphic.offset_y,
+            });
+        }
+
+        cells.push(cell);
+    }
+    let background_color = content.color(NamedColor::Background as usize);
+    let cursor = content.cursor();
+
+    let mut renderer = SoftwareRenderer::default();
+    renderer.resize(&size_info);
+    renderer.clear(background_color);
+
+    if let Some(graphics_queues) = terminal.graphics_mut().take_queues() {
+        renderer.update_graphics(graphics_queues);
+    }
+
+    let (graphics_below, graphics): (Vec<_>, Vec<_>) =
+        graphics.into_iter().partition(|graphic| graphic.placement.z_index < 0);
+    renderer.draw_graphics(&size_info, graphics_below);
+
+    let mut lines = RenderLines::new();
+    let cells = cells.into_iter().inspect(|cell| lines.update(cell));
+    renderer.draw_cells(&size_info, &mut glyph_cache, cells);
+
+    renderer.draw_graphics(&size_info, graphics);
+
+    let mut rects = lines.rects(&metrics, &size_info);
+    rects.extend(cursor.rects(&size_info, config.cursor.thickness()));
+    renderer.draw_rects(&size_info, &metrics, rects);
+
+    renderer.image().write_png(&options.output)
+}
+
+#[derive(Debug)]
+pub enum Error {
+    /// Error reading the input or writing the image.
+    Io(io::Error),
+
+    /// Error encoding the PNG image.
+    Encoding(png::EncodingError),
+
+    /// Error loading the font.
+    Font(crossfont::Error),
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io(err) => err.source(),
+            Error::Encoding(err) => err.source(),
+            Error::Font(err) => err.source(),
+        }
+    }
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Io(err) => err.fmt(f),
+            Error::Encoding(err) => write!(f, "Unable to encode PNG: {err}"),
+            Error::Font(err) => write!(f, "Unable to load font: {err}"),
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(val: io::Error) -> Self {
+        Error::Io(val)
+    }
+}
+
+impl From<png::EncodingError> for Error {
+    fn from(val: png::EncodingError) -> Self {
+        Error::Encoding(val)
+    }
+}
+
+impl From<crossfont::Error> for Error {
+    fn from(val: crossfont::Error) -> Self {
+        Error::Font(val)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn write_png() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("screenshot.png");
+
+        let pixels = vec![0xff, 0x00, 0x00, 0x00, 0x00, 0xff];
+        Image::new(2, 1, pixels.clone()).write_png(&path).unwrap();
+
+        let decoder = png::Decoder::new(File::open(&path).unwrap());
+        let mut reader = decoder.read_info().unwrap();
+        let mut buffer = vec![0; reader.output_buffer_size()];
+        let info = reader.next_frame(&mut buffer).unwrap();
+
+        assert_eq!((info.width, info.height), (2, 1));
+        assert_eq!(info.color_type, png::ColorType::Rgb);
+        assert_eq!(&buffer[..info.buffer_size()], pixels);
+    }
+
+    #[test]
+    fn timestamped_directory() {
+        let dir = tempfile::tempdir().unwrap();
+        let screenshot = Screenshot::timestamped(dir.path());
+        assert_eq!(screenshot.path.parent(), Some(dir.path()));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn reply() {
+        use std::io::{BufRead, BufReader};
+
+        let dir = tempfile::tempdir().unwrap();
+        let image = Image::new(1, 1, vec![0; 3]);
+        let read_reply = |stream| {
+            let mut reply = String::new();
+            BufReader::new(stream).read_line(&mut reply).unwrap();
+            reply
+        };
+
+        // Successful screenshots close the stream without any reply.
+        let (stream, client) = UnixStream::pair().unwrap();
+        let path = dir.path().join("screenshot.png");
+        Screenshot::with_reply(path.clone(), Arc::new(stream)).save(&image);
+        assert_eq!(read_reply(client), "");
+        assert!(path.exists());
+
+        let (stream, client) = UnixStream::pair().unwrap();
+        let path = dir.path().join("missing").join("screenshot.png");
+        Screenshot::with_reply(path, Arc::new(stream)).save(&image);
+        assert!(read_reply(client).starts_with(r#"{"Error":"#));
+
+        let (stream, client) = UnixStream::pair().unwrap();
+        Screenshot::with_reply(dir.path().join("hidden.png"), Arc::new(stream)).cancel();
+        assert_eq!(read_reply(client), r#"{"Error":"window is not visible"}"#);
+    }
+}
@@ -0,0 +1,311 @@
+//! Session persistence across restarts.
+
+use std::fmt::{self, Display, Formatter};
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+#[cfg(unix)]
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::{Path, PathBuf};
+
+use log::info;
+use miniz_oxide::deflate::compress_to_vec_zlib;
+use miniz_oxide::inflate::decompress_to_vec_zlib;
+use serde::{Deserialize, Serialize};
+use serde_json as json;
+
+use alacritty_terminal::grid::{Dimensions, Grid, Row};
+use alacritty_terminal::index::{Column, Line, Point};
+use alacritty_terminal::term::Term;
+use alacritty_terminal::term::cell::{Cell, Flags};
+
+/// Version of the session file format.
+const VERSION: u32 = 1;
+
+/// Label centered in the separator between restored history and the new shell.
+const SEPARATOR_LABEL: &str = " restored ";
+
+/// Persisted state of all windows.
+#[derive(Serialize, Deserialize)]
+struct Session {
+    version: u32,
+    windows: Vec<SavedWindow>,
+}
+
+/// Persisted state of a single window.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct SavedWindow {
+    pub title: String,
+    pub columns: usize,
+    pub lines: usize,
+    pub working_directory: Option<PathBuf>,
+
+    /// Grid cursor position, which is not serialized with the grid itself.
+    cursor: Point,
+
+    /// Primary grid including its scrollback history.
+    grid: Grid<Cell>,
+}
+
+impl SavedWindow {
+    pub fn new<T>(
+        terminal: &Term<T>,
+        title: String,
+        columns: usize,
+        lines: usize,
+        working_directory: Option<PathBuf>,
+    ) -> Self {
+        let mut grid = terminal.primary_grid().clone();
+        grid.truncate();
+
+        Self { cursor: grid.cursor.point, title, columns, lines, working_directory, grid }
+    }
+
+    /// Rows of the saved history reflowed to `columns`, followed by a separator.
+    ///
+    /// Everything below the cursor is dropped, since it will be replaced by the new shell.
+    pub fn history(mut self, columns: usize) -> Vec<Row<Cell>> {
+        self.grid.cursor.point = self.cursor;
+        let lines = self.grid.screen_lines();
+        self.grid.resize(true, lines, columns);
+
+        let topmost_line = self.grid.topmost_line().0;
+        let cursor_line = self.grid.cursor.point.line.0;
+        let mut rows: Vec<_> =
+            (topmost_line..=cursor_line).map(|line| self.grid[Line(line)].clone()).collect();
+
+        // Skip trailing empty lines, like a cleared screen.
+        while rows.last().is_some_and(|row| row.is_clear()) {
+            rows.pop();
+        }
+
+        if !rows.is_empty() {
+            rows.push(separator(columns));
+        }
+
+        rows
+    }
+}
+
+/// Create the line separating restored history from the new shell.
+fn separator(columns: usize) -> Row<Cell> {
+    let label: Vec<char> = SEPARATOR_LABEL.chars().collect();
+    let label_start = columns.saturating_sub(label.len()) / 2;
+
+    let mut row = Row::<Cell>::new(columns);
+    for column in 0..columns {
+        let cell = &mut row[Column(column)];
+        let label_char = column.checked_sub(label_start).and_then(|i| label.get(i));
+        cell.c = label_char.copied().unwrap_or('─');
+        cell.flags = Flags::DIM;
+    }
+
+    row
+}
+
+/// Write the windows to the session file.
+pub fn save(windows: Vec<SavedWindow>) -> Result<(), Error> {
+    let path = session_path()?;
+    write_atomic(&path, &encode(windows)?)?;
+
+    info!("Saved session to {path:?}");
+
+    Ok(())
+}
+
+/// Replace a file, without leaving it partially written on failure.
+///
+/// The scrollback can contain passwords or other secrets, so the file is only readable by its
+/// owner. To ensure this applies even if the file already exists, a new temporary file is created
+/// next to it and then renamed over it.
+fn write_atomic(path: &Path, data: &[u8]) -> io::Result<()> {
+    let mut tmp_path = path.as_os_str().to_owned();
+    tmp_path.push(".tmp");
+    let tmp_path = PathBuf::from(tmp_path);
+
+    // Remove leftovers from a previous failed save.
+    match fs::remove_file(&tmp_path) {
+        Err(err) if err.kind() != io::ErrorKind::NotFound => return Err(err),
+        _ => (),
+    }
+
+    let mut options = OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    options.mode(0o600);
+
+    let result = options.open(&tmp_path).and_then(|mut file| {
+        file.write_all(data)?;
+        file.sync_all()
+    });
+    let result = result.and_then(|_| fs::rename(&tmp_path, path));
+    if result.is_err() {
+        let _ = fs::remove_file(&tmp_path);
+    }
+
+    result
+}
+
+/// Load and remove the session file.
+///
+/// No windows are returned when there is no session file.
+pub fn load() -> Result<Vec<SavedWindow>, Error> {
+    let path = session_path()?;
+    let session = match fs::read(&path) {
+        Ok(session) => session,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(err) => return Err(err.into()),
+    };
+
+    // Remove the session, to avoid restoring it more than once.
+    fs::remove_file(&path)?;
+
+    info!("Restoring session from {path:?}");
+
+    decode(&session)
+}
+
+/// Serialize and compress windows.
+fn encode(windows: Vec<SavedWindow>) -> Result<Vec<u8>, Error> {
+    let session = json::to_vec(&Session { version: VERSION, windows })?;
+    Ok(compress_to_vec_zlib(&session, 6))
+}
+
+/// Decompress and deserialize windows.
+fn decode(session: &[u8]) -> Result<Vec<SavedWindow>, Error> {
+    let session = decompress_to_vec_zlib(session).map_err(|_| Error::Compression)?;
+    let session: Session = json::from_slice(&session)?;
+
+    if session.version != VERSION {
+        return Err(Error::Version(session.version));
+    }
+
+    Ok(session.windows)
+}
+
+/// Location of the session file.
+#[cfg(not(windows))]
+fn session_path() -> io::Result<PathBuf> {
+    xdg::BaseDirectories::with_prefix("alacritty").place_state_file("session")
+}
+
+/// Location of the session file.
+#[cfg(windows)]
+fn session_path() -> io::Result<PathBuf> {
+    let directory = dirs::data_local_dir()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no local data directory"))?
+        .join("alacritty");
+    fs::create_dir_all(&directory)?;
+    Ok(directory.join("session"))
+}
+
+#[derive(Debug)]
+pub enum Error {
+    /// Error accessing the session file.
+    Io(io::Error),
+
+    /// Invalid JSON in the session.
+    Json(json::Error),
+
+    /// Invalid compressed data.
+    Compression,
+
+    /// Unsupported session version.
+    Version(u32),
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io(err) => err.source(),
+            Error::Json(err) => err.source(),
+            Error::Compression | Error::Version(_) => None,
+        }
+    }
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Io(err) => write!(f, "Unable to access session: {err}"),
+            Error::Json(err) => write!(f, "Invalid session: {err}"),
+            Error::Compression => write!(f, "Invalid session: bad compression"),
+            Error::Version(version) => write!(f, "Unsupported session version {version}"),
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(val: io::Error) -> Self {
+        Error::Io(val)
+    }
+}
+
+impl From<json::Error> for Error {
+    fn from(val: json::Error) -> Self {
+        Error::Json(val)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use alacritty_terminal::event::VoidListener;
+    use alacritty_terminal::term::Config;
+    use alacritty_terminal::term::test::{TermSize, mock_term};
+
+    #[test]
+    fn session_round_trip() {
+        let mut term = mock_term("first\r\nsecond\r\n\r\n");
+        term.grid_mut().cursor.point = Point::new(Line(2), Column(0));
+        let window = SavedWindow::new(&term, "title".into(), 6, 3, None);
+
+        let mut windows = decode(&encode(vec![window]).unwrap()).unwrap();
+        assert_eq!(windows.len(), 1);
+
+        let window = windows.remove(0);
+        assert_eq!(window.title, "title");
+
+        let rows = window.history(12);
+        assert_eq!(rows.len(), 3);
+        assert_eq!(rows[0][Column(0)].c, 'f');
+        assert_eq!(rows[1][Column(5)].c, 'd');
+        assert_eq!(rows[2][Column(0)].c, '─');
+        assert_eq!(rows[2][Column(1)].c, ' ');
+        assert_eq!(rows[2][Column(2)].c, 'r');
+        assert!(rows[2][Column(0)].flags.contains(Flags::DIM));
+
+        let mut term = Term::new(Config::default(), &TermSize::new(12, 2), VoidListener);
+        term.restore_history(rows);
+        assert_eq!(term.grid().history_size(), 3);
+    }
+
+    #[test]
+    fn replace_session_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("session");
+
+        fs::write(&path, "old").unwrap();
+        write_atomic(&path, b"new").unwrap();
+        assert_eq!(fs::read(&path).unwrap(), b"new");
+
+        // Only the session file remains, without the temporary file.
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 1);
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+
+            let metadata = fs::metadata(&path).unwrap();
+            assert_eq!(metadata.permissions().mode() & 0o777, 0o600);
+        }
+    }
+
+    #[test]
+    fn invalid_session() {
+        assert!(matches!(decode(b"session"), Err(Error::Compression)));
+
+        let session = compress_to_vec_zlib(br#"{"version":0,"windows":[]}"#, 6);
+        assert!(matches!(decode(&session), Err(Error::Version(0))));
+    }
+}
@@ -1,47 +1,62 @@
 //! Terminal window context.
 
+use std::collections::HashMap;
 use std::error::Error;
 use std::fs::File;
 use std::io::Write;
+#[cfg(unix)]
+use std::iter;
 use std::mem;
 #[cfg(not(windows))]
-use std::os::unix::io::{AsRawFd, RawFd};
+use std::os::unix::io::AsRawFd;
+#[cfg(unix)]
+use std::path::PathBuf;
 use std::rc::Rc;
 use std::sync::Arc;
 use std::time::Instant;
 
-use glutin::config::Config as GlutinConfig;
-use glutin::display::GetGlDisplay;
-#[cfg(all(feature = "x11", not(any(target_os = "macos", windows))))]
-use glutin::platform::x11::X11GlConfigExt;
-use log::info;
+use log::{error, info, warn};
 use serde_json as json;
-use winit::event::{Event as WinitEvent, Modifiers, WindowEvent};
+use winit::dpi::PhysicalPosition;
+use winit::event::{ElementState, Event as WinitEvent, Modifiers, MouseButton, WindowEvent};
 use winit::event_loop::{ActiveEventLoop, EventLoopProxy};
 use winit::raw_window_handle::HasDisplayHandle;
 use winit::window::WindowId;
 
-use alacritty_terminal::event::Event as TerminalEvent;
-use alacritty_terminal::event_loop::{EventLoop as PtyEventLoop, Msg, Notifier};
-use alacritty_terminal::grid::{Dimensions, Scroll};
+use alacritty_terminal::event::{Event as TerminalEvent, Notify, OnResize};
+use alacritty_terminal::event_loop::{EventLoop as PtyEventLoop, EventLoopSender, Notifier};
+use alacritty_terminal::grid::{Dimensions, Row, Scroll};
 use alacritty_terminal::index::Direction;
+use alacritty_terminal::recording::Replay;
 use alacritty_terminal::sync::FairMutex;
+use alacritty_terminal::term::cell::Cell;
 use alacritty_terminal::term::test::TermSize;
 use alacritty_terminal::term::{Term, TermMode};
-use alacritty_terminal::tty;
+use alacritty_terminal::tty::{self, Options as PtyOptions};
 
-use crate::cli::{ParsedOptions, WindowOptions};
+use crate::cli::{ParsedOptions, ReplayOptions, WindowOptions};
 use crate::clipboard::Clipboard;
-use crate::config::UiConfig;
-use crate::display::Display;
+use crate::config::debug::RendererPreference;
+use crate::config::{self, UiConfig};
+#[cfg(unix)]
+use crate::daemon;
 use crate::display::window::Window;
-use crate::event::{
-    ActionContext, Event, EventProxy, InlineSearchState, Mouse, SearchState, TouchPurpose,
-};
+use crate::display::{Display, SizeInfo};
+use crate::event::{ActionContext, Event, EventProxy, EventType, Mouse, SearchState, TouchPurpose};
+#[cfg(unix)]
+use crate::export::{self, Format, Region};
+#[cfg(unix)]
+use crate::ipc::{self, IpcEvent};
 #[cfg(unix)]
 use crate::logging::LOG_TARGET_IPC_CONFIG;
+use crate::macros::Macros;
 use crate::message_bar::MessageBuffer;
+use crate::notification::RateLimiter;
+use crate::pane::{Divider, Layout, Pane, PaneAction, PaneId, Split};
+use crate::recording::{self, Recording};
+use crate::renderer::platform::GraphicsPlatform;
 use crate::scheduler::Scheduler;
+use crate::session::SavedWindow;
 use crate::{input, renderer};
 
 /// Event context for one individual Alacritty window.
@@ -50,21 +65,18 @@ pub struct WindowContext {
     pub display: Display,
     pub dirty: bool,
     event_queue: Vec<WinitEvent<Event>>,
-    terminal: Arc<FairMutex<Term<EventProxy>>>,
+    pane: Pane,
+    panes: HashMap<PaneId, Pane>,
     cursor_blink_timed_out: bool,
     prev_bell_cmd: Option<Instant>,
+    notification_limiter: RateLimiter,
     modifiers: Modifiers,
-    inline_search_state: InlineSearchState,
-    search_state: SearchState,
-    notifier: Notifier,
     mouse: Mouse,
     touch: TouchPurpose,
+    cursor_position: PhysicalPosition<f64>,
+    divider_drag: Option<Divider>,
     occluded: bool,
     preserve_title: bool,
-    #[cfg(not(windows))]
-    master_fd: RawFd,
-    #[cfg(not(windows))]
-    shell_pid: u32,
     window_config: ParsedOptions,
     config: Rc<UiConfig>,
 }
@@ -76,6 +88,8 @@ impl WindowContext {
         proxy: EventLoopProxy<Event>,
         config: Rc<UiConfig>,
         mut options: WindowOptions,
+        replay: Option<ReplayOptions>,
+        saved_window: Option<SavedWindow>,
     ) -> Result<Self, Box<dyn Error>> {
         let raw_display_handle = event_loop.display_handle().unwrap().as_raw();
 
@@ -92,12 +106,17 @@ impl WindowContext {
         #[cfg(not(windows))]
         let raw_window_handle = None;
 
-        let gl_display = renderer::platform::create_gl_display(
-            raw_display_handle,
-            raw_window_handle,
-            config.debug.prefer_egl,
-        )?;
-        let gl_config = renderer::platform::pick_gl_config(&gl_display, raw_window_handle)?;
+        let platform = if config.debug.renderer == Some(RendererPreference::Software) {
+            GraphicsPlatform::Software
+        } else {
+            let gl_display = renderer::platform::create_gl_display(
+                raw_display_handle,
+                raw_window_handle,
+                config.debug.prefer_egl,
+            )?;
+            let gl_config = renderer::platform::pick_gl_config(&gl_display, raw_window_handle)?;
+            GraphicsPlatform::Gl(gl_config)
+        };
 
         #[cfg(not(windows))]
         let window = Window::new(
@@ -106,29 +125,90 @@ impl WindowContext {
             &identity,
             &mut options,
             #[cfg(all(feature = "x11", not(any(target_os = "macos", windows))))]
-            gl_config.x11_visual(),
+            platform.x11_visual(),
         )?;
 
         // Create context.
-        let gl_context =
-            renderer::platform::create_gl_context(&gl_display, &gl_config, raw_window_handle)?;
+        let gl_context = platform.create_context(raw_window_handle)?;
 
         let display = Display::new(window, gl_context, &config, false)?;
 
-        Self::new(display, config, options, proxy)
+        Self::new(display, config, options, proxy, replay, saved_window)
     }
 
     /// Create additional context with the graphics platform other windows are using.
     pub fn additional(
-        gl_config: &GlutinConfig,
+        platform: &GraphicsPlatform,
         event_loop: &ActiveEventLoop,
         proxy: EventLoopProxy<Event>,
         config: Rc<UiConfig>,
         mut options: WindowOptions,
         config_overrides: ParsedOptions,
+        saved_window: Option<SavedWindow>,
     ) -> Result<Self, Box<dyn Error>> {
-        let gl_display = gl_config.display();
+        let display = Self::additional_display(platform, event_loop, &config, &mut options)?;
+
+        let mut window_context = Self::new(display, config, options, proxy, None, saved_window)?;
+
+        // Set the config overrides at startup.
+        //
+        // These are already applied to `config`, so no update is necessary.
+        window_context.window_config = config_overrides;
+
+        Ok(window_context)
+    }
 
+    /// Create additional context around the terminal of a detached pane.
+    #[cfg(unix)]
+    pub fn attach(
+        platform: &GraphicsPlatform,
+        event_loop: &ActiveEventLoop,
+        config: Rc<UiConfig>,
+        sessions: &mut HashMap<PaneId, Pane>,
+        session_id: PaneId,
+    ) -> Result<Self, Box<dyn Error>> {
+        let mut options = WindowOptions::default();
+        let display = Self::additional_display(platform, event_loop, &config, &mut options)?;
+
+        // Only take the pane once its window was created, to keep it detached on failure.
+        let mut pane = sessions.remove(&session_id).ok_or("detached session is gone")?;
+
+        // Send all terminal events to the new window.
+        pane.event_proxy.set_window_id(display.window.id());
+
+        // Resize the existing terminal to the new window.
+        let size_info = display.size_info;
+        let mut terminal = pane.terminal.lock();
+        if size_info.screen_lines() != terminal.screen_lines()
+            || size_info.columns() != terminal.columns()
+        {
+            pane.notifier.on_resize(size_info.into());
+            terminal.resize(size_info);
+        }
+        terminal.set_window_size(size_info.into());
+
+        // Reapply the config, which also restores the terminal's title.
+        terminal.set_options(config.term_options());
+        drop(terminal);
+
+        pane.size_info = size_info;
+
+        let mut window_context = Self::with_pane(display, config, false, pane);
+
+        // Update cursor blinking for the new window.
+        let event = Event::new(TerminalEvent::CursorBlinkingChange.into(), None);
+        window_context.event_queue.push(event.into());
+
+        Ok(window_context)
+    }
+
+    /// Create the display for a window using an existing graphics platform.
+    fn additional_display(
+        platform: &GraphicsPlatform,
+        event_loop: &ActiveEventLoop,
+        config: &UiConfig,
+        options: &mut WindowOptions,
+    ) -> Result<Display, Box<dyn Error>> {
         let mut identity = config.window.identity.clone();
         options.window_identity.override_identity_config(&mut identity);
 
@@ -141,36 +221,27 @@ impl WindowContext {
 
         let window = Window::new(
             event_loop,
-            &config,
+            config,
             &identity,
-            &mut options,
+            options,
             #[cfg(all(feature = "x11", not(any(target_os = "macos", windows))))]
-            gl_config.x11_visual(),
+            platform.x11_visual(),
         )?;
 
         // Create context.
-        let raw_window_handle = window.raw_window_handle();
-        let gl_context =
-            renderer::platform::create_gl_context(&gl_display, gl_config, Some(raw_window_handle))?;
-
-        let display = Display::new(window, gl_context, &config, tabbed)?;
-
-        let mut window_context = Self::new(display, config, options, proxy)?;
-
-        // Set the config overrides at startup.
-        //
-        // These are already applied to `config`, so no update is necessary.
-        window_context.window_config = config_overrides;
+        let gl_context = platform.create_context(Some(window.raw_window_handle()))?;
 
-        Ok(window_context)
+        Ok(Display::new(window, gl_context, config, tabbed)?)
     }
 
     /// Create a new terminal window context.
     fn new(
-        display: Display,
+        mut display: Display,
         config: Rc<UiConfig>,
         options: WindowOptions,
         proxy: EventLoopProxy<Event>,
+        replay: Option<ReplayOptions>,
+        saved_window: Option<SavedWindow>,
     ) -> Result<Self, Box<dyn Error>> {
         let mut pty_config = config.pty_config();
         options.terminal_options.override_pty_config(&mut pty_config);
@@ -183,80 +254,201 @@ impl WindowContext {
             display.size_info.columns()
         );
 
-        let event_proxy = EventProxy::new(proxy, display.window.id());
+        // Restore the window's title and scrollback from the previous session.
+        let mut history = Vec::new();
+        let mut dimensions = None;
+        if let Some(saved_window) = saved_window {
+            display.window.set_title(saved_window.title.clone());
+            dimensions = Some(config::window::Dimensions {
+                columns: saved_window.columns,
+                lines: saved_window.lines,
+            });
+            history = saved_window.history(display.size_info.columns());
+        }
+
+        let id = display.layout.focused();
+        let size_info = display.size_info;
+        let mut pane = Self::spawn_pane(
+            &display,
+            &config,
+            &pty_config,
+            id,
+            size_info,
+            proxy,
+            replay,
+            history,
+        )?;
+
+        // Start recording the session.
+        if let Some(path) = options.terminal_options.record {
+            pane.recording.start(path);
+        }
+
+        let mut window_context = Self::with_pane(display, config, preserve_title, pane);
+
+        // Restore the window's dimensions from the previous session.
+        if let Some(dimensions) = dimensions {
+            let event = Event::new(EventType::ResizeTerminal(dimensions), None);
+            window_context.event_queue.push(event.into());
+        }
+
+        Ok(window_context)
+    }
+
+    /// Create context for the Alacritty window around its initial pane.
+    fn with_pane(
+        mut display: Display,
+        config: Rc<UiConfig>,
+        preserve_title: bool,
+        pane: Pane,
+    ) -> Self {
+        display.layout = Layout::new(pane.id);
+
+        WindowContext {
+            preserve_title,
+            display,
+            config,
+            pane,
+            panes: Default::default(),
+            cursor_blink_timed_out: Default::default(),
+            prev_bell_cmd: Default::default(),
+            notification_limiter: Default::default(),
+            message_buffer: Default::default(),
+            window_config: Default::default(),
+            cursor_position: Default::default(),
+            divider_drag: Default::default(),
+            event_queue: Default::default(),
+            modifiers: Default::default(),
+            occluded: Default::default(),
+            mouse: Default::default(),
+            touch: Default::default(),
+            dirty: Default::default(),
+        }
+    }
+
+    /// Create a new terminal inside a pane.
+    #[allow(clippy::too_many_arguments)]
+    fn spawn_pane(
+        display: &Display,
+        config: &UiConfig,
+        pty_config: &PtyOptions,
+        id: PaneId,
+        size_info: SizeInfo,
+        proxy: EventLoopProxy<Event>,
+        replay: Option<ReplayOptions>,
+        history: Vec<Row<Cell>>,
+    ) -> Result<Pane, Box<dyn Error>> {
+        let event_proxy = EventProxy::new(proxy, display.window.id(), id);
 
         // Create the terminal.
         //
         // This object contains all of the state about what's being displayed. It's
         // wrapped in a clonable mutex since both the I/O loop and display need to
         // access it.
-        let terminal = Term::new(config.term_options(), &display.size_info, event_proxy.clone());
-        let terminal = Arc::new(FairMutex::new(terminal));
+        let mut terminal = Term::new(config.term_options(), &size_info, event_proxy.clone());
+        terminal.set_window_size(size_info.into());
 
-        // Create the PTY.
-        //
-        // The PTY forks a process to run the shell on the slave side of the
-        // pseudoterminal. A file descriptor for the master side is retained for
-        // reading/writing to the shell.
-        let pty = tty::new(&pty_config, display.size_info.into(), display.window.id().into())?;
+        // Insert restored history before the shell can write to the terminal.
+        if !history.is_empty() {
+            terminal.restore_history(history);
+        }
+        let terminal = Arc::new(FairMutex::new(terminal));
 
+        // Without a PTY, fall back to Alacritty's own working directory.
         #[cfg(not(windows))]
-        let master_fd = pty.file().as_raw_fd();
-        #[cfg(not(windows))]
-        let shell_pid = pty.child().id();
+        let (mut master_fd, mut shell_pid) = (-1, std::process::id());
 
-        // Create the pseudoterminal I/O loop.
-        //
-        // PTY I/O is ran on another thread as to not occupy cycles used by the
-        // renderer and input processing. Note that access to the terminal state is
-        // synchronized since the I/O loop updates the state, and the display
-        // consumes it periodically.
-        let event_loop = PtyEventLoop::new(
-            Arc::clone(&terminal),
-            event_proxy.clone(),
-            pty,
-            pty_config.drain_on_exit,
-            config.debug.ref_test,
-        )?;
+        let loop_tx = if let Some(replay) = replay {
+            // Replay the recording in place of the PTY.
+            Self::spawn_replay(&terminal, &event_proxy, replay)?
+        } else {
+            // Create the PTY.
+            //
+            // The PTY forks a process to run the shell on the slave side of the
+            // pseudoterminal. A file descriptor for the master side is retained for
+            // reading/writing to the shell.
+            let pty = tty::new(pty_config, size_info.into(), display.window.id().into())?;
 
-        // The event loop channel allows write requests from the event processor
-        // to be sent to the pty loop and ultimately written to the pty.
-        let loop_tx = event_loop.channel();
+            #[cfg(not(windows))]
+            {
+                master_fd = pty.file().as_raw_fd();
+                shell_pid = pty.child().id();
+            }
 
-        // Kick off the I/O thread.
-        let _io_thread = event_loop.spawn();
+            // Create the pseudoterminal I/O loop.
+            //
+            // PTY I/O is ran on another thread as to not occupy cycles used by the
+            // renderer and input processing. Note that access to the terminal state is
+            // synchronized since the I/O loop updates the state, and the display
+            // consumes it periodically.
+            let event_loop = PtyEventLoop::new(
+                Arc::clone(&terminal),
+                event_proxy.clone(),
+                pty,
+                pty_config.drain_on_exit,
+                config.debug.ref_test,
+            )?;
+
+            // The event loop channel allows write requests from the event processor
+            // to be sent to the pty loop and ultimately written to the pty.
+            let loop_tx = event_loop.channel();
+
+            // Kick off the I/O thread.
+            let _io_thread = event_loop.spawn();
+
+            loop_tx
+        };
 
         // Start cursor blinking, in case `Focused` isn't sent on startup.
         if config.cursor.style().blinking {
             event_proxy.send_event(TerminalEvent::CursorBlinkingChange.into());
         }
 
-        // Create context for the Alacritty window.
-        Ok(WindowContext {
-            preserve_title,
+        Ok(Pane {
+            id,
             terminal,
-            display,
+            size_info,
             #[cfg(not(windows))]
             master_fd,
             #[cfg(not(windows))]
             shell_pid,
-            config,
+            recording: Recording::new(loop_tx.clone()),
             notifier: Notifier(loop_tx),
-            cursor_blink_timed_out: Default::default(),
-            prev_bell_cmd: Default::default(),
+            #[cfg(unix)]
+            event_proxy,
             inline_search_state: Default::default(),
-            message_buffer: Default::default(),
-            window_config: Default::default(),
             search_state: Default::default(),
-            event_queue: Default::default(),
-            modifiers: Default::default(),
-            occluded: Default::default(),
-            mouse: Default::default(),
-            touch: Default::default(),
-            dirty: Default::default(),
         })
     }
 
+    /// Spawn a thread replaying a recording into the terminal.
+    fn spawn_replay(
+        terminal: &Arc<FairMutex<Term<EventProxy>>>,
+        event_proxy: &EventProxy,
+        replay: ReplayOptions,
+    ) -> Result<EventLoopSender, Box<dyn Error>> {
+        let records = recording::load(&replay.file)?;
+
+        // Resize the window to match the recording's dimensions.
+        let resize_proxy = event_proxy.clone();
+        let on_resize = Box::new(move |columns, lines| {
+            let dimensions = config::window::Dimensions { columns, lines };
+            resize_proxy.send_event(EventType::ResizeTerminal(dimensions));
+        });
+
+        let replay = Replay::new(
+            Arc::clone(terminal),
+            event_proxy.clone(),
+            records,
+            replay.speed,
+            on_resize,
+        )?;
+        let loop_tx = replay.channel();
+        let _replay_thread = replay.spawn();
+
+        Ok(loop_tx)
+    }
+
     /// Update the terminal window to the latest config.
     pub fn update_config(&mut self, new_config: Rc<UiConfig>) {
         let old_config = mem::replace(&mut self.config, new_config);
@@ -265,7 +457,10 @@ impl WindowContext {
         self.config = self.window_config.override_config_rc(self.config.clone());
 
         self.display.update_config(&self.config);
-        self.terminal.lock().set_options(self.config.term_options());
+        self.pane.terminal.lock().set_options(self.config.term_options());
+        for pane in self.panes.values() {
+            pane.terminal.lock().set_options(self.config.term_options());
+        }
 
         // Reload cursor if its thickness has changed.
         if (old_config.cursor.thickness() - self.config.cursor.thickness()).abs() > f32::EPSILON {
@@ -338,6 +533,82 @@ impl WindowContext {
         &self.config
     }
 
+    /// Get the state of this window for restoring it on the next start.
+    ///
+    /// Only the focused pane is saved, since the pane layout is not persisted.
+    pub fn saved_window(&self) -> SavedWindow {
+        let size_info = &self.display.size_info;
+        let title = self.display.window.title().into();
+
+        #[cfg(unix)]
+        let working_directory = self.working_directory();
+        #[cfg(not(unix))]
+        let working_directory = None;
+
+        let terminal = self.pane.terminal.lock();
+        SavedWindow::new(
+            &terminal,
+            title,
+            size_info.columns(),
+            size_info.screen_lines(),
+            working_directory,
+        )
+    }
+
+    /// Get the working directory of the shell.
+    #[cfg(unix)]
+    pub fn working_directory(&self) -> Option<PathBuf> {
+        let pane = &self.pane;
+        let terminal = pane.terminal.lock();
+        daemon::working_directory(terminal.working_directory(), pane.master_fd, pane.shell_pid)
+    }
+
+    /// Write text to the focused terminal's shell.
+    #[cfg(unix)]
+    pub fn send_text(&self, text: String) {
+        self.pane.notifier.notify(text.into_bytes());
+    }
+
+    /// Get the text of the focused terminal.
+    #[cfg(unix)]
+    pub fn text(&self, region: Region, format: Format) -> Option<String> {
+        let terminal = self.pane.terminal.lock();
+        export::export(&terminal, &self.config, region, format)
+    }
+
+    /// Check if the window has keyboard focus.
+    #[cfg(unix)]
+    pub fn is_focused(&self) -> bool {
+        self.pane.terminal.lock().is_focused
+    }
+
+    /// Check if the window is hidden, which stops it from being drawn.
+    #[cfg(unix)]
+    pub fn is_occluded(&self) -> bool {
+        self.occluded
+    }
+
+    /// Get the description of this window for IPC.
+    #[cfg(unix)]
+    pub fn info(&self) -> ipc::WindowInfo {
+        let size_info = &self.display.size_info;
+        let focused = self.is_focused();
+        let foreground_process =
+            daemon::foreground_process_name(self.pane.master_fd, self.pane.shell_pid).ok();
+
+        ipc::WindowInfo {
+            id: self.id().into(),
+            title: self.display.window.title().into(),
+            columns: size_info.columns(),
+            lines: size_info.screen_lines(),
+            width: size_info.window_width() as u32,
+            height: size_info.window_height() as u32,
+            working_directory: self.working_directory(),
+            foreground_process,
+            focused,
+        }
+    }
+
     /// Clear the window config overrides.
     #[cfg(unix)]
     pub fn reset_window_config(&mut self, config: Rc<UiConfig>) {
@@ -386,14 +657,25 @@ impl WindowContext {
             }
         }
 
+        // Collect the visible unfocused panes.
+        let (width, height) =
+            (self.display.size_info.window_width(), self.display.size_info.window_height());
+        let visible = self.display.layout.panes(width, height, self.display.pane_gap());
+        let mut panes: Vec<_> = self
+            .panes
+            .values_mut()
+            .filter(|pane| visible.iter().any(|(id, _)| *id == pane.id))
+            .collect();
+
         // Redraw the window.
-        let terminal = self.terminal.lock();
+        let terminal = self.pane.terminal.lock();
         self.display.draw(
             terminal,
             scheduler,
             &self.message_buffer,
             &self.config,
-            &mut self.search_state,
+            &mut self.pane.search_state,
+            &mut panes,
         );
     }
 
@@ -403,6 +685,7 @@ impl WindowContext {
         #[cfg(target_os = "macos")] event_loop: &ActiveEventLoop,
         event_proxy: &EventLoopProxy<Event>,
         clipboard: &mut Clipboard,
+        macros: &mut Macros,
         scheduler: &mut Scheduler,
         event: WinitEvent<Event>,
     ) {
@@ -422,57 +705,138 @@ impl WindowContext {
             },
         }
 
-        let mut terminal = self.terminal.lock();
-
-        let old_is_searching = self.search_state.history_index.is_some();
+        let old_is_searching = self.pane.search_state.history_index.is_some();
+        #[cfg(unix)]
+        let old_is_vi_mode = self.pane.terminal.lock().mode().contains(TermMode::VI);
+
+        // Process events in batches, interrupted by events changing the focused pane.
+        let mut batch = Vec::new();
+        for event in mem::take(&mut self.event_queue) {
+            let event = match event {
+                WinitEvent::UserEvent(event) => match (event.payload(), event.pane_id()) {
+                    (EventType::Pane(action), _) => {
+                        let action = *action;
+                        self.process_events(
+                            #[cfg(target_os = "macos")]
+                            event_loop,
+                            event_proxy,
+                            clipboard,
+                            macros,
+                            scheduler,
+                            mem::take(&mut batch),
+                        );
+                        self.pane_action(event_proxy, action);
+                        continue;
+                    },
+                    // Process events of unfocused panes with the pane temporarily focused.
+                    (_, Some(id)) if id != self.pane.id => {
+                        let pane = match self.panes.remove(&id) {
+                            Some(pane) => pane,
+                            None => continue,
+                        };
+
+                        self.process_events(
+                            #[cfg(target_os = "macos")]
+                            event_loop,
+                            event_proxy,
+                            clipboard,
+                            macros,
+                            scheduler,
+                            mem::take(&mut batch),
+                        );
+
+                        let focused = self.swap_pane(pane);
+                        let preserve_title = mem::replace(&mut self.preserve_title, true);
+                        self.process_events(
+                            #[cfg(target_os = "macos")]
+                            event_loop,
+                            event_proxy,
+                            clipboard,
+                            macros,
+                            scheduler,
+                            vec![WinitEvent::UserEvent(event)],
+                        );
+                        self.preserve_title = preserve_title;
+                        let pane = self.swap_pane(focused);
+                        self.panes.insert(pane.id, pane);
+
+                        continue;
+                    },
+                    _ => WinitEvent::UserEvent(event),
+                },
+                WinitEvent::WindowEvent { window_id, event } => {
+                    match self.pane_mouse_input(event) {
+                        Ok(event) => WinitEvent::WindowEvent { window_id, event },
+                        Err(Some(id)) => {
+                            self.process_events(
+                                #[cfg(target_os = "macos")]
+                                event_loop,
+                                event_proxy,
+                                clipboard,
+                                macros,
+                                scheduler,
+                                mem::take(&mut batch),
+                            );
+                            self.focus_pane(id);
+                            continue;
+                        },
+                        Err(None) => continue,
+                    }
+                },
+                event => event,
+            };
+
+            batch.push(event);
+        }
 
-        let context = ActionContext {
-            cursor_blink_timed_out: &mut self.cursor_blink_timed_out,
-            prev_bell_cmd: &mut self.prev_bell_cmd,
-            message_buffer: &mut self.message_buffer,
-            inline_search_state: &mut self.inline_search_state,
-            search_state: &mut self.search_state,
-            modifiers: &mut self.modifiers,
-            notifier: &mut self.notifier,
-            display: &mut self.display,
-            mouse: &mut self.mouse,
-            touch: &mut self.touch,
-            dirty: &mut self.dirty,
-            occluded: &mut self.occluded,
-            terminal: &mut terminal,
-            #[cfg(not(windows))]
-            master_fd: self.master_fd,
-            #[cfg(not(windows))]
-            shell_pid: self.shell_pid,
-            preserve_title: self.preserve_title,
-            config: &self.config,
-            event_proxy,
+        self.process_events(
             #[cfg(target_os = "macos")]
             event_loop,
+            event_proxy,
             clipboard,
+            macros,
             scheduler,
-        };
-        let mut processor = input::Processor::new(context);
-
-        for event in self.event_queue.drain(..) {
-            processor.handle_event(event);
-        }
+            batch,
+        );
 
         // Process DisplayUpdate events.
         if self.display.pending_update.dirty {
+            let mut terminal = self.pane.terminal.lock();
             Self::submit_display_update(
                 &mut terminal,
                 &mut self.display,
-                &mut self.notifier,
+                &mut self.pane.notifier,
                 &self.message_buffer,
-                &mut self.search_state,
+                &mut self.pane.search_state,
                 old_is_searching,
                 &self.config,
             );
+            drop(terminal);
+
+            self.resize_panes();
             self.dirty = true;
         }
 
+        // Notify IPC subscribers about search and vi mode changes.
+        #[cfg(unix)]
+        {
+            let window_id = self.id().into();
+
+            let active = self.pane.search_state.history_index.is_some();
+            if active != old_is_searching {
+                let event = EventType::IpcEvent(IpcEvent::Search { window_id, active });
+                let _ = event_proxy.send_event(Event::new(event, None));
+            }
+
+            let active = self.pane.terminal.lock().mode().contains(TermMode::VI);
+            if active != old_is_vi_mode {
+                let event = EventType::IpcEvent(IpcEvent::ViMode { window_id, active });
+                let _ = event_proxy.send_event(Event::new(event, None));
+            }
+        }
+
         if self.dirty || self.mouse.hint_highlight_dirty {
+            let terminal = self.pane.terminal.lock();
             self.dirty |= self.display.update_highlighted_hints(
                 &terminal,
                 &self.config,
@@ -493,6 +857,344 @@ impl WindowContext {
         }
     }
 
+    /// Process events for the focused pane.
+    fn process_events(
+        &mut self,
+        #[cfg(target_os = "macos")] event_loop: &ActiveEventLoop,
+        event_proxy: &EventLoopProxy<Event>,
+        clipboard: &mut Clipboard,
+        macros: &mut Macros,
+        scheduler: &mut Scheduler,
+        events: Vec<WinitEvent<Event>>,
+    ) {
+        if events.is_empty() {
+            return;
+        }
+
+        let pane = &mut self.pane;
+        let mut terminal = pane.terminal.lock();
+
+        let context = ActionContext {
+            cursor_blink_timed_out: &mut self.cursor_blink_timed_out,
+            prev_bell_cmd: &mut self.prev_bell_cmd,
+            notification_limiter: &mut self.notification_limiter,
+            recording: &mut pane.recording,
+            message_buffer: &mut self.message_buffer,
+            inline_search_state: &mut pane.inline_search_state,
+            search_state: &mut pane.search_state,
+            modifiers: &mut self.modifiers,
+            notifier: &mut pane.notifier,
+            display: &mut self.display,
+            mouse: &mut self.mouse,
+            touch: &mut self.touch,
+            dirty: &mut self.dirty,
+            occluded: &mut self.occluded,
+            terminal: &mut terminal,
+            #[cfg(not(windows))]
+            master_fd: pane.master_fd,
+            #[cfg(not(windows))]
+            shell_pid: pane.shell_pid,
+            preserve_title: self.preserve_title,
+            config: &self.config,
+            event_proxy,
+            #[cfg(target_os = "macos")]
+            event_loop,
+            clipboard,
+            macros,
+            scheduler,
+        };
+        let mut processor = input::Processor::new(context);
+
+        for event in events {
+            processor.handle_event(event);
+        }
+    }
+
+    /// Handle mouse input affecting the panes.
+    ///
+    /// Events are translated to the focused pane's coordinates. If the event was consumed, the
+    /// pane which should receive focus is returned instead.
+    fn pane_mouse_input(&mut self, event: WindowEvent) -> Result<WindowEvent, Option<PaneId>> {
+        let size_info = self.display.size_info;
+        let (width, height) = (size_info.window_width(), size_info.window_height());
+        let gap = self.display.pane_gap();
+        let (x, y) = (self.cursor_position.x as f32, self.cursor_position.y as f32);
+
+        match event {
+            WindowEvent::CursorMoved { device_id, mut position } => {
+                self.cursor_position = position;
+
+                // Move the divider while it's being dragged.
+                if let Some(divider) = &self.divider_drag {
+                    let (x, y) = (position.x as f32, position.y as f32);
+                    self.display.layout.drag_divider(divider, x, y, width, height, gap);
+                    self.display.pending_update.dirty = true;
+                    return Err(None);
+                }
+
+                position.x -= f64::from(size_info.x());
+                position.y -= f64::from(size_info.y());
+
+                Ok(WindowEvent::CursorMoved { device_id, position })
+            },
+            WindowEvent::MouseInput { state, button: MouseButton::Left, .. }
+                if self.display.layout.is_split() =>
+            {
+                if state == ElementState::Released {
+                    return match self.divider_drag.take() {
+                        Some(_) => Err(None),
+                        None => Ok(event),
+                    };
+                }
+
+                if let Some(divider) = self.display.layout.divider_at(x, y, width, height, gap) {
+                    self.divider_drag = Some(divider);
+                    return Err(None);
+                }
+
+                // Clicking on another pane moves focus to it.
+                match self.display.layout.pane_at(x, y, width, height, gap) {
+                    Some(id) if id != self.pane.id => Err(Some(id)),
+                    _ => Ok(event),
+                }
+            },
+            WindowEvent::Touch(mut touch) => {
+                touch.location.x -= f64::from(size_info.x());
+                touch.location.y -= f64::from(size_info.y());
+                Ok(WindowEvent::Touch(touch))
+            },
+            event => Ok(event),
+        }
+    }
+
+    /// Perform an operation on the panes.
+    fn pane_action(&mut self, event_proxy: &EventLoopProxy<Event>, action: PaneAction) {
+        let size_info = self.display.size_info;
+        let (width, height) = (size_info.window_width(), size_info.window_height());
+
+        match action {
+            PaneAction::Split(split) => self.split_pane(event_proxy, split),
+            PaneAction::Focus(direction) => {
+                if let Some(id) = self.display.layout.neighbor(direction, width, height) {
+                    self.focus_pane(id);
+                }
+            },
+            PaneAction::Resize(direction) => {
+                let amount = match direction.split() {
+                    Split::Horizontal => size_info.cell_width(),
+                    Split::Vertical => size_info.cell_height(),
+                };
+                let gap = self.display.pane_gap();
+                self.display.layout.resize(direction, amount, width, height, gap);
+                self.display.pending_update.dirty = true;
+            },
+            PaneAction::Close if self.display.layout.is_split() => self.remove_pane(self.pane.id),
+            PaneAction::Close => {
+                // Closing the last pane closes the window.
+                self.display.window.hold = false;
+                self.pane.terminal.lock().exit();
+            },
+            PaneAction::Zoom => {
+                self.display.layout.toggle_zoom();
+                self.display.pending_update.dirty = true;
+            },
+        }
+    }
+
+    /// Split the focused pane and start a new terminal inside it.
+    fn split_pane(&mut self, event_proxy: &EventLoopProxy<Event>, split: Split) {
+        #[allow(unused_mut)]
+        let mut pty_config = self.config.pty_config();
+
+        // Start the new shell in the focused terminal's working directory.
+        #[cfg(unix)]
+        {
+            pty_config.working_directory = self.working_directory();
+        }
+
+        let layout = self.display.layout.clone();
+        let focused = layout.focused();
+        let id = self.display.layout.split(split);
+
+        // Refuse splits which leave no space for the terminal grid of either pane.
+        let size_info = self.display.pane_size_info(&self.config, id);
+        let focused_size_info = self.display.pane_size_info(&self.config, focused);
+        let size_info = match (size_info, focused_size_info) {
+            (Some(size_info), Some(focused_size_info))
+                if size_info.fits_grid() && focused_size_info.fits_grid() =>
+            {
+                size_info
+            },
+            _ => {
+                warn!("Window is too small to split pane");
+                self.display.layout = layout;
+                return;
+            },
+        };
+
+        let proxy = event_proxy.clone();
+        let pane = Self::spawn_pane(
+            &self.display,
+            &self.config,
+            &pty_config,
+            id,
+            size_info,
+            proxy,
+            None,
+            Vec::new(),
+        );
+        match pane {
+            Ok(pane) => {
+                self.panes.insert(id, pane);
+                self.focus_pane(id);
+            },
+            Err(err) => {
+                error!("Could not create pane: {err}");
+                self.display.layout = layout;
+            },
+        }
+    }
+
+    /// Move focus to another pane.
+    fn focus_pane(&mut self, id: PaneId) {
+        let pane = match self.panes.remove(&id) {
+            Some(pane) => pane,
+            None => return,
+        };
+
+        self.display.layout.focus(id);
+
+        let pane = self.activate_pane(pane);
+        self.panes.insert(pane.id, pane);
+    }
+
+    /// Close a pane after its terminal exited.
+    ///
+    /// Returns `false` if the entire window should be closed instead.
+    pub fn close_pane(&mut self, id: Option<PaneId>) -> bool {
+        let id = match id {
+            Some(id) => id,
+            None => return false,
+        };
+
+        // Ignore panes which were already closed.
+        if id != self.pane.id && !self.panes.contains_key(&id) {
+            return true;
+        }
+
+        if !self.display.layout.is_split() || self.display.window.hold {
+            return false;
+        }
+
+        self.remove_pane(id);
+
+        true
+    }
+
+    /// Close the window without shutting down its terminals.
+    #[cfg(unix)]
+    pub fn detach(self) -> Vec<Pane> {
+        let WindowContext { mut pane, panes, display, .. } = self;
+
+        // Keep the focused pane's size, to answer size queries while it's detached.
+        pane.size_info = display.size_info;
+
+        let panes: Vec<_> = iter::once(pane).chain(panes.into_values()).collect();
+        for pane in &panes {
+            Self::set_pane_focus(pane, false);
+        }
+        panes
+    }
+
+    /// Remove a pane, shutting down its terminal.
+    fn remove_pane(&mut self, id: PaneId) {
+        if !self.display.layout.remove(id) {
+            return;
+        }
+
+        if id == self.pane.id {
+            let focused = self.display.layout.focused();
+            if let Some(pane) = self.panes.remove(&focused) {
+                drop(self.activate_pane(pane));
+            }
+        } else {
+            self.panes.remove(&id);
+        }
+
+        self.display.pending_update.dirty = true;
+        self.dirty = true;
+    }
+
+    /// Replace the focused pane, returning the previously focused pane.
+    fn activate_pane(&mut self, pane: Pane) -> Pane {
+        let is_focused = self.pane.terminal.lock().is_focused;
+
+        let pane = self.swap_pane(pane);
+        Self::set_pane_focus(&pane, false);
+        Self::set_pane_focus(&self.pane, is_focused);
+
+        // Reset UI state belonging to the previously focused pane.
+        let size_info = self.display.size_info;
+        self.display.damage_tracker.resize(size_info.screen_lines(), size_info.columns());
+        self.display.highlighted_hint = None;
+        self.display.vi_highlighted_hint = None;
+        self.display.cursor_hidden = false;
+        self.divider_drag = None;
+
+        // Update cursor blinking for the new terminal.
+        let event = Event::new(TerminalEvent::CursorBlinkingChange.into(), None);
+        self.event_queue.push(event.into());
+
+        self.display.pending_update.dirty = true;
+        self.dirty = true;
+
+        pane
+    }
+
+    /// Swap the focused pane with another one.
+    fn swap_pane(&mut self, mut pane: Pane) -> Pane {
+        mem::swap(&mut self.pane, &mut pane);
+        pane.size_info = mem::replace(&mut self.display.size_info, self.pane.size_info);
+        pane
+    }
+
+    /// Update a pane's terminal focus, reporting the change to the application.
+    fn set_pane_focus(pane: &Pane, is_focused: bool) {
+        let mut terminal = pane.terminal.lock();
+        if terminal.is_focused == is_focused {
+            return;
+        }
+
+        terminal.is_focused = is_focused;
+
+        if terminal.mode().contains(TermMode::FOCUS_IN_OUT) {
+            let chr = if is_focused { "I" } else { "O" };
+            pane.notifier.notify(format!("\x1b[{chr}").into_bytes());
+        }
+    }
+
+    /// Resize the unfocused panes to the current layout.
+    fn resize_panes(&mut self) {
+        for pane in self.panes.values_mut() {
+            let size_info = match self.display.pane_size_info(&self.config, pane.id) {
+                Some(size_info) if size_info != pane.size_info => size_info,
+                _ => continue,
+            };
+
+            let mut terminal = pane.terminal.lock();
+            if size_info.screen_lines() != pane.size_info.screen_lines()
+                || size_info.columns() != pane.size_info.columns()
+            {
+                pane.notifier.on_resize(size_info.into());
+                terminal.resize(size_info);
+            }
+            terminal.set_window_size(size_info.into());
+            drop(terminal);
+
+            pane.size_info = size_info;
+        }
+    }
+
     /// ID of this terminal context.
     pub fn id(&self) -> WindowId {
         self.display.window.id()
@@ -501,7 +1203,7 @@ impl WindowContext {
     /// Write the ref test results to the disk.
     pub fn write_ref_test_results(&self) {
         // Dump grid state.
-        let mut grid = self.terminal.lock().grid().clone();
+        let mut grid = self.pane.terminal.lock().grid().clone();
         grid.initialize_all();
         grid.truncate();
 
@@ -559,10 +1261,3 @@ impl WindowContext {
         }
     }
 }
-
-impl Drop for WindowContext {
-    fn drop(&mut self) {
-        // Shutdown the terminal's PTY.
-        let _ = self.notifier.0.send(Msg::Shutdown);
-    }
-}
@@ -0,0 +1,101 @@
+//! Memory usage and throughput of the scrollback history.
+//!
+//! Run with `cargo bench -p alacritty_terminal --bench scrollback`.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Instant;
+
+use alacritty_terminal::event::VoidListener;
+use alacritty_terminal::grid::{Dimensions, Scroll};
+use alacritty_terminal::index::{Column, Line, Point};
+use alacritty_terminal::term::search::RegexSearch;
+use alacritty_terminal::term::test::TermSize;
+use alacritty_terminal::term::{Config, Term};
+use alacritty_terminal::vte::ansi;
+
+/// Allocator keeping track of the number of allocated bytes.
+struct CountingAllocator;
+
+static ALLOCATED: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATED.fetch_add(layout.size(), Ordering::Relaxed);
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        ALLOCATED.fetch_sub(layout.size(), Ordering::Relaxed);
+        unsafe { System.dealloc(ptr, layout) }
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATED.fetch_add(new_size, Ordering::Relaxed);
+        ALLOCATED.fetch_sub(layout.size(), Ordering::Relaxed);
+        unsafe { System.realloc(ptr, layout, new_size) }
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+const COLUMNS: usize = 120;
+const SCREEN_LINES: usize = 50;
+
+fn main() {
+    for lines in [100_000, 1_000_000] {
+        bench("plain", lines, |i| format!("[{i:>8}] test suite::module::case ... ok\r\n"));
+        bench("colored", lines, |i| {
+            format!("\x1b[32m[{i:>8}]\x1b[0m \x1b[1mcompiling\x1b[0m crate v0.{i}.0\r\n")
+        });
+    }
+}
+
+/// Fill the scrollback history and report its memory usage.
+fn bench(name: &str, lines: usize, line: impl Fn(usize) -> String) {
+    let config = Config { scrolling_history: lines, ..Config::default() };
+    let size = TermSize::new(COLUMNS, SCREEN_LINES);
+
+    let before = ALLOCATED.load(Ordering::Relaxed);
+    let mut term = Term::new(config, &size, VoidListener);
+    let mut parser: ansi::Processor = ansi::Processor::new();
+
+    let start = Instant::now();
+    for i in 0..lines + SCREEN_LINES {
+        parser.advance(&mut term, line(i).as_bytes());
+    }
+    let fill_time = start.elapsed();
+
+    let memory = ALLOCATED.load(Ordering::Relaxed) - before;
+    let history = term.grid().history_size();
+
+    // Read every line of the history once.
+    let start = Instant::now();
+    let grid = term.grid();
+    let mut cells = 0;
+    for line in grid.topmost_line().0..=grid.bottommost_line().0 {
+        cells += grid[Line(line)][Column(0)].c as usize;
+    }
+    let read_time = start.elapsed();
+    std::hint::black_box(cells);
+
+    // Search through the entire history.
+    let mut regex = RegexSearch::new("no match here").unwrap();
+    let start = Instant::now();
+    let origin = Point::new(term.bottommost_line(), Column(0));
+    let end = Point::new(term.topmost_line(), Column(0));
+    std::hint::black_box(term.regex_search_left(&mut regex, origin, end));
+    let search_time = start.elapsed();
+
+    term.scroll_display(Scroll::Bottom);
+
+    println!(
+        "{name:>8} {history:>9} lines: {:>7.1} bytes/line, fill {:>7.1?}, read {:>7.1?}, search \
+         {:>7.1?}",
+        memory as f64 / history as f64,
+        fill_time,
+        read_time,
+        search_time,
+    );
+}
@@ -2,7 +2,7 @@ use std::borrow::Cow;
 use std::fmt::{self, Debug, Formatter};
 use std::sync::Arc;
 
-use crate::term::ClipboardType;
+use crate::term::{ClipboardType, Urgency, WorkingDirectory};
 use crate::vte::ansi::Rgb;
 
 /// Terminal event.
@@ -20,6 +20,9 @@ pub enum Event {
     /// Reset to the default window title.
     ResetTitle,
 
+    /// Working directory change reported by the shell.
+    WorkingDirectory(WorkingDirectory),
+
     /// Request to store a text string in the clipboard.
     ClipboardStore(ClipboardType, String),
 
@@ -50,6 +53,9 @@ pub enum Event {
     /// Terminal bell ring.
     Bell,
 
+    /// Desktop notification request.
+    Notification { title: String, body: String, urgency: Urgency },
+
     /// Shutdown request.
     Exit,
 
@@ -69,8 +75,12 @@ impl Debug for Event {
             Event::CursorBlinkingChange => write!(f, "CursorBlinkingChange"),
             Event::MouseCursorDirty => write!(f, "MouseCursorDirty"),
             Event::ResetTitle => write!(f, "ResetTitle"),
+            Event::WorkingDirectory(directory) => write!(f, "WorkingDirectory({directory:?})"),
             Event::Wakeup => write!(f, "Wakeup"),
             Event::Bell => write!(f, "Bell"),
+            Event::Notification { title, body, urgency } => {
+                write!(f, "Notification({title}, {body}, {urgency:?})")
+            },
             Event::Exit => write!(f, "Exit"),
             Event::ChildExit(code) => write!(f, "ChildExit({code})"),
         }
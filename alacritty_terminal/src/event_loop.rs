@@ -15,10 +15,12 @@ use log::error;
 use polling::{Event as PollingEvent, Events, PollMode};
 
 use crate::event::{self, Event, EventListener, WindowSize};
+use crate::grid::Dimensions;
+use crate::parser::Processor;
+use crate::recording::Recorder;
 use crate::sync::FairMutex;
 use crate::term::Term;
 use crate::{thread, tty};
-use vte::ansi;
 
 /// Max bytes to read from the PTY before forced terminal synchronization.
 pub(crate) const READ_BUFFER_SIZE: usize = 0x10_0000;
@@ -37,6 +39,12 @@ pub enum Msg {
 
     /// Instruction to resize the PTY.
     Resize(WindowSize),
+
+    /// Start recording the terminal output to the specified file.
+    StartRecording(File),
+
+    /// Stop the active recording.
+    StopRecording,
 }
 
 /// The main event loop.
@@ -92,7 +100,34 @@ where
         while let Some(msg) = self.rx.recv() {
             match msg {
                 Msg::Input(input) => state.write_list.push_back(input),
-                Msg::Resize(window_size) => self.pty.on_resize(window_size),
+                Msg::Resize(window_size) => {
+                    self.pty.on_resize(window_size);
+
+                    let size = (window_size.num_cols as usize, window_size.num_lines as usize);
+                    if let Some(recorder) = &mut state.recorder {
+                        if let Err(err) = recorder.resize(size.0, size.1) {
+                            error!("Unable to record resize: {err}");
+                            state.recorder = None;
+                        }
+                    }
+                },
+                Msg::StartRecording(file) => {
+                    let terminal = self.terminal.lock();
+                    let (columns, lines) = (terminal.columns(), terminal.screen_lines());
+                    drop(terminal);
+
+                    match Recorder::new(file, columns, lines) {
+                        Ok(recorder) => state.recorder = Some(recorder),
+                        Err(err) => error!("Unable to start recording: {err}"),
+                    }
+                },
+                Msg::StopRecording => {
+                    if let Some(mut recorder) = state.recorder.take() {
+                        if let Err(err) = recorder.flush() {
+                            error!("Unable to write recording: {err}");
+                        }
+                    }
+                },
                 Msg::Shutdown => return false,
             }
         }
@@ -150,6 +185,14 @@ where
                 writer.write_all(&buf[..unprocessed]).unwrap();
             }
 
+            // Write a copy of the bytes to the active recording.
+            if let Some(recorder) = &mut state.recorder {
+                if let Err(err) = recorder.output(&buf[..unprocessed]) {
+                    error!("Unable to record output: {err}");
+                    state.recorder = None;
+                }
+            }
+
             // Parse the incoming bytes.
             state.parser.advance(&mut **terminal, &buf[..unprocessed]);
 
@@ -162,6 +205,14 @@ where
             }
         }
 
+        // Flush recorded output, so recordings are usable while still in progress.
+        if let Some(recorder) = &mut state.recorder {
+            if let Err(err) = recorder.flush() {
+                error!("Unable to write recording: {err}");
+                state.recorder = None;
+            }
+        }
+
         // Queue terminal redraw unless all processed bytes were synchronized.
         if state.parser.sync_bytes_count() < processed && processed > 0 {
             self.event_proxy.send_event(Event::Wakeup);
@@ -386,6 +437,10 @@ pub struct EventLoopSender {
 }
 
 impl EventLoopSender {
+    pub(crate) fn new(sender: Sender<Msg>, poller: Arc<polling::Poller>) -> Self {
+        Self { sender, poller }
+    }
+
     pub fn send(&self, msg: Msg) -> Result<(), EventLoopSendError> {
         self.sender.send(msg).map_err(EventLoopSendError::Send)?;
         self.poller.notify().map_err(EventLoopSendError::Io)
@@ -400,7 +455,8 @@ impl EventLoopSender {
 pub struct State {
     write_list: VecDeque<Cow<'static, [u8]>>,
     writing: Option<Writing>,
-    parser: ansi::Processor,
+    parser: Processor,
+    recorder: Option<Recorder>,
 }
 
 impl State {
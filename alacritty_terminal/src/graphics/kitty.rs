@@ -0,0 +1,886 @@
+//! Kitty graphics protocol.
+//!
+//! Images are transmitted through `APC G <control data> ; <payload> ST` sequences. Transmitted
+//! images are kept in the [`ImageStore`] until they are deleted or evicted, which allows
+//! displaying them multiple times without transmitting them again.
+
+use std::collections::HashMap;
+use std::fs::{self, OpenOptions};
+use std::io::{Read, Seek, SeekFrom};
+#[cfg(unix)]
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::{env, fmt, io, str};
+
+use base64::Engine;
+use base64::alphabet::STANDARD;
+use base64::engine::{DecodePaddingMode, GeneralPurpose, GeneralPurposeConfig};
+use log::debug;
+use png::{ColorType, Transformations};
+
+use crate::graphics::{GraphicCell, GraphicData, MAX_GRAPHIC_DIMENSIONS, Placement, TextureRef};
+use crate::term::cell::Cell;
+use crate::vte::ansi::Color;
+
+/// Maximum number of bytes used by stored images.
+pub const STORAGE_QUOTA: usize = 320 * 1024 * 1024;
+
+/// Character used for Unicode placeholders.
+pub const PLACEHOLDER: char = '\u{10EEEE}';
+
+/// Base64 engine accepting payloads with and without padding.
+const BASE64: GeneralPurpose = GeneralPurpose::new(
+    &STANDARD,
+    GeneralPurposeConfig::new().with_decode_padding_mode(DecodePaddingMode::Indifferent),
+);
+
+/// Diacritics encoding the row and column of Unicode placeholders.
+///
+/// The index of the diacritic in this table is the encoded number.
+#[rustfmt::skip]
+const DIACRITICS: [char; 297] = [
+    '\u{0305}', '\u{030D}', '\u{030E}', '\u{0310}', '\u{0312}', '\u{033D}', '\u{033E}', '\u{033F}',
+    '\u{0346}', '\u{034A}', '\u{034B}', '\u{034C}', '\u{0350}', '\u{0351}', '\u{0352}', '\u{0357}',
+    '\u{035B}', '\u{0363}', '\u{0364}', '\u{0365}', '\u{0366}', '\u{0367}', '\u{0368}', '\u{0369}',
+    '\u{036A}', '\u{036B}', '\u{036C}', '\u{036D}', '\u{036E}', '\u{036F}', '\u{0483}', '\u{0484}',
+    '\u{0485}', '\u{0486}', '\u{0487}', '\u{0592}', '\u{0593}', '\u{0594}', '\u{0595}', '\u{0597}',
+    '\u{0598}', '\u{0599}', '\u{059C}', '\u{059D}', '\u{059E}', '\u{059F}', '\u{05A0}', '\u{05A1}',
+    '\u{05A8}', '\u{05A9}', '\u{05AB}', '\u{05AC}', '\u{05AF}', '\u{05C4}', '\u{0610}', '\u{0611}',
+    '\u{0612}', '\u{0613}', '\u{0614}', '\u{0615}', '\u{0616}', '\u{0617}', '\u{0657}', '\u{0658}',
+    '\u{0659}', '\u{065A}', '\u{065B}', '\u{065D}', '\u{065E}', '\u{06D6}', '\u{06D7}', '\u{06D8}',
+    '\u{06D9}', '\u{06DA}', '\u{06DB}', '\u{06DC}', '\u{06DF}', '\u{06E0}', '\u{06E1}', '\u{06E2}',
+    '\u{06E4}', '\u{06E7}', '\u{06E8}', '\u{06EB}', '\u{06EC}', '\u{0730}', '\u{0732}', '\u{0733}',
+    '\u{0735}', '\u{0736}', '\u{073A}', '\u{073D}', '\u{073F}', '\u{0740}', '\u{0741}', '\u{0743}',
+    '\u{0745}', '\u{0747}', '\u{0749}', '\u{074A}', '\u{07EB}', '\u{07EC}', '\u{07ED}', '\u{07EE}',
+    '\u{07EF}', '\u{07F0}', '\u{07F1}', '\u{07F3}', '\u{0816}', '\u{0817}', '\u{0818}', '\u{0819}',
+    '\u{081B}', '\u{081C}', '\u{081D}', '\u{081E}', '\u{081F}', '\u{0820}', '\u{0821}', '\u{0822}',
+    '\u{0823}', '\u{0825}', '\u{0826}', '\u{0827}', '\u{0829}', '\u{082A}', '\u{082B}', '\u{082C}',
+    '\u{082D}', '\u{0951}', '\u{0953}', '\u{0954}', '\u{0F82}', '\u{0F83}', '\u{0F86}', '\u{0F87}',
+    '\u{135D}', '\u{135E}', '\u{135F}', '\u{17DD}', '\u{193A}', '\u{1A17}', '\u{1A75}', '\u{1A76}',
+    '\u{1A77}', '\u{1A78}', '\u{1A79}', '\u{1A7A}', '\u{1A7B}', '\u{1A7C}', '\u{1B6B}', '\u{1B6D}',
+    '\u{1B6E}', '\u{1B6F}', '\u{1B70}', '\u{1B71}', '\u{1B72}', '\u{1B73}', '\u{1CD0}', '\u{1CD1}',
+    '\u{1CD2}', '\u{1CDA}', '\u{1CDB}', '\u{1CE0}', '\u{1DC0}', '\u{1DC1}', '\u{1DC3}', '\u{1DC4}',
+    '\u{1DC5}', '\u{1DC6}', '\u{1DC7}', '\u{1DC8}', '\u{1DC9}', '\u{1DCB}', '\u{1DCC}', '\u{1DD1}',
+    '\u{1DD2}', '\u{1DD3}', '\u{1DD4}', '\u{1DD5}', '\u{1DD6}', '\u{1DD7}', '\u{1DD8}', '\u{1DD9}',
+    '\u{1DDA}', '\u{1DDB}', '\u{1DDC}', '\u{1DDD}', '\u{1DDE}', '\u{1DDF}', '\u{1DE0}', '\u{1DE1}',
+    '\u{1DE2}', '\u{1DE3}', '\u{1DE4}', '\u{1DE5}', '\u{1DE6}', '\u{1DFE}', '\u{20D0}', '\u{20D1}',
+    '\u{20D4}', '\u{20D5}', '\u{20D6}', '\u{20D7}', '\u{20DB}', '\u{20DC}', '\u{20E1}', '\u{20E7}',
+    '\u{20E9}', '\u{20F0}', '\u{2CEF}', '\u{2CF0}', '\u{2CF1}', '\u{2DE0}', '\u{2DE1}', '\u{2DE2}',
+    '\u{2DE3}', '\u{2DE4}', '\u{2DE5}', '\u{2DE6}', '\u{2DE7}', '\u{2DE8}', '\u{2DE9}', '\u{2DEA}',
+    '\u{2DEB}', '\u{2DEC}', '\u{2DED}', '\u{2DEE}', '\u{2DEF}', '\u{2DF0}', '\u{2DF1}', '\u{2DF2}',
+    '\u{2DF3}', '\u{2DF4}', '\u{2DF5}', '\u{2DF6}', '\u{2DF7}', '\u{2DF8}', '\u{2DF9}', '\u{2DFA}',
+    '\u{2DFB}', '\u{2DFC}', '\u{2DFD}', '\u{2DFE}', '\u{2DFF}', '\u{A66F}', '\u{A67C}', '\u{A67D}',
+    '\u{A6F0}', '\u{A6F1}', '\u{A8E0}', '\u{A8E1}', '\u{A8E2}', '\u{A8E3}', '\u{A8E4}', '\u{A8E5}',
+    '\u{A8E6}', '\u{A8E7}', '\u{A8E8}', '\u{A8E9}', '\u{A8EA}', '\u{A8EB}', '\u{A8EC}', '\u{A8ED}',
+    '\u{A8EE}', '\u{A8EF}', '\u{A8F0}', '\u{A8F1}', '\u{AAB0}', '\u{AAB2}', '\u{AAB3}', '\u{AAB7}',
+    '\u{AAB8}', '\u{AABE}', '\u{AABF}', '\u{AAC1}', '\u{FE20}', '\u{FE21}', '\u{FE22}', '\u{FE23}',
+    '\u{FE24}', '\u{FE25}', '\u{FE26}', '\u{10A0F}', '\u{10A38}', '\u{1D185}', '\u{1D186}',
+    '\u{1D187}', '\u{1D188}', '\u{1D189}', '\u{1D1AA}', '\u{1D1AB}', '\u{1D1AC}', '\u{1D1AD}',
+    '\u{1D242}', '\u{1D243}', '\u{1D244}',
+];
+
+/// Action requested by a graphics command.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub enum Action {
+    /// Transmit an image without displaying it.
+    #[default]
+    Transmit,
+    /// Transmit an image and display it at the cursor.
+    TransmitAndDisplay,
+    /// Display a previously transmitted image.
+    Display,
+    /// Delete placements and images.
+    Delete,
+    /// Check if an image could be loaded, without storing it.
+    Query,
+}
+
+/// Pixel format of the transmitted data.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub enum Format {
+    /// 24-bit RGB pixels.
+    Rgb,
+    /// 32-bit RGBA pixels.
+    #[default]
+    Rgba,
+    /// PNG image.
+    Png,
+}
+
+/// Transmission medium of the image data.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub enum Medium {
+    /// Data is stored in the payload.
+    #[default]
+    Direct,
+    /// Payload is the path of a file containing the data.
+    File,
+    /// Payload is the path of a temporary file, which is deleted after reading it.
+    TempFile,
+    /// Payload is the name of a shared memory object.
+    SharedMemory,
+}
+
+/// Graphics command sent through `APC G`.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Command {
+    pub action: Action,
+    pub format: Format,
+    pub medium: Medium,
+
+    /// Data is compressed with zlib.
+    pub compressed: bool,
+
+    /// More chunks of the payload follow.
+    pub more: bool,
+
+    /// Response suppression, `1` suppresses success responses and `2` suppresses all of them.
+    pub quiet: u8,
+
+    pub image_id: u32,
+    pub image_number: u32,
+    pub placement_id: u32,
+
+    /// Dimensions of raw pixel data.
+    pub width: u32,
+    pub height: u32,
+
+    /// Number of bytes read from files, reading the entire file when zero.
+    pub size: u64,
+
+    /// Offset of the data within files.
+    pub offset: u64,
+
+    /// Region of the image which is displayed.
+    pub source_x: u32,
+    pub source_y: u32,
+    pub source_width: u32,
+    pub source_height: u32,
+
+    /// Offset of the placement within the cursor cell in pixels.
+    pub cell_offset_x: u32,
+    pub cell_offset_y: u32,
+
+    /// Number of columns and lines the image is scaled to.
+    pub columns: u32,
+    pub lines: u32,
+
+    pub z_index: i32,
+
+    /// Keep the cursor in place after displaying the image.
+    pub keep_cursor: bool,
+
+    /// Create a virtual placement displayed through Unicode placeholders.
+    pub virtual_placement: bool,
+
+    /// Deletion target.
+    pub delete: u8,
+
+    /// Base64 encoded payload.
+    pub payload: Vec<u8>,
+}
+
+impl Command {
+    /// Parse the content of an `APC G` sequence, without the leading `G`.
+    pub fn parse(data: &[u8]) -> Option<Self> {
+        let (control, payload) = match data.iter().position(|&byte| byte == b';') {
+            Some(position) => (&data[..position], &data[position + 1..]),
+            None => (data, &[][..]),
+        };
+
+        let mut command = Self { delete: b'a', payload: payload.to_vec(), ..Default::default() };
+        for pair in control.split(|&byte| byte == b',').filter(|pair| !pair.is_empty()) {
+            let (key, value) = match pair {
+                [key, b'=', value @ ..] => (*key, value),
+                _ => return None,
+            };
+            let number = || str::from_utf8(value).ok()?.parse::<u32>().ok();
+
+            match key {
+                b'a' => {
+                    command.action = match value {
+                        b"t" => Action::Transmit,
+                        b"T" => Action::TransmitAndDisplay,
+                        b"p" => Action::Display,
+                        b"d" => Action::Delete,
+                        b"q" => Action::Query,
+                        _ => return None,
+                    }
+                },
+                b'f' => {
+                    command.format = match number()? {
+                        24 => Format::Rgb,
+                        32 => Format::Rgba,
+                        100 => Format::Png,
+                        _ => return None,
+                    }
+                },
+                b't' => {
+                    command.medium = match value {
+                        b"d" => Medium::Direct,
+                        b"f" => Medium::File,
+                        b"t" => Medium::TempFile,
+                        b"s" => Medium::SharedMemory,
+                        _ => return None,
+                    }
+                },
+                b'o' => command.compressed = value == b"z",
+                b'm' => command.more = number()? == 1,
+                b'q' => command.quiet = number()?.min(2) as u8,
+                b'i' => command.image_id = number()?,
+                b'I' => command.image_number = number()?,
+                b'p' => command.placement_id = number()?,
+                b's' => command.width = number()?,
+                b'v' => command.height = number()?,
+                b'S' => command.size = number()?.into(),
+                b'O' => command.offset = number()?.into(),
+                b'x' => command.source_x = number()?,
+                b'y' => command.source_y = number()?,
+                b'w' => command.source_width = number()?,
+                b'h' => command.source_height = number()?,
+                b'X' => command.cell_offset_x = number()?,
+                b'Y' => command.cell_offset_y = number()?,
+                b'c' => command.columns = number()?,
+                b'r' => command.lines = number()?,
+                b'z' => command.z_index = str::from_utf8(value).ok()?.parse().ok()?,
+                b'C' => command.keep_cursor = number()? == 1,
+                b'U' => command.virtual_placement = number()? == 1,
+                b'd' => match value {
+                    [target] => command.delete = *target,
+                    _ => return None,
+                },
+                _ => (),
+            }
+        }
+
+        Some(command)
+    }
+
+    /// Build the response to this command, from the ID of the affected image or an error.
+    ///
+    /// Returns `None` if the client did not ask for a response.
+    pub fn response(&self, result: &Result<u32, Error>) -> Option<String> {
+        let (image_id, suppressed) = match result {
+            Ok(image_id) => (*image_id, self.quiet >= 1),
+            Err(_) => (self.image_id, self.quiet >= 2),
+        };
+        if suppressed || (self.image_id == 0 && self.image_number == 0) {
+            return None;
+        }
+
+        let mut response = format!("\x1b_Gi={image_id}");
+        if self.image_number != 0 {
+            response.push_str(&format!(",I={}", self.image_number));
+        }
+        if self.placement_id != 0 {
+            response.push_str(&format!(",p={}", self.placement_id));
+        }
+
+        match result {
+            Ok(_) => response.push_str(";OK\x1b\\"),
+            Err(err) => response.push_str(&format!(";{err}\x1b\\")),
+        }
+
+        Some(response)
+    }
+}
+
+/// Error reported to the client.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Error {
+    code: &'static str,
+    message: String,
+}
+
+impl Error {
+    pub fn new(code: &'static str, message: impl Into<String>) -> Self {
+        Self { code, message: message.into() }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(err: io::Error) -> Self {
+        let code = match err.kind() {
+            io::ErrorKind::NotFound => "ENOENT",
+            io::ErrorKind::PermissionDenied => "EPERM",
+            _ => "EBADF",
+        };
+        Self::new(code, err.to_string())
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.code, self.message)
+    }
+}
+
+/// Image stored by the terminal.
+#[derive(Debug, Clone)]
+pub struct Image {
+    pub texture: Arc<TextureRef>,
+    pub width: u32,
+    pub height: u32,
+    pub number: u32,
+
+    /// Transmission order, used to find the newest image with a number and the oldest images
+    /// when evicting.
+    generation: u64,
+}
+
+impl Image {
+    pub fn new(texture: Arc<TextureRef>, width: u32, height: u32, number: u32) -> Self {
+        Self { texture, width, height, number, generation: 0 }
+    }
+
+    /// Memory used by the image's pixels.
+    fn size(&self) -> usize {
+        self.width as usize * self.height as usize * 4
+    }
+}
+
+/// Placement displayed through Unicode placeholders.
+#[derive(Debug, Clone)]
+pub struct VirtualPlacement {
+    pub texture: Arc<TextureRef>,
+    pub placement: Placement,
+
+    /// Number of placeholder columns and lines covered by the image.
+    pub columns: u32,
+    pub lines: u32,
+
+    /// Cell dimensions at the time the placement was created.
+    pub cell_width: u32,
+    pub cell_height: u32,
+
+    /// Offset of the image within the placeholder area, to center it.
+    pub offset_x: u32,
+    pub offset_y: u32,
+}
+
+impl VirtualPlacement {
+    /// Part of the image displayed by the placeholder in the specified cell.
+    pub fn cell(&self, row: u32, column: u32) -> Option<GraphicCell> {
+        if row >= self.lines || column >= self.columns {
+            return None;
+        }
+
+        Some(GraphicCell {
+            texture: self.texture.clone(),
+            placement: self.placement,
+            offset_x: (column * self.cell_width) as i32 - self.offset_x as i32,
+            offset_y: (row * self.cell_height) as i32 - self.offset_y as i32,
+        })
+    }
+}
+
+/// Storage for transmitted images.
+#[derive(Debug, Default)]
+pub struct ImageStore {
+    images: HashMap<u32, Image>,
+    virtual_placements: HashMap<(u32, u32), VirtualPlacement>,
+
+    /// Transmission split across multiple chunks.
+    transmission: Option<(Command, Vec<u8>)>,
+
+    /// Number of bytes used by all stored images.
+    memory_usage: usize,
+
+    /// Last transmission order assigned to an image.
+    generation: u64,
+
+    /// Last ID assigned to an image transmitted without one.
+    last_id: u32,
+}
+
+impl ImageStore {
+    /// Check if a chunked transmission is in progress.
+    pub fn receiving(&self) -> bool {
+        self.transmission.is_some()
+    }
+
+    /// Receive a chunk of an image transmission.
+    ///
+    /// Returns the initial command with the decoded payload once the last chunk was received.
+    pub fn receive(&mut self, command: Command) -> Option<(Command, Result<Vec<u8>, Error>)> {
+        let (first, mut data) = self.transmission.take().unwrap_or((command.clone(), Vec::new()));
+
+        if let Err(err) = BASE64.decode_vec(&command.payload, &mut data) {
+            return Some((first, Err(Error::new("EINVAL", format!("invalid payload: {err}")))));
+        }
+
+        if data.len() > STORAGE_QUOTA {
+            return Some((first, Err(Error::new("EFBIG", "image exceeds storage quota"))));
+        }
+
+        if command.more {
+            self.transmission = Some((first, data));
+            return None;
+        }
+
+        Some((first, Ok(data)))
+    }
+
+    /// Store an image, returning its ID.
+    ///
+    /// Images without an ID are assigned a new one. Once the memory quota is exceeded, the
+    /// oldest images are evicted. The `reserved` bytes are used by other graphics and count
+    /// against the quota.
+    pub fn insert(&mut self, image_id: u32, mut image: Image, reserved: usize) -> u32 {
+        let image_id = match image_id {
+            0 => self.unused_id(),
+            image_id => image_id,
+        };
+
+        self.generation += 1;
+        image.generation = self.generation;
+
+        self.memory_usage += image.size();
+        if let Some(old) = self.images.insert(image_id, image) {
+            self.memory_usage -= old.size();
+        }
+
+        self.evict(reserved, Some(image_id));
+
+        image_id
+    }
+
+    /// Evict the oldest images, until they fit into the memory quota with `reserved` bytes used
+    /// by other graphics.
+    pub fn evict(&mut self, reserved: usize, keep: Option<u32>) {
+        while self.memory_usage.saturating_add(reserved) > STORAGE_QUOTA {
+            let oldest = self.images.iter().min_by_key(|(_, image)| image.generation);
+            match oldest.map(|(&id, _)| id).filter(|&id| Some(id) != keep) {
+                Some(id) => {
+                    debug!("Evicting kitty image {id}");
+                    self.remove(id);
+                },
+                None => break,
+            }
+        }
+    }
+
+    /// Get an image by its ID, or the newest image with the number if the ID is zero.
+    pub fn get(&self, image_id: u32, number: u32) -> Option<(u32, &Image)> {
+        if image_id != 0 {
+            return self.images.get(&image_id).map(|image| (image_id, image));
+        }
+
+        self.images
+            .iter()
+            .filter(|(_, image)| number != 0 && image.number == number)
+            .max_by_key(|(_, image)| image.generation)
+            .map(|(&id, image)| (id, image))
+    }
+
+    /// Remove an image and its virtual placements.
+    pub fn remove(&mut self, image_id: u32) {
+        if let Some(image) = self.images.remove(&image_id) {
+            self.memory_usage -= image.size();
+        }
+        self.virtual_placements.retain(|&(id, _), _| id != image_id);
+    }
+
+    /// Remove all images matching a predicate.
+    pub fn remove_matching(&mut self, mut predicate: impl FnMut(u32, &Image) -> bool) {
+        let ids: Vec<u32> = self
+            .images
+            .iter()
+            .filter(|(id, image)| predicate(**id, image))
+            .map(|(&id, _)| id)
+            .collect();
+
+        for id in ids {
+            self.remove(id);
+        }
+    }
+
+    /// IDs of all images with a number.
+    pub fn ids_with_number(&self, number: u32) -> Vec<u32> {
+        self.images.iter().filter(|(_, image)| image.number == number).map(|(&id, _)| id).collect()
+    }
+
+    /// Add a placement displayed through Unicode placeholders.
+    pub fn insert_virtual_placement(&mut self, virtual_placement: VirtualPlacement) {
+        let key = (virtual_placement.placement.image_id, virtual_placement.placement.placement_id);
+        self.virtual_placements.insert(key, virtual_placement);
+    }
+
+    /// Get a virtual placement.
+    ///
+    /// Without a placement ID, any virtual placement of the image is used.
+    pub fn virtual_placement(&self, image_id: u32, placement_id: u32) -> Option<&VirtualPlacement> {
+        match self.virtual_placements.get(&(image_id, placement_id)) {
+            None if placement_id == 0 => self
+                .virtual_placements
+                .iter()
+                .filter(|((id, _), _)| *id == image_id)
+                .min_by_key(|((_, placement_id), _)| *placement_id)
+                .map(|(_, virtual_placement)| virtual_placement),
+            virtual_placement => virtual_placement,
+        }
+    }
+
+    /// Remove virtual placements matching a predicate.
+    pub fn remove_virtual_placements(&mut self, mut predicate: impl FnMut(&Placement) -> bool) {
+        self.virtual_placements
+            .retain(|_, virtual_placement| !predicate(&virtual_placement.placement));
+    }
+
+    /// Find an ID which is not used by any image.
+    fn unused_id(&mut self) -> u32 {
+        loop {
+            self.last_id = self.last_id.wrapping_add(1).max(1);
+            if !self.images.contains_key(&self.last_id) {
+                return self.last_id;
+            }
+        }
+    }
+}
+
+/// Load the image described by a transmission command.
+pub fn load(command: &Command, data: Vec<u8>) -> Result<GraphicData, Error> {
+    let data = match command.medium {
+        Medium::Direct => data,
+        Medium::File => read_file(command, &data, false)?,
+        Medium::TempFile => read_file(command, &data, true)?,
+        Medium::SharedMemory => {
+            return Err(Error::new("EINVAL", "shared memory transmission is not supported"));
+        },
+    };
+
+    let data = if command.compressed {
+        miniz_oxide::inflate::decompress_to_vec_zlib_with_limit(&data, STORAGE_QUOTA)
+            .map_err(|err| Error::new("EINVAL", format!("invalid compressed data: {err}")))?
+    } else {
+        data
+    };
+
+    let graphic = match command.format {
+        Format::Png => decode_png(&data)?,
+        Format::Rgb | Format::Rgba => {
+            let (width, height) = (command.width as usize, command.height as usize);
+            check_dimensions(width, height)?;
+
+            let channels = if command.format == Format::Rgb { 3 } else { 4 };
+            let Some(pixels) = data.get(..width * height * channels) else {
+                return Err(Error::new("ENODATA", "insufficient image data"));
+            };
+
+            let pixels = match command.format {
+                Format::Rgb => {
+                    pixels.chunks_exact(3).flat_map(|rgb| [rgb[0], rgb[1], rgb[2], 255]).collect()
+                },
+                _ => pixels.to_vec(),
+            };
+
+            GraphicData { width, height, pixels }
+        },
+    };
+
+    Ok(graphic)
+}
+
+/// Read image data from a file.
+fn read_file(command: &Command, path: &[u8], temporary: bool) -> Result<Vec<u8>, Error> {
+    let path = str::from_utf8(path).map_err(|_| Error::new("EINVAL", "invalid file path"))?;
+    let path = fs::canonicalize(path)?;
+
+    // Prevent reading from devices and kernel interfaces.
+    if !path.starts_with("/dev/shm")
+        && ["/proc", "/sys", "/dev"].iter().any(|d| path.starts_with(d))
+    {
+        return Err(Error::new("EPERM", "reading from this directory is not permitted"));
+    }
+
+    // Only delete files which are clearly meant for the graphics protocol.
+    if temporary && !is_temporary_file(&path) {
+        return Err(Error::new("EPERM", "not a graphics protocol temporary file"));
+    }
+
+    // Opening FIFOs would block until another process opens them for writing.
+    if !fs::metadata(&path)?.is_file() {
+        return Err(Error::new("EINVAL", "not a regular file"));
+    }
+
+    // Don't block if the file was replaced after the check above.
+    let mut options = OpenOptions::new();
+    options.read(true);
+    #[cfg(unix)]
+    options.custom_flags(libc::O_NONBLOCK);
+
+    let mut file = options.open(&path)?;
+    if !file.metadata()?.is_file() {
+        return Err(Error::new("EINVAL", "not a regular file"));
+    }
+
+    file.seek(SeekFrom::Start(command.offset))?;
+    let limit = if command.size > 0 { command.size } else { STORAGE_QUOTA as u64 };
+    let mut data = Vec::new();
+    file.take(limit).read_to_end(&mut data)?;
+
+    if temporary {
+        let _ = fs::remove_file(&path);
+    }
+
+    Ok(data)
+}
+
+/// Check if a path is a temporary file of the graphics protocol.
+fn is_temporary_file(path: &Path) -> bool {
+    let temp_dirs = [env::temp_dir(), PathBuf::from("/tmp"), PathBuf::from("/dev/shm")];
+    path.to_string_lossy().contains("tty-graphics-protocol")
+        && temp_dirs.iter().any(|dir| path.starts_with(dir))
+}
+
+/// Decode a PNG image.
+fn decode_png(data: &[u8]) -> Result<GraphicData, Error> {
+    let png_error = |err: png::DecodingError| Error::new("EBADPNG", err.to_string());
+
+    let mut decoder = png::Decoder::new(data);
+    decoder.set_transformations(Transformations::normalize_to_color8());
+    let mut reader = decoder.read_info().map_err(png_error)?;
+
+    let info = reader.info();
+    check_dimensions(info.width as usize, info.height as usize)?;
+
+    let mut buffer = vec![0; reader.output_buffer_size()];
+    let frame = reader.next_frame(&mut buffer).map_err(png_error)?;
+    buffer.truncate(frame.buffer_size());
+
+    let pixels = match frame.color_type {
+        ColorType::Rgba => buffer,
+        ColorType::Rgb => buffer.chunks_exact(3).flat_map(|p| [p[0], p[1], p[2], 255]).collect(),
+        ColorType::GrayscaleAlpha => {
+            buffer.chunks_exact(2).flat_map(|p| [p[0], p[0], p[0], p[1]]).collect()
+        },
+        ColorType::Grayscale => buffer.iter().flat_map(|&p| [p, p, p, 255]).collect(),
+        ColorType::Indexed => return Err(Error::new("EBADPNG", "unexpected indexed colors")),
+    };
+
+    Ok(GraphicData { width: frame.width as usize, height: frame.height as usize, pixels })
+}
+
+/// Ensure image dimensions are within the supported limits.
+fn check_dimensions(width: usize, height: usize) -> Result<(), Error> {
+    let (max_width, max_height) = MAX_GRAPHIC_DIMENSIONS;
+    if width == 0 || height == 0 {
+        Err(Error::new("EINVAL", "missing image dimensions"))
+    } else if width > max_width || height > max_height {
+        Err(Error::new("EFBIG", format!("image exceeds {max_width}x{max_height} pixels")))
+    } else {
+        Ok(())
+    }
+}
+
+/// Image cell referenced by a Unicode placeholder.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Placeholder {
+    pub image_id: u32,
+    pub placement_id: u32,
+    pub row: u32,
+    pub column: u32,
+}
+
+impl Placeholder {
+    /// Decode the placeholder stored in a cell.
+    ///
+    /// The image ID is encoded in the foreground color and the placement ID in the underline
+    /// color. Diacritics encode the row, column, and the most significant byte of the image ID.
+    /// Missing diacritics are inferred from the placeholder in the previous cell.
+    pub fn from_cell(cell: &Cell, previous: Option<Placeholder>) -> Option<Self> {
+        if cell.c != PLACEHOLDER {
+            return None;
+        }
+
+        let image_id = color_id(cell.fg)?;
+        let placement_id = cell.underline_color().and_then(color_id).unwrap_or(0);
+
+        let mut diacritics = cell.zerowidth().unwrap_or_default().iter().map(|c| {
+            DIACRITICS.iter().position(|diacritic| diacritic == c).map(|index| index as u32)
+        });
+        let row = diacritics.next().flatten();
+        let column = diacritics.next().flatten();
+        let high_byte = diacritics.next().flatten();
+
+        let previous = previous.filter(|previous| {
+            previous.image_id & 0xff_ffff == image_id && previous.placement_id == placement_id
+        });
+
+        let (row, column, high_byte) = match (row, column, previous) {
+            (Some(row), Some(column), _) => (row, column, high_byte),
+            (row, None, Some(previous)) if row.is_none_or(|row| row == previous.row) => {
+                (previous.row, previous.column + 1, Some(previous.image_id >> 24))
+            },
+            (row, column, _) => (row.unwrap_or(0), column.unwrap_or(0), high_byte),
+        };
+
+        let image_id = image_id | high_byte.unwrap_or(0) << 24;
+        Some(Self { image_id, placement_id, row, column })
+    }
+}
+
+/// Number encoded in a placeholder color.
+fn color_id(color: Color) -> Option<u32> {
+    match color {
+        Color::Spec(rgb) => Some((rgb.r as u32) << 16 | (rgb.g as u32) << 8 | rgb.b as u32),
+        Color::Indexed(index) => Some(index as u32),
+        Color::Named(_) => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::File;
+    use std::io::Write;
+
+    use super::*;
+    use crate::vte::ansi::Rgb;
+
+    #[test]
+    fn parse_command() {
+        let command = Command::parse(b"a=T,f=100,i=3,p=7,q=2,c=10,r=5,z=-1,C=1;AAAA").unwrap();
+
+        assert_eq!(command.action, Action::TransmitAndDisplay);
+        assert_eq!(command.format, Format::Png);
+        assert_eq!((command.image_id, command.placement_id), (3, 7));
+        assert_eq!((command.columns, command.lines), (10, 5));
+        assert_eq!(command.z_index, -1);
+        assert_eq!(command.quiet, 2);
+        assert!(command.keep_cursor);
+        assert_eq!(command.payload, b"AAAA");
+
+        assert_eq!(Command::parse(b"a=d,d=I").unwrap().delete, b'I');
+        assert_eq!(Command::parse(b"a=x"), None);
+        assert_eq!(Command::parse(b"i=-1"), None);
+        assert_eq!(Command::parse(b"i"), None);
+    }
+
+    #[test]
+    fn responses() {
+        let command = Command::parse(b"i=1,p=2").unwrap();
+        assert_eq!(command.response(&Ok(1)).as_deref(), Some("\x1b_Gi=1,p=2;OK\x1b\\"));
+
+        let command = Command::parse(b"I=5").unwrap();
+        assert_eq!(command.response(&Ok(9)).as_deref(), Some("\x1b_Gi=9,I=5;OK\x1b\\"));
+
+        let command = Command::parse(b"i=3,q=1").unwrap();
+        let err = Err(Error::new("ENOENT", "image not found"));
+        assert_eq!(command.response(&Ok(3)), None);
+        assert_eq!(
+            command.response(&err).as_deref(),
+            Some("\x1b_Gi=3;ENOENT:image not found\x1b\\")
+        );
+
+        assert_eq!(Command::parse(b"").unwrap().response(&Ok(1)), None);
+    }
+
+    #[test]
+    fn chunked_transmission() {
+        let mut store = ImageStore::default();
+
+        let first = Command::parse(b"i=1,s=1,v=1,m=1;AAAA").unwrap();
+        assert_eq!(store.receive(first), None);
+        assert!(store.receiving());
+
+        let (command, data) = store.receive(Command::parse(b"m=0;AA").unwrap()).unwrap();
+        assert_eq!(command.image_id, 1);
+        assert_eq!(data, Ok(vec![0; 4]));
+        assert!(!store.receiving());
+    }
+
+    #[test]
+    fn load_raw_pixels() {
+        let command = Command::parse(b"f=24,s=2,v=1").unwrap();
+        let graphic = load(&command, vec![1, 2, 3, 4, 5, 6]).unwrap();
+        assert_eq!(graphic.pixels, vec![1, 2, 3, 255, 4, 5, 6, 255]);
+
+        let err = load(&command, vec![1, 2, 3]).unwrap_err();
+        assert_eq!(err.code, "ENODATA");
+
+        let command = Command::parse(b"f=32,s=0,v=1").unwrap();
+        assert_eq!(load(&command, Vec::new()).unwrap_err().code, "EINVAL");
+    }
+
+    #[test]
+    fn load_compressed() {
+        let pixels = vec![7; 16];
+        let compressed = miniz_oxide::deflate::compress_to_vec_zlib(&pixels, 6);
+
+        let command = Command::parse(b"o=z,s=2,v=2").unwrap();
+        assert_eq!(load(&command, compressed).unwrap().pixels, pixels);
+    }
+
+    #[test]
+    fn load_png() {
+        let mut data = Vec::new();
+        let mut encoder = png::Encoder::new(&mut data, 2, 1);
+        encoder.set_color(ColorType::Grayscale);
+        encoder.set_depth(png::BitDepth::Eight);
+        encoder.write_header().unwrap().write_image_data(&[0, 255]).unwrap();
+
+        let command = Command::parse(b"f=100").unwrap();
+        let graphic = load(&command, data).unwrap();
+        assert_eq!((graphic.width, graphic.height), (2, 1));
+        assert_eq!(graphic.pixels, vec![0, 0, 0, 255, 255, 255, 255, 255]);
+    }
+
+    #[test]
+    fn load_temporary_file() {
+        let path = env::temp_dir().join(format!("tty-graphics-protocol-{}", std::process::id()));
+        File::create(&path).unwrap().write_all(&[9, 1, 2, 3, 4]).unwrap();
+
+        let command = Command::parse(b"t=t,O=1,S=4,s=1,v=1").unwrap();
+        let graphic = load(&command, path.to_string_lossy().as_bytes().to_vec()).unwrap();
+        assert_eq!(graphic.pixels, vec![1, 2, 3, 4]);
+        assert!(!path.exists());
+
+        // Temporary files must be named after the protocol.
+        let path = env::temp_dir().join(format!("alacritty-kitty-{}", std::process::id()));
+        File::create(&path).unwrap().write_all(&[0; 4]).unwrap();
+        let err = load(&command, path.to_string_lossy().as_bytes().to_vec()).unwrap_err();
+        assert_eq!(err.code, "EPERM");
+        assert!(path.exists());
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn load_fifo() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("fifo");
+        let c_path = std::ffi::CString::new(path.to_string_lossy().as_bytes()).unwrap();
+        assert_eq!(unsafe { libc::mkfifo(c_path.as_ptr(), 0o600) }, 0);
+
+        let command = Command::parse(b"t=f,f=32,s=1,v=1").unwrap();
+        let err = load(&command, path.to_string_lossy().as_bytes().to_vec()).unwrap_err();
+        assert_eq!(err.code, "EINVAL");
+    }
+
+    #[test]
+    fn placeholders() {
+        let fg = Color::Spec(Rgb { r: 0, g: 1, b: 2 });
+        let mut cell = Cell { c: PLACEHOLDER, fg, ..Cell::default() };
+        cell.set_underline_color(Some(Color::Indexed(5)));
+        cell.push_zerowidth(DIACRITICS[3]);
+        cell.push_zerowidth(DIACRITICS[4]);
+        cell.push_zerowidth(DIACRITICS[1]);
+
+        let placeholder = Placeholder::from_cell(&cell, None).unwrap();
+        assert_eq!(placeholder, Placeholder {
+            image_id: 0x0100_0102,
+            placement_id: 5,
+            row: 3,
+            column: 4
+        });
+
+        // Missing diacritics are inherited from the previous cell.
+        let mut next = Cell { c: PLACEHOLDER, fg: cell.fg, ..Cell::default() };
+        next.set_underline_color(Some(Color::Indexed(5)));
+        let inferred = Placeholder::from_cell(&next, Some(placeholder)).unwrap();
+        assert_eq!(inferred, Placeholder { column: 5, ..placeholder });
+
+        assert_eq!(Placeholder::from_cell(&Cell::default(), None), None);
+    }
+}
@@ -0,0 +1,257 @@
+//! Inline graphics support.
+//!
+//! Graphics are decoded by the [`crate::parser::Processor`] and anchored to the grid by storing a
+//! [`GraphicCell`] in every cell they cover. This way graphics scroll with the text and are
+//! removed by any operation which clears the cells. Once the last cell or stored kitty image
+//! referencing a graphic is dropped, its texture is queued for removal so the renderer can free
+//! it.
+//!
+//! Memory used by stored kitty images and sixel graphics is limited by [`kitty::STORAGE_QUOTA`].
+
+use std::mem;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Weak};
+
+use parking_lot::Mutex;
+
+use crate::graphics::kitty::{ImageStore, Placeholder, STORAGE_QUOTA};
+
+pub mod kitty;
+pub mod sixel;
+
+/// Maximum width and height of a single graphic in pixels.
+pub const MAX_GRAPHIC_DIMENSIONS: (usize, usize) = (4096, 4096);
+
+/// Last identifier assigned to a graphic.
+///
+/// Identifiers are shared between all terminals, so a renderer drawing multiple terminals can
+/// use them to identify its textures.
+static LAST_GRAPHIC_ID: AtomicU64 = AtomicU64::new(0);
+
+/// Unique identifier for every graphic added to the terminal.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct GraphicId(pub u64);
+
+/// Decoded graphic.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GraphicData {
+    /// Width in pixels.
+    pub width: usize,
+
+    /// Height in pixels.
+    pub height: usize,
+
+    /// Pixels in the RGBA format, with 8 bits per channel.
+    pub pixels: Vec<u8>,
+}
+
+/// Reference to a graphic's texture.
+///
+/// The texture is queued for removal once the last reference to it is dropped.
+#[derive(Debug)]
+pub struct TextureRef {
+    pub id: GraphicId,
+    remove_queue: Weak<Mutex<Vec<GraphicId>>>,
+}
+
+impl PartialEq for TextureRef {
+    fn eq(&self, other: &Self) -> bool {
+        self.id == other.id
+    }
+}
+
+impl Eq for TextureRef {}
+
+impl Drop for TextureRef {
+    fn drop(&mut self) {
+        if let Some(remove_queue) = self.remove_queue.upgrade() {
+            remove_queue.lock().push(self.id);
+        }
+    }
+}
+
+/// Region of a graphic's texture in pixels.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct SourceRect {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// Position and size of a displayed graphic.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct Placement {
+    /// Unique identifier of this placement.
+    pub id: u64,
+
+    /// Kitty image ID, zero for sixel graphics.
+    pub image_id: u32,
+
+    /// Kitty placement ID.
+    pub placement_id: u32,
+
+    /// Region of the texture which is displayed.
+    pub source: SourceRect,
+
+    /// Displayed width in pixels.
+    pub width: u32,
+
+    /// Displayed height in pixels.
+    pub height: u32,
+
+    /// Stacking order, graphics with a negative Z-index are drawn below the text.
+    pub z_index: i32,
+}
+
+/// Part of a graphic stored in a single cell.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GraphicCell {
+    /// Texture of the graphic.
+    pub texture: Arc<TextureRef>,
+
+    /// Placement this cell is part of.
+    pub placement: Placement,
+
+    /// Horizontal offset of the cell's origin from the placement's origin in pixels.
+    pub offset_x: i32,
+
+    /// Vertical offset of the cell's origin from the placement's origin in pixels.
+    pub offset_y: i32,
+}
+
+/// Graphics which need to be uploaded or removed by the renderer.
+#[derive(Debug, Default)]
+pub struct UpdateQueues {
+    /// Graphics added since the last update.
+    pub pending: Vec<(GraphicId, GraphicData)>,
+
+    /// Graphics which are no longer referenced by any cell.
+    pub remove_queue: Vec<GraphicId>,
+}
+
+/// Graphics state of the terminal.
+#[derive(Debug, Default)]
+pub struct Graphics {
+    /// Last identifier assigned to a placement.
+    last_placement_id: u64,
+
+    /// Images transmitted through the kitty graphics protocol.
+    pub kitty: ImageStore,
+
+    /// Sixel graphics and the memory used by their pixels.
+    sixels: Vec<(Weak<TextureRef>, usize)>,
+
+    /// Graphics added since the last update.
+    pending: Vec<(GraphicId, GraphicData)>,
+
+    /// Graphics which are no longer referenced by any cell.
+    remove_queue: Arc<Mutex<Vec<GraphicId>>>,
+}
+
+impl Graphics {
+    /// Register a new graphic, returning the reference stored in its cells.
+    pub fn add(&mut self, graphic: GraphicData) -> Arc<TextureRef> {
+        let id = GraphicId(LAST_GRAPHIC_ID.fetch_add(1, Ordering::Relaxed) + 1);
+
+        self.pending.push((id, graphic));
+
+        Arc::new(TextureRef { id, remove_queue: Arc::downgrade(&self.remove_queue) })
+    }
+
+    /// Register a new sixel graphic, returning the reference stored in its cells.
+    ///
+    /// Kitty images are evicted to make room for the graphic. Returns `None` if the sixel
+    /// graphics which are still displayed leave no room for it.
+    pub fn add_sixel(&mut self, graphic: GraphicData) -> Option<Arc<TextureRef>> {
+        let size = graphic.width * graphic.height * 4;
+        let memory_usage = self.sixel_memory_usage() + size;
+        if memory_usage > STORAGE_QUOTA {
+            return None;
+        }
+
+        self.kitty.evict(memory_usage, None);
+
+        let texture = self.add(graphic);
+        self.sixels.push((Arc::downgrade(&texture), size));
+        Some(texture)
+    }
+
+    /// Number of bytes used by sixel graphics which are still displayed.
+    pub fn sixel_memory_usage(&mut self) -> usize {
+        self.sixels.retain(|(texture, _)| texture.strong_count() > 0);
+        self.sixels.iter().map(|(_, size)| size).sum()
+    }
+
+    /// Get a new unique placement identifier.
+    pub fn next_placement_id(&mut self) -> u64 {
+        self.last_placement_id += 1;
+        self.last_placement_id
+    }
+
+    /// Resolve a kitty Unicode placeholder to the part of the image it displays.
+    pub fn placeholder_graphic(&self, placeholder: &Placeholder) -> Option<GraphicCell> {
+        let virtual_placement =
+            self.kitty.virtual_placement(placeholder.image_id, placeholder.placement_id)?;
+        virtual_placement.cell(placeholder.row, placeholder.column)
+    }
+
+    /// Take all queued graphics updates.
+    pub fn take_queues(&mut self) -> Option<UpdateQueues> {
+        let remove_queue = mem::take(&mut *self.remove_queue.lock());
+        if self.pending.is_empty() && remove_queue.is_empty() {
+            return None;
+        }
+
+        Some(UpdateQueues { pending: mem::take(&mut self.pending), remove_queue })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn remove_unreferenced_textures() {
+        let mut graphics = Graphics::default();
+        let graphic = GraphicData { width: 1, height: 1, pixels: vec![0; 4] };
+
+        let texture = graphics.add(graphic.clone());
+        let clone = texture.clone();
+        let id = texture.id;
+
+        let queues = graphics.take_queues().unwrap();
+        assert_eq!(queues.pending, vec![(id, graphic)]);
+        assert!(queues.remove_queue.is_empty());
+        assert!(graphics.take_queues().is_none());
+
+        drop(texture);
+        assert!(graphics.take_queues().is_none());
+
+        drop(clone);
+        let queues = graphics.take_queues().unwrap();
+        assert_eq!(queues.remove_queue, vec![id]);
+    }
+
+    #[test]
+    fn sixel_quota() {
+        // Graphics covering half of the storage quota.
+        let graphic = || GraphicData { width: STORAGE_QUOTA / 8, height: 1, pixels: Vec::new() };
+
+        let mut graphics = Graphics::default();
+        let (width, height) = (STORAGE_QUOTA as u32 / 8, 1);
+        let image = kitty::Image::new(graphics.add(graphic()), width, height, 0);
+        let image_id = graphics.kitty.insert(0, image, 0);
+
+        // Sixel graphics evict kitty images, but can't exceed the quota themselves.
+        let sixel = graphics.add_sixel(graphic()).unwrap();
+        assert!(graphics.kitty.get(image_id, 0).is_some());
+        let _sixel = graphics.add_sixel(graphic()).unwrap();
+        assert!(graphics.kitty.get(image_id, 0).is_none());
+        assert!(graphics.add_sixel(graphic()).is_none());
+
+        drop(sixel);
+        assert_eq!(graphics.sixel_memory_usage(), STORAGE_QUOTA / 2);
+        assert!(graphics.add_sixel(graphic()).is_some());
+    }
+}
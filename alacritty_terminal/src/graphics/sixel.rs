@@ -0,0 +1,346 @@
+//! Sixel graphics decoder.
+//!
+//! The decoder receives the data of a `DCS P1 ; P2 ; P3 q ... ST` sequence one byte at a time and
+//! produces an RGBA image once the sequence has been terminated.
+
+use log::debug;
+
+use crate::graphics::{GraphicData, MAX_GRAPHIC_DIMENSIONS};
+use crate::vte::ansi::Rgb;
+
+/// Number of color registers available to a single image.
+pub const MAX_COLOR_REGISTERS: usize = 256;
+
+/// Default color registers, matching the VT340 palette.
+const DEFAULT_PALETTE: [(u8, u8, u8); 16] = [
+    (0, 0, 0),
+    (20, 20, 80),
+    (80, 13, 13),
+    (20, 80, 20),
+    (80, 20, 80),
+    (20, 80, 80),
+    (80, 80, 20),
+    (53, 53, 53),
+    (26, 26, 26),
+    (33, 33, 60),
+    (60, 26, 26),
+    (33, 60, 33),
+    (60, 33, 60),
+    (33, 60, 60),
+    (60, 60, 33),
+    (80, 80, 80),
+];
+
+/// Maximum number of numeric parameters stored for a single command.
+const MAX_PARAMS: usize = 5;
+
+/// Command which is currently receiving parameters.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Command {
+    /// Sixel data, without any parameters.
+    Data,
+    /// Graphics repeat introducer `!`.
+    Repeat,
+    /// Color introducer `#`.
+    Color,
+    /// Raster attributes `"`.
+    Raster,
+}
+
+/// Incremental sixel decoder.
+#[derive(Debug)]
+pub struct Parser {
+    command: Command,
+    params: [u16; MAX_PARAMS],
+    param_count: usize,
+
+    /// Color registers.
+    palette: Vec<Rgb>,
+
+    /// Active color register.
+    color: u8,
+
+    /// Repeat count for the next sixel.
+    repeat: usize,
+
+    /// Horizontal position of the next sixel.
+    x: usize,
+
+    /// Top of the active sixel band.
+    y: usize,
+
+    /// Pixel rows, storing the color register of every pixel.
+    ///
+    /// Pixels which were never written to are `None`.
+    rows: Vec<Vec<Option<u8>>>,
+
+    /// Whether unwritten pixels are transparent.
+    transparent_background: bool,
+}
+
+impl Parser {
+    /// Create a decoder from the parameters of the DCS sequence.
+    pub fn new(params: &[u16]) -> Self {
+        let palette = (0..MAX_COLOR_REGISTERS)
+            .map(|index| match DEFAULT_PALETTE.get(index) {
+                Some(&(r, g, b)) => rgb_from_percent(r.into(), g.into(), b.into()),
+                None => Rgb::default(),
+            })
+            .collect();
+
+        Self {
+            palette,
+            transparent_background: params.get(1) == Some(&1),
+            command: Command::Data,
+            params: Default::default(),
+            param_count: 0,
+            color: 0,
+            repeat: 1,
+            x: 0,
+            y: 0,
+            rows: Vec::new(),
+        }
+    }
+
+    /// Process the next byte of sixel data.
+    pub fn put(&mut self, byte: u8) {
+        match byte {
+            b'0'..=b'9' if self.command != Command::Data => {
+                if self.param_count == 0 {
+                    self.param_count = 1;
+                }
+
+                let param = &mut self.params[self.param_count - 1];
+                *param = param.saturating_mul(10).saturating_add((byte - b'0') as u16);
+            },
+            b';' if self.command != Command::Data => {
+                self.param_count = (self.param_count.max(1) + 1).min(MAX_PARAMS);
+            },
+            _ => {
+                self.finish_command();
+
+                match byte {
+                    b'?'..=b'~' => self.put_sixel(byte - b'?'),
+                    b'$' => self.x = 0,
+                    b'-' => {
+                        self.x = 0;
+                        self.y += 6;
+                    },
+                    b'!' => self.command = Command::Repeat,
+                    b'#' => self.command = Command::Color,
+                    b'"' => self.command = Command::Raster,
+                    _ => (),
+                }
+            },
+        }
+    }
+
+    /// Finish decoding and build the image.
+    ///
+    /// The image covers all drawn pixels, the dimensions declared through the raster attributes
+    /// are ignored. Returns `None` if the image contains no pixels.
+    pub fn finish(mut self) -> Option<GraphicData> {
+        self.finish_command();
+
+        let width = self.rows.iter().map(Vec::len).max().unwrap_or(0);
+        let height = self.rows.len();
+        if width == 0 || height == 0 {
+            return None;
+        }
+
+        let background = if self.transparent_background {
+            [0, 0, 0, 0]
+        } else {
+            let color = self.palette[0];
+            [color.r, color.g, color.b, 255]
+        };
+
+        let mut pixels = Vec::with_capacity(width * height * 4);
+        for y in 0..height {
+            let row = self.rows.get(y).map(Vec::as_slice).unwrap_or_default();
+            for x in 0..width {
+                match row.get(x).copied().flatten() {
+                    Some(register) => {
+                        let color = self.palette[register as usize];
+                        pixels.extend_from_slice(&[color.r, color.g, color.b, 255]);
+                    },
+                    None => pixels.extend_from_slice(&background),
+                }
+            }
+        }
+
+        Some(GraphicData { width, height, pixels })
+    }
+
+    /// Apply the parameters of the current command.
+    fn finish_command(&mut self) {
+        let params = &self.params[..self.param_count];
+        match (self.command, params) {
+            (Command::Data, _) => (),
+            (Command::Repeat, _) => self.repeat = params.first().map_or(1, |&n| n.max(1) as usize),
+            (Command::Color, &[register, ..]) if register as usize >= MAX_COLOR_REGISTERS => {
+                debug!("[unhandled] Sixel color register {register}");
+            },
+            (Command::Color, &[register]) => self.color = register as u8,
+            (Command::Color, &[register, space, x, y, z]) => {
+                let color = match space {
+                    1 => hls_to_rgb(x, y, z),
+                    2 => rgb_from_percent(x, y, z),
+                    _ => {
+                        debug!("[unhandled] Sixel color space {space}");
+                        return self.reset_command();
+                    },
+                };
+
+                self.color = register as u8;
+                self.palette[self.color as usize] = color;
+            },
+            // Allocating the declared dimensions would allow tiny sequences to use lots of memory.
+            (Command::Raster, _) => (),
+            (command, params) => debug!("[unhandled] Sixel command {command:?} {params:?}"),
+        }
+
+        self.reset_command();
+    }
+
+    /// Reset the command state.
+    fn reset_command(&mut self) {
+        self.command = Command::Data;
+        self.params = Default::default();
+        self.param_count = 0;
+    }
+
+    /// Draw a sixel at the current position.
+    fn put_sixel(&mut self, sixel: u8) {
+        let repeat = std::mem::replace(&mut self.repeat, 1);
+        let start = self.x;
+        self.x += repeat;
+
+        let end = self.x.min(MAX_GRAPHIC_DIMENSIONS.0);
+        if sixel == 0 || start >= end {
+            return;
+        }
+
+        for bit in 0..6 {
+            let y = self.y + bit;
+            if sixel & (1 << bit) == 0 || y >= MAX_GRAPHIC_DIMENSIONS.1 {
+                continue;
+            }
+
+            if self.rows.len() <= y {
+                self.rows.resize_with(y + 1, Vec::new);
+            }
+
+            let row = &mut self.rows[y];
+            if row.len() < end {
+                row.resize(end, None);
+            }
+            row[start..end].fill(Some(self.color));
+        }
+    }
+}
+
+/// Convert RGB components in the range `0..=100` to [`Rgb`].
+fn rgb_from_percent(r: u16, g: u16, b: u16) -> Rgb {
+    let convert = |value: u16| (value.min(100) as u32 * 255 / 100) as u8;
+    Rgb { r: convert(r), g: convert(g), b: convert(b) }
+}
+
+/// Convert HLS components to [`Rgb`].
+///
+/// Sixel hues are rotated by 120 degrees compared to the usual HSL color wheel, placing blue at
+/// 0 degrees.
+fn hls_to_rgb(hue: u16, lightness: u16, saturation: u16) -> Rgb {
+    let hue = ((hue as f32 + 240.) % 360.) / 60.;
+    let lightness = lightness.min(100) as f32 / 100.;
+    let saturation = saturation.min(100) as f32 / 100.;
+
+    let chroma = (1. - (2. * lightness - 1.).abs()) * saturation;
+    let x = chroma * (1. - (hue % 2. - 1.).abs());
+    let (r, g, b) = match hue as u8 {
+        0 => (chroma, x, 0.),
+        1 => (x, chroma, 0.),
+        2 => (0., chroma, x),
+        3 => (0., x, chroma),
+        4 => (x, 0., chroma),
+        _ => (chroma, 0., x),
+    };
+
+    let m = lightness - chroma / 2.;
+    let convert = |value: f32| ((value + m) * 255.).round() as u8;
+    Rgb { r: convert(r), g: convert(g), b: convert(b) }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decode(params: &[u16], data: &[u8]) -> Option<GraphicData> {
+        let mut parser = Parser::new(params);
+        for &byte in data {
+            parser.put(byte);
+        }
+        parser.finish()
+    }
+
+    #[test]
+    fn decode_pixels() {
+        // Red top row, green bottom row, with a transparent column in between.
+        let graphic = decode(&[0, 1], b"#1;2;100;0;0#2;2;0;100;0#1@?@$#2A?A").unwrap();
+
+        assert_eq!(graphic.width, 3);
+        assert_eq!(graphic.height, 2);
+        assert_eq!(graphic.pixels, vec![
+            255, 0, 0, 255, 0, 0, 0, 0, 255, 0, 0, 255, //
+            0, 255, 0, 255, 0, 0, 0, 0, 0, 255, 0, 255,
+        ]);
+    }
+
+    #[test]
+    fn repeat_and_bands() {
+        let graphic = decode(&[], b"#1;2;100;100;100!3~-~").unwrap();
+
+        assert_eq!(graphic.width, 3);
+        assert_eq!(graphic.height, 12);
+
+        let pixel = |x: usize, y: usize| &graphic.pixels[(y * 3 + x) * 4..][..4];
+        assert_eq!(pixel(2, 5), &[255, 255, 255, 255]);
+        assert_eq!(pixel(0, 11), &[255, 255, 255, 255]);
+        assert_eq!(pixel(1, 11), &[0, 0, 0, 255]);
+    }
+
+    #[test]
+    fn opaque_background() {
+        let graphic = decode(&[], b"#0;2;0;0;100#1;2;100;0;0#1@?@").unwrap();
+
+        assert_eq!(graphic.pixels, vec![255, 0, 0, 255, 0, 0, 255, 255, 255, 0, 0, 255]);
+    }
+
+    #[test]
+    fn ignore_raster_size() {
+        let graphic = decode(&[], b"\"1;1;4096;4096#1;2;100;0;0@").unwrap();
+
+        assert_eq!(graphic.width, 1);
+        assert_eq!(graphic.height, 1);
+    }
+
+    #[test]
+    fn invalid_color_register() {
+        let graphic = decode(&[0, 1], b"#1;2;100;0;0#257;2;0;100;0#1@#257@").unwrap();
+
+        assert_eq!(graphic.pixels, vec![255, 0, 0, 255, 255, 0, 0, 255]);
+    }
+
+    #[test]
+    fn empty_image() {
+        assert_eq!(decode(&[], b"#1;2;100;0;0"), None);
+    }
+
+    #[test]
+    fn hls_colors() {
+        assert_eq!(hls_to_rgb(0, 50, 100), Rgb { r: 0, g: 0, b: 255 });
+        assert_eq!(hls_to_rgb(120, 50, 100), Rgb { r: 255, g: 0, b: 0 });
+        assert_eq!(hls_to_rgb(240, 50, 100), Rgb { r: 0, g: 255, b: 0 });
+        assert_eq!(hls_to_rgb(0, 100, 0), Rgb { r: 255, g: 255, b: 255 });
+    }
+}
@@ -0,0 +1,948 @@
+//! Compressed storage for the oldest lines of the scrollback history.
+
+use std::borrow::Cow;
+use std::cell::{OnceCell, RefCell};
+use std::collections::VecDeque;
+use std::fs::File;
+#[cfg(unix)]
+use std::fs::Permissions;
+use std::ops::Range;
+#[cfg(unix)]
+use std::os::unix::fs::{FileExt, PermissionsExt};
+#[cfg(windows)]
+use std::os::windows::fs::FileExt;
+use std::sync::Arc;
+use std::{io, iter, mem, vec};
+
+use log::error;
+use miniz_oxide::deflate::compress_to_vec;
+use miniz_oxide::inflate::decompress_to_vec;
+use parking_lot::Mutex;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::grid::{GridCell, PromptMarks, Row};
+use crate::index::Column;
+
+/// Number of lines compressed together.
+pub const BLOCK_SIZE: usize = 1_000;
+
+/// Deflate compression level, optimized for speed since blocks are compressed during output.
+const COMPRESSION_LEVEL: u8 = 1;
+
+/// Number of recent templates checked before adding a new one to a block.
+const TEMPLATE_LOOKBEHIND: usize = 64;
+
+/// Number of blocks kept in memory when the archive is disk-backed.
+const MEMORY_BLOCKS: usize = 10;
+
+/// Number of decompressed blocks kept around when lines are modified or added.
+const MAX_DECOMPRESSED_BLOCKS: usize = 4;
+
+/// Compressed scrollback history.
+///
+/// Lines are stored in blocks of [`BLOCK_SIZE`] lines, with the newest block at the front. Only
+/// the oldest block at the back can contain fewer lines, when the history was truncated.
+///
+/// Blocks are decompressed on demand when any of their lines are accessed, which allows indexing
+/// through a shared reference. Since lines borrowed through a shared reference cannot be dropped,
+/// only the [`MAX_DECOMPRESSED_BLOCKS`] most recently used blocks are kept once the archive is
+/// modified, and all of them are dropped when [`Archive::evict`] is called.
+///
+/// Lines are not reflowed while they are compressed. To reflow them when the grid is resized,
+/// [`Archive::drain`] decompresses one block at a time.
+///
+/// When the archive is disk-backed, all but the newest [`MEMORY_BLOCKS`] blocks are moved into a
+/// temporary file and read back when they are decompressed.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Archive<T> {
+    blocks: VecDeque<Block<T>>,
+
+    /// IDs of the decompressed blocks, from least to most recently used.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    recent: RefCell<VecDeque<usize>>,
+
+    /// Number of blocks removed from the back, used as ID of the oldest block.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    removed: usize,
+
+    /// Whether old blocks should be moved to disk.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    disk_backed: bool,
+
+    /// File storing the blocks moved to disk.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    file: Option<Arc<SpillFile>>,
+}
+
+impl<T> Default for Archive<T> {
+    fn default() -> Self {
+        Self {
+            blocks: Default::default(),
+            recent: Default::default(),
+            removed: Default::default(),
+            disk_backed: Default::default(),
+            file: Default::default(),
+        }
+    }
+}
+
+impl<T: PartialEq> PartialEq for Archive<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.blocks == other.blocks
+    }
+}
+
+impl<T> Archive<T> {
+    /// Number of lines in the archive.
+    #[inline]
+    pub fn len(&self) -> usize {
+        match self.blocks.back() {
+            Some(block) => (self.blocks.len() - 1) * BLOCK_SIZE + block.len,
+            None => 0,
+        }
+    }
+
+    /// Remove up to `count` of the oldest lines.
+    ///
+    /// Returns the number of lines which could not be removed.
+    pub fn shrink(&mut self, mut count: usize) -> usize {
+        while let Some(block) = self.blocks.back_mut() {
+            if block.len > count {
+                block.len -= count;
+                return 0;
+            }
+
+            count -= block.len;
+            self.blocks.pop_back();
+            self.removed += 1;
+        }
+
+        // Start over with an empty file, since nothing references it anymore.
+        self.file = None;
+
+        count
+    }
+
+    /// Enable or disable moving old blocks to disk.
+    ///
+    /// Blocks which are already stored on disk are not moved back into memory.
+    pub fn set_disk_backed(&mut self, disk_backed: bool) {
+        self.disk_backed = disk_backed;
+    }
+
+    /// Remove all lines, oldest first, decompressing one block at a time.
+    ///
+    /// Lines keep the width they were compressed with. Lines which cannot be decoded are replaced
+    /// with empty lines with `columns` cells.
+    ///
+    /// The temporary file is kept, so its space can be reused by new blocks while the removed
+    /// blocks are read back from it.
+    pub fn drain(&mut self, columns: usize) -> Drain<T> {
+        self.recent.get_mut().clear();
+        Drain { blocks: mem::take(&mut self.blocks), rows: Vec::new().into_iter().rev(), columns }
+    }
+
+    /// Index of the block with an ID.
+    ///
+    /// Blocks are numbered starting from the oldest block, so their IDs are not affected by new
+    /// blocks being added.
+    fn block_index(&self, id: usize) -> Option<usize> {
+        let index = (self.removed + self.blocks.len()).checked_sub(id + 1)?;
+        (index < self.blocks.len()).then_some(index)
+    }
+
+    /// Mark a block as the most recently used block.
+    fn touch(&self, index: usize) {
+        let id = self.removed + self.blocks.len() - 1 - index;
+        let mut recent = self.recent.borrow_mut();
+        if recent.back() == Some(&id) {
+            return;
+        }
+
+        // Only blocks which are already decompressed can be part of the list.
+        if self.blocks[index].rows.get().is_some() {
+            if let Some(position) = recent.iter().rposition(|&recent| recent == id) {
+                recent.remove(position);
+            }
+        }
+
+        recent.push_back(id);
+    }
+
+    /// Move a block to disk, if it exceeds the number of blocks kept in memory.
+    fn spill(&mut self, index: usize) {
+        if !self.disk_backed || index < MEMORY_BLOCKS || index >= self.blocks.len() {
+            return;
+        }
+
+        let file = match &self.file {
+            Some(file) => file.clone(),
+            None => match SpillFile::new() {
+                Ok(file) => self.file.insert(Arc::new(file)).clone(),
+                Err(err) => {
+                    error!("Unable to create scrollback file: {err}");
+                    self.disk_backed = false;
+                    return;
+                },
+            },
+        };
+
+        if let Err(err) = self.blocks[index].spill(file) {
+            error!("Unable to write scrollback file: {err}");
+        }
+    }
+}
+
+impl<T: GridCell + Default + PartialEq> Archive<T> {
+    /// Compress lines and add them as the newest lines of the archive.
+    ///
+    /// The lines must be ordered from newest to oldest.
+    pub fn push<'a, I>(&mut self, rows: I)
+    where
+        I: IntoIterator<Item = &'a Row<T>>,
+        T: 'a,
+    {
+        self.trim(MAX_DECOMPRESSED_BLOCKS);
+
+        let block = Block::new(rows);
+        debug_assert_eq!(block.len, BLOCK_SIZE);
+        self.blocks.push_front(block);
+
+        self.spill(MEMORY_BLOCKS);
+    }
+
+    /// Drop all decompressed lines, compressing any modified lines again.
+    pub fn evict(&mut self) {
+        self.trim(0);
+    }
+
+    /// Drop the decompressed lines of all but the `keep` most recently used blocks.
+    fn trim(&mut self, keep: usize) {
+        while self.recent.get_mut().len() > keep {
+            let id = self.recent.get_mut().pop_front().unwrap();
+            let Some(index) = self.block_index(id) else { continue };
+
+            // Modified blocks are compressed into memory, so they might have to be moved again.
+            if self.blocks[index].evict() {
+                self.spill(index);
+            }
+        }
+    }
+}
+
+impl<T: GridCell + Default> Archive<T> {
+    /// Get a line, with `0` being the newest line of the archive.
+    #[inline]
+    pub fn get(&self, index: usize, columns: usize) -> &Row<T> {
+        self.touch(index / BLOCK_SIZE);
+        &self.blocks[index / BLOCK_SIZE].rows(columns)[index % BLOCK_SIZE]
+    }
+}
+
+impl<T> Archive<T> {
+    /// Modify the templates of all compressed cells, without decompressing any lines.
+    ///
+    /// Since the cell's character is not part of its template, `f` must not depend on it.
+    pub fn update_templates(&mut self, mut f: impl FnMut(&mut T)) {
+        for block in &mut self.blocks {
+            block.templates.iter_mut().for_each(&mut f);
+
+            // Keep decompressed lines in sync with their templates.
+            for row in block.rows.get_mut().into_iter().flatten() {
+                let occ = row.occ;
+                row[..Column(occ)].iter_mut().for_each(&mut f);
+            }
+        }
+    }
+}
+
+impl<T: GridCell + Default + PartialEq> Archive<T> {
+    /// Get a mutable line, with `0` being the newest line of the archive.
+    #[inline]
+    pub fn get_mut(&mut self, index: usize, columns: usize) -> &mut Row<T> {
+        self.touch(index / BLOCK_SIZE);
+        self.trim(MAX_DECOMPRESSED_BLOCKS);
+
+        let block = &mut self.blocks[index / BLOCK_SIZE];
+        block.rows(columns);
+        block.modified = true;
+        &mut block.rows.get_mut().unwrap()[index % BLOCK_SIZE]
+    }
+}
+
+/// Lines removed from an [`Archive`], from oldest to newest.
+#[derive(Debug)]
+pub struct Drain<T> {
+    /// Remaining blocks, with the oldest block at the back.
+    blocks: VecDeque<Block<T>>,
+
+    /// Remaining lines of the current block.
+    rows: iter::Rev<vec::IntoIter<Row<T>>>,
+
+    columns: usize,
+}
+
+impl<T: GridCell + Default> Iterator for Drain<T> {
+    type Item = Row<T>;
+
+    fn next(&mut self) -> Option<Row<T>> {
+        loop {
+            if let Some(row) = self.rows.next() {
+                return Some(row);
+            }
+
+            let block = self.blocks.pop_back()?;
+            self.rows = block.into_rows(self.columns).into_iter().rev();
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.rows.len() + self.blocks.iter().map(|block| block.len).sum::<usize>();
+        (len, Some(len))
+    }
+}
+
+/// Attribute-run-encoded and compressed lines.
+///
+/// Every line is encoded as its number of columns, occupied cells and shell integration marks,
+/// followed by runs of cells sharing the same template. A run stores the index of its template and
+/// the characters of all its cells.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(bound(deserialize = "T: Deserialize<'de>")))]
+struct Block<T> {
+    /// Compressed lines, from newest to oldest.
+    data: Data,
+
+    /// Cell templates referenced by the runs.
+    templates: Vec<T>,
+
+    /// Number of lines still part of the history.
+    len: usize,
+
+    /// Decompressed lines.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    rows: OnceCell<Vec<Row<T>>>,
+
+    /// Whether the decompressed lines have been modified.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    modified: bool,
+}
+
+impl<T: PartialEq> PartialEq for Block<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.data.load() == other.data.load()
+            && self.templates == other.templates
+            && self.len == other.len
+    }
+}
+
+impl<T: GridCell + Default + PartialEq> Block<T> {
+    fn new<'a, I>(rows: I) -> Self
+    where
+        I: IntoIterator<Item = &'a Row<T>>,
+        T: 'a,
+    {
+        let mut templates = Vec::new();
+        let mut data = Vec::new();
+        let mut len = 0;
+
+        for row in rows {
+            encode_row(row, &mut data, &mut templates);
+            len += 1;
+        }
+
+        let data = Data::Memory(compress_to_vec(&data, COMPRESSION_LEVEL).into_boxed_slice());
+
+        Self { data, templates, len, rows: Default::default(), modified: false }
+    }
+
+    /// Drop the decompressed lines.
+    ///
+    /// Returns `true` if modified lines were compressed again.
+    fn evict(&mut self) -> bool {
+        let rows = match self.rows.take() {
+            Some(rows) => rows,
+            None => return false,
+        };
+
+        if !self.modified {
+            return false;
+        }
+
+        let len = self.len;
+        *self = Self::new(&rows);
+        self.len = len;
+
+        true
+    }
+}
+
+impl<T> Block<T> {
+    /// Move the compressed lines to disk.
+    fn spill(&mut self, file: Arc<SpillFile>) -> io::Result<()> {
+        if let Data::Memory(data) = &self.data {
+            self.data = Data::Disk(Arc::new(Extent::write(file, data)?));
+        }
+
+        Ok(())
+    }
+}
+
+impl<T: GridCell + Default> Block<T> {
+    /// Get the decompressed lines, resized to `columns`.
+    fn rows(&self, columns: usize) -> &Vec<Row<T>> {
+        self.rows.get_or_init(|| self.decode(Some(columns), columns))
+    }
+
+    /// Take the lines still part of the history, at the width they were compressed with.
+    fn into_rows(mut self, columns: usize) -> Vec<Row<T>> {
+        let mut rows = match self.rows.take() {
+            Some(rows) => rows,
+            None => self.decode(None, columns),
+        };
+        rows.truncate(self.len);
+        rows
+    }
+
+    /// Decompress all lines, resizing them to `width` if specified.
+    ///
+    /// Lines which could not be decoded are replaced with empty lines with `columns` cells.
+    fn decode(&self, width: Option<usize>, columns: usize) -> Vec<Row<T>> {
+        let data = decompress_to_vec(&self.data.load()).unwrap_or_default();
+        let mut data = data.as_slice();
+
+        let mut rows = Vec::with_capacity(BLOCK_SIZE);
+        while let Some(row) = decode_row(&mut data, &self.templates, width) {
+            rows.push(row);
+        }
+
+        let len = self.len.max(rows.len());
+        rows.resize_with(len, || Row::new(columns));
+
+        rows
+    }
+}
+
+/// Compressed data of a block.
+#[derive(Clone, Debug)]
+enum Data {
+    Memory(Box<[u8]>),
+    Disk(Arc<Extent>),
+}
+
+impl Data {
+    /// Get the compressed data, reading it from disk if necessary.
+    ///
+    /// Data which cannot be read is replaced by empty lines after decompression.
+    fn load(&self) -> Cow<'_, [u8]> {
+        match self {
+            Self::Memory(data) => Cow::Borrowed(data),
+            Self::Disk(extent) => match extent.read() {
+                Ok(data) => Cow::Owned(data),
+                Err(err) => {
+                    error!("Unable to read scrollback file: {err}");
+                    Cow::Borrowed(&[])
+                },
+            },
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl Serialize for Data {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        self.load().serialize(serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> Deserialize<'de> for Data {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        Box::<[u8]>::deserialize(deserializer).map(Self::Memory)
+    }
+}
+
+/// Temporary file storing compressed blocks.
+///
+/// The file is created with mode 0600 and has no path on the filesystem, so it is removed
+/// automatically once it is closed, even if Alacritty does not exit cleanly.
+///
+/// Space of blocks which are dropped or compressed again is reused for new blocks.
+#[derive(Debug)]
+struct SpillFile {
+    file: File,
+    space: Mutex<Space>,
+}
+
+impl SpillFile {
+    fn new() -> io::Result<Self> {
+        let file = tempfile::tempfile()?;
+
+        // Unnamed files are not restricted to the current user by default.
+        #[cfg(unix)]
+        file.set_permissions(Permissions::from_mode(0o600))?;
+
+        Ok(Self { file, space: Default::default() })
+    }
+}
+
+/// Allocated space of a [`SpillFile`].
+#[derive(Default, Debug)]
+struct Space {
+    /// End of the last allocated extent.
+    len: u64,
+
+    /// Unused extents before `len`, sorted by offset and never adjacent to each other.
+    free: Vec<Range<u64>>,
+}
+
+impl Space {
+    /// Allocate `len` bytes, returning their offset.
+    fn allocate(&mut self, len: u64) -> u64 {
+        match self.free.iter().position(|free| free.end - free.start >= len) {
+            Some(index) => {
+                let offset = self.free[index].start;
+                self.free[index].start += len;
+                if self.free[index].is_empty() {
+                    self.free.remove(index);
+                }
+                offset
+            },
+            None => {
+                let offset = self.len;
+                self.len += len;
+                offset
+            },
+        }
+    }
+
+    /// Release an extent, merging it with adjacent unused extents.
+    fn release(&mut self, mut extent: Range<u64>) {
+        let index = self.free.partition_point(|free| free.start < extent.start);
+
+        if self.free.get(index).is_some_and(|next| next.start == extent.end) {
+            extent.end = self.free.remove(index).end;
+        }
+
+        match index.checked_sub(1).and_then(|index| self.free.get_mut(index)) {
+            Some(previous) if previous.end == extent.start => previous.end = extent.end,
+            _ => self.free.insert(index, extent),
+        }
+
+        // Shrink the allocated space when its end is unused.
+        if self.free.last().is_some_and(|free| free.end == self.len) {
+            self.len = self.free.pop().unwrap().start;
+        }
+    }
+}
+
+/// Compressed data stored in a [`SpillFile`].
+///
+/// The space is released once the extent is dropped.
+#[derive(Debug)]
+struct Extent {
+    file: Arc<SpillFile>,
+    offset: u64,
+    len: usize,
+}
+
+impl Extent {
+    /// Write data to unused space of the file.
+    fn write(file: Arc<SpillFile>, data: &[u8]) -> io::Result<Self> {
+        let offset = file.space.lock().allocate(data.len() as u64);
+        let extent = Self { file, offset, len: data.len() };
+        write_all_at(&extent.file.file, data, offset)?;
+        Ok(extent)
+    }
+
+    /// Read the data back from the file.
+    fn read(&self) -> io::Result<Vec<u8>> {
+        let mut data = vec![0; self.len];
+        read_exact_at(&self.file.file, &mut data, self.offset)?;
+        Ok(data)
+    }
+}
+
+impl Drop for Extent {
+    fn drop(&mut self) {
+        self.file.space.lock().release(self.offset..self.offset + self.len as u64);
+    }
+}
+
+#[cfg(unix)]
+fn write_all_at(file: &File, data: &[u8], offset: u64) -> io::Result<()> {
+    file.write_all_at(data, offset)
+}
+
+#[cfg(windows)]
+fn write_all_at(file: &File, mut data: &[u8], mut offset: u64) -> io::Result<()> {
+    while !data.is_empty() {
+        match file.seek_write(data, offset)? {
+            0 => return Err(io::ErrorKind::WriteZero.into()),
+            written => {
+                data = &data[written..];
+                offset += written as u64;
+            },
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(unix)]
+fn read_exact_at(file: &File, data: &mut [u8], offset: u64) -> io::Result<()> {
+    file.read_exact_at(data, offset)
+}
+
+#[cfg(windows)]
+fn read_exact_at(file: &File, mut data: &mut [u8], mut offset: u64) -> io::Result<()> {
+    while !data.is_empty() {
+        match file.seek_read(data, offset)? {
+            0 => return Err(io::ErrorKind::UnexpectedEof.into()),
+            read => {
+                data = &mut data[read..];
+                offset += read as u64;
+            },
+        }
+    }
+
+    Ok(())
+}
+
+/// Append a line to the uncompressed block data.
+fn encode_row<T: GridCell + PartialEq>(row: &Row<T>, data: &mut Vec<u8>, templates: &mut Vec<T>) {
+    write_varint(data, row.len());
+    write_varint(data, row.occ);
+    data.push(row.marks().bits());
+
+    let mut chars = Vec::new();
+    let mut cells = row[..].iter().map(GridCell::split_char).peekable();
+    while let Some((c, template)) = cells.next() {
+        chars.clear();
+        chars.push(c);
+        while let Some((c, _)) = cells.next_if(|(_, next)| *next == template) {
+            chars.push(c);
+        }
+
+        // Reuse recent templates, to avoid searching through all of them.
+        let lookbehind = templates.len().saturating_sub(TEMPLATE_LOOKBEHIND);
+        let index = match templates[lookbehind..].iter().rposition(|t| *t == template) {
+            Some(index) => lookbehind + index,
+            None => {
+                templates.push(template);
+                templates.len() - 1
+            },
+        };
+
+        write_varint(data, index);
+        write_varint(data, chars.len());
+        for &c in &chars {
+            write_varint(data, c as usize);
+        }
+    }
+}
+
+/// Read the next line from the uncompressed block data.
+///
+/// Without a `width`, the line keeps the number of columns it was encoded with.
+fn decode_row<T: GridCell + Default>(
+    data: &mut &[u8],
+    templates: &[T],
+    width: Option<usize>,
+) -> Option<Row<T>> {
+    let len = read_varint(data)?;
+    let occ = read_varint(data)?;
+    let (&marks, rest) = data.split_first()?;
+    *data = rest;
+
+    let columns = width.unwrap_or(len);
+    let mut cells = Vec::with_capacity(len.max(columns));
+    while cells.len() < len {
+        let template = templates.get(read_varint(data)?)?;
+        let count = read_varint(data)?;
+        for _ in 0..count {
+            let c = char::from_u32(read_varint(data)? as u32)?;
+            cells.push(T::join_char(c, template));
+        }
+    }
+
+    // Lines are cut off or padded to fit the grid when they are accessed without reflow.
+    cells.resize_with(columns, T::default);
+
+    let mut row = Row::from_vec(cells, occ.min(columns));
+    row.insert_mark(PromptMarks::from_bits_truncate(marks));
+
+    Some(row)
+}
+
+/// Write an unsigned LEB128 integer.
+fn write_varint(data: &mut Vec<u8>, mut value: usize) {
+    while value >= 0x80 {
+        data.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    data.push(value as u8);
+}
+
+/// Read an unsigned LEB128 integer.
+fn read_varint(data: &mut &[u8]) -> Option<usize> {
+    let mut value = 0;
+    for shift in (0..usize::BITS).step_by(7) {
+        let (&byte, rest) = data.split_first()?;
+        *data = rest;
+
+        value |= ((byte & 0x7f) as usize) << shift;
+        if byte & 0x80 == 0 {
+            return Some(value);
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::term::cell::{Cell, Flags};
+    use crate::vte::ansi::{Color, NamedColor};
+
+    fn row(text: &str, columns: usize) -> Row<Cell> {
+        let mut row = Row::<Cell>::new(columns);
+        for (i, c) in text.chars().enumerate() {
+            row[Column(i)].c = c;
+        }
+        row
+    }
+
+    #[test]
+    fn varint() {
+        let mut data = Vec::new();
+        for value in [0, 1, 0x7f, 0x80, 0x3fff, 0x4000, usize::MAX] {
+            write_varint(&mut data, value);
+        }
+
+        let mut data = data.as_slice();
+        for value in [0, 1, 0x7f, 0x80, 0x3fff, 0x4000, usize::MAX] {
+            assert_eq!(read_varint(&mut data), Some(value));
+        }
+        assert_eq!(read_varint(&mut data), None);
+    }
+
+    #[test]
+    fn round_trip() {
+        let mut rows: Vec<_> = (0..BLOCK_SIZE).map(|i| row(&i.to_string(), 5)).collect();
+        rows[1][Column(0)].flags.insert(Flags::BOLD);
+        rows[1][Column(1)].fg = Color::Named(NamedColor::Red);
+        rows[1][Column(2)].push_zerowidth('\u{301}');
+        rows[2].insert_mark(PromptMarks::PROMPT_START);
+
+        let mut archive = Archive::default();
+        archive.push(&rows);
+
+        assert_eq!(archive.len(), BLOCK_SIZE);
+        for (i, row) in rows.iter().enumerate() {
+            assert_eq!(archive.get(i, 5), row);
+        }
+        assert_eq!(archive.get(2, 5).marks(), PromptMarks::PROMPT_START);
+        assert_eq!(archive.get(1, 5)[Column(2)].zerowidth(), Some(&['\u{301}'][..]));
+    }
+
+    #[test]
+    fn resize_on_decompression() {
+        let rows = vec![row("abcde", 5); BLOCK_SIZE];
+        let mut archive = Archive::default();
+        archive.push(&rows);
+
+        assert_eq!(archive.get(0, 3), &row("abc", 3));
+
+        archive.evict();
+        assert_eq!(archive.get(0, 7), &row("abcde", 7));
+    }
+
+    #[test]
+    fn modify_compressed() {
+        let rows = vec![row("a", 1); BLOCK_SIZE];
+        let mut archive = Archive::default();
+        archive.push(&rows);
+
+        archive.get_mut(10, 1)[Column(0)].c = 'b';
+        archive.evict();
+
+        assert_eq!(archive.get(10, 1), &row("b", 1));
+        assert_eq!(archive.get(11, 1), &row("a", 1));
+    }
+
+    #[test]
+    fn shrink() {
+        let rows = vec![row("a", 1); BLOCK_SIZE];
+        let mut archive = Archive::default();
+        archive.push(&rows);
+        archive.push(&rows);
+
+        assert_eq!(archive.shrink(BLOCK_SIZE + 1), 0);
+        assert_eq!(archive.len(), BLOCK_SIZE - 1);
+
+        assert_eq!(archive.shrink(BLOCK_SIZE), 1);
+        assert_eq!(archive.len(), 0);
+    }
+
+    #[test]
+    fn limit_decompressed_blocks() {
+        let mut archive = Archive::default();
+        for _ in 0..MAX_DECOMPRESSED_BLOCKS + 2 {
+            archive.push(&vec![row("a", 1); BLOCK_SIZE]);
+        }
+
+        let decompressed = |archive: &Archive<Cell>| -> Vec<usize> {
+            let blocks = archive.blocks.iter().enumerate();
+            blocks.filter(|(_, block)| block.rows.get().is_some()).map(|(i, _)| i).collect()
+        };
+
+        // Lines borrowed through a shared reference stay decompressed.
+        for i in 0..archive.len() {
+            archive.get(i, 1);
+        }
+        assert_eq!(decompressed(&archive).len(), MAX_DECOMPRESSED_BLOCKS + 2);
+
+        // Modifying the archive drops the least recently used blocks.
+        archive.get_mut(0, 1)[Column(0)].c = 'b';
+        assert_eq!(decompressed(&archive), vec![0, 3, 4, 5]);
+
+        archive.push(&vec![row("a", 1); BLOCK_SIZE]);
+        assert_eq!(decompressed(&archive), vec![1, 4, 5, 6]);
+
+        archive.evict();
+        assert!(decompressed(&archive).is_empty());
+        assert_eq!(archive.get(BLOCK_SIZE, 1), &row("b", 1));
+    }
+
+    #[test]
+    fn update_templates() {
+        let mut archive = Archive::default();
+        archive.push(&vec![row("ab", 2); BLOCK_SIZE]);
+        archive.push(&vec![row("cd", 2); BLOCK_SIZE]);
+        archive.get(0, 2);
+
+        archive.update_templates(|cell| cell.fg = Color::Named(NamedColor::Red));
+
+        // Compressed lines are updated without decompressing them.
+        assert!(archive.blocks[1].rows.get().is_none());
+        for (index, text) in [(0, "cd"), (BLOCK_SIZE, "ab")] {
+            let mut expected = row(text, 2);
+            expected[..].iter_mut().for_each(|cell| cell.fg = Color::Named(NamedColor::Red));
+            assert_eq!(archive.get(index, 2), &expected);
+        }
+    }
+
+    #[test]
+    fn drain() {
+        let mut archive = Archive::default();
+        archive.set_disk_backed(true);
+        for i in 0..MEMORY_BLOCKS + 2 {
+            let rows = vec![row(&i.to_string(), 2); BLOCK_SIZE];
+            archive.push(&rows);
+        }
+        archive.shrink(1);
+
+        let file = archive.file.clone().unwrap();
+        let mut drain = archive.drain(2);
+        assert_eq!(archive.len(), 0);
+        assert_eq!(drain.size_hint().0, (MEMORY_BLOCKS + 2) * BLOCK_SIZE - 1);
+
+        // Lines are removed from oldest to newest.
+        assert_eq!(drain.next(), Some(row("0", 2)));
+        let rows: Vec<_> = drain.collect();
+        assert_eq!(rows.len(), (MEMORY_BLOCKS + 2) * BLOCK_SIZE - 2);
+        assert_eq!(rows[BLOCK_SIZE - 3], row("0", 2));
+        assert_eq!(rows[BLOCK_SIZE - 2], row("1", 2));
+        assert_eq!(rows.last(), Some(&row(&(MEMORY_BLOCKS + 1).to_string(), 2)));
+
+        // The file is kept for new blocks, with the space of the drained blocks released.
+        assert!(Arc::ptr_eq(archive.file.as_ref().unwrap(), &file));
+        assert_eq!(file.space.lock().len, 0);
+    }
+
+    #[test]
+    fn disk_backed() {
+        let mut archive = Archive::default();
+        archive.set_disk_backed(true);
+        for i in 0..MEMORY_BLOCKS + 2 {
+            let rows = vec![row(&i.to_string(), 2); BLOCK_SIZE];
+            archive.push(&rows);
+        }
+
+        assert!(archive.file.is_some());
+        assert!(matches!(archive.blocks[MEMORY_BLOCKS - 1].data, Data::Memory(_)));
+        assert!(matches!(archive.blocks[MEMORY_BLOCKS].data, Data::Disk(_)));
+        assert!(matches!(archive.blocks[MEMORY_BLOCKS + 1].data, Data::Disk(_)));
+
+        let oldest = archive.len() - 1;
+        assert_eq!(archive.get(0, 2), &row(&(MEMORY_BLOCKS + 1).to_string(), 2));
+        assert_eq!(archive.get(oldest, 2), &row("0", 2));
+
+        archive.get_mut(oldest, 2)[Column(1)].c = 'x';
+        archive.evict();
+        assert!(matches!(archive.blocks[MEMORY_BLOCKS + 1].data, Data::Disk(_)));
+
+        // Compressing modified blocks again reuses their space in the file.
+        let file_len = archive.file.as_ref().unwrap().space.lock().len;
+        for _ in 0..3 {
+            archive.get_mut(oldest, 2)[Column(1)].c = 'x';
+            archive.evict();
+        }
+        assert_eq!(archive.file.as_ref().unwrap().space.lock().len, file_len);
+        assert_eq!(archive.get(oldest, 2), &row("0x", 2));
+        assert_eq!(archive.get(oldest - 1, 2), &row("0", 2));
+
+        archive.shrink(archive.len());
+        assert!(archive.file.is_none());
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn spill_file_permissions() {
+        let file = SpillFile::new().unwrap();
+        assert_eq!(file.file.metadata().unwrap().permissions().mode() & 0o777, 0o600);
+
+        let extent = Extent::write(Arc::new(file), b"data").unwrap();
+        assert_eq!(extent.read().unwrap(), b"data");
+    }
+
+    #[test]
+    fn spill_file_space() {
+        let mut space = Space::default();
+        assert_eq!(space.allocate(4), 0);
+        assert_eq!(space.allocate(4), 4);
+        assert_eq!(space.allocate(4), 8);
+        assert_eq!(space.allocate(4), 12);
+
+        space.release(4..8);
+        space.release(0..4);
+        assert_eq!(space.free.len(), 1);
+        assert_eq!(space.free[0], 0..8);
+
+        // Freed space is reused before the file is extended.
+        assert_eq!(space.allocate(2), 0);
+        assert_eq!(space.allocate(8), 16);
+        assert_eq!(space.free.len(), 1);
+        assert_eq!(space.free[0], 2..8);
+
+        // Unused space at the end of the file is released entirely.
+        space.release(16..24);
+        space.release(12..16);
+        assert_eq!(space.len, 12);
+        space.release(8..12);
+        assert_eq!(space.len, 2);
+        assert!(space.free.is_empty());
+    }
+}
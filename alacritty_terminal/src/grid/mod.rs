@@ -10,15 +10,21 @@ use crate::index::{Column, Line, Point};
 use crate::term::cell::{Flags, ResetDiscriminant};
 use crate::vte::ansi::{CharsetIndex, StandardCharset};
 
+mod archive;
 pub mod resize;
 mod row;
 mod storage;
 #[cfg(test)]
 mod tests;
 
-pub use self::row::Row;
+pub use self::row::{PromptMarks, Row};
 use self::storage::Storage;
 
+/// Scrollback history size without any limit.
+///
+/// Old lines of unlimited history are moved to a temporary file.
+pub const UNLIMITED_HISTORY: usize = usize::MAX;
+
 pub trait GridCell: Sized {
     /// Check if the cell contains any content.
     fn is_empty(&self) -> bool;
@@ -28,6 +34,14 @@ pub trait GridCell: Sized {
 
     fn flags(&self) -> &Flags;
     fn flags_mut(&mut self) -> &mut Flags;
+
+    /// Split the cell into its character and a template holding all other attributes.
+    ///
+    /// This is used to store compressed history as runs of cells sharing the same template.
+    fn split_char(&self) -> (char, Self);
+
+    /// Create a cell from its character and a template created by [`GridCell::split_char`].
+    fn join_char(c: char, template: &Self) -> Self;
 }
 
 #[derive(Debug, Default, Clone, PartialEq, Eq)]
@@ -139,8 +153,11 @@ pub struct Grid<T> {
 
 impl<T: GridCell + Default + PartialEq> Grid<T> {
     pub fn new(lines: usize, columns: usize, max_scroll_limit: usize) -> Grid<T> {
+        let mut raw = Storage::with_capacity(lines, columns);
+        raw.set_disk_backed(max_scroll_limit == UNLIMITED_HISTORY);
+
         Grid {
-            raw: Storage::with_capacity(lines, columns),
+            raw,
             max_scroll_limit,
             display_offset: 0,
             saved_cursor: Cursor::default(),
@@ -154,13 +171,33 @@ impl<T: GridCell + Default + PartialEq> Grid<T> {
     pub fn update_history(&mut self, history_size: usize) {
         let current_history_size = self.history_size();
         if current_history_size > history_size {
-            self.raw.shrink_lines(current_history_size - history_size);
+            self.raw.shrink_history(current_history_size - history_size);
         }
         self.display_offset = min(self.display_offset, history_size);
         self.max_scroll_limit = history_size;
+        self.raw.set_disk_backed(history_size == UNLIMITED_HISTORY);
+    }
+
+    /// Modify all cells of the grid and its history which have been written to.
+    ///
+    /// Compressed history is not decompressed. Instead, `f` is called with the templates of its
+    /// cells, which hold everything but the character as described in [`GridCell::split_char`].
+    pub fn update_cells(&mut self, f: impl FnMut(&mut T)) {
+        self.raw.update_cells(f);
+    }
+
+    /// Drop decompressed history lines.
+    ///
+    /// Lines read from compressed history are otherwise kept in memory until the display is
+    /// scrolled.
+    pub fn evict_history(&mut self) {
+        self.raw.evict();
     }
 
     pub fn scroll_display(&mut self, scroll: Scroll) {
+        // Drop previously viewed compressed lines.
+        self.raw.evict();
+
         self.display_offset = match scroll {
             Scroll::Delta(count) => {
                 min(max((self.display_offset as i32) + count, 0) as usize, self.history_size())
@@ -173,7 +210,11 @@ impl<T: GridCell + Default + PartialEq> Grid<T> {
     }
 
     fn increase_scroll_limit(&mut self, count: usize) {
-        let count = min(count, self.max_scroll_limit - self.history_size());
+        // Make room by removing compressed lines, since they are older than all other lines.
+        let excess = (self.history_size() + count).saturating_sub(self.max_scroll_limit);
+        self.raw.shrink_archive(excess);
+
+        let count = min(count, self.max_scroll_limit.saturating_sub(self.history_size()));
         if count != 0 {
             self.raw.initialize(count, self.columns);
         }
@@ -265,7 +306,8 @@ impl<T: GridCell + Default + PartialEq> Grid<T> {
 
         // Update display offset when not pinned to active area.
         if self.display_offset != 0 {
-            self.display_offset = min(self.display_offset + positions, self.max_scroll_limit);
+            self.display_offset =
+                min(self.display_offset.saturating_add(positions), self.max_scroll_limit);
         }
 
         // Only rotate the entire history if the active region starts at the top.
@@ -293,6 +335,8 @@ impl<T: GridCell + Default + PartialEq> Grid<T> {
             for i in (region.end.0..screen_lines).rev().map(Line::from) {
                 self.raw.swap(i, i - positions);
             }
+
+            self.raw.compress_history();
         } else {
             // Rotate lines without moving anything into history.
             for i in (region.start.0..region.end.0 - positions as i32).map(Line::from) {
@@ -352,7 +396,7 @@ impl<T: GridCell + Default + PartialEq> Grid<T> {
     }
 }
 
-impl<T> Grid<T> {
+impl<T: GridCell + Default + PartialEq> Grid<T> {
     /// Reset a visible region within the grid.
     pub fn reset_region<D, R: RangeBounds<Line>>(&mut self, bounds: R)
     where
@@ -382,7 +426,8 @@ impl<T> Grid<T> {
     #[inline]
     pub fn clear_history(&mut self) {
         // Explicitly purge all lines from history.
-        self.raw.shrink_lines(self.history_size());
+        self.raw.shrink_history(self.history_size());
+        self.raw.truncate();
 
         // Reset display offset.
         self.display_offset = 0;
@@ -397,13 +442,17 @@ impl<T> Grid<T> {
         // Remove all cached lines to clear them of any content.
         self.truncate();
 
-        // Initialize everything with empty new lines.
-        self.raw.initialize(self.max_scroll_limit - self.history_size(), self.columns);
+        // Initialize everything with empty new lines, unless the history has no limit.
+        if self.max_scroll_limit != UNLIMITED_HISTORY {
+            let additional_rows = self.max_scroll_limit.saturating_sub(self.history_size());
+            self.raw.initialize(additional_rows, self.columns);
+        }
     }
 
     /// This is used only for truncating before saving ref-tests.
     #[inline]
     pub fn truncate(&mut self) {
+        self.raw.evict();
         self.raw.truncate();
     }
 
@@ -450,7 +499,7 @@ impl<T: PartialEq> PartialEq for Grid<T> {
     }
 }
 
-impl<T> Index<Line> for Grid<T> {
+impl<T: GridCell + Default> Index<Line> for Grid<T> {
     type Output = Row<T>;
 
     #[inline]
@@ -459,14 +508,14 @@ impl<T> Index<Line> for Grid<T> {
     }
 }
 
-impl<T> IndexMut<Line> for Grid<T> {
+impl<T: GridCell + Default + PartialEq> IndexMut<Line> for Grid<T> {
     #[inline]
     fn index_mut(&mut self, index: Line) -> &mut Row<T> {
         &mut self.raw[index]
     }
 }
 
-impl<T> Index<Point> for Grid<T> {
+impl<T: GridCell + Default> Index<Point> for Grid<T> {
     type Output = T;
 
     #[inline]
@@ -475,7 +524,7 @@ impl<T> Index<Point> for Grid<T> {
     }
 }
 
-impl<T> IndexMut<Point> for Grid<T> {
+impl<T: GridCell + Default + PartialEq> IndexMut<Point> for Grid<T> {
     #[inline]
     fn index_mut(&mut self, point: Point) -> &mut T {
         &mut self[point.line][point.column]
@@ -577,7 +626,7 @@ pub struct GridIterator<'a, T> {
     end: Point,
 }
 
-impl<'a, T> GridIterator<'a, T> {
+impl<'a, T: GridCell + Default> GridIterator<'a, T> {
     /// Current iterator position.
     pub fn point(&self) -> Point {
         self.point
@@ -589,7 +638,7 @@ impl<'a, T> GridIterator<'a, T> {
     }
 }
 
-impl<'a, T> Iterator for GridIterator<'a, T> {
+impl<'a, T: GridCell + Default> Iterator for GridIterator<'a, T> {
     type Item = Indexed<&'a T>;
 
     fn next(&mut self) -> Option<Self::Item> {
@@ -633,7 +682,7 @@ pub trait BidirectionalIterator: Iterator {
     fn prev(&mut self) -> Option<Self::Item>;
 }
 
-impl<T> BidirectionalIterator for GridIterator<'_, T> {
+impl<T: GridCell + Default> BidirectionalIterator for GridIterator<'_, T> {
     fn prev(&mut self) -> Option<Self::Item> {
         let topmost_line = self.grid.topmost_line();
         let last_column = self.grid.last_column();
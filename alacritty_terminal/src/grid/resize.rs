@@ -1,6 +1,7 @@
 //! Grid resize and reflow.
 
 use std::cmp::{Ordering, max, min};
+use std::collections::VecDeque;
 use std::mem;
 
 use crate::index::{Boundary, Column, Line};
@@ -107,7 +108,7 @@ impl<T: GridCell + Default + PartialEq> Grid<T> {
 
         self.columns = columns;
 
-        let mut reversed: Vec<Row<T>> = Vec::with_capacity(self.raw.len());
+        let mut reversed: VecDeque<Row<T>> = VecDeque::new();
         let mut cursor_line_delta = 0;
 
         // Remove the linewrap special case, by moving the cursor outside of the grid.
@@ -116,14 +117,18 @@ impl<T: GridCell + Default + PartialEq> Grid<T> {
             self.cursor.point.column += 1;
         }
 
-        let mut rows = self.raw.take_all();
+        let total_lines = self.raw.len();
+        let rows = self.raw.take_all();
+
+        for (i, mut row) in (0..total_lines).rev().zip(rows) {
+            // Compress old lines while reflowing, to avoid decompressing all of them at once.
+            self.raw.compress_reflowed(&mut reversed, columns);
 
-        for (i, mut row) in rows.drain(..).enumerate().rev() {
             // Check if reflowing should be performed.
-            let last_row = match reversed.last_mut() {
+            let last_row = match reversed.back_mut() {
                 Some(last_row) if should_reflow(last_row) => last_row,
                 _ => {
-                    reversed.push(row);
+                    reversed.push_back(row);
                     continue;
                 },
             };
@@ -206,7 +211,7 @@ impl<T: GridCell + Default + PartialEq> Grid<T> {
                 cell.flags_mut().insert(Flags::WRAPLINE);
             }
 
-            reversed.push(row);
+            reversed.push_back(row);
         }
 
         // Make sure we have at least the viewport filled.
@@ -228,7 +233,7 @@ impl<T: GridCell + Default + PartialEq> Grid<T> {
 
         // Reverse iterator and fill all rows that are still too short.
         let mut new_raw = Vec::with_capacity(reversed.len());
-        for mut row in reversed.drain(..).rev() {
+        for mut row in reversed.into_iter().rev() {
             if row.len() < columns {
                 row.grow(columns);
             }
@@ -251,11 +256,15 @@ impl<T: GridCell + Default + PartialEq> Grid<T> {
             self.cursor.point.column += 1;
         }
 
-        let mut new_raw = Vec::with_capacity(self.raw.len());
+        let mut new_raw: VecDeque<Row<T>> = VecDeque::new();
         let mut buffered: Option<Vec<T>> = None;
 
-        let mut rows = self.raw.take_all();
-        for (i, mut row) in rows.drain(..).enumerate().rev() {
+        let total_lines = self.raw.len();
+        let rows = self.raw.take_all();
+        for (i, mut row) in (0..total_lines).rev().zip(rows) {
+            // Compress old lines while reflowing, to avoid decompressing all of them at once.
+            self.raw.compress_reflowed(&mut new_raw, columns);
+
             // Append lines left over from the previous row.
             if let Some(buffered) = buffered.take() {
                 // Add a column for every cell added before the cursor, if it goes beyond the new
@@ -280,7 +289,7 @@ impl<T: GridCell + Default + PartialEq> Grid<T> {
                             Vec::new()
                         } else {
                             // Since it fits, just push the existing line without any reflow.
-                            new_raw.push(row);
+                            new_raw.push_back(row);
                             break;
                         }
                     },
@@ -302,7 +311,7 @@ impl<T: GridCell + Default + PartialEq> Grid<T> {
                 if len > 0 && wrapped[len - 1].flags().contains(Flags::LEADING_WIDE_CHAR_SPACER) {
                     if len == 1 {
                         row[Column(columns - 1)].flags_mut().insert(Flags::WRAPLINE);
-                        new_raw.push(row);
+                        new_raw.push_back(row);
                         break;
                     } else {
                         // Remove the leading spacer from the end of the wrapped row.
@@ -311,10 +320,10 @@ impl<T: GridCell + Default + PartialEq> Grid<T> {
                     }
                 }
 
-                new_raw.push(row);
+                new_raw.push_back(row);
 
                 // Set line as wrapped if cells got removed.
-                if let Some(cell) = new_raw.last_mut().and_then(|r| r.last_mut()) {
+                if let Some(cell) = new_raw.back_mut().and_then(|r| r.last_mut()) {
                     cell.flags_mut().insert(Flags::WRAPLINE);
                 }
 
@@ -364,10 +373,13 @@ impl<T: GridCell + Default + PartialEq> Grid<T> {
         }
 
         // Reverse iterator and use it as the new grid storage.
-        let mut reversed: Vec<Row<T>> = new_raw.drain(..).rev().collect();
-        reversed.truncate(self.max_scroll_limit + self.lines);
+        let reversed: Vec<Row<T>> = new_raw.into_iter().rev().collect();
         self.raw.replace_inner(reversed);
 
+        // Remove the oldest lines exceeding the scrollback limit.
+        let max_lines = self.max_scroll_limit.saturating_add(self.lines);
+        self.raw.shrink_history(self.raw.len().saturating_sub(max_lines));
+
         // Clamp display offset in case some lines went off.
         self.display_offset = min(self.display_offset, self.history_size());
 
@@ -4,6 +4,7 @@ use std::cmp::{max, min};
 use std::ops::{Index, IndexMut, Range, RangeFrom, RangeFull, RangeTo, RangeToInclusive};
 use std::{ptr, slice};
 
+use bitflags::bitflags;
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 
@@ -11,6 +12,22 @@ use crate::grid::GridCell;
 use crate::index::Column;
 use crate::term::cell::ResetDiscriminant;
 
+bitflags! {
+    /// Shell integration marks reported through OSC 133.
+    #[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+    #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+    pub struct PromptMarks: u8 {
+        /// Start of the shell prompt.
+        const PROMPT_START  = 0b0001;
+        /// End of the prompt and start of the user's command.
+        const COMMAND_START = 0b0010;
+        /// Start of the command's output.
+        const OUTPUT_START  = 0b0100;
+        /// End of the command's output.
+        const COMMAND_END   = 0b1000;
+    }
+}
+
 /// A row in the grid.
 #[derive(Default, Clone, Debug)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
@@ -22,6 +39,10 @@ pub struct Row<T> {
     /// This is the upper bound on the number of elements in the row, which have been modified
     /// since the last reset. All cells after this point are guaranteed to be equal.
     pub(crate) occ: usize,
+
+    /// Shell integration marks on this row.
+    #[cfg_attr(feature = "serde", serde(default))]
+    marks: PromptMarks,
 }
 
 impl<T: PartialEq> PartialEq for Row<T> {
@@ -52,7 +73,7 @@ impl<T: Default> Row<T> {
             inner.set_len(columns);
         }
 
-        Row { inner, occ: 0 }
+        Row { inner, occ: 0, marks: PromptMarks::empty() }
     }
 
     /// Increase the number of columns in the row.
@@ -107,6 +128,7 @@ impl<T: Default> Row<T> {
         }
 
         self.occ = 0;
+        self.marks = PromptMarks::empty();
     }
 }
 
@@ -114,7 +136,7 @@ impl<T: Default> Row<T> {
 impl<T> Row<T> {
     #[inline]
     pub fn from_vec(vec: Vec<T>, occ: usize) -> Row<T> {
-        Row { inner: vec, occ }
+        Row { inner: vec, occ, marks: PromptMarks::empty() }
     }
 
     #[inline]
@@ -150,6 +172,18 @@ impl<T> Row<T> {
         self.inner = vec;
     }
 
+    /// Shell integration marks on this row.
+    #[inline]
+    pub fn marks(&self) -> PromptMarks {
+        self.marks
+    }
+
+    /// Add a shell integration mark to this row.
+    #[inline]
+    pub fn insert_mark(&mut self, mark: PromptMarks) {
+        self.marks.insert(mark);
+    }
+
     /// Check if all cells in the row are empty.
     #[inline]
     pub fn is_clear(&self) -> bool
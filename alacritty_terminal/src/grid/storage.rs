@@ -1,4 +1,5 @@
 use std::cmp::max;
+use std::collections::VecDeque;
 use std::mem;
 use std::mem::MaybeUninit;
 use std::ops::{Index, IndexMut};
@@ -6,12 +7,16 @@ use std::ops::{Index, IndexMut};
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 
-use super::Row;
-use crate::index::Line;
+use super::archive::{Archive, BLOCK_SIZE};
+use super::{GridCell, Row};
+use crate::index::{Column, Line};
 
 /// Maximum number of buffered lines outside of the grid for performance optimization.
 const MAX_CACHE_SIZE: usize = 1_000;
 
+/// Number of history lines kept uncompressed.
+const MAX_UNCOMPRESSED_HISTORY: usize = 10_000;
+
 /// A ring buffer for optimizing indexing and rotation.
 ///
 /// The [`Storage::rotate`] and [`Storage::rotate_down`] functions are fast modular additions on
@@ -25,6 +30,9 @@ const MAX_CACHE_SIZE: usize = 1_000;
 /// implementation is provided. Anything from [`Vec`] that should be exposed must be done so
 /// manually.
 ///
+/// Once the history grows beyond [`MAX_UNCOMPRESSED_HISTORY`] lines, the oldest lines are moved
+/// out of the ring buffer into a compressed [`Archive`].
+///
 /// [`slice::rotate_left`]: https://doc.rust-lang.org/std/primitive.slice.html#method.rotate_left
 /// [`Deref`]: std::ops::Deref
 /// [`zero`]: #structfield.zero
@@ -49,7 +57,13 @@ pub struct Storage<T> {
     /// having to truncate the raw `inner` buffer.
     /// As long as `len` is bigger than `inner`, it is also possible to grow the scrollback buffer
     /// without any additional insertions.
+    ///
+    /// This does not include the lines in the `archive`.
     len: usize,
+
+    /// Compressed lines above the topmost line of the ring buffer.
+    #[cfg_attr(feature = "serde", serde(default))]
+    archive: Archive<T>,
 }
 
 impl<T: PartialEq> PartialEq for Storage<T> {
@@ -58,7 +72,7 @@ impl<T: PartialEq> PartialEq for Storage<T> {
         assert_eq!(self.zero, 0);
         assert_eq!(other.zero, 0);
 
-        self.inner == other.inner && self.len == other.len
+        self.inner == other.inner && self.len == other.len && self.archive == other.archive
     }
 }
 
@@ -72,7 +86,7 @@ impl<T> Storage<T> {
         let mut inner = Vec::with_capacity(visible_lines);
         inner.resize_with(visible_lines, || Row::new(columns));
 
-        Storage { inner, zero: 0, visible_lines, len: visible_lines }
+        Storage { inner, zero: 0, visible_lines, len: visible_lines, archive: Default::default() }
     }
 
     /// Increase the number of lines in the buffer.
@@ -84,7 +98,7 @@ impl<T> Storage<T> {
         // Number of lines the buffer needs to grow.
         let additional_lines = next - self.visible_lines;
 
-        let columns = self[Line(0)].len();
+        let columns = self.columns();
         self.initialize(additional_lines, columns);
 
         // Update visible lines.
@@ -102,7 +116,9 @@ impl<T> Storage<T> {
         self.visible_lines = next;
     }
 
-    /// Shrink the number of lines in the buffer.
+    /// Shrink the number of lines in the ring buffer.
+    ///
+    /// This removes the topmost lines of the ring buffer, without touching the compressed lines.
     #[inline]
     pub fn shrink_lines(&mut self, shrinkage: usize) {
         self.len -= shrinkage;
@@ -113,6 +129,64 @@ impl<T> Storage<T> {
         }
     }
 
+    /// Remove the oldest lines from history, starting with the compressed lines.
+    #[inline]
+    pub fn shrink_history(&mut self, count: usize) {
+        let count = self.archive.shrink(count);
+        if count > 0 {
+            self.shrink_lines(count);
+        }
+    }
+
+    /// Remove up to `count` of the compressed lines.
+    #[inline]
+    pub fn shrink_archive(&mut self, count: usize) {
+        self.archive.shrink(count);
+    }
+
+    /// Enable or disable moving old compressed lines to a temporary file.
+    #[inline]
+    pub fn set_disk_backed(&mut self, disk_backed: bool) {
+        self.archive.set_disk_backed(disk_backed);
+    }
+
+    /// Compress the oldest history lines once there are too many uncompressed lines.
+    #[inline]
+    pub fn compress_history(&mut self)
+    where
+        T: GridCell + Default + PartialEq,
+    {
+        while self.len - self.visible_lines >= MAX_UNCOMPRESSED_HISTORY + BLOCK_SIZE {
+            // Move the topmost lines from the ring buffer into the archive.
+            let (inner, zero) = (&self.inner, self.zero);
+            let rows = (self.len - BLOCK_SIZE..self.len).map(|i| &inner[(zero + i) % inner.len()]);
+            self.archive.push(rows);
+
+            self.len -= BLOCK_SIZE;
+        }
+    }
+
+    /// Modify all cells which have been written to, without decompressing any lines.
+    ///
+    /// Compressed lines only pass the templates of their cells, which exclude the character.
+    pub fn update_cells(&mut self, mut f: impl FnMut(&mut T)) {
+        for row in &mut self.inner {
+            let occ = row.occ;
+            row[..Column(occ)].iter_mut().for_each(&mut f);
+        }
+
+        self.archive.update_templates(f);
+    }
+
+    /// Drop decompressed lines, to reduce memory usage.
+    #[inline]
+    pub fn evict(&mut self)
+    where
+        T: GridCell + Default + PartialEq,
+    {
+        self.archive.evict();
+    }
+
     /// Truncate the invisible elements from the raw buffer.
     #[inline]
     pub fn truncate(&mut self) {
@@ -137,21 +211,18 @@ impl<T> Storage<T> {
         self.len += additional_rows;
     }
 
+    /// Total number of lines, including compressed lines.
     #[inline]
     pub fn len(&self) -> usize {
-        self.len
+        self.len + self.archive.len()
     }
 
     /// Swap implementation for Row<T>.
     ///
-    /// Exploits the known size of Row<T> to produce a slightly more efficient
-    /// swap than going through slice::swap.
-    ///
-    /// The default implementation from swap generates 8 movups and 4 movaps
-    /// instructions. This implementation achieves the swap in only 8 movups
-    /// instructions.
+    /// Exploits the known size of Row<T> to swap the rows one qword at a time,
+    /// instead of going through slice::swap.
     pub fn swap(&mut self, a: Line, b: Line) {
-        debug_assert_eq!(mem::size_of::<Row<T>>(), mem::size_of::<usize>() * 4);
+        debug_assert_eq!(mem::size_of::<Row<T>>(), mem::size_of::<usize>() * 5);
 
         let a = self.compute_index(a);
         let b = self.compute_index(b);
@@ -167,7 +238,7 @@ impl<T> Storage<T> {
             //
             // The optimizer unrolls this loop and vectorizes it.
             let mut tmp: MaybeUninit<usize>;
-            for i in 0..4 {
+            for i in 0..5 {
                 tmp = *a_ptr.offset(i);
                 *a_ptr.offset(i) = *b_ptr.offset(i);
                 *b_ptr.offset(i) = tmp;
@@ -194,36 +265,86 @@ impl<T> Storage<T> {
         self.zero = (self.zero + count) % self.inner.len();
     }
 
+    /// Compress the oldest reflowed lines once there are too many uncompressed lines.
+    ///
+    /// The `rows` are ordered from oldest to newest and are added as the newest compressed lines,
+    /// so this must only be called while the storage is being rebuilt after [`Storage::take_all`].
+    /// Lines with fewer than `columns` cells are grown before they are compressed.
+    #[inline]
+    pub fn compress_reflowed(&mut self, rows: &mut VecDeque<Row<T>>, columns: usize)
+    where
+        T: GridCell + Default + PartialEq,
+    {
+        while rows.len() >= self.visible_lines + MAX_UNCOMPRESSED_HISTORY + BLOCK_SIZE {
+            rows.range_mut(..BLOCK_SIZE).for_each(|row| row.grow(columns));
+            self.archive.push(rows.range(..BLOCK_SIZE).rev());
+            rows.drain(..BLOCK_SIZE);
+        }
+    }
+
     /// Update the raw storage buffer.
+    ///
+    /// The `vec` contains the newest lines, any lines compressed with
+    /// [`Storage::compress_reflowed`] are kept above them. The oldest lines of `vec` are compressed
+    /// again, if there are too many uncompressed lines.
     #[inline]
-    pub fn replace_inner(&mut self, vec: Vec<Row<T>>) {
+    pub fn replace_inner(&mut self, vec: Vec<Row<T>>)
+    where
+        T: GridCell + Default + PartialEq,
+    {
         self.len = vec.len();
         self.inner = vec;
         self.zero = 0;
+
+        self.compress_history();
+        self.inner.truncate(self.len);
     }
 
-    /// Remove all rows from storage.
+    /// Remove all rows from storage, from oldest to newest.
+    ///
+    /// Compressed lines are decompressed one block at a time while iterating, so they can be
+    /// reflowed like any other line without decompressing the entire history at once.
     #[inline]
-    pub fn take_all(&mut self) -> Vec<Row<T>> {
+    pub fn take_all(&mut self) -> impl Iterator<Item = Row<T>> + use<T>
+    where
+        T: GridCell + Default,
+    {
         self.truncate();
 
-        let mut buffer = Vec::new();
-
-        mem::swap(&mut buffer, &mut self.inner);
+        let columns = self.columns();
+        let buffer = mem::take(&mut self.inner);
         self.len = 0;
 
-        buffer
+        self.archive.drain(columns).chain(buffer.into_iter().rev())
     }
 
-    /// Compute actual index in underlying storage given the requested index.
+    /// Number of columns in the ring buffer's lines.
     #[inline]
-    fn compute_index(&self, requested: Line) -> usize {
+    fn columns(&self) -> usize {
+        self.inner.first().map_or(0, Row::len)
+    }
+
+    /// Compute the distance of the requested index from the bottommost line.
+    #[inline]
+    fn compute_offset(&self, requested: Line) -> usize {
         debug_assert!(requested.0 < self.visible_lines as i32);
 
-        let positive = -(requested - self.visible_lines).0 as usize - 1;
+        -(requested - self.visible_lines).0 as usize - 1
+    }
+
+    /// Compute actual index in underlying storage given the requested index.
+    #[inline]
+    fn compute_index(&self, requested: Line) -> usize {
+        let positive = self.compute_offset(requested);
 
         debug_assert!(positive < self.len);
 
+        self.offset_to_index(positive)
+    }
+
+    /// Compute actual index in underlying storage given the distance from the bottommost line.
+    #[inline]
+    fn offset_to_index(&self, positive: usize) -> usize {
         let zeroed = self.zero + positive;
 
         // Use if/else instead of remainder here to improve performance.
@@ -245,20 +366,30 @@ impl<T> Storage<T> {
     }
 }
 
-impl<T> Index<Line> for Storage<T> {
+impl<T: GridCell + Default> Index<Line> for Storage<T> {
     type Output = Row<T>;
 
     #[inline]
     fn index(&self, index: Line) -> &Self::Output {
-        let index = self.compute_index(index);
-        &self.inner[index]
+        let positive = self.compute_offset(index);
+        if positive >= self.len {
+            return self.archive.get(positive - self.len, self.columns());
+        }
+
+        &self.inner[self.offset_to_index(positive)]
     }
 }
 
-impl<T> IndexMut<Line> for Storage<T> {
+impl<T: GridCell + Default + PartialEq> IndexMut<Line> for Storage<T> {
     #[inline]
     fn index_mut(&mut self, index: Line) -> &mut Self::Output {
-        let index = self.compute_index(index);
+        let positive = self.compute_offset(index);
+        if positive >= self.len {
+            let columns = self.columns();
+            return self.archive.get_mut(positive - self.len, columns);
+        }
+
+        let index = self.offset_to_index(positive);
         &mut self.inner[index]
     }
 }
@@ -287,6 +418,14 @@ mod tests {
         fn flags_mut(&mut self) -> &mut Flags {
             unimplemented!();
         }
+
+        fn split_char(&self) -> (char, Self) {
+            (*self, ' ')
+        }
+
+        fn join_char(c: char, _template: &Self) -> Self {
+            c
+        }
     }
 
     #[test]
@@ -354,6 +493,7 @@ mod tests {
             zero: 0,
             visible_lines: 3,
             len: 3,
+            archive: Default::default(),
         };
 
         // Grow buffer.
@@ -365,6 +505,7 @@ mod tests {
             zero: 0,
             visible_lines: 4,
             len: 4,
+            archive: Default::default(),
         };
         expected.inner.append(&mut vec![filled_row('\0'); MAX_CACHE_SIZE]);
 
@@ -395,6 +536,7 @@ mod tests {
             zero: 1,
             visible_lines: 3,
             len: 3,
+            archive: Default::default(),
         };
 
         // Grow buffer.
@@ -406,6 +548,7 @@ mod tests {
             zero: 0,
             visible_lines: 4,
             len: 4,
+            archive: Default::default(),
         };
         expected.inner.append(&mut vec![filled_row('\0'); MAX_CACHE_SIZE]);
 
@@ -433,6 +576,7 @@ mod tests {
             zero: 1,
             visible_lines: 3,
             len: 3,
+            archive: Default::default(),
         };
 
         // Shrink buffer.
@@ -444,6 +588,7 @@ mod tests {
             zero: 1,
             visible_lines: 2,
             len: 2,
+            archive: Default::default(),
         };
         assert_eq!(storage.visible_lines, expected.visible_lines);
         assert_eq!(storage.inner, expected.inner);
@@ -469,6 +614,7 @@ mod tests {
             zero: 0,
             visible_lines: 3,
             len: 3,
+            archive: Default::default(),
         };
 
         // Shrink buffer.
@@ -480,6 +626,7 @@ mod tests {
             zero: 0,
             visible_lines: 2,
             len: 2,
+            archive: Default::default(),
         };
         assert_eq!(storage.visible_lines, expected.visible_lines);
         assert_eq!(storage.inner, expected.inner);
@@ -518,6 +665,7 @@ mod tests {
             zero: 2,
             visible_lines: 6,
             len: 6,
+            archive: Default::default(),
         };
 
         // Shrink buffer.
@@ -536,6 +684,7 @@ mod tests {
             zero: 2,
             visible_lines: 2,
             len: 2,
+            archive: Default::default(),
         };
         assert_eq!(storage.visible_lines, expected.visible_lines);
         assert_eq!(storage.inner, expected.inner);
@@ -570,6 +719,7 @@ mod tests {
             zero: 2,
             visible_lines: 1,
             len: 2,
+            archive: Default::default(),
         };
 
         // Truncate buffer.
@@ -581,6 +731,7 @@ mod tests {
             zero: 0,
             visible_lines: 1,
             len: 2,
+            archive: Default::default(),
         };
         assert_eq!(storage.visible_lines, expected.visible_lines);
         assert_eq!(storage.inner, expected.inner);
@@ -605,6 +756,7 @@ mod tests {
             zero: 2,
             visible_lines: 1,
             len: 2,
+            archive: Default::default(),
         };
 
         // Truncate buffer.
@@ -616,6 +768,7 @@ mod tests {
             zero: 0,
             visible_lines: 1,
             len: 2,
+            archive: Default::default(),
         };
         assert_eq!(storage.visible_lines, expected.visible_lines);
         assert_eq!(storage.inner, expected.inner);
@@ -662,6 +815,7 @@ mod tests {
             zero: 2,
             visible_lines: 0,
             len: 6,
+            archive: Default::default(),
         };
 
         // Shrink buffer.
@@ -680,6 +834,7 @@ mod tests {
             zero: 2,
             visible_lines: 0,
             len: 3,
+            archive: Default::default(),
         };
         assert_eq!(storage.inner, shrinking_expected.inner);
         assert_eq!(storage.zero, shrinking_expected.zero);
@@ -701,6 +856,7 @@ mod tests {
             zero: 2,
             visible_lines: 0,
             len: 4,
+            archive: Default::default(),
         };
 
         assert_eq!(storage.inner, growing_expected.inner);
@@ -723,6 +879,7 @@ mod tests {
             zero: 2,
             visible_lines: 0,
             len: 6,
+            archive: Default::default(),
         };
 
         // Initialize additional lines.
@@ -740,7 +897,13 @@ mod tests {
         ];
         let expected_init_size = std::cmp::max(init_size, MAX_CACHE_SIZE);
         expected_inner.append(&mut vec![filled_row('\0'); expected_init_size]);
-        let expected_storage = Storage { inner: expected_inner, zero: 0, visible_lines: 0, len: 9 };
+        let expected_storage = Storage {
+            inner: expected_inner,
+            zero: 0,
+            visible_lines: 0,
+            len: 9,
+            archive: Default::default(),
+        };
 
         assert_eq!(storage.len, expected_storage.len);
         assert_eq!(storage.zero, expected_storage.zero);
@@ -754,6 +917,7 @@ mod tests {
             zero: 2,
             visible_lines: 0,
             len: 3,
+            archive: Default::default(),
         };
 
         storage.rotate(2);
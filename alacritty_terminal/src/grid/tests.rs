@@ -3,6 +3,7 @@
 use super::*;
 
 use crate::term::cell::Cell;
+use crate::vte::ansi::Color;
 
 impl GridCell for usize {
     fn is_empty(&self) -> bool {
@@ -20,6 +21,14 @@ impl GridCell for usize {
     fn flags_mut(&mut self) -> &mut Flags {
         unimplemented!();
     }
+
+    fn split_char(&self) -> (char, Self) {
+        (char::from_u32(*self as u32).unwrap_or_default(), 0)
+    }
+
+    fn join_char(c: char, _template: &Self) -> Self {
+        c as usize
+    }
 }
 
 // Scroll up moves lines upward.
@@ -161,6 +170,99 @@ fn test_iter() {
     assert_indexed(23, final_iter.prev());
 }
 
+// Compressed history lines are accessible like any other line.
+#[test]
+fn compressed_history() {
+    let mut grid = Grid::<usize>::new(1, 1, 15_000);
+    for i in 1..=20_000 {
+        grid.scroll_up::<usize>(&(Line(0)..Line(1)), 1);
+        grid[Line(0)][Column(0)] = i;
+    }
+
+    assert_eq!(grid.history_size(), 15_000);
+    for line in 0..=15_000 {
+        assert_eq!(grid[Line(-line)][Column(0)], 20_000 - line as usize);
+    }
+
+    grid[Line(-14_000)][Column(0)] = 1;
+    grid.scroll_display(Scroll::Bottom);
+    assert_eq!(grid[Line(-14_000)][Column(0)], 1);
+
+    grid.update_history(12_000);
+    assert_eq!(grid.history_size(), 12_000);
+    assert_eq!(grid[Line(-12_000)][Column(0)], 8_000);
+}
+
+// Compressed history lines are reflowed like any other line.
+#[test]
+fn reflow_compressed_history() {
+    let mut grid = Grid::<Cell>::new(1, 4, 30_000);
+    for i in 0..12_000 {
+        let c = char::from(b'a' + (i % 26) as u8);
+        for (column, c) in [c, 'b', 'c', 'd'].into_iter().enumerate() {
+            grid[Line(0)][Column(column)] = cell(c);
+        }
+        grid.scroll_up::<Color>(&(Line(0)..Line(1)), 1);
+    }
+    assert_eq!(grid.history_size(), 12_000);
+
+    grid.resize(true, 1, 2);
+
+    assert_eq!(grid.history_size(), 24_000);
+    assert_eq!(grid[Line(-24_000)][Column(0)], cell('a'));
+    assert_eq!(grid[Line(-24_000)][Column(1)], wrap_cell('b'));
+    assert_eq!(grid[Line(-23_999)][Column(0)], cell('c'));
+    assert_eq!(grid[Line(-23_999)][Column(1)], cell('d'));
+    assert_eq!(grid[Line(-23_998)][Column(0)], cell('b'));
+
+    // Modified lines keep their content when they are compressed again.
+    grid[Line(-23_999)][Column(1)] = cell('e');
+    grid.resize(true, 1, 4);
+
+    assert_eq!(grid.history_size(), 12_000);
+    assert_eq!(grid[Line(-12_000)][Column(0)], cell('a'));
+    assert_eq!(grid[Line(-12_000)][Column(3)], cell('e'));
+    assert_eq!(grid[Line(-11_999)][Column(0)], cell('b'));
+    assert_eq!(grid[Line(-1)][Column(0)], cell('n'));
+}
+
+#[test]
+fn reflow_compressed_history_limit() {
+    let mut grid = Grid::<Cell>::new(1, 4, 20_000);
+    for i in 0..12_000 {
+        let c = char::from(b'a' + (i % 26) as u8);
+        for (column, c) in [c, 'b', 'c', 'd'].into_iter().enumerate() {
+            grid[Line(0)][Column(column)] = cell(c);
+        }
+        grid.scroll_up::<Color>(&(Line(0)..Line(1)), 1);
+    }
+
+    grid.resize(true, 1, 2);
+
+    // The oldest lines exceeding the scrollback limit are removed.
+    assert_eq!(grid.history_size(), 20_000);
+    assert_eq!(grid[Line(-20_000)][Column(0)], cell('y'));
+    assert_eq!(grid[Line(-20_000)][Column(1)], wrap_cell('b'));
+    assert_eq!(grid[Line(-19_999)][Column(0)], cell('c'));
+    assert_eq!(grid[Line(-1)][Column(0)], cell('c'));
+    assert_eq!(grid[Line(-2)][Column(0)], cell('n'));
+}
+
+#[test]
+fn initialize_unlimited_history() {
+    let mut grid = Grid::<usize>::new(2, 1, UNLIMITED_HISTORY);
+    for i in 1..=5 {
+        grid.scroll_up::<usize>(&(Line(0)..Line(2)), 1);
+        grid[Line(1)][Column(0)] = i;
+    }
+
+    grid.initialize_all();
+    grid.truncate();
+
+    assert_eq!(grid.history_size(), 5);
+    assert_eq!(grid[Line(1)][Column(0)], 5);
+}
+
 #[test]
 fn shrink_reflow() {
     let mut grid = Grid::<Cell>::new(1, 5, 2);
@@ -0,0 +1,762 @@
+//! Escape sequence parsing on top of the [`vte`] ANSI processor.
+//!
+//! The [`ansi::Processor`] silently drops a number of sequences which are required for features
+//! like shell integration or inline graphics. This module scans the byte stream for these
+//! sequences, while still forwarding every byte to the wrapped processor, and dispatches them to
+//! the [`Handler`] once they have been terminated.
+
+#[cfg(unix)]
+use std::ffi::OsString;
+#[cfg(unix)]
+use std::os::unix::ffi::OsStringExt;
+use std::path::PathBuf;
+use std::{mem, str};
+
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as Base64;
+use log::debug;
+
+use crate::graphics::{GraphicData, kitty, sixel};
+use crate::grid::PromptMarks;
+use crate::term::{Urgency, WorkingDirectory};
+use crate::vte::ansi::{self, StdSyncHandler, Timeout};
+
+/// Maximum number of bytes stored for a single OSC sequence.
+const MAX_OSC_LEN: usize = 0x1_0000;
+
+/// Maximum number of bytes stored for a single APC sequence.
+const MAX_APC_LEN: usize = 0x100_0000;
+
+/// Maximum number of bytes stored for CSI and DCS parameters.
+const MAX_PARAMS_LEN: usize = 32;
+
+/// OSC sequences which are dispatched through the [`Handler`].
+const INTERCEPTED_OSC: &[&[u8]] = &[b"7", b"9", b"99", b"133", b"777"];
+
+/// Escape sequence beginning a synchronized update.
+const BSU_CSI: &[u8] = b"\x1b[?2026h";
+
+/// Handler for escape sequences unsupported by [`ansi::Handler`].
+pub trait Handler: ansi::Handler {
+    /// OSC 133 shell integration mark at the cursor line.
+    fn prompt_mark(&mut self, _mark: PromptMarks) {}
+
+    /// OSC 7 working directory change.
+    fn set_working_directory(&mut self, _directory: WorkingDirectory) {}
+
+    /// OSC 9, OSC 99, or OSC 777 desktop notification.
+    fn desktop_notification(&mut self, _title: String, _body: String, _urgency: Urgency) {}
+
+    /// DCS sixel graphic at the cursor position.
+    fn insert_graphic(&mut self, _graphic: GraphicData) {}
+
+    /// XTSMGRAPHICS graphics attribute request.
+    fn graphics_attribute(&mut self, _item: u16, _action: u16) {}
+
+    /// APC kitty graphics protocol command.
+    fn kitty_graphics(&mut self, _command: kitty::Command) {}
+}
+
+/// Terminated escape sequence which needs to be dispatched.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Sequence {
+    Osc(Vec<u8>),
+    Sixel(GraphicData),
+    GraphicsAttribute(Vec<u16>),
+    KittyGraphics(Vec<u8>),
+}
+
+/// Desktop notification split across multiple OSC 99 sequences.
+#[derive(Debug, Default)]
+struct PartialNotification {
+    id: Vec<u8>,
+    title: String,
+    body: String,
+    urgency: Urgency,
+}
+
+/// Scanner state.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+enum State {
+    /// Outside of any sequence of interest.
+    #[default]
+    Ground,
+    /// After an ESC byte.
+    Escape,
+    /// Inside an OSC string, before its identifier was fully read.
+    OscIdentifier,
+    /// Inside an intercepted OSC string.
+    Osc,
+    /// Inside a CSI sequence.
+    Csi,
+    /// Inside a DCS sequence, before its final byte was read.
+    DcsParams,
+    /// Inside a sixel DCS string.
+    Sixel,
+    /// Inside an APC string.
+    Apc,
+    /// Inside a string sequence which is not intercepted.
+    IgnoredString,
+}
+
+/// Escape sequence processor.
+///
+/// This wraps the [`ansi::Processor`] and additionally dispatches escape sequences to
+/// [`Handler`] methods which have no equivalent in the [`ansi::Handler`].
+#[derive(Default)]
+pub struct Processor<T: Timeout = StdSyncHandler> {
+    parser: ansi::Processor<T>,
+    state: State,
+    buffer: Vec<u8>,
+
+    /// Sequence terminated by ESC during a synchronized update, waiting for the `\\` of its string
+    /// terminator.
+    unterminated: Option<Sequence>,
+
+    /// Incomplete OSC 99 notification.
+    notification: Option<PartialNotification>,
+
+    /// Decoder for the active sixel sequence.
+    sixel: Option<sixel::Parser>,
+}
+
+impl<T: Timeout> Processor<T> {
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Synchronized update timeout.
+    #[inline]
+    pub fn sync_timeout(&self) -> &T {
+        self.parser.sync_timeout()
+    }
+
+    /// Number of bytes in the synchronization buffer.
+    #[inline]
+    pub fn sync_bytes_count(&self) -> usize {
+        self.parser.sync_bytes_count()
+    }
+
+    /// Process new bytes from the PTY.
+    pub fn advance<H: Handler>(&mut self, handler: &mut H, bytes: &[u8]) {
+        let mut processed = 0;
+        let mut offset = 0;
+        while offset < bytes.len() {
+            // Skip ahead to the next escape outside of sequences.
+            if self.state == State::Ground {
+                match bytes[offset..].iter().position(|&byte| byte == 0x1b) {
+                    Some(position) => offset += position,
+                    None => break,
+                }
+            }
+
+            let sequence = self.scan(bytes[offset]);
+            offset += 1;
+
+            // Dispatch the previous sequence once its string terminator is complete.
+            if let Some(sequence) = self.unterminated.take() {
+                let terminated = bytes[offset - 1] == b'\\';
+                if terminated {
+                    self.parser.advance(handler, &bytes[processed..offset]);
+                    processed = offset;
+                }
+
+                self.interrupt_sync(handler, sequence, terminated);
+            }
+
+            // Forward everything up to the sequence terminator before dispatching it.
+            if let Some(sequence) = sequence {
+                self.parser.advance(handler, &bytes[processed..offset]);
+                processed = offset;
+
+                self.dispatch_ordered(handler, sequence);
+            }
+        }
+
+        self.parser.advance(handler, &bytes[processed..]);
+    }
+
+    /// End a synchronized update.
+    #[inline]
+    pub fn stop_sync<H: Handler>(&mut self, handler: &mut H) {
+        self.parser.stop_sync(handler);
+
+        if let Some(sequence) = self.unterminated.take() {
+            self.dispatch(handler, sequence);
+        }
+    }
+
+    /// Advance the scanner by a single byte.
+    ///
+    /// Returns a sequence when the byte terminated an intercepted sequence.
+    fn scan(&mut self, byte: u8) -> Option<Sequence> {
+        match (self.state, byte) {
+            // CAN and SUB abort all sequences.
+            (_, 0x18 | 0x1a) => {
+                self.state = State::Ground;
+                self.sixel = None;
+            },
+            // ESC terminates strings and starts a new escape.
+            (State::Osc, 0x1b) => {
+                self.state = State::Escape;
+                return Some(self.take_sequence());
+            },
+            (State::Sixel, 0x1b) => {
+                self.state = State::Escape;
+                return self.sixel.take().and_then(sixel::Parser::finish).map(Sequence::Sixel);
+            },
+            (State::Apc, 0x1b) if !self.buffer.is_empty() => {
+                self.state = State::Escape;
+                return Some(Sequence::KittyGraphics(mem::take(&mut self.buffer)));
+            },
+            (_, 0x1b) => self.state = State::Escape,
+            (State::Escape, b']') => {
+                self.buffer.clear();
+                self.state = State::OscIdentifier;
+            },
+            (State::Escape, b'[') => {
+                self.buffer.clear();
+                self.state = State::Csi;
+            },
+            (State::Escape, b'P') => {
+                self.buffer.clear();
+                self.state = State::DcsParams;
+            },
+            (State::Escape, b'_') => {
+                self.buffer.clear();
+                self.state = State::Apc;
+            },
+            (State::Escape, b'X' | b'^') => self.state = State::IgnoredString,
+            (State::Escape, _) => self.state = State::Ground,
+            (State::OscIdentifier, 0x07) => {
+                self.state = State::Ground;
+                if self.intercepted() {
+                    return Some(self.take_sequence());
+                }
+            },
+            (State::OscIdentifier, b';') => {
+                self.state = if self.intercepted() { State::Osc } else { State::IgnoredString };
+                self.buffer.push(byte);
+            },
+            (State::OscIdentifier, _) if self.buffer.len() < 8 => self.buffer.push(byte),
+            (State::OscIdentifier, _) => self.state = State::IgnoredString,
+            (State::Osc, 0x07) => {
+                self.state = State::Ground;
+                return Some(self.take_sequence());
+            },
+            (State::Osc, _) if self.buffer.len() < MAX_OSC_LEN => self.buffer.push(byte),
+            (State::Osc, _) => {
+                debug!("Ignoring OSC exceeding {MAX_OSC_LEN} bytes");
+                self.state = State::IgnoredString;
+            },
+            (State::Csi, 0x40..=0x7e) => {
+                self.state = State::Ground;
+
+                // XTSMGRAPHICS uses the `?` private marker with the `S` final byte.
+                if let (b'S', Some((b'?', params))) = (byte, self.buffer.split_first()) {
+                    return parse_params(params).map(Sequence::GraphicsAttribute);
+                }
+            },
+            (State::Csi, _) if self.buffer.len() < MAX_PARAMS_LEN => self.buffer.push(byte),
+            (State::Csi, _) => self.state = State::Ground,
+            (State::DcsParams, b'q') => {
+                let params = parse_params(&self.buffer).unwrap_or_default();
+                self.sixel = Some(sixel::Parser::new(&params));
+                self.state = State::Sixel;
+            },
+            (State::DcsParams, b'0'..=b'9' | b';') if self.buffer.len() < MAX_PARAMS_LEN => {
+                self.buffer.push(byte);
+            },
+            (State::DcsParams, _) => self.state = State::IgnoredString,
+            (State::Sixel, _) => {
+                if let Some(sixel) = &mut self.sixel {
+                    sixel.put(byte);
+                }
+            },
+            // Only kitty graphics APC sequences are intercepted.
+            (State::Apc, _) if self.buffer.is_empty() && byte != b'G' => {
+                self.state = State::IgnoredString;
+            },
+            (State::Apc, _) if self.buffer.len() < MAX_APC_LEN => self.buffer.push(byte),
+            (State::Apc, _) => {
+                debug!("Ignoring APC exceeding {MAX_APC_LEN} bytes");
+                self.state = State::IgnoredString;
+            },
+            (State::IgnoredString, 0x07) => self.state = State::Ground,
+            (State::Ground | State::IgnoredString, _) => (),
+        }
+
+        None
+    }
+
+    /// Check if the current OSC identifier should be intercepted.
+    fn intercepted(&self) -> bool {
+        INTERCEPTED_OSC.contains(&self.buffer.as_slice())
+    }
+
+    /// Take the buffered OSC sequence.
+    fn take_sequence(&mut self) -> Sequence {
+        Sequence::Osc(mem::take(&mut self.buffer))
+    }
+
+    /// Dispatch a sequence in order with the bytes preceding it.
+    ///
+    /// During synchronized updates, the [`ansi::Processor`] buffers all bytes until the update
+    /// ends, so the update is interrupted to dispatch the sequence at the right position.
+    fn dispatch_ordered<H: Handler>(&mut self, handler: &mut H, sequence: Sequence) {
+        if !self.parser.sync_timeout().pending_timeout() {
+            self.dispatch(handler, sequence);
+        } else if self.state == State::Ground {
+            self.interrupt_sync(handler, sequence, true);
+        } else {
+            // Strings terminated by ESC have to be completed before the update can continue.
+            self.unterminated = Some(sequence);
+        }
+    }
+
+    /// Apply the synchronized update up to a sequence and dispatch it.
+    ///
+    /// The update is started again afterwards when `resume` is set, so the partial update is not
+    /// drawn. Otherwise the parser could be inside of an escape sequence, so the update just ends
+    /// early.
+    fn interrupt_sync<H: Handler>(&mut self, handler: &mut H, sequence: Sequence, resume: bool) {
+        self.parser.stop_sync(handler);
+        self.dispatch(handler, sequence);
+
+        if resume {
+            self.parser.advance(handler, BSU_CSI);
+        }
+    }
+
+    /// Dispatch a terminated sequence to the handler.
+    fn dispatch<H: Handler>(&mut self, handler: &mut H, sequence: Sequence) {
+        match sequence {
+            Sequence::Osc(buffer) => {
+                let params: Vec<&[u8]> = buffer.split(|&byte| byte == b';').collect();
+                self.osc_dispatch(handler, &params);
+            },
+            Sequence::Sixel(graphic) => handler.insert_graphic(graphic),
+            Sequence::GraphicsAttribute(params) => match params[..] {
+                [item, action, ..] => handler.graphics_attribute(item, action),
+                _ => debug!("[unhandled] XTSMGRAPHICS {params:?}"),
+            },
+            Sequence::KittyGraphics(buffer) => match kitty::Command::parse(&buffer[1..]) {
+                Some(command) => handler.kitty_graphics(command),
+                None => debug!("[unhandled] kitty graphics {:?}", String::from_utf8_lossy(&buffer)),
+            },
+        }
+    }
+
+    /// Dispatch an OSC sequence to the handler.
+    fn osc_dispatch<H: Handler>(&mut self, handler: &mut H, params: &[&[u8]]) {
+        match params {
+            // Kitty desktop notification.
+            [b"99", metadata, payload @ ..] => {
+                self.kitty_notification(handler, metadata, &payload.join(&b';'));
+            },
+            _ => osc_dispatch(handler, params),
+        }
+    }
+
+    /// Handle a chunk of an OSC 99 desktop notification.
+    fn kitty_notification<H: Handler>(&mut self, handler: &mut H, metadata: &[u8], payload: &[u8]) {
+        let mut id: &[u8] = &[];
+        let mut done = true;
+        let mut base64 = false;
+        let mut body = false;
+        let mut urgency = None;
+        for (key, value) in metadata.split(|&byte| byte == b':').filter_map(|pair| {
+            let position = pair.iter().position(|&byte| byte == b'=')?;
+            Some((&pair[..position], &pair[position + 1..]))
+        }) {
+            match (key, value) {
+                (b"i", _) => id = value,
+                (b"d", b"0") => done = false,
+                (b"e", b"1") => base64 = true,
+                (b"p", b"title") => body = false,
+                (b"p", b"body") => body = true,
+                (b"p", _) => {
+                    debug!("[unhandled] OSC 99 payload type {:?}", String::from_utf8_lossy(value));
+                    return;
+                },
+                (b"u", b"0") => urgency = Some(Urgency::Low),
+                (b"u", b"1") => urgency = Some(Urgency::Normal),
+                (b"u", b"2") => urgency = Some(Urgency::Critical),
+                _ => (),
+            }
+        }
+
+        let payload = if base64 {
+            match Base64.decode(payload) {
+                Ok(payload) => payload,
+                Err(_) => {
+                    debug!("[unhandled] OSC 99 invalid base64 payload");
+                    return;
+                },
+            }
+        } else {
+            payload.to_vec()
+        };
+
+        // Chunks with a different ID discard the previous notification.
+        let mut notification = match self.notification.take() {
+            Some(notification) if notification.id == id => notification,
+            _ => PartialNotification { id: id.to_vec(), ..Default::default() },
+        };
+
+        let text = String::from_utf8_lossy(&payload);
+        if body {
+            notification.body.push_str(&text);
+        } else {
+            notification.title.push_str(&text);
+        }
+        notification.urgency = urgency.unwrap_or(notification.urgency);
+
+        // Prevent unbounded growth from notifications which are never completed.
+        if notification.title.len() + notification.body.len() > MAX_OSC_LEN {
+            debug!("Ignoring OSC 99 notification exceeding {MAX_OSC_LEN} bytes");
+            return;
+        }
+
+        if !done {
+            self.notification = Some(notification);
+        } else if !notification.title.is_empty() || !notification.body.is_empty() {
+            let PartialNotification { title, body, urgency, .. } = notification;
+            handler.desktop_notification(title, body, urgency);
+        }
+    }
+}
+
+/// Dispatch a stateless OSC sequence to the handler.
+fn osc_dispatch<H: Handler>(handler: &mut H, params: &[&[u8]]) {
+    match params {
+        // ConEmu extensions like progress reporting share the iTerm2 notification identifier.
+        [b"9", kind, ..] if !kind.is_empty() && kind.iter().all(u8::is_ascii_digit) => {
+            debug!("[unhandled] OSC 9 extension {:?}", String::from_utf8_lossy(kind));
+        },
+        // iTerm2 desktop notification.
+        [b"9", body @ ..] => {
+            let body = String::from_utf8_lossy(&body.join(&b';')).into_owned();
+            handler.desktop_notification(String::new(), body, Urgency::Normal);
+        },
+        // rxvt-unicode desktop notification.
+        [b"777", b"notify", title, body @ ..] => {
+            let title = String::from_utf8_lossy(title).into_owned();
+            let body = String::from_utf8_lossy(&body.join(&b';')).into_owned();
+            handler.desktop_notification(title, body, Urgency::Normal);
+        },
+        // Working directory.
+        [b"7", uri @ ..] => {
+            let uri = uri.join(&b';');
+            match parse_file_uri(&uri) {
+                Some(directory) => handler.set_working_directory(directory),
+                None => debug!("[unhandled] OSC 7 URI {:?}", String::from_utf8_lossy(&uri)),
+            }
+        },
+        // Shell integration marks.
+        [b"133", kind, ..] => {
+            let mark = match *kind {
+                b"A" => PromptMarks::PROMPT_START,
+                b"B" => PromptMarks::COMMAND_START,
+                b"C" => PromptMarks::OUTPUT_START,
+                b"D" => PromptMarks::COMMAND_END,
+                _ => {
+                    debug!("[unhandled] OSC 133 mark {:?}", String::from_utf8_lossy(kind));
+                    return;
+                },
+            };
+            handler.prompt_mark(mark);
+        },
+        _ => debug!("[unhandled] intercepted OSC {params:?}"),
+    }
+}
+
+/// Parse numeric parameters separated by `;`.
+///
+/// Empty parameters are treated as zero.
+fn parse_params(params: &[u8]) -> Option<Vec<u16>> {
+    params
+        .split(|&byte| byte == b';')
+        .map(|param| match param {
+            [] => Some(0),
+            param => str::from_utf8(param).ok()?.parse().ok(),
+        })
+        .collect()
+}
+
+/// Parse a `file://` URI.
+///
+/// On Unix, the decoded path can contain arbitrary bytes, since paths are not required to be
+/// valid UTF-8.
+fn parse_file_uri(uri: &[u8]) -> Option<WorkingDirectory> {
+    let uri = uri.strip_prefix(b"file://")?;
+    let (hostname, path) = uri.split_at(uri.iter().position(|&byte| byte == b'/')?);
+    let hostname = str::from_utf8(hostname).ok()?;
+
+    // Decode percent-encoded bytes.
+    let mut decoded = Vec::with_capacity(path.len());
+    let mut bytes = path.iter().copied();
+    while let Some(byte) = bytes.next() {
+        if byte == b'%' {
+            let hex = [bytes.next()?, bytes.next()?];
+            decoded.push(u8::from_str_radix(str::from_utf8(&hex).ok()?, 16).ok()?);
+        } else {
+            decoded.push(byte);
+        }
+    }
+
+    #[cfg(unix)]
+    let path = PathBuf::from(OsString::from_vec(decoded));
+    #[cfg(not(unix))]
+    let path = PathBuf::from(String::from_utf8(decoded).ok()?);
+
+    Some(WorkingDirectory { hostname: hostname.into(), path })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Default)]
+    struct MockHandler {
+        marks: Vec<PromptMarks>,
+        working_directory: Option<WorkingDirectory>,
+        notifications: Vec<(String, String, Urgency)>,
+        graphics: Vec<GraphicData>,
+        graphics_attributes: Vec<(u16, u16)>,
+        kitty_graphics: Vec<kitty::Command>,
+        text: String,
+    }
+
+    impl ansi::Handler for MockHandler {
+        fn input(&mut self, c: char) {
+            self.text.push(c);
+        }
+    }
+
+    impl Handler for MockHandler {
+        fn prompt_mark(&mut self, mark: PromptMarks) {
+            self.marks.push(mark);
+            self.text.push('|');
+        }
+
+        fn set_working_directory(&mut self, directory: WorkingDirectory) {
+            self.working_directory = Some(directory);
+        }
+
+        fn desktop_notification(&mut self, title: String, body: String, urgency: Urgency) {
+            self.notifications.push((title, body, urgency));
+        }
+
+        fn insert_graphic(&mut self, graphic: GraphicData) {
+            self.graphics.push(graphic);
+        }
+
+        fn graphics_attribute(&mut self, item: u16, action: u16) {
+            self.graphics_attributes.push((item, action));
+        }
+
+        fn kitty_graphics(&mut self, command: kitty::Command) {
+            self.kitty_graphics.push(command);
+        }
+    }
+
+    #[test]
+    fn prompt_marks() {
+        let mut handler = MockHandler::default();
+        let mut parser: Processor = Processor::new();
+
+        parser.advance(&mut handler, b"\x1b]133;A\x07$ \x1b]133;B\x1b\\ls\x1b]133;C;x\x07");
+        parser.advance(&mut handler, b"out\x1b]133;D;0\x1b\\");
+
+        assert_eq!(handler.marks, vec![
+            PromptMarks::PROMPT_START,
+            PromptMarks::COMMAND_START,
+            PromptMarks::OUTPUT_START,
+            PromptMarks::COMMAND_END,
+        ]);
+        assert_eq!(handler.text, "|$ |ls|out|");
+    }
+
+    #[test]
+    fn split_sequence() {
+        let mut handler = MockHandler::default();
+        let mut parser: Processor = Processor::new();
+
+        for byte in b"a\x1b]13".iter().chain(b"3;A\x1b\\b") {
+            parser.advance(&mut handler, &[*byte]);
+        }
+
+        assert_eq!(handler.marks, vec![PromptMarks::PROMPT_START]);
+        assert_eq!(handler.text, "a|b");
+    }
+
+    #[test]
+    fn ignore_other_sequences() {
+        let mut handler = MockHandler::default();
+        let mut parser: Processor = Processor::new();
+
+        parser.advance(&mut handler, b"\x1b]1337;A\x07\x1bP133;A\x1b\\\x1b]133;A\x18x");
+
+        assert!(handler.marks.is_empty());
+        assert_eq!(handler.text, "x");
+    }
+
+    #[test]
+    fn synchronized_update() {
+        let mut handler = MockHandler::default();
+        let mut parser: Processor = Processor::new();
+
+        parser.advance(&mut handler, b"\x1b[?2026ha\x1b]133;A\x07b\x1b]133;B\x1b");
+        assert_eq!(handler.marks, vec![PromptMarks::PROMPT_START]);
+        assert_eq!(handler.text, "a|");
+        assert!(parser.sync_timeout().pending_timeout());
+
+        // Sequences terminated by ESC are dispatched once the string terminator is complete.
+        parser.advance(&mut handler, b"\\c");
+        assert_eq!(handler.marks, vec![PromptMarks::PROMPT_START, PromptMarks::COMMAND_START]);
+        assert_eq!(handler.text, "a|b|");
+        assert!(parser.sync_timeout().pending_timeout());
+
+        parser.advance(&mut handler, b"\x1b[?2026l");
+        assert_eq!(handler.text, "a|b|c");
+        assert!(!parser.sync_timeout().pending_timeout());
+    }
+
+    #[test]
+    fn synchronized_graphics() {
+        let mut handler = MockHandler::default();
+        let mut parser: Processor = Processor::new();
+
+        // Graphics are inserted after the text preceding them.
+        parser.advance(&mut handler, b"\x1b[?2026ha\x1bPq@\x1b\\b");
+        assert_eq!(handler.graphics.len(), 1);
+        assert_eq!(handler.text, "a");
+
+        parser.advance(&mut handler, b"\x1b_Ga=T,i=1;AAAA\x1b\\c");
+        assert_eq!(handler.kitty_graphics.len(), 1);
+        assert_eq!(handler.text, "ab");
+
+        parser.advance(&mut handler, b"\x1b[?2026l");
+        assert_eq!(handler.text, "abc");
+    }
+
+    #[test]
+    fn working_directory() {
+        let mut handler = MockHandler::default();
+        let mut parser: Processor = Processor::new();
+
+        parser.advance(&mut handler, b"\x1b]7;file://host/tmp/a%20b;c\x07");
+
+        let expected = WorkingDirectory { hostname: "host".into(), path: "/tmp/a b;c".into() };
+        assert_eq!(handler.working_directory, Some(expected));
+
+        parser.advance(&mut handler, b"\x1b]7;file:///\x1b\\");
+
+        let expected = WorkingDirectory { hostname: String::new(), path: "/".into() };
+        assert_eq!(handler.working_directory, Some(expected));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn non_utf8_working_directory() {
+        let directory = parse_file_uri(b"file://host/tmp/%FF").unwrap();
+        assert_eq!(directory.path, PathBuf::from(OsString::from_vec(b"/tmp/\xff".to_vec())));
+    }
+
+    #[test]
+    fn invalid_working_directory() {
+        assert_eq!(parse_file_uri(b"file://host"), None);
+        assert_eq!(parse_file_uri(b"http://host/tmp"), None);
+        assert_eq!(parse_file_uri(b"file://host/tmp%2"), None);
+        assert_eq!(parse_file_uri(b"file://host/tmp%zz"), None);
+    }
+
+    #[test]
+    fn notifications() {
+        let mut handler = MockHandler::default();
+        let mut parser: Processor = Processor::new();
+
+        parser.advance(&mut handler, b"\x1b]9;done; ok\x07\x1b]9;4;1;50\x07");
+        parser.advance(&mut handler, b"\x1b]777;notify;Title;a;b\x1b\\\x1b]777;other\x07");
+
+        assert_eq!(handler.notifications, vec![
+            (String::new(), "done; ok".into(), Urgency::Normal),
+            ("Title".into(), "a;b".into(), Urgency::Normal),
+        ]);
+    }
+
+    #[test]
+    fn kitty_notifications() {
+        let mut handler = MockHandler::default();
+        let mut parser: Processor = Processor::new();
+
+        parser.advance(&mut handler, b"\x1b]99;;Hello\x1b\\");
+        parser.advance(&mut handler, b"\x1b]99;i=1:d=0:u=2;Ti\x1b\\\x1b]99;i=1:d=0;tle\x1b\\");
+        parser.advance(&mut handler, b"\x1b]99;i=1:p=body:e=1;Qm9keQ==\x1b\\");
+        parser.advance(&mut handler, b"\x1b]99;i=2:d=0;Lost\x1b\\\x1b]99;i=3:p=?;\x1b\\");
+
+        assert_eq!(handler.notifications, vec![
+            ("Hello".into(), String::new(), Urgency::Normal),
+            ("Title".into(), "Body".into(), Urgency::Critical),
+        ]);
+    }
+
+    #[test]
+    fn kitty_notification_size_limit() {
+        let mut handler = MockHandler::default();
+        let mut parser: Processor = Processor::new();
+
+        let chunk = [b"\x1b]99;i=1:d=0;".as_slice(), &[b'a'; MAX_OSC_LEN / 2], b"\x1b\\"].concat();
+        parser.advance(&mut handler, &chunk);
+        parser.advance(&mut handler, &chunk);
+        assert!(parser.notification.is_some());
+
+        // Exceeding the limit drops the partial notification.
+        parser.advance(&mut handler, &chunk);
+        assert!(parser.notification.is_none());
+
+        parser.advance(&mut handler, b"\x1b]99;i=1;\x1b\\");
+        assert_eq!(handler.notifications, vec![]);
+    }
+
+    #[test]
+    fn sixel() {
+        let mut handler = MockHandler::default();
+        let mut parser: Processor = Processor::new();
+
+        parser.advance(&mut handler, b"a\x1bP0;1;0q#1;2;100;0;0@\x1b\\b\x1bPq@\x18c\x1bP1$q\x1b\\");
+
+        let pixels = vec![255, 0, 0, 255];
+        assert_eq!(handler.graphics, vec![GraphicData { width: 1, height: 1, pixels }]);
+        assert_eq!(handler.text, "abc");
+    }
+
+    #[test]
+    fn graphics_attributes() {
+        let mut handler = MockHandler::default();
+        let mut parser: Processor = Processor::new();
+
+        parser.advance(&mut handler, b"\x1b[?2;1S\x1b[?1;4;0S\x1b[2S\x1b[?S\x1b[31mx");
+
+        assert_eq!(handler.graphics_attributes, vec![(2, 1), (1, 4)]);
+        assert_eq!(handler.text, "x");
+    }
+
+    #[test]
+    fn kitty_graphics() {
+        let mut handler = MockHandler::default();
+        let mut parser: Processor = Processor::new();
+
+        parser.advance(
+            &mut handler,
+            b"a\x1b_Ga=T,i=1;AAAA\x1b\\b\x1b_Gi=x\x1b\\\x1b_X\x1b\\\x1b_\x1b\\c",
+        );
+
+        assert_eq!(handler.kitty_graphics.len(), 1);
+        assert_eq!(handler.kitty_graphics[0].image_id, 1);
+        assert_eq!(handler.kitty_graphics[0].payload, b"AAAA");
+        assert_eq!(handler.text, "abc");
+    }
+}
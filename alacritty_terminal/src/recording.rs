@@ -0,0 +1,385 @@
+//! Session recording and replay in the asciicast v2 format.
+//!
+//! See <https://docs.asciinema.org/manual/asciicast/v2/> for the format specification.
+
+use std::borrow::Cow;
+use std::fmt::Write as _;
+use std::fs::File;
+use std::io::{self, BufWriter, ErrorKind, Write};
+use std::num::NonZeroUsize;
+use std::sync::Arc;
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use std::{env, str};
+
+use log::{error, info, warn};
+use polling::{Events, Poller};
+
+use crate::event::{Event, EventListener};
+use crate::event_loop::{EventLoopSender, Msg};
+use crate::grid::Dimensions;
+use crate::parser::Processor;
+use crate::sync::FairMutex;
+use crate::term::Term;
+use crate::thread;
+
+/// Maximum time to wait for the terminal to apply a recorded resize.
+const RESIZE_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Writer for asciicast v2 recordings.
+pub struct Recorder<W: Write = BufWriter<File>> {
+    writer: W,
+    start: Instant,
+
+    /// Trailing bytes of an incomplete UTF-8 sequence.
+    remainder: Vec<u8>,
+}
+
+impl Recorder {
+    /// Start a new recording in the specified file.
+    pub fn new(file: File, columns: usize, lines: usize) -> io::Result<Self> {
+        Self::with_writer(BufWriter::new(file), columns, lines)
+    }
+}
+
+impl<W: Write> Recorder<W> {
+    /// Start a new recording and write its header.
+    pub fn with_writer(mut writer: W, columns: usize, lines: usize) -> io::Result<Self> {
+        let timestamp = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+
+        let mut header = format!(
+            "{{\"version\":2,\"width\":{columns},\"height\":{lines},\"timestamp\":{}",
+            timestamp.as_secs()
+        );
+
+        // Add environment variables commonly used by players.
+        let env = ["TERM", "SHELL"]
+            .into_iter()
+            .filter_map(|key| Some((key, env::var(key).ok()?)))
+            .map(|(key, value)| format!("\"{key}\":{}", json_string(&value)))
+            .collect::<Vec<_>>();
+        if !env.is_empty() {
+            let _ = write!(header, ",\"env\":{{{}}}", env.join(","));
+        }
+
+        writeln!(writer, "{header}}}")?;
+
+        Ok(Self { writer, start: Instant::now(), remainder: Vec::new() })
+    }
+
+    /// Record bytes written to the terminal.
+    pub fn output(&mut self, bytes: &[u8]) -> io::Result<()> {
+        let bytes = if self.remainder.is_empty() {
+            Cow::Borrowed(bytes)
+        } else {
+            let mut remainder = std::mem::take(&mut self.remainder);
+            remainder.extend_from_slice(bytes);
+            Cow::Owned(remainder)
+        };
+
+        // Decode as much valid UTF-8 as possible, keeping incomplete sequences for later.
+        let mut text = String::new();
+        let mut bytes = &bytes[..];
+        while !bytes.is_empty() {
+            match str::from_utf8(bytes) {
+                Ok(valid) => {
+                    text.push_str(valid);
+                    break;
+                },
+                Err(err) => {
+                    let (valid, rest) = bytes.split_at(err.valid_up_to());
+                    text.push_str(str::from_utf8(valid).unwrap_or_default());
+
+                    match err.error_len() {
+                        Some(len) => {
+                            text.push(char::REPLACEMENT_CHARACTER);
+                            bytes = &rest[len..];
+                        },
+                        None => {
+                            self.remainder = rest.to_vec();
+                            break;
+                        },
+                    }
+                },
+            }
+        }
+
+        if text.is_empty() {
+            return Ok(());
+        }
+
+        self.event('o', &text)
+    }
+
+    /// Record a change of the terminal dimensions.
+    pub fn resize(&mut self, columns: usize, lines: usize) -> io::Result<()> {
+        self.event('r', &format!("{columns}x{lines}"))
+    }
+
+    /// Flush buffered events to the underlying writer.
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+
+    /// Write a single event line.
+    fn event(&mut self, code: char, data: &str) -> io::Result<()> {
+        let time = self.start.elapsed().as_secs_f64();
+        writeln!(self.writer, "[{time:.6}, \"{code}\", {}]", json_string(data))
+    }
+}
+
+/// Escape a string for use in JSON.
+fn json_string(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len() + 2);
+    escaped.push('"');
+    for c in text.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            c if (c as u32) < 0x20 || c == '\x7f' => {
+                let _ = write!(escaped, "\\u{:04x}", c as u32);
+            },
+            c => escaped.push(c),
+        }
+    }
+    escaped.push('"');
+    escaped
+}
+
+/// Single event of a recording.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Record {
+    /// Time since the start of the recording.
+    pub time: Duration,
+    pub kind: RecordKind,
+}
+
+/// Content of a recorded event.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RecordKind {
+    /// Data written to the terminal.
+    Output(String),
+
+    /// Terminal resize to the specified columns and lines.
+    Resize(usize, usize),
+}
+
+/// Playback of a recording into a terminal.
+///
+/// This is used in place of the [`EventLoop`] to drive a terminal without a PTY. Input and resize
+/// messages are ignored, since the terminal's content is determined by the recording.
+///
+/// [`EventLoop`]: crate::event_loop::EventLoop
+pub struct Replay<U: EventListener> {
+    poll: Arc<Poller>,
+    rx: Receiver<Msg>,
+    sender: EventLoopSender,
+    terminal: Arc<FairMutex<Term<U>>>,
+    event_proxy: U,
+    records: Vec<Record>,
+    speed: f64,
+    on_resize: Box<dyn FnMut(usize, usize) + Send>,
+}
+
+impl<U> Replay<U>
+where
+    U: EventListener + Send + 'static,
+{
+    /// Create a new replay.
+    ///
+    /// The `on_resize` callback is executed for recorded resizes and should change the terminal
+    /// dimensions accordingly.
+    pub fn new(
+        terminal: Arc<FairMutex<Term<U>>>,
+        event_proxy: U,
+        records: Vec<Record>,
+        speed: f64,
+        on_resize: Box<dyn FnMut(usize, usize) + Send>,
+    ) -> io::Result<Self> {
+        let (tx, rx) = mpsc::channel();
+        let poll: Arc<Poller> = Poller::new()?.into();
+        let sender = EventLoopSender::new(tx, poll.clone());
+        Ok(Self { poll, rx, sender, terminal, event_proxy, records, speed, on_resize })
+    }
+
+    pub fn channel(&self) -> EventLoopSender {
+        self.sender.clone()
+    }
+
+    pub fn spawn(mut self) -> JoinHandle<()> {
+        thread::spawn_named("replay", move || {
+            let mut parser: Processor = Processor::new();
+            let mut events = Events::with_capacity(NonZeroUsize::new(1).unwrap());
+            let mut start = Instant::now();
+
+            for record in std::mem::take(&mut self.records) {
+                let deadline = start + record.time.div_f64(self.speed);
+
+                // Wait for the record's deadline, ending synchronized updates on timeout.
+                loop {
+                    let sync_timeout = parser.sync_timeout().sync_timeout();
+                    let timeout = sync_timeout.map_or(deadline, |sync| sync.min(deadline));
+                    if !self.wait(&mut events, Some(timeout)) {
+                        return;
+                    }
+
+                    let now = Instant::now();
+                    if sync_timeout.is_some_and(|sync| sync <= now) {
+                        parser.stop_sync(&mut *self.terminal.lock());
+                        self.event_proxy.send_event(Event::Wakeup);
+                    }
+
+                    if deadline <= now {
+                        break;
+                    }
+                }
+
+                match record.kind {
+                    RecordKind::Output(text) => {
+                        parser.advance(&mut *self.terminal.lock(), text.as_bytes());
+                        if parser.sync_bytes_count() == 0 {
+                            self.event_proxy.send_event(Event::Wakeup);
+                        }
+                    },
+                    RecordKind::Resize(columns, lines) => {
+                        let resize_start = Instant::now();
+                        (self.on_resize)(columns, lines);
+
+                        // Give the window a chance to apply the resize before the next output.
+                        let timeout = resize_start + RESIZE_TIMEOUT;
+                        while Instant::now() < timeout {
+                            let terminal = self.terminal.lock();
+                            if terminal.columns() == columns && terminal.screen_lines() == lines {
+                                break;
+                            }
+                            drop(terminal);
+
+                            let poll_timeout = Instant::now() + Duration::from_millis(10);
+                            if !self.wait(&mut events, Some(poll_timeout.min(timeout))) {
+                                return;
+                            }
+                        }
+
+                        // Preserve the relative timing of later records.
+                        start += resize_start.elapsed();
+                    },
+                }
+            }
+
+            info!("Replay finished");
+
+            // Flush pending synchronized updates.
+            parser.stop_sync(&mut *self.terminal.lock());
+            self.event_proxy.send_event(Event::Wakeup);
+
+            // Keep the channel alive until shutdown.
+            while self.wait(&mut events, None) {}
+        })
+    }
+
+    /// Wait for new messages or the deadline.
+    ///
+    /// Returns `false` when a shutdown message was received.
+    fn wait(&mut self, events: &mut Events, deadline: Option<Instant>) -> bool {
+        events.clear();
+        let timeout = deadline.map(|deadline| deadline.saturating_duration_since(Instant::now()));
+        if let Err(err) = self.poll.wait(events, timeout) {
+            if err.kind() != ErrorKind::Interrupted {
+                error!("Replay polling error: {err}");
+                return false;
+            }
+        }
+
+        loop {
+            match self.rx.try_recv() {
+                Ok(Msg::Shutdown) | Err(TryRecvError::Disconnected) => return false,
+                Ok(Msg::StartRecording(_)) => warn!("Recording is not supported during replay"),
+                Ok(_) => (),
+                Err(TryRecvError::Empty) => return true,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::event::VoidListener;
+    use crate::index::{Column, Line, Point};
+    use crate::term::Config;
+    use crate::term::test::TermSize;
+
+    #[test]
+    fn recorder_output() {
+        let mut recorder = Recorder::with_writer(Vec::new(), 80, 24).unwrap();
+        recorder.output(b"a\"b\\\x1b[0m\r\n").unwrap();
+
+        // Incomplete UTF-8 sequences are written once they're complete.
+        recorder.output(&"é".as_bytes()[..1]).unwrap();
+        recorder.output(&"é".as_bytes()[1..]).unwrap();
+
+        // Invalid UTF-8 is replaced.
+        recorder.output(b"\xffx").unwrap();
+
+        recorder.resize(100, 30).unwrap();
+
+        let recording = String::from_utf8(recorder.writer).unwrap();
+        let mut lines = recording.lines();
+
+        let header: serde_json::Value = serde_json::from_str(lines.next().unwrap()).unwrap();
+        assert_eq!(header["version"], 2);
+        assert_eq!(header["width"], 80);
+        assert_eq!(header["height"], 24);
+
+        let events: Vec<(f64, String, String)> =
+            lines.map(|line| serde_json::from_str(line).unwrap()).collect();
+        let events: Vec<_> =
+            events.iter().map(|(_, code, data)| (code.as_str(), data.as_str())).collect();
+        assert_eq!(events, [
+            ("o", "a\"b\\\x1b[0m\r\n"),
+            ("o", "é"),
+            ("o", "\u{fffd}x"),
+            ("r", "100x30"),
+        ]);
+    }
+
+    #[test]
+    fn replay() {
+        let size = TermSize::new(10, 5);
+        let terminal = Arc::new(FairMutex::new(Term::new(Config::default(), &size, VoidListener)));
+
+        let resize_terminal = terminal.clone();
+        let on_resize = Box::new(move |columns, lines| {
+            resize_terminal.lock().resize(TermSize::new(columns, lines));
+        });
+
+        let records = vec![
+            Record { time: Duration::ZERO, kind: RecordKind::Output("a".into()) },
+            Record { time: Duration::from_millis(10), kind: RecordKind::Resize(20, 3) },
+            Record { time: Duration::from_millis(20), kind: RecordKind::Output("b".into()) },
+        ];
+        let replay = Replay::new(terminal.clone(), VoidListener, records, 10., on_resize).unwrap();
+        let sender = replay.channel();
+        let thread = replay.spawn();
+
+        // Wait for the replay to process all records.
+        let start = Instant::now();
+        while terminal.lock().grid()[Point::new(Line(0), Column(1))].c != 'b' {
+            assert!(start.elapsed() < Duration::from_secs(5));
+            std::thread::sleep(Duration::from_millis(1));
+        }
+
+        sender.send(Msg::Shutdown).unwrap();
+        thread.join().unwrap();
+
+        let terminal = terminal.lock();
+        assert_eq!(terminal.columns(), 20);
+        assert_eq!(terminal.screen_lines(), 3);
+        assert_eq!(terminal.grid()[Point::new(Line(0), Column(0))].c, 'a');
+    }
+}
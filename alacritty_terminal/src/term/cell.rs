@@ -5,6 +5,7 @@ use bitflags::bitflags;
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 
+use crate::graphics::GraphicCell;
 use crate::grid::{self, GridCell};
 use crate::index::Column;
 use crate::vte::ansi::{Color, Hyperlink as VteHyperlink, NamedColor};
@@ -126,6 +127,8 @@ pub struct CellExtra {
     zerowidth: Vec<char>,
     underline_color: Option<Color>,
     hyperlink: Option<Hyperlink>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    graphic: Option<GraphicCell>,
 }
 
 /// Content and attributes of a single cell in the terminal grid.
@@ -180,10 +183,9 @@ impl Cell {
     pub fn set_underline_color(&mut self, color: Option<Color>) {
         // If we reset color and we don't have zerowidth we should drop extra storage.
         if color.is_none()
-            && self
-                .extra
-                .as_ref()
-                .is_none_or(|extra| extra.zerowidth.is_empty() && extra.hyperlink.is_none())
+            && self.extra.as_ref().is_none_or(|extra| {
+                extra.zerowidth.is_empty() && extra.hyperlink.is_none() && extra.graphic.is_none()
+            })
         {
             self.extra = None;
         } else {
@@ -201,10 +203,11 @@ impl Cell {
     /// Set hyperlink.
     pub fn set_hyperlink(&mut self, hyperlink: Option<Hyperlink>) {
         let should_drop = hyperlink.is_none()
-            && self
-                .extra
-                .as_ref()
-                .is_none_or(|extra| extra.zerowidth.is_empty() && extra.underline_color.is_none());
+            && self.extra.as_ref().is_none_or(|extra| {
+                extra.zerowidth.is_empty()
+                    && extra.underline_color.is_none()
+                    && extra.graphic.is_none()
+            });
 
         if should_drop {
             self.extra = None;
@@ -219,6 +222,86 @@ impl Cell {
     pub fn hyperlink(&self) -> Option<Hyperlink> {
         self.extra.as_ref()?.hyperlink.clone()
     }
+
+    /// Graphic stored in this cell.
+    #[inline]
+    pub fn graphic(&self) -> Option<&GraphicCell> {
+        self.extra.as_ref()?.graphic.as_ref()
+    }
+
+    /// Set the graphic displayed in this cell.
+    pub fn set_graphic(&mut self, graphic: Option<GraphicCell>) {
+        let should_drop = graphic.is_none()
+            && self.extra.as_ref().is_none_or(|extra| {
+                extra.zerowidth.is_empty()
+                    && extra.underline_color.is_none()
+                    && extra.hyperlink.is_none()
+            });
+
+        if should_drop {
+            self.extra = None;
+        } else {
+            let extra = self.extra.get_or_insert(Default::default());
+            Arc::make_mut(extra).graphic = graphic;
+        }
+    }
+
+    /// SGR parameters reproducing the cell's text attributes.
+    ///
+    /// The parameters are separated by semicolons and do not include the initial reset.
+    pub(crate) fn sgr_parameters(&self) -> String {
+        let mut params = Vec::new();
+
+        let attributes = [
+            (Flags::BOLD, "1"),
+            (Flags::DIM, "2"),
+            (Flags::ITALIC, "3"),
+            (Flags::UNDERLINE, "4"),
+            (Flags::DOUBLE_UNDERLINE, "4:2"),
+            (Flags::UNDERCURL, "4:3"),
+            (Flags::DOTTED_UNDERLINE, "4:4"),
+            (Flags::DASHED_UNDERLINE, "4:5"),
+            (Flags::INVERSE, "7"),
+            (Flags::HIDDEN, "8"),
+            (Flags::STRIKEOUT, "9"),
+        ];
+        for (flag, param) in attributes {
+            if self.flags.contains(flag) {
+                params.push(param.into());
+            }
+        }
+
+        params.extend(sgr_color(self.fg, Some((30, 90)), "38"));
+        params.extend(sgr_color(self.bg, Some((40, 100)), "48"));
+        if let Some(color) = self.underline_color() {
+            params.extend(sgr_color(color, None, "58"));
+        }
+
+        params.join(";")
+    }
+}
+
+/// SGR parameter for a color.
+///
+/// Named colors use the short form with the regular and bright bases, if they are available.
+/// Returns `None` for the default colors.
+fn sgr_color(color: Color, named_bases: Option<(usize, usize)>, extended: &str) -> Option<String> {
+    let index = match color {
+        Color::Spec(rgb) => return Some(format!("{extended};2;{};{};{}", rgb.r, rgb.g, rgb.b)),
+        Color::Indexed(index) => return Some(format!("{extended};5;{index}")),
+        Color::Named(named) if (named as usize) < 16 => named as usize,
+        // Dim colors are already covered by the dim attribute.
+        Color::Named(named) if (NamedColor::DimBlack..=NamedColor::DimWhite).contains(&named) => {
+            named as usize - NamedColor::DimBlack as usize
+        },
+        Color::Named(_) => return None,
+    };
+
+    match named_bases {
+        Some((base, _)) if index < 8 => Some((base + index).to_string()),
+        Some((_, bright_base)) => Some((bright_base + index - 8).to_string()),
+        None => Some(format!("{extended};5;{index}")),
+    }
 }
 
 impl GridCell for Cell {
@@ -235,7 +318,10 @@ impl GridCell for Cell {
                     | Flags::WIDE_CHAR_SPACER
                     | Flags::LEADING_WIDE_CHAR_SPACER,
             )
-            && self.extra.as_ref().map(|extra| extra.zerowidth.is_empty()) != Some(false)
+            && self
+                .extra
+                .as_ref()
+                .is_none_or(|extra| extra.zerowidth.is_empty() && extra.graphic.is_none())
     }
 
     #[inline]
@@ -252,6 +338,16 @@ impl GridCell for Cell {
     fn reset(&mut self, template: &Self) {
         *self = Cell { bg: template.bg, ..Cell::default() };
     }
+
+    #[inline]
+    fn split_char(&self) -> (char, Self) {
+        (self.c, Cell { c: ' ', ..self.clone() })
+    }
+
+    #[inline]
+    fn join_char(c: char, template: &Self) -> Self {
+        Cell { c, ..template.clone() }
+    }
 }
 
 impl From<Color> for Cell {
@@ -296,6 +392,7 @@ mod tests {
 
     use crate::grid::Row;
     use crate::index::Column;
+    use crate::vte::ansi::Rgb;
 
     #[test]
     fn cell_size_is_below_cap() {
@@ -321,4 +418,25 @@ mod tests {
 
         assert_eq!(row.line_length(), Column(10));
     }
+
+    #[test]
+    fn sgr_parameters() {
+        let mut cell = Cell::default();
+        assert_eq!(cell.sgr_parameters(), "");
+
+        cell.flags = Flags::BOLD | Flags::UNDERCURL;
+        cell.fg = Color::Named(NamedColor::BrightRed);
+        cell.bg = Color::Named(NamedColor::Blue);
+        assert_eq!(cell.sgr_parameters(), "1;4:3;91;44");
+
+        cell.flags = Flags::DIM;
+        cell.fg = Color::Named(NamedColor::DimGreen);
+        cell.bg = Color::Indexed(200);
+        cell.set_underline_color(Some(Color::Named(NamedColor::Cyan)));
+        assert_eq!(cell.sgr_parameters(), "2;32;48;5;200;58;5;6");
+
+        cell = Cell::default();
+        cell.fg = Color::Spec(Rgb { r: 1, g: 2, b: 3 });
+        assert_eq!(cell.sgr_parameters(), "38;2;1;2;3");
+    }
 }
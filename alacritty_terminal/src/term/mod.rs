@@ -1,6 +1,7 @@
 //! Exports the `Term` type which is a high-level API for the Grid.
 
-use std::ops::{Index, IndexMut, Range};
+use std::ops::{Index, IndexMut, Range, RangeInclusive};
+use std::path::PathBuf;
 use std::sync::Arc;
 use std::{cmp, mem, ptr, slice, str};
 
@@ -13,9 +14,15 @@ use bitflags::bitflags;
 use log::{debug, trace};
 use unicode_width::UnicodeWidthChar;
 
-use crate::event::{Event, EventListener};
-use crate::grid::{Dimensions, Grid, GridIterator, Scroll};
+use crate::event::{Event, EventListener, WindowSize};
+use crate::graphics::kitty::{self, Action as KittyAction};
+use crate::graphics::sixel::MAX_COLOR_REGISTERS;
+use crate::graphics::{
+    GraphicCell, GraphicData, Graphics, MAX_GRAPHIC_DIMENSIONS, Placement, SourceRect,
+};
+use crate::grid::{Dimensions, Grid, GridIterator, PromptMarks, Row, Scroll};
 use crate::index::{self, Boundary, Column, Direction, Line, Point, Side};
+use crate::parser;
 use crate::selection::{Selection, SelectionRange, SelectionType};
 use crate::term::cell::{Cell, Flags, LineLength};
 use crate::term::color::Colors;
@@ -316,6 +323,15 @@ pub struct Term<T> {
     /// term is set.
     title_stack: Vec<Option<String>>,
 
+    /// Working directory reported by the shell.
+    working_directory: Option<WorkingDirectory>,
+
+    /// Inline graphics state.
+    graphics: Graphics,
+
+    /// Size of the window's text area, used for placing graphics.
+    window_size: Option<WindowSize>,
+
     /// The stack for the keyboard modes.
     keyboard_mode_stack: Vec<KeyboardModes>,
 
@@ -333,6 +349,9 @@ pub struct Term<T> {
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Config {
     /// The maximum amount of scrolling history.
+    ///
+    /// Use [`UNLIMITED_HISTORY`](crate::grid::UNLIMITED_HISTORY) to keep all lines, moving old
+    /// lines into a temporary file.
     pub scrolling_history: usize,
 
     /// Default cursor style to reset the cursor to.
@@ -437,6 +456,9 @@ impl<T> Term<T> {
             cursor_style: Default::default(),
             colors: color::Colors::default(),
             title_stack: Default::default(),
+            working_directory: Default::default(),
+            graphics: Default::default(),
+            window_size: Default::default(),
             is_focused: Default::default(),
             selection: Default::default(),
             title: Default::default(),
@@ -495,6 +517,12 @@ impl<T> Term<T> {
         self.damage.full = true;
     }
 
+    /// Current title of the window.
+    #[inline]
+    pub fn title(&self) -> Option<&str> {
+        self.title.as_deref()
+    }
+
     /// Set new options for the [`Term`].
     pub fn set_options(&mut self, options: Config)
     where
@@ -536,12 +564,13 @@ impl<T> Term<T> {
             Some(Selection { ty: SelectionType::Block, .. }) => {
                 for line in (start.line.0..end.line.0).map(Line::from) {
                     res += self
-                        .line_to_string(line, start.column..end.column, start.column.0 != 0)
+                        .line_to_string(line, start.column..end.column, start.column.0 != 0, false)
                         .trim_end();
                     res += "\n";
                 }
 
-                res += self.line_to_string(end.line, start.column..end.column, true).trim_end();
+                res +=
+                    self.line_to_string(end.line, start.column..end.column, true, false).trim_end();
             },
             Some(Selection { ty: SelectionType::Lines, .. }) => {
                 res = self.bounds_to_string(start, end) + "\n";
@@ -556,13 +585,25 @@ impl<T> Term<T> {
 
     /// Convert range between two points to a String.
     pub fn bounds_to_string(&self, start: Point, end: Point) -> String {
+        self.bounds_to_text(start, end, false)
+    }
+
+    /// Convert range between two points to a String, with SGR escapes for the text attributes.
+    ///
+    /// All attributes are reset at the end of every line.
+    pub fn bounds_to_ansi(&self, start: Point, end: Point) -> String {
+        self.bounds_to_text(start, end, true)
+    }
+
+    /// Convert range between two points to a String, optionally including SGR escapes.
+    fn bounds_to_text(&self, start: Point, end: Point, ansi: bool) -> String {
         let mut res = String::new();
 
         for line in (start.line.0..=end.line.0).map(Line::from) {
             let start_col = if line == start.line { start.column } else { Column(0) };
             let end_col = if line == end.line { end.column } else { self.last_column() };
 
-            res += &self.line_to_string(line, start_col..end_col, line == end.line);
+            res += &self.line_to_string(line, start_col..end_col, line == end.line, ansi);
         }
 
         res.strip_suffix('\n').map(str::to_owned).unwrap_or(res)
@@ -574,8 +615,10 @@ impl<T> Term<T> {
         line: Line,
         mut cols: Range<Column>,
         include_wrapped_wide: bool,
+        ansi: bool,
     ) -> String {
         let mut text = String::new();
+        let mut sgr = String::new();
 
         let grid_line = &self.grid[line];
         let line_length = cmp::min(grid_line.line_length(), cols.end + 1);
@@ -603,6 +646,20 @@ impl<T> Term<T> {
             }
 
             if !cell.flags.intersects(Flags::WIDE_CHAR_SPACER | Flags::LEADING_WIDE_CHAR_SPACER) {
+                // Update text attributes when they changed.
+                if ansi {
+                    let cell_sgr = cell.sgr_parameters();
+                    if cell_sgr != sgr {
+                        text.push_str("\x1b[0");
+                        if !cell_sgr.is_empty() {
+                            text.push(';');
+                            text.push_str(&cell_sgr);
+                        }
+                        text.push('m');
+                        sgr = cell_sgr;
+                    }
+                }
+
                 // Push cells primary character.
                 text.push(cell.c);
 
@@ -613,6 +670,11 @@ impl<T> Term<T> {
             }
         }
 
+        // Reset attributes before the end of the line.
+        if !sgr.is_empty() {
+            text.push_str("\x1b[0m");
+        }
+
         if cols.end >= self.columns() - 1
             && (line_length.0 == 0
                 || !self.grid[line][line_length - 1].flags.contains(Flags::WRAPLINE))
@@ -651,6 +713,49 @@ impl<T> Term<T> {
         &mut self.grid
     }
 
+    /// Access to the primary grid, even while the alternate screen is active.
+    pub fn primary_grid(&self) -> &Grid<Cell> {
+        if self.mode.contains(TermMode::ALT_SCREEN) { &self.inactive_grid } else { &self.grid }
+    }
+
+    /// Insert rows from a previous session into the scrollback history.
+    ///
+    /// The rows are resized to the terminal's columns and the visible region is left empty, so
+    /// this should be called before any output is written to the terminal.
+    pub fn restore_history<I>(&mut self, rows: I)
+    where
+        I: IntoIterator<Item = Row<Cell>>,
+    {
+        let columns = self.columns();
+        let screen_lines = self.screen_lines();
+        let region = Line(0)..Line(screen_lines as i32);
+
+        let mut line = Line(0);
+        for mut row in rows {
+            if row.len() < columns {
+                row.grow(columns);
+            } else if row.len() > columns {
+                row.shrink(columns);
+            }
+
+            // Push the topmost line into history once the screen is full.
+            if line == screen_lines {
+                self.grid.scroll_up(&region, 1);
+                line -= 1;
+            }
+
+            self.grid[line] = row;
+            line += 1;
+        }
+
+        // Move all restored lines into history.
+        if line.0 > 0 {
+            self.grid.scroll_up(&region, line.0 as usize);
+        }
+
+        self.mark_fully_damaged();
+    }
+
     /// Resize terminal to new dimensions.
     pub fn resize<S: Dimensions>(&mut self, size: S) {
         let old_cols = self.columns();
@@ -897,6 +1002,42 @@ impl<T> Term<T> {
         }
     }
 
+    /// Find the closest line with a prompt start mark in the specified direction.
+    ///
+    /// The `line` itself is never considered a match.
+    pub fn prompt_line(&self, line: Line, direction: Direction) -> Option<Line> {
+        let is_prompt =
+            |line: &i32| self.grid[Line(*line)].marks().contains(PromptMarks::PROMPT_START);
+
+        match direction {
+            Direction::Left => (*self.topmost_line()..*line).rev().find(is_prompt).map(Line),
+            Direction::Right => (*line + 1..=*self.bottommost_line()).find(is_prompt).map(Line),
+        }
+    }
+
+    /// Bounds of the output produced by the last finished command.
+    ///
+    /// This relies on the shell emitting OSC 133 marks. Commands which did not produce any
+    /// output are skipped.
+    pub fn last_command_output(&self) -> Option<RangeInclusive<Point>> {
+        let mut end = None;
+
+        for line in (*self.topmost_line()..=*self.bottommost_line()).rev().map(Line) {
+            let marks = self.grid[line].marks();
+
+            // Output ends on the line before the next prompt.
+            if let Some(end) = end.filter(|_| marks.contains(PromptMarks::OUTPUT_START)) {
+                return Some(Point::new(line, Column(0))..=Point::new(end - 1, self.last_column()));
+            }
+
+            if marks.intersects(PromptMarks::PROMPT_START | PromptMarks::COMMAND_END) {
+                end = Some(line);
+            }
+        }
+
+        None
+    }
+
     /// Jump to the end of a wide cell.
     pub fn expand_wide(&self, mut point: Point, direction: Direction) -> Point {
         let flags = self.grid[point.line][point.column].flags;
@@ -953,6 +1094,358 @@ impl<T> Term<T> {
         &self.colors
     }
 
+    /// Working directory reported by the shell through OSC 7.
+    #[inline]
+    pub fn working_directory(&self) -> Option<&WorkingDirectory> {
+        self.working_directory.as_ref()
+    }
+
+    /// Update the size of the window's text area.
+    ///
+    /// This is required for placing graphics, since their size is measured in pixels.
+    #[inline]
+    pub fn set_window_size(&mut self, window_size: WindowSize) {
+        self.window_size = Some(window_size);
+    }
+
+    /// Inline graphics state.
+    #[inline]
+    pub fn graphics(&self) -> &Graphics {
+        &self.graphics
+    }
+
+    /// Inline graphics state.
+    #[inline]
+    pub fn graphics_mut(&mut self) -> &mut Graphics {
+        &mut self.graphics
+    }
+
+    /// Cell dimensions in pixels, if they are known.
+    fn cell_size(&self) -> Option<(usize, usize)> {
+        self.window_size
+            .map(|size| (size.cell_width as usize, size.cell_height as usize))
+            .filter(|&(width, height)| width > 0 && height > 0)
+    }
+
+    /// Anchor a graphic to the cells starting at the cursor.
+    ///
+    /// The offsets of `graphic` are used for the cursor cell. When `move_cursor` is set, the
+    /// cursor is moved to the last line of the graphic, scrolling if necessary. Otherwise the
+    /// graphic is clipped at the bottom of the screen and the cursor is left in place.
+    fn place_graphic(
+        &mut self,
+        graphic: GraphicCell,
+        columns: usize,
+        lines: usize,
+        move_cursor: bool,
+    ) where
+        T: EventListener,
+    {
+        let Some((cell_width, cell_height)) = self.cell_size() else { return };
+
+        let origin = self.grid.cursor.point;
+        let end = cmp::min(origin.column + columns, Column(self.columns()));
+        for line in 0..lines {
+            if line > 0 && move_cursor {
+                self.linefeed();
+            } else if line > 0 {
+                if self.grid.cursor.point.line >= self.bottommost_line() {
+                    break;
+                }
+                self.grid.cursor.point.line += 1;
+            }
+
+            let cursor_line = self.grid.cursor.point.line;
+            for column in origin.column.0..end.0 {
+                let mut graphic = graphic.clone();
+                graphic.offset_x += ((column - origin.column.0) * cell_width) as i32;
+                graphic.offset_y += (line * cell_height) as i32;
+                self.grid[cursor_line][Column(column)].set_graphic(Some(graphic));
+            }
+        }
+
+        if !move_cursor {
+            self.grid.cursor.point = origin;
+        }
+
+        self.mark_fully_damaged();
+    }
+
+    /// Remove all graphic placements matching a predicate from the grid.
+    fn remove_placements(&mut self, predicate: impl Fn(&Placement) -> bool) {
+        self.grid.update_cells(|cell| {
+            if cell.graphic().is_some_and(|graphic| predicate(&graphic.placement)) {
+                cell.set_graphic(None);
+            }
+        });
+
+        self.graphics.kitty.remove_virtual_placements(predicate);
+        self.mark_fully_damaged();
+    }
+
+    /// Find all graphic placements visible on the screen matching a predicate.
+    fn visible_placements(&self, predicate: impl Fn(Point, &Placement) -> bool) -> Vec<Placement> {
+        let mut placements: Vec<Placement> = Vec::new();
+        for line in (0..self.screen_lines() as i32).map(Line) {
+            for column in (0..self.columns()).map(Column) {
+                let Some(graphic) = self.grid[line][column].graphic() else { continue };
+                let point = Point::new(line, column);
+                if predicate(point, &graphic.placement)
+                    && placements.iter().all(|placement| placement.id != graphic.placement.id)
+                {
+                    placements.push(graphic.placement);
+                }
+            }
+        }
+        placements
+    }
+
+    /// Store a transmitted kitty image, displaying it if requested.
+    ///
+    /// Returns the ID of the image.
+    fn kitty_transmit(
+        &mut self,
+        command: &kitty::Command,
+        data: Vec<u8>,
+    ) -> Result<u32, kitty::Error>
+    where
+        T: EventListener,
+    {
+        let graphic = kitty::load(command, data)?;
+        if command.action == KittyAction::Query {
+            return Ok(command.image_id);
+        }
+
+        trace!("Storing {}x{} kitty image", graphic.width, graphic.height);
+
+        // Transmitting an image with an existing ID replaces it.
+        if command.image_id != 0 && self.graphics.kitty.get(command.image_id, 0).is_some() {
+            let image_id = command.image_id;
+            self.remove_placements(|placement| placement.image_id == image_id);
+        }
+
+        let (width, height) = (graphic.width as u32, graphic.height as u32);
+        let texture = self.graphics.add(graphic);
+        let image = kitty::Image::new(texture, width, height, command.image_number);
+        let reserved = self.graphics.sixel_memory_usage();
+        let image_id = self.graphics.kitty.insert(command.image_id, image, reserved);
+
+        if command.action == KittyAction::TransmitAndDisplay {
+            self.kitty_display(command, image_id)?;
+        }
+
+        Ok(image_id)
+    }
+
+    /// Display a stored kitty image.
+    ///
+    /// Returns the ID of the image.
+    fn kitty_display(
+        &mut self,
+        command: &kitty::Command,
+        image_id: u32,
+    ) -> Result<u32, kitty::Error>
+    where
+        T: EventListener,
+    {
+        let Some((cell_width, cell_height)) = self.cell_size() else {
+            return Err(kitty::Error::new("EINVAL", "unknown cell dimensions"));
+        };
+
+        let Some((image_id, image)) = self.graphics.kitty.get(image_id, command.image_number)
+        else {
+            return Err(kitty::Error::new("ENOENT", "image not found"));
+        };
+        let image = image.clone();
+
+        // Clip the displayed region to the image.
+        let x = command.source_x.min(image.width);
+        let y = command.source_y.min(image.height);
+        let source_width = match command.source_width {
+            0 => image.width - x,
+            width => width.min(image.width - x),
+        };
+        let source_height = match command.source_height {
+            0 => image.height - y,
+            height => height.min(image.height - y),
+        };
+        if source_width == 0 || source_height == 0 {
+            return Err(kitty::Error::new("EINVAL", "empty source rectangle"));
+        }
+        let source = SourceRect { x, y, width: source_width, height: source_height };
+
+        let (cell_width, cell_height) = (cell_width as u32, cell_height as u32);
+        let (max_width, max_height) =
+            (MAX_GRAPHIC_DIMENSIONS.0 as u32, MAX_GRAPHIC_DIMENSIONS.1 as u32);
+
+        // Limit the placement to the maximum graphic size, since the number of cells is
+        // controlled by the application.
+        let columns = command.columns.min((max_width / cell_width).max(1));
+        let lines = command.lines.min((max_height / cell_height).max(1));
+        let to_pixels = |cells: u32, cell_size: u32| {
+            cells
+                .checked_mul(cell_size)
+                .ok_or_else(|| kitty::Error::new("EINVAL", "placement too large"))
+        };
+
+        let scale = |size: u32, from: u32, to: u32, max: u32| {
+            (size as u64 * to as u64 / from as u64).clamp(1, max as u64) as u32
+        };
+        let (width, height) = match (columns, lines) {
+            (0, 0) => (source_width, source_height),
+            (columns, 0) => {
+                let width = to_pixels(columns, cell_width)?;
+                (width, scale(source_height, source_width, width, max_height))
+            },
+            (0, lines) => {
+                let height = to_pixels(lines, cell_height)?;
+                (scale(source_width, source_height, height, max_width), height)
+            },
+            (columns, lines) => (to_pixels(columns, cell_width)?, to_pixels(lines, cell_height)?),
+        };
+
+        // Placements with an existing ID replace the previous one.
+        let placement_id = command.placement_id;
+        if placement_id != 0 {
+            self.remove_placements(|placement| {
+                placement.image_id == image_id && placement.placement_id == placement_id
+            });
+        }
+
+        let mut placement = Placement {
+            id: self.graphics.next_placement_id(),
+            image_id,
+            placement_id,
+            source,
+            width,
+            height,
+            z_index: command.z_index,
+        };
+
+        if command.virtual_placement {
+            let columns = if columns == 0 { width.div_ceil(cell_width) } else { columns };
+            let lines = if lines == 0 { height.div_ceil(cell_height) } else { lines };
+
+            // Fit the image into the placeholder area, preserving its aspect ratio.
+            let area_width = to_pixels(columns, cell_width)?;
+            let area_height = to_pixels(lines, cell_height)?;
+            if (area_width as u64 * source_height as u64)
+                < (area_height as u64 * source_width as u64)
+            {
+                placement.width = area_width;
+                placement.height = scale(source_height, source_width, area_width, area_height);
+            } else {
+                placement.width = scale(source_width, source_height, area_height, area_width);
+                placement.height = area_height;
+            }
+
+            self.graphics.kitty.insert_virtual_placement(kitty::VirtualPlacement {
+                texture: image.texture,
+                placement,
+                columns,
+                lines,
+                cell_width,
+                cell_height,
+                offset_x: (area_width - placement.width) / 2,
+                offset_y: (area_height - placement.height) / 2,
+            });
+
+            return Ok(image_id);
+        }
+
+        let offset_x = command.cell_offset_x.min(cell_width - 1);
+        let offset_y = command.cell_offset_y.min(cell_height - 1);
+        let columns = (offset_x + width).div_ceil(cell_width) as usize;
+        let lines = (offset_y + height).div_ceil(cell_height) as usize;
+
+        let graphic = GraphicCell {
+            texture: image.texture,
+            placement,
+            offset_x: -(offset_x as i32),
+            offset_y: -(offset_y as i32),
+        };
+        self.place_graphic(graphic, columns, lines, !command.keep_cursor);
+
+        // Move the cursor behind the image.
+        if !command.keep_cursor {
+            let column = self.grid.cursor.point.column + columns;
+            self.grid.cursor.point.column = cmp::min(column, self.last_column());
+            self.grid.cursor.input_needs_wrap = false;
+        }
+
+        Ok(image_id)
+    }
+
+    /// Delete kitty image placements.
+    ///
+    /// Uppercase targets also free the stored images of the deleted placements.
+    fn kitty_delete(&mut self, command: &kitty::Command) {
+        let free = command.delete.is_ascii_uppercase();
+        let cursor = self.grid.cursor.point;
+        let (x, y) = (command.source_x, command.source_y);
+        let cell = Point::new(Line(y as i32 - 1), Column((x as usize).saturating_sub(1)));
+
+        let image_ids: Vec<u32> = match command.delete.to_ascii_lowercase() {
+            b'i' => vec![command.image_id],
+            b'n' => self.graphics.kitty.ids_with_number(command.image_number),
+            b'r' => {
+                self.remove_placements(|placement| (x..=y).contains(&placement.image_id));
+                if free {
+                    self.graphics.kitty.remove_matching(|id, _| (x..=y).contains(&id));
+                }
+                return;
+            },
+            target => {
+                let z_index = command.z_index;
+                let matches = |point: Point, placement: &Placement| {
+                    placement.image_id != 0
+                        && match target {
+                            b'a' => true,
+                            b'c' => point == cursor,
+                            b'p' => point == cell,
+                            b'x' => point.column == cell.column,
+                            b'y' => point.line == cell.line,
+                            b'z' => placement.z_index == z_index,
+                            _ => false,
+                        }
+                };
+
+                if !b"acpxyz".contains(&target) {
+                    debug!("[unhandled] Kitty graphics deletion target {}", target as char);
+                    return;
+                }
+
+                let placements = self.visible_placements(matches);
+                let ids: Vec<u64> = placements.iter().map(|placement| placement.id).collect();
+                self.remove_placements(|placement| ids.contains(&placement.id));
+
+                // Clearing all placements also removes the virtual placements.
+                if target == b'a' {
+                    self.graphics.kitty.remove_virtual_placements(|_| true);
+                }
+
+                if free {
+                    for placement in placements {
+                        self.graphics.kitty.remove(placement.image_id);
+                    }
+                }
+                return;
+            },
+        };
+
+        let placement_id = command.placement_id;
+        for image_id in image_ids {
+            self.remove_placements(|placement| {
+                placement.image_id == image_id
+                    && (placement_id == 0 || placement.placement_id == placement_id)
+            });
+
+            if free {
+                self.graphics.kitty.remove(image_id);
+            }
+        }
+    }
+
     /// Insert a linebreak at the current cursor position.
     #[inline]
     fn wrapline(&mut self)
@@ -1258,7 +1751,7 @@ impl<T: EventListener> Handler for Term<T> {
         match intermediate {
             None => {
                 trace!("Reporting primary device attributes");
-                let text = String::from("\x1b[?6c");
+                let text = String::from("\x1b[?62;4c");
                 self.event_proxy.send_event(Event::PtyWrite(text));
             },
             Some('>') => {
@@ -2271,6 +2764,122 @@ impl<T: EventListener> Handler for Term<T> {
     }
 }
 
+impl<T: EventListener> parser::Handler for Term<T> {
+    #[inline]
+    fn prompt_mark(&mut self, mark: PromptMarks) {
+        trace!("Setting prompt mark: {mark:?}");
+        let line = self.grid.cursor.point.line;
+        self.grid[line].insert_mark(mark);
+    }
+
+    #[inline]
+    fn set_working_directory(&mut self, directory: WorkingDirectory) {
+        trace!("Setting working directory: {directory:?}");
+        self.event_proxy.send_event(Event::WorkingDirectory(directory.clone()));
+        self.working_directory = Some(directory);
+    }
+
+    #[inline]
+    fn desktop_notification(&mut self, title: String, body: String, urgency: Urgency) {
+        trace!("Requesting desktop notification: {title:?} {body:?} ({urgency:?})");
+        self.event_proxy.send_event(Event::Notification { title, body, urgency });
+    }
+
+    #[inline]
+    fn insert_graphic(&mut self, graphic: GraphicData) {
+        let Some((cell_width, cell_height)) = self.cell_size() else {
+            debug!("Ignoring graphic with unknown cell dimensions");
+            return;
+        };
+
+        trace!("Inserting {}x{} graphic", graphic.width, graphic.height);
+
+        let columns = graphic.width.div_ceil(cell_width);
+        let lines = graphic.height.div_ceil(cell_height);
+        let (width, height) = (graphic.width as u32, graphic.height as u32);
+        let placement = Placement {
+            id: self.graphics.next_placement_id(),
+            source: SourceRect { x: 0, y: 0, width, height },
+            width,
+            height,
+            ..Default::default()
+        };
+
+        let Some(texture) = self.graphics.add_sixel(graphic) else {
+            debug!("Ignoring graphic exceeding the storage quota");
+            return;
+        };
+        let graphic = GraphicCell { texture, placement, offset_x: 0, offset_y: 0 };
+        self.place_graphic(graphic, columns, lines, true);
+
+        // Move the cursor below the graphic.
+        self.linefeed();
+        self.grid.cursor.input_needs_wrap = false;
+    }
+
+    #[inline]
+    fn kitty_graphics(&mut self, command: kitty::Command) {
+        // Continuation chunks only contain the payload, so everything received during a chunked
+        // transmission is part of it.
+        let receiving = self.graphics.kitty.receiving();
+        let (command, result) = match command.action {
+            KittyAction::Delete if !receiving => {
+                trace!("Deleting kitty graphics: {}", command.delete as char);
+                self.kitty_delete(&command);
+                return;
+            },
+            KittyAction::Display if !receiving => {
+                let result = self.kitty_display(&command, command.image_id);
+                (command, result)
+            },
+            _ => {
+                let Some((command, data)) = self.graphics.kitty.receive(command) else { return };
+                let result = data.and_then(|data| self.kitty_transmit(&command, data));
+                (command, result)
+            },
+        };
+
+        if let Err(err) = &result {
+            debug!("Kitty graphics command failed: {err}");
+        }
+
+        if let Some(response) = command.response(&result) {
+            self.event_proxy.send_event(Event::PtyWrite(response));
+        }
+    }
+
+    #[inline]
+    fn graphics_attribute(&mut self, item: u16, action: u16) {
+        trace!("Reporting graphics attribute {item} for action {action}");
+
+        let text = match (item, action) {
+            // Number of color registers.
+            (1, 1..=4) => format!("\x1b[?1;0;{MAX_COLOR_REGISTERS}S"),
+            // Sixel geometry, limited by the text area.
+            (2, 1..=3) => {
+                let (max_width, max_height) = MAX_GRAPHIC_DIMENSIONS;
+                let (width, height) = self.window_size.map_or((max_width, max_height), |size| {
+                    let width = size.num_cols as usize * size.cell_width as usize;
+                    let height = size.num_lines as usize * size.cell_height as usize;
+                    (width.min(max_width), height.min(max_height))
+                });
+                format!("\x1b[?2;0;{width};{height}S")
+            },
+            // Maximum sixel geometry.
+            (2, 4) => {
+                let (width, height) = MAX_GRAPHIC_DIMENSIONS;
+                format!("\x1b[?2;0;{width};{height}S")
+            },
+            // Invalid action.
+            (1 | 2, _) => format!("\x1b[?{item};2S"),
+            // Unsupported item.
+            _ => format!("\x1b[?{item};1S"),
+        };
+
+        self.event_proxy.send_event(Event::PtyWrite(text));
+    }
+}
+
 /// The state of the [`Mode`] and [`PrivateMode`].
 #[repr(u8)]
 #[derive(Debug, Clone, Copy)]
@@ -2316,6 +2925,27 @@ pub enum ClipboardType {
     Selection,
 }
 
+/// Working directory reported through OSC 7.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WorkingDirectory {
+    /// Hostname of the machine the directory is located on.
+    ///
+    /// This is empty when the shell did not specify a hostname.
+    pub hostname: String,
+
+    /// Absolute path of the directory.
+    pub path: PathBuf,
+}
+
+/// Urgency of a desktop notification.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub enum Urgency {
+    Low,
+    #[default]
+    Normal,
+    Critical,
+}
+
 struct TabStops {
     tabs: Vec<bool>,
 }
@@ -2757,6 +3387,45 @@ mod tests {
         assert_eq!(term.grid()[cursor].c, '▒');
     }
 
+    #[test]
+    fn bounds_to_ansi() {
+        let size = TermSize::new(5, 2);
+        let mut term = Term::new(Config::default(), &size, VoidListener);
+        let grid = term.grid_mut();
+        for (i, c) in "abc".chars().enumerate() {
+            grid[Line(0)][Column(i)].c = c;
+        }
+        grid[Line(0)][Column(1)].flags.insert(Flags::BOLD);
+        grid[Line(0)][Column(2)].flags.insert(Flags::BOLD);
+        grid[Line(1)][Column(0)].c = 'd';
+        grid[Line(1)][Column(0)].fg = Color::Named(NamedColor::Red);
+
+        let start = Point::new(Line(0), Column(0));
+        let end = Point::new(Line(1), Column(4));
+        assert_eq!(term.bounds_to_string(start, end), "abc\nd");
+        assert_eq!(term.bounds_to_ansi(start, end), "a\x1b[0;1mbc\x1b[0m\n\x1b[0;31md\x1b[0m");
+    }
+
+    #[test]
+    fn restore_history() {
+        let size = TermSize::new(5, 2);
+        let mut term = Term::new(Config::default(), &size, VoidListener);
+
+        let rows = ['a', 'b', 'c'].into_iter().map(|c| {
+            let mut row = Row::<Cell>::new(3);
+            row[Column(0)].c = c;
+            row
+        });
+        term.restore_history(rows);
+
+        assert_eq!(term.history_size(), 3);
+        assert_eq!(term.grid[Line(-3)][Column(0)].c, 'a');
+        assert_eq!(term.grid[Line(-1)][Column(0)].c, 'c');
+        assert_eq!(term.grid[Line(-1)].len(), 5);
+        assert!(term.grid[Line(0)].is_clear());
+        assert!(term.grid[Line(1)].is_clear());
+    }
+
     #[test]
     fn clearing_viewport_keeps_history_position() {
         let size = TermSize::new(10, 20);
@@ -3291,6 +3960,202 @@ mod tests {
         assert_eq!(term.title, None);
     }
 
+    #[test]
+    fn prompt_marks_scroll_into_history() {
+        let size = TermSize::new(5, 5);
+        let mut term = Term::new(Config::default(), &size, VoidListener);
+
+        parser::Handler::prompt_mark(&mut term, PromptMarks::PROMPT_START);
+        for _ in 0..7 {
+            term.newline();
+        }
+
+        assert!(term.grid[Line(-3)].marks().contains(PromptMarks::PROMPT_START));
+        assert_eq!(term.prompt_line(Line(4), Direction::Left), Some(Line(-3)));
+        assert_eq!(term.prompt_line(Line(-3), Direction::Left), None);
+        assert_eq!(term.prompt_line(Line(-3), Direction::Right), None);
+
+        // Marks are removed with the line content.
+        term.clear_screen(ansi::ClearMode::Saved);
+        assert_eq!(term.prompt_line(Line(4), Direction::Left), None);
+    }
+
+    #[test]
+    fn last_command_output() {
+        let size = TermSize::new(5, 10);
+        let mut term = Term::new(Config::default(), &size, VoidListener);
+
+        let mark_line = |term: &mut Term<_>, mark| {
+            parser::Handler::prompt_mark(term, mark);
+            term.newline();
+        };
+
+        // Command with two lines of output.
+        mark_line(&mut term, PromptMarks::PROMPT_START | PromptMarks::COMMAND_START);
+        mark_line(&mut term, PromptMarks::OUTPUT_START);
+        mark_line(&mut term, PromptMarks::empty());
+
+        // Command without output.
+        mark_line(&mut term, PromptMarks::COMMAND_END | PromptMarks::PROMPT_START);
+        parser::Handler::prompt_mark(&mut term, PromptMarks::OUTPUT_START);
+
+        // Running command.
+        mark_line(&mut term, PromptMarks::COMMAND_END | PromptMarks::PROMPT_START);
+        mark_line(&mut term, PromptMarks::OUTPUT_START);
+
+        let expected = Point::new(Line(1), Column(0))..=Point::new(Line(2), Column(4));
+        assert_eq!(term.last_command_output(), Some(expected));
+    }
+
+    #[test]
+    fn working_directory() {
+        let size = TermSize::new(5, 5);
+        let mut term = Term::new(Config::default(), &size, VoidListener);
+        assert_eq!(term.working_directory(), None);
+
+        let directory = WorkingDirectory { hostname: "host".into(), path: "/tmp".into() };
+        parser::Handler::set_working_directory(&mut term, directory.clone());
+        assert_eq!(term.working_directory(), Some(&directory));
+
+        // Working directory is not affected by terminal resets.
+        term.reset_state();
+        assert_eq!(term.working_directory(), Some(&directory));
+    }
+
+    #[test]
+    fn graphics_anchored_to_cells() {
+        let size = TermSize::new(5, 3);
+        let mut term = Term::new(Config::default(), &size, VoidListener);
+        let window_size = WindowSize { num_cols: 5, num_lines: 3, cell_width: 2, cell_height: 4 };
+        term.set_window_size(window_size);
+
+        // A 3x5 graphic covers two columns and two lines.
+        let graphic = GraphicData { width: 3, height: 5, pixels: vec![255; 60] };
+        term.goto(1, 1);
+        parser::Handler::insert_graphic(&mut term, graphic.clone());
+
+        let queues = term.graphics_mut().take_queues().unwrap();
+        assert_eq!(queues.pending.len(), 1);
+        let id = queues.pending[0].0;
+
+        let offsets = |term: &Term<VoidListener>, line: i32| {
+            (0..5)
+                .map(|column| {
+                    let cell = &term.grid[Line(line)][Column(column)];
+                    cell.graphic().map(|graphic| (graphic.offset_x, graphic.offset_y))
+                })
+                .collect::<Vec<_>>()
+        };
+        // Cursor is moved below the graphic, scrolling it up.
+        assert_eq!(term.grid.cursor.point, Point::new(Line(2), Column(1)));
+        assert_eq!(offsets(&term, 0), vec![None, Some((0, 0)), Some((2, 0)), None, None]);
+        assert_eq!(offsets(&term, 1), vec![None, Some((0, 4)), Some((2, 4)), None, None]);
+        assert_eq!(offsets(&term, 2), vec![None; 5]);
+
+        // Texture is removed once all cells are cleared.
+        term.clear_screen(ansi::ClearMode::All);
+        assert!(term.graphics_mut().take_queues().is_none());
+        term.clear_screen(ansi::ClearMode::Saved);
+        assert_eq!(term.graphics_mut().take_queues().unwrap().remove_queue, vec![id]);
+    }
+
+    #[test]
+    fn kitty_graphics_placements() {
+        let size = TermSize::new(5, 3);
+        let mut term = Term::new(Config::default(), &size, VoidListener);
+        let window_size = WindowSize { num_cols: 5, num_lines: 3, cell_width: 2, cell_height: 4 };
+        term.set_window_size(window_size);
+
+        // Transmit and display a 3x5 image, covering two columns and two lines.
+        let payload = Base64.encode([255; 60]);
+        let command = format!("a=T,i=1,p=2,f=32,s=3,v=5,z=-1;{payload}");
+        term.goto(0, 1);
+        parser::Handler::kitty_graphics(
+            &mut term,
+            kitty::Command::parse(command.as_bytes()).unwrap(),
+        );
+
+        let id = term.graphics_mut().take_queues().unwrap().pending[0].0;
+        let placements = |term: &Term<VoidListener>| {
+            (0..2)
+                .flat_map(|line| (0..5).map(move |column| (line, column)))
+                .filter_map(|(line, column)| {
+                    let graphic = term.grid[Line(line)][Column(column)].graphic()?;
+                    assert_eq!(graphic.placement.z_index, -1);
+                    Some((line, column, graphic.offset_x, graphic.offset_y))
+                })
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(placements(&term), vec![(0, 1, 0, 0), (0, 2, 2, 0), (1, 1, 0, 4), (1, 2, 2, 4)]);
+
+        // Cursor is moved behind the image.
+        assert_eq!(term.grid.cursor.point, Point::new(Line(1), Column(3)));
+
+        // Display the stored image again without moving the cursor.
+        let command = kitty::Command::parse(b"a=p,i=1,C=1,X=1").unwrap();
+        term.goto(2, 3);
+        parser::Handler::kitty_graphics(&mut term, command);
+        assert_eq!(term.grid.cursor.point, Point::new(Line(2), Column(3)));
+        assert_eq!(term.grid[Line(2)][Column(3)].graphic().unwrap().offset_x, -1);
+
+        // Deleting placements keeps the image data.
+        let command = kitty::Command::parse(b"a=d,d=i,i=1,p=2").unwrap();
+        parser::Handler::kitty_graphics(&mut term, command);
+        assert_eq!(placements(&term), vec![]);
+        assert!(term.grid[Line(2)][Column(3)].graphic().is_some());
+        assert!(term.graphics.kitty.get(1, 0).is_some());
+
+        // Deleting with an uppercase target frees the image.
+        let command = kitty::Command::parse(b"a=d,d=A").unwrap();
+        parser::Handler::kitty_graphics(&mut term, command);
+        assert!(term.grid[Line(2)][Column(3)].graphic().is_none());
+        assert!(term.graphics.kitty.get(1, 0).is_none());
+        assert_eq!(term.graphics_mut().take_queues().unwrap().remove_queue, vec![id]);
+    }
+
+    #[test]
+    fn kitty_graphics_huge_placements() {
+        let size = TermSize::new(5, 3);
+        let mut term = Term::new(Config::default(), &size, VoidListener);
+        let window_size = WindowSize { num_cols: 5, num_lines: 3, cell_width: 2, cell_height: 4 };
+        term.set_window_size(window_size);
+
+        let payload = Base64.encode([255; 60]);
+        let command = format!("a=t,i=1,f=32,s=3,v=5;{payload}");
+        parser::Handler::kitty_graphics(
+            &mut term,
+            kitty::Command::parse(command.as_bytes()).unwrap(),
+        );
+
+        let placement = |term: &Term<VoidListener>| {
+            term.grid[Line(0)][Column(0)].graphic().map(|graphic| graphic.placement)
+        };
+
+        // Cell counts are limited to the maximum graphic size.
+        for command in [&b"a=p,i=1,C=1,c=4294967295"[..], b"a=p,i=1,C=1,r=4294967295"] {
+            term.goto(0, 0);
+            parser::Handler::kitty_graphics(&mut term, kitty::Command::parse(command).unwrap());
+            let placement = placement(&term).unwrap();
+            assert!(placement.width as usize <= MAX_GRAPHIC_DIMENSIONS.0);
+            assert!(placement.height as usize <= MAX_GRAPHIC_DIMENSIONS.1);
+        }
+
+        let command = kitty::Command::parse(b"a=p,i=1,C=1,c=4294967295,r=4294967295").unwrap();
+        parser::Handler::kitty_graphics(&mut term, command);
+        let placement = placement(&term).unwrap();
+        assert_eq!(placement.width as usize, MAX_GRAPHIC_DIMENSIONS.0);
+        assert_eq!(placement.height as usize, MAX_GRAPHIC_DIMENSIONS.1);
+
+        // Moving the cursor below huge placements only scrolls the limited number of lines.
+        let command = kitty::Command::parse(b"a=p,i=1,r=4294967295").unwrap();
+        parser::Handler::kitty_graphics(&mut term, command);
+        assert!(term.grid.history_size() <= MAX_GRAPHIC_DIMENSIONS.1 / 4);
+
+        // Virtual placements are limited the same way.
+        let command = kitty::Command::parse(b"a=p,i=1,U=1,c=4294967295,r=4294967295").unwrap();
+        parser::Handler::kitty_graphics(&mut term, command);
+    }
+
     #[test]
     fn parse_cargo_version() {
         assert!(version_number(env!("CARGO_PKG_VERSION")) >= 10_01);
@@ -56,6 +56,10 @@ pub enum ViMotion {
     ParagraphUp,
     /// Move below the current paragraph.
     ParagraphDown,
+    /// Move to the previous shell prompt.
+    PromptUp,
+    /// Move to the next shell prompt.
+    PromptDown,
 }
 
 /// Cursor tracking vi mode position.
@@ -178,6 +182,16 @@ impl ViModeCursor {
                     .map_or(bottommost_line, Line);
                 self.point.column = Column(0);
             },
+            ViMotion::PromptUp => {
+                if let Some(line) = term.prompt_line(self.point.line, Direction::Left) {
+                    self.point = Point::new(line, Column(0));
+                }
+            },
+            ViMotion::PromptDown => {
+                if let Some(line) = term.prompt_line(self.point.line, Direction::Right) {
+                    self.point = Point::new(line, Column(0));
+                }
+            },
         }
 
         term.scroll_to_point(self.point);
@@ -414,6 +428,7 @@ mod tests {
     use super::*;
 
     use crate::event::VoidListener;
+    use crate::grid::PromptMarks;
     use crate::index::{Column, Line};
     use crate::term::test::TermSize;
     use crate::term::{Config, Term};
@@ -528,6 +543,28 @@ mod tests {
         assert_eq!(cursor.point, Point::new(Line(0), Column(0)));
     }
 
+    #[test]
+    fn motion_prompt() {
+        let mut term = term();
+        term.grid_mut()[Line(2)].insert_mark(PromptMarks::PROMPT_START);
+        term.grid_mut()[Line(3)].insert_mark(PromptMarks::OUTPUT_START);
+        term.grid_mut()[Line(7)].insert_mark(PromptMarks::PROMPT_START);
+
+        let mut cursor = ViModeCursor::new(Point::new(Line(5), Column(3)));
+
+        cursor = cursor.motion(&mut term, ViMotion::PromptUp);
+        assert_eq!(cursor.point, Point::new(Line(2), Column(0)));
+
+        cursor = cursor.motion(&mut term, ViMotion::PromptUp);
+        assert_eq!(cursor.point, Point::new(Line(2), Column(0)));
+
+        cursor = cursor.motion(&mut term, ViMotion::PromptDown);
+        assert_eq!(cursor.point, Point::new(Line(7), Column(0)));
+
+        cursor = cursor.motion(&mut term, ViMotion::PromptDown);
+        assert_eq!(cursor.point, Point::new(Line(7), Column(0)));
+    }
+
     fn motion_semantic_term() -> Term<VoidListener> {
         let mut term = term();
 